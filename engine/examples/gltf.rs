@@ -1,7 +1,6 @@
 use std::convert::TryInto;
-use utilities::Window;
-
 use engine::{
+	Window,
 	Renderer,
 	Camera,
 	Scene,
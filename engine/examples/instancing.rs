@@ -1,6 +1,5 @@
-use utilities::Window;
-
 use engine::{
+	Window,
 	Renderer,
 	Camera,
 	lights::PointLight,
@@ -1,23 +1,87 @@
 use crate::{component::Transform3D, math::{matrix4, Matrix4}};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+	Perspective { aspect: f32, fov: f32, near: f32, far: f32 },
+	// The view volume is centered on the camera
+	Orthographic { width: f32, height: f32, near: f32, far: f32 }
+}
+
 pub struct Camera {
 	pub projection_matrix: Matrix4,
-	pub transform: Transform3D
+	pub transform: Transform3D,
+	projection: Projection
 }
 
 impl Camera {
 	pub fn new(aspect: f32, fov: f32, near: f32, far: f32) -> Self {
-		let mut projection_matrix = matrix4::IDENTITY;
-		projection_matrix.make_perspective(aspect, fov, near, far);
+		Self::new_with_projection(Projection::Perspective { aspect, fov, near, far })
+	}
+
+	pub fn new_orthographic(width: f32, height: f32, near: f32, far: f32) -> Self {
+		Self::new_with_projection(Projection::Orthographic { width, height, near, far })
+	}
+
+	fn new_with_projection(projection: Projection) -> Self {
+		let mut camera = Self {
+			projection_matrix: matrix4::IDENTITY,
+			transform: Transform3D::new(),
+			projection
+		};
 
-		Self {
-			projection_matrix,
-			transform: Transform3D::new()
+		camera.set_projection(projection);
+		camera
+	}
+
+	pub fn projection(&self) -> Projection {
+		self.projection
+	}
+
+	pub fn set_projection(&mut self, projection: Projection) {
+		match projection {
+			Projection::Perspective { aspect, fov, near, far } => {
+				self.projection_matrix.make_perspective(aspect, fov, near, far);
+			},
+			Projection::Orthographic { width, height, near, far } => {
+				self.projection_matrix.make_orthographic(-width / 2.0, width / 2.0, -height / 2.0, height / 2.0, near, far);
+			}
 		}
+
+		self.projection = projection;
 	}
 
 	pub fn update(&mut self) {
 		self.transform.update_local_matrix();
 		self.transform.global_matrix = self.transform.local_matrix;
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn new_orthographic() {
+		let camera = Camera::new_orthographic(4.0, 2.0, 1.0, 5.0);
+
+		let mut expected = matrix4::IDENTITY;
+		expected.make_orthographic(-2.0, 2.0, -1.0, 1.0, 1.0, 5.0);
+		assert_eq!(camera.projection_matrix, expected);
+	}
+
+	#[test]
+	fn set_projection() {
+		let mut camera = Camera::new(0.5, 90.0, 1.0, 5.0);
+
+		let orthographic = Projection::Orthographic { width: 4.0, height: 2.0, near: 1.0, far: 5.0 };
+		camera.set_projection(orthographic);
+		assert_eq!(camera.projection(), orthographic);
+		assert_eq!(camera.projection_matrix, Camera::new_orthographic(4.0, 2.0, 1.0, 5.0).projection_matrix);
+
+		camera.set_projection(Projection::Perspective { aspect: 0.5, fov: 90.0, near: 1.0, far: 5.0 });
+
+		let mut expected = matrix4::IDENTITY;
+		expected.make_perspective(0.5, 90.0, 1.0, 5.0);
+		assert_eq!(camera.projection_matrix, expected);
+	}
 }
\ No newline at end of file
@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+// Measures the real time between ticks
+// The delta is capped so a long stall, like dragging the window, doesn't ask the update loop to catch up on all of it at once
+// Smoothing blends each delta with the previous one to even out jitter, 0 disables it
+pub struct Clock {
+	last_tick: Instant,
+	max_delta: Duration,
+	smoothing: f64,
+	delta: Duration
+}
+
+impl Clock {
+	pub fn new(max_delta: Duration) -> Self {
+		Self::new_with_smoothing(max_delta, 0.0)
+	}
+
+	pub fn new_with_smoothing(max_delta: Duration, smoothing: f64) -> Self {
+		assert!((0.0..1.0).contains(&smoothing), "Cannot create clock because the smoothing {} is not in the range [0, 1)", smoothing);
+
+		Self {
+			last_tick: Instant::now(),
+			max_delta,
+			smoothing,
+			delta: Duration::new(0, 0)
+		}
+	}
+
+	// Called once per frame, returns the new delta
+	pub fn tick(&mut self) -> Duration {
+		let now = Instant::now();
+		let raw_delta = now.duration_since(self.last_tick);
+		self.last_tick = now;
+		self.advance(raw_delta)
+	}
+
+	pub fn delta(&self) -> Duration {
+		self.delta
+	}
+
+	fn advance(&mut self, raw_delta: Duration) -> Duration {
+		let capped_delta = raw_delta.min(self.max_delta);
+
+		// The first delta has nothing to be blended with
+		self.delta = if self.delta == Duration::new(0, 0) {
+			capped_delta
+		}
+		else {
+			Duration::from_secs_f64(self.delta.as_secs_f64() * self.smoothing + capped_delta.as_secs_f64() * (1.0 - self.smoothing))
+		};
+
+		self.delta
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn huge_delta_is_capped() {
+		let mut clock = Clock::new(Duration::from_millis(250));
+		assert_eq!(clock.advance(Duration::from_secs(30)), Duration::from_millis(250));
+		assert_eq!(clock.delta(), Duration::from_millis(250));
+
+		assert_eq!(clock.advance(Duration::from_millis(16)), Duration::from_millis(16));
+	}
+
+	#[test]
+	fn smoothing() {
+		let mut clock = Clock::new_with_smoothing(Duration::from_millis(250), 0.5);
+		assert_eq!(clock.advance(Duration::from_millis(10)), Duration::from_millis(10));
+
+		let delta = clock.advance(Duration::from_millis(20));
+		assert!((delta.as_secs_f64() - 0.015).abs() < 1e-9);
+
+		// The cap applies before smoothing so a stall only moves the delta part way to the cap
+		let delta = clock.advance(Duration::from_secs(30));
+		assert!((delta.as_secs_f64() - 0.1325).abs() < 1e-9);
+	}
+
+	#[test]
+	#[should_panic]
+	fn smoothing_out_of_range() {
+		Clock::new_with_smoothing(Duration::from_millis(250), 1.0);
+	}
+}
\ No newline at end of file
@@ -1,58 +1,129 @@
-use crate::entity_manager::MAX_ENTITY_COUNT;
+// Implemented by every component list so an entity can be removed from all of them when it's destroyed
+pub trait ComponentStore {
+	fn try_remove(&mut self, entity: usize) -> bool;
+}
 
 pub struct ComponentList<T> {
 	components: Vec<(usize, T)>,
-	entity_to_index_map: [Option<usize>; MAX_ENTITY_COUNT]
+	entity_to_index_map: Vec<Option<usize>>
 }
 
 impl<T> ComponentList<T> {
 	pub fn new() -> Self {
 		Self {
 			components: Vec::new(),
-			entity_to_index_map: [None; MAX_ENTITY_COUNT]
+			entity_to_index_map: Vec::new()
 		}
 	}
 
 	pub fn add(&mut self, entity: usize, component: T) {
-		assert!(self.entity_to_index_map[entity].is_none(), "Cannot add component to entity {} because it already has this component type", entity);
+		assert!(self.index(entity).is_none(), "Cannot add component to entity {} because it already has this component type", entity);
 		self.components.push((entity, component));
 		let index = self.components.len() - 1;
-		self.entity_to_index_map[entity] = Some(index);
+		self.map_entity(entity, index);
 	}
 
 	pub fn remove(&mut self, entity: usize) {
-		let index_option = self.entity_to_index_map[entity];
+		let index_option = self.index(entity);
 		assert!(index_option.is_some(), "Cannot remove component from entity {} because it does not have this component type", entity);
 		let index = index_option.unwrap();
 		self.entity_to_index_map[entity] = None;
 		self.components.swap_remove(index);
-		let (swapped_entity, _) = self.components[index];
-		self.entity_to_index_map[swapped_entity] = Some(index);
+
+		if let Some((swapped_entity, _)) = self.components.get(index) {
+			self.entity_to_index_map[*swapped_entity] = Some(index);
+		}
 	}
 
 	pub fn borrow(&self, entity: usize) -> &T {
-		let index = self.entity_to_index_map[entity];
+		let index = self.index(entity);
 		assert!(index.is_some(), "Cannot borrow component from entity {} because it does not have this component type", entity);
 		&self.components[index.unwrap()].1
 	}
 
 	pub fn borrow_mut(&mut self, entity: usize) -> &mut T {
-		let index = self.entity_to_index_map[entity];
+		let index = self.index(entity);
 		assert!(index.is_some(), "Cannot mutably borrow component from entity {} because it does not have this component type", entity);
 		&mut self.components[index.unwrap()].1
 	}
 
 	pub fn try_borrow(&self, entity: usize) -> Option<&T> {
-		let index = self.entity_to_index_map[entity]?;
+		let index = self.index(entity)?;
 		Some(&self.components[index].1)
 	}
 
 	pub fn try_borrow_mut(&mut self, entity: usize) -> Option<&mut T> {
-		let index = self.entity_to_index_map[entity]?;
+		let index = self.index(entity)?;
 		Some(&mut self.components[index].1)
 	}
 
+	pub fn len(&self) -> usize {
+		self.components.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.components.is_empty()
+	}
+
 	pub fn iter(&self) -> impl Iterator<Item = &(usize, T)> {
 		self.components.iter()
 	}
+
+	fn index(&self, entity: usize) -> Option<usize> {
+		self.entity_to_index_map.get(entity).copied().flatten()
+	}
+
+	// The map grows to fit the largest entity so there's no limit on the entity count
+	fn map_entity(&mut self, entity: usize, index: usize) {
+		if entity >= self.entity_to_index_map.len() {
+			self.entity_to_index_map.resize(entity + 1, None);
+		}
+
+		self.entity_to_index_map[entity] = Some(index);
+	}
+}
+
+impl<T> ComponentStore for ComponentList<T> {
+	fn try_remove(&mut self, entity: usize) -> bool {
+		if self.index(entity).is_none() {
+			return false;
+		}
+
+		self.remove(entity);
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::EntityManager;
+
+	#[test]
+	fn grow() {
+		let mut entity_manager = EntityManager::new();
+		let mut components = ComponentList::<usize>::new();
+		let entities: Vec<usize> = (0..1000).map(|_| entity_manager.create()).collect();
+
+		for entity in &entities {
+			components.add(*entity, entity * 2);
+		}
+
+		for entity in &entities {
+			assert_eq!(*components.borrow(*entity), entity * 2);
+		}
+
+		// Entities past the end of the map don't have the component
+		assert!(components.try_borrow(5000).is_none());
+	}
+
+	#[test]
+	fn remove_last() {
+		let mut components = ComponentList::<u32>::new();
+		components.add(3, 1);
+		components.add(7, 2);
+		components.remove(7);
+		components.remove(3);
+		assert!(components.is_empty());
+	}
 }
\ No newline at end of file
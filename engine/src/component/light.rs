@@ -1,8 +1,10 @@
 use crate::math::Vector3;
+use super::ComponentList;
 
 pub enum Light {
 	PointLight(PointLight),
-	AmbientLight(AmbientLight)
+	AmbientLight(AmbientLight),
+	DirectionalLight(DirectionalLight)
 }
 
 pub struct PointLight {
@@ -15,11 +17,72 @@ pub struct AmbientLight {
 	pub intensity: f32
 }
 
+// Shines along the +Z axis of the entity's transform which is also where the shadow map is rendered from
+pub struct DirectionalLight {
+	pub color: Vector3,
+	pub intensity: f32
+}
+
 impl Light {
+	pub fn new_point(color: Vector3, intensity: f32) -> Self {
+		Light::PointLight(PointLight { color, intensity })
+	}
+
+	pub fn new_ambient(color: Vector3, intensity: f32) -> Self {
+		Light::AmbientLight(AmbientLight { color, intensity })
+	}
+
+	pub fn new_directional(color: Vector3, intensity: f32) -> Self {
+		Light::DirectionalLight(DirectionalLight { color, intensity })
+	}
+
 	pub fn as_point_light(&self) -> &PointLight {
 		match self {
 			Light::PointLight(point_light) => point_light,
 			_ => panic!("Cannot cast Light to PointLight varient because it's not a PointLight")
 		}
 	}
+
+	pub fn as_point_light_mut(&mut self) -> &mut PointLight {
+		match self {
+			Light::PointLight(point_light) => point_light,
+			_ => panic!("Cannot cast Light to PointLight varient because it's not a PointLight")
+		}
+	}
+
+	pub fn as_ambient_light_mut(&mut self) -> &mut AmbientLight {
+		match self {
+			Light::AmbientLight(ambient_light) => ambient_light,
+			_ => panic!("Cannot cast Light to AmbientLight varient because it's not an AmbientLight")
+		}
+	}
+}
+
+impl ComponentList<Light> {
+	// The render system can only upload a limited number of these
+	pub fn point_light_count(&self) -> usize {
+		self.iter().filter(|(_, light)| matches!(light, Light::PointLight(_))).count()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::vector3;
+
+	#[test]
+	fn point_light_count() {
+		let mut light_components = ComponentList::<Light>::new();
+		light_components.add(0, Light::new_ambient(vector3::ONE, 0.1));
+		light_components.add(1, Light::new_point(vector3::ONE, 1.0));
+		light_components.add(2, Light::new_point(vector3::ONE, 2.0));
+		assert_eq!(light_components.point_light_count(), 2);
+
+		light_components.remove(1);
+		assert_eq!(light_components.point_light_count(), 1);
+		assert_eq!(light_components.borrow(2).as_point_light().intensity, 2.0);
+
+		light_components.borrow_mut(2).as_point_light_mut().intensity = 3.0;
+		assert_eq!(light_components.borrow(2).as_point_light().intensity, 3.0);
+	}
 }
\ No newline at end of file
@@ -1,14 +1,45 @@
-use crate::pool::Handle;
+use crate::{math::{Vector3, vector3}, pool::Handle};
 
 #[derive(Copy, Clone)]
 pub enum Material {
 	Line,
-	Basic,
+	Basic { color: Vector3 },
 	Normal,
-	Lambert
+	Lambert,
+	Phong { shininess: f32, specular: Vector3 },
+	// Only for geometries created with vertex colors
+	VertexColor
 }
 
+impl Material {
+	// Each material has its own pipeline and instance data array, this indexes them
+	pub(crate) fn index(&self) -> usize {
+		match self {
+			Self::Line => 0,
+			Self::Basic { .. } => 1,
+			Self::Normal => 2,
+			Self::Lambert => 3,
+			Self::Phong { .. } => 4,
+			Self::VertexColor => 5
+		}
+	}
+}
+
+// The tint multiplies the material's color, a strength of 0 leaves it unchanged and 1 fully applies it
 pub struct Mesh {
 	pub geometry_handle: Handle,
-	pub material: Material
+	pub material: Material,
+	pub tint: Vector3,
+	pub tint_strength: f32
+}
+
+impl Mesh {
+	pub fn new(geometry_handle: Handle, material: Material) -> Self {
+		Self {
+			geometry_handle,
+			material,
+			tint: vector3::ONE,
+			tint_strength: 0.0
+		}
+	}
 }
\ No newline at end of file
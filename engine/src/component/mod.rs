@@ -1,5 +1,5 @@
 pub mod component_list;
-pub use component_list::ComponentList;
+pub use component_list::{ComponentList, ComponentStore};
 
 pub mod multi_component_list;
 pub use multi_component_list::MultiComponentList;
@@ -29,4 +29,7 @@ pub mod text;
 pub use text::Text;
 
 pub mod text_component_list;
-pub use text_component_list::TextComponentList;
\ No newline at end of file
+pub use text_component_list::TextComponentList;
+
+pub mod name_component_list;
+pub use name_component_list::NameComponentList;
\ No newline at end of file
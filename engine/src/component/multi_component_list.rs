@@ -1,15 +1,15 @@
-use crate::entity_manager::MAX_ENTITY_COUNT;
+use super::ComponentStore;
 
 pub struct MultiComponentList<T> {
 	components: Vec<(Vec<usize>, T)>,
-	entity_to_index_map: [Option<usize>; MAX_ENTITY_COUNT]
+	entity_to_index_map: Vec<Option<usize>>
 }
 
 impl<T> MultiComponentList<T> {
 	pub fn new() -> Self {
 		Self {
 			components: Vec::new(),
-			entity_to_index_map: [None; MAX_ENTITY_COUNT]
+			entity_to_index_map: Vec::new()
 		}
 	}
 
@@ -33,12 +33,12 @@ impl<T> MultiComponentList<T> {
 	}
 
 	pub fn assign(&mut self, entity: usize, index: usize) {
-		self.entity_to_index_map[entity] = Some(index);
+		self.map_entity(entity, index);
 		self.components[index].0.push(entity);
 	}
 
 	pub fn unassign(&mut self, entity: usize) {
-		let index = self.entity_to_index_map[entity];
+		let index = self.index(entity);
 		assert!(index.is_some(), "Cannot unassign component from entity {} because it does not have this component type", entity);
 		let (entities, _) = &mut self.components[index.unwrap()];
 		let entity_index = entities.iter().position(|e| *e == entity).unwrap();
@@ -47,28 +47,51 @@ impl<T> MultiComponentList<T> {
 	}
 
 	pub fn borrow(&self, entity: usize) -> &T {
-		let index = self.entity_to_index_map[entity];
+		let index = self.index(entity);
 		assert!(index.is_some(), "Cannot borrow component from entity {} because it does not have this component type", entity);
 		&self.components[index.unwrap()].1
 	}
 
 	pub fn borrow_mut(&mut self, entity: usize) -> &mut T {
-		let index = self.entity_to_index_map[entity];
+		let index = self.index(entity);
 		assert!(index.is_some(), "Cannot mutably borrow component from entity {} because it does not have this component type", entity);
 		&mut self.components[index.unwrap()].1
 	}
 
 	pub fn try_borrow(&self, entity: usize) -> Option<&T> {
-		let index = self.entity_to_index_map[entity]?;
+		let index = self.index(entity)?;
 		Some(&self.components[index].1)
 	}
 
 	pub fn try_borrow_mut(&mut self, entity: usize) -> Option<&mut T> {
-		let index = self.entity_to_index_map[entity]?;
+		let index = self.index(entity)?;
 		Some(&mut self.components[index].1)
 	}
 
 	pub fn iter(&self) -> impl Iterator<Item = &(Vec<usize>, T)> {
 		self.components.iter()
 	}
+
+	fn index(&self, entity: usize) -> Option<usize> {
+		self.entity_to_index_map.get(entity).copied().flatten()
+	}
+
+	fn map_entity(&mut self, entity: usize, index: usize) {
+		if entity >= self.entity_to_index_map.len() {
+			self.entity_to_index_map.resize(entity + 1, None);
+		}
+
+		self.entity_to_index_map[entity] = Some(index);
+	}
+}
+
+impl<T> ComponentStore for MultiComponentList<T> {
+	fn try_remove(&mut self, entity: usize) -> bool {
+		if self.index(entity).is_none() {
+			return false;
+		}
+
+		self.unassign(entity);
+		true
+	}
 }
\ No newline at end of file
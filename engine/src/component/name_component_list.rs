@@ -0,0 +1,63 @@
+use super::{ComponentList, ComponentStore};
+
+// Names aren't unique so lookups return the first or all matching entities
+pub struct NameComponentList {
+	component_list: ComponentList<String>
+}
+
+impl NameComponentList {
+	pub fn new() -> Self {
+		Self {
+			component_list: ComponentList::<String>::new()
+		}
+	}
+
+	pub fn add(&mut self, entity: usize, name: String) {
+		self.component_list.add(entity, name);
+	}
+
+	pub fn remove(&mut self, entity: usize) {
+		self.component_list.remove(entity);
+	}
+
+	pub fn borrow(&self, entity: usize) -> &str {
+		self.component_list.borrow(entity)
+	}
+
+	pub fn try_borrow(&self, entity: usize) -> Option<&str> {
+		self.component_list.try_borrow(entity).map(|name| name.as_str())
+	}
+
+	pub fn find_by_name(&self, name: &str) -> Option<usize> {
+		self.component_list.iter().find(|(_, n)| n == name).map(|(entity, _)| *entity)
+	}
+
+	pub fn find_all_by_name(&self, name: &str) -> Vec<usize> {
+		self.component_list.iter().filter(|(_, n)| n == name).map(|(entity, _)| *entity).collect()
+	}
+}
+
+impl ComponentStore for NameComponentList {
+	fn try_remove(&mut self, entity: usize) -> bool {
+		self.component_list.try_remove(entity)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn find_by_name() {
+		let mut name_components = NameComponentList::new();
+		name_components.add(4, String::from("LeftDoor"));
+		name_components.add(2, String::from("RightDoor"));
+		name_components.add(9, String::from("LeftDoor"));
+
+		assert_eq!(name_components.find_by_name("LeftDoor"), Some(4));
+		assert_eq!(name_components.find_by_name("RightDoor"), Some(2));
+		assert_eq!(name_components.find_by_name("Hood"), None);
+		assert_eq!(name_components.find_all_by_name("LeftDoor"), vec![4, 9]);
+		assert_eq!(name_components.borrow(2), "RightDoor");
+	}
+}
\ No newline at end of file
@@ -1,19 +1,71 @@
-use crate::pool::Handle;
+use crate::{Font, font::Glyph, math::{Vector3, vector3}, pool::{Handle, Pool}};
+
+pub const MAX_FALLBACK_FONTS: usize = 3;
+
+// Vertices of glyphs which no font has are given this slot instead of a font's slot
+pub const TOFU_FONT_SLOT: usize = MAX_FALLBACK_FONTS + 1;
+
+// Each glyph is a quad of 4 vertices which each have a position, atlas position, glyph bounds and font slot
+pub const ATTRIBUTES_PER_VERTEX: usize = 9;
+
+// Which part of the text block sits at the transform's position, the baseline is the first line's
+// The other anchors use the measured bounds of the glyphs
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VerticalAnchor {
+	Baseline,
+	Top,
+	Middle,
+	Bottom
+}
 
 pub struct Text {
 	pub font: Handle,
+	pub fallback_fonts: Vec<Handle>,
 	pub string: String,
+	pub max_width: f32,
+	pub vertical_anchor: VerticalAnchor,
+	// Tab stops are this many spaces apart
+	pub tab_size: usize,
+	pub color: Vector3,
+	pub outline_color: Vector3,
+	pub outline_width: f32,
 	pub(crate) indices: Vec<u16>,
-	pub(crate) attributes: Vec<f32>
+	pub(crate) attributes: Vec<f32>,
+	line_count: usize,
+	pub(crate) generate: bool
 }
 
 impl Text {
 	pub fn new(font: Handle, string: String) -> Self {
 		Self {
 			font,
+			fallback_fonts: Vec::new(),
 			string,
+			max_width: f32::INFINITY,
+			vertical_anchor: VerticalAnchor::Baseline,
+			tab_size: 4,
+			color: vector3::ONE,
+			outline_color: vector3::ZERO,
+			outline_width: 0.0,
 			indices: Vec::new(),
-			attributes: Vec::new()
+			attributes: Vec::new(),
+			line_count: 0,
+			generate: true
+		}
+	}
+
+	// Only flags the text to be generated again if the string changed
+	pub fn set_string(&mut self, string: String) {
+		if self.string != string {
+			self.string = string;
+			self.generate = true;
+		}
+	}
+
+	pub fn set_font(&mut self, font: Handle) {
+		if self.font != font {
+			self.font = font;
+			self.generate = true;
 		}
 	}
 
@@ -24,4 +76,385 @@ impl Text {
 	pub fn attributes(&self) -> &[f32] {
 		&self.attributes
 	}
+
+	pub fn line_count(&self) -> usize {
+		self.line_count
+	}
+
+	pub(crate) fn generate(&mut self, fonts: &Pool<Font>) {
+		assert!(self.fallback_fonts.len() <= MAX_FALLBACK_FONTS, "Cannot generate text because it has {} fallback fonts and the max is {}", self.fallback_fonts.len(), MAX_FALLBACK_FONTS);
+
+		self.indices.clear();
+		self.attributes.clear();
+		self.line_count = 1;
+		self.generate = false;
+
+		// The primary font is in slot 0 followed by the fallback fonts in order
+		let fonts: Vec<&Font> = std::iter::once(&self.font).chain(&self.fallback_fonts).map(|handle| fonts.borrow(*handle)).collect();
+		let font = fonts[0];
+
+		// Quads are grown by the outline width so the outline isn't clipped
+		let padding = self.outline_width.max(0.0).ceil();
+
+		let mut glyph_count = 0;
+		let mut cursor_x = 0.0;
+		let mut cursor_y = 0.0;
+		let mut block_top = f32::INFINITY;
+		let mut block_bottom = f32::NEG_INFINITY;
+
+		// Newlines are hard breaks and tabs move to the next tab stop, the words between them are split on spaces
+		let tab_stop_width = self.tab_size as f32 * font.space_advance;
+
+		for (line_index, line) in self.string.split('\n').enumerate() {
+			if line_index > 0 {
+				cursor_x = 0.0;
+				cursor_y += font.line_height;
+				self.line_count += 1;
+			}
+
+			for (segment_index, segment) in line.split('\t').enumerate() {
+				if segment_index > 0 && tab_stop_width > 0.0 {
+					cursor_x = ((cursor_x / tab_stop_width).floor() + 1.0) * tab_stop_width;
+				}
+
+				for (word_index, word) in segment.split(' ').enumerate() {
+					if word_index > 0 {
+						cursor_x += font.space_advance;
+					}
+
+					// Move the word down to the next line if it doesn't fit on this one
+					let word_width = Self::word_width(&fonts, word);
+
+					if cursor_x > 0.0 && cursor_x + word_width > self.max_width {
+						cursor_x = 0.0;
+						cursor_y += font.line_height;
+						self.line_count += 1;
+					}
+
+					let mut previous = None;
+
+					for c in word.chars() {
+						let (font_slot, glyph) = Self::resolve_glyph(&fonts, c);
+
+						// Kerning only applies between glyphs from the same font
+						if let Some((previous_font_slot, previous_char)) = previous {
+							if previous_font_slot == font_slot && font_slot != TOFU_FONT_SLOT {
+								cursor_x += fonts[font_slot].find_kerning(previous_char, c);
+							}
+						}
+
+						previous = Some((font_slot, c));
+
+						// Break words which are longer than the max width
+						if cursor_x > 0.0 && cursor_x + glyph.advance > self.max_width {
+							cursor_x = 0.0;
+							cursor_y += font.line_height;
+							self.line_count += 1;
+						}
+
+						let index_offset = glyph_count * 4;
+						self.indices.extend_from_slice(&[
+							index_offset, index_offset + 1, index_offset + 2,
+							index_offset, index_offset + 2, index_offset + 3
+						]);
+
+						let screen_left = cursor_x + glyph.bearing_x - padding;
+						let screen_top = cursor_y + glyph.bearing_y - padding;
+						let screen_right = screen_left + glyph.width + 2.0 * padding;
+						let screen_bottom = screen_top + glyph.height + 2.0 * padding;
+
+						let atlas_left = glyph.position_x - padding;
+						let atlas_top = glyph.position_y - padding;
+						let atlas_right = glyph.position_x + glyph.width + padding;
+						let atlas_bottom = glyph.position_y + glyph.height + padding;
+
+						// Each vertex also holds the glyph's bounds in the atlas so sampling stays within the glyph and the slot of the font it's from
+						let (min_x, min_y, max_x, max_y) = (glyph.position_x, glyph.position_y, glyph.position_x + glyph.width, glyph.position_y + glyph.height);
+						let slot = font_slot as f32;
+
+						self.attributes.extend_from_slice(&[
+							screen_left, screen_top, atlas_left, atlas_top, min_x, min_y, max_x, max_y, slot,
+							screen_right, screen_top, atlas_right, atlas_top, min_x, min_y, max_x, max_y, slot,
+							screen_right, screen_bottom, atlas_right, atlas_bottom, min_x, min_y, max_x, max_y, slot,
+							screen_left, screen_bottom, atlas_left, atlas_bottom, min_x, min_y, max_x, max_y, slot
+						]);
+
+						block_top = block_top.min(cursor_y + glyph.bearing_y);
+						block_bottom = block_bottom.max(cursor_y + glyph.bearing_y + glyph.height);
+
+						glyph_count += 1;
+						cursor_x += glyph.advance;
+					}
+				}
+			}
+		}
+
+		if glyph_count == 0 {
+			return;
+		}
+
+		let offset_y = match self.vertical_anchor {
+			VerticalAnchor::Baseline => 0.0,
+			VerticalAnchor::Top => -block_top,
+			VerticalAnchor::Middle => -(block_top + block_bottom) / 2.0,
+			VerticalAnchor::Bottom => -block_bottom
+		};
+
+		if offset_y != 0.0 {
+			for vertex in self.attributes.chunks_exact_mut(ATTRIBUTES_PER_VERTEX) {
+				vertex[1] += offset_y;
+			}
+		}
+	}
+
+	// The glyph comes from the first font which has it, otherwise it's a tofu box sized from the primary font
+	fn resolve_glyph(fonts: &[&Font], c: char) -> (usize, Glyph) {
+		for (font_slot, font) in fonts.iter().enumerate() {
+			if let Some(glyph) = font.try_find_glyph(c) {
+				return (font_slot, *glyph);
+			}
+		}
+
+		let height = (fonts[0].line_height * 0.6).round();
+		let width = (height * 0.6).round();
+
+		// The tofu's atlas position is the origin so the shader can find its edges from the glyph bounds
+		let tofu = Glyph {
+			char_code: c as u32,
+			position_x: 0.0,
+			position_y: 0.0,
+			width,
+			height,
+			bearing_x: 1.0,
+			bearing_y: -height,
+			advance: width + 2.0
+		};
+
+		(TOFU_FONT_SLOT, tofu)
+	}
+
+	fn word_width(fonts: &[&Font], word: &str) -> f32 {
+		let mut width = 0.0;
+		let mut previous = None;
+
+		for c in word.chars() {
+			let (font_slot, glyph) = Self::resolve_glyph(fonts, c);
+
+			if let Some((previous_font_slot, previous_char)) = previous {
+				if previous_font_slot == font_slot && font_slot != TOFU_FONT_SLOT {
+					width += fonts[font_slot].find_kerning(previous_char, c);
+				}
+			}
+
+			width += glyph.advance;
+			previous = Some((font_slot, c));
+		}
+
+		width
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::font::KerningPair;
+
+	fn create_font() -> Font {
+		create_font_with_chars("abc")
+	}
+
+	fn create_font_with_chars(chars: &str) -> Font {
+		let glyphs = chars.chars().map(|c| Glyph {
+			char_code: c as u32,
+			position_x: 0.0,
+			position_y: 0.0,
+			width: 8.0,
+			height: 10.0,
+			bearing_x: 1.0,
+			bearing_y: -10.0,
+			advance: 10.0
+		}).collect();
+
+		Font {
+			fnt_path: String::new(),
+			atlas_width: 0,
+			atlas_height: 0,
+			space_advance: 5.0,
+			line_height: 12.0,
+			glyphs,
+			kerning_pairs: vec![KerningPair { left: 'a' as u32, right: 'b' as u32, amount: -2.0 }],
+			submission_info: None
+		}
+	}
+
+	fn create_text(string: &str, max_width: f32) -> Text {
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(create_font());
+		let mut text = Text::new(font_handle, String::from(string));
+		text.max_width = max_width;
+		text.generate(&fonts);
+		text
+	}
+
+	#[test]
+	fn generate_single_line() {
+		let text = create_text("abc abc abc", f32::INFINITY);
+		assert_eq!(text.line_count(), 1);
+		assert_eq!(text.indices().len(), 9 * 6);
+		assert_eq!(text.attributes().len(), 9 * 4 * ATTRIBUTES_PER_VERTEX);
+	}
+
+	#[test]
+	fn generate_word_wrap() {
+		// "abc abc" is 65 wide so the third word wraps
+		let text = create_text("abc abc abc", 75.0);
+		assert_eq!(text.line_count(), 2);
+
+		// The first glyph of the third word starts the second line
+		let attributes = &text.attributes()[6 * 4 * ATTRIBUTES_PER_VERTEX..];
+		assert_eq!(attributes[0], 1.0);
+		assert_eq!(attributes[1], 2.0);
+	}
+
+	#[test]
+	fn generate_hard_break() {
+		// Only 4 glyphs fit on each line
+		let text = create_text("aaaaaaaaaa", 45.0);
+		assert_eq!(text.line_count(), 3);
+		assert_eq!(text.indices().len(), 10 * 6);
+	}
+
+	#[test]
+	fn generate_kerning() {
+		// The b is pulled 2 towards the a but the kerning doesn't apply across the space
+		let text = create_text("ab b", f32::INFINITY);
+		assert_eq!(text.attributes()[4 * ATTRIBUTES_PER_VERTEX], 9.0);
+		assert_eq!(text.attributes()[8 * ATTRIBUTES_PER_VERTEX], 24.0);
+	}
+
+	#[test]
+	fn set_string() {
+		let mut text = create_text("abc", f32::INFINITY);
+		assert!(!text.generate);
+
+		text.set_string(String::from("abc"));
+		assert!(!text.generate);
+
+		text.set_string(String::from("cba"));
+		assert!(text.generate);
+		assert_eq!(text.string, "cba");
+	}
+
+	#[test]
+	fn set_font() {
+		let mut text = create_text("abc", f32::INFINITY);
+		let font = text.font;
+
+		text.set_font(font);
+		assert!(!text.generate);
+
+		text.set_font(Handle::null());
+		assert!(text.generate);
+	}
+
+	#[test]
+	fn generate_outline_padding() {
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(create_font());
+
+		let mut text = Text::new(font_handle, String::from("ab"));
+		text.generate(&fonts);
+		let attributes = text.attributes().to_vec();
+
+		// A zero width outline doesn't change the quads
+		text.outline_width = 0.0;
+		text.generate(&fonts);
+		assert_eq!(text.attributes(), &attributes[..]);
+
+		// The first vertex moves up and left by the rounded up outline width but the glyph bounds stay the same
+		text.outline_width = 1.5;
+		text.generate(&fonts);
+		assert_eq!(&text.attributes()[0..4], &[attributes[0] - 2.0, attributes[1] - 2.0, attributes[2] - 2.0, attributes[3] - 2.0]);
+		assert_eq!(&text.attributes()[4..8], &attributes[4..8]);
+	}
+
+	#[test]
+	fn generate_fallback_font() {
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(create_font());
+		let fallback_font_handle = fonts.add(create_font_with_chars("★"));
+
+		let mut text = Text::new(font_handle, String::from("a★"));
+		text.fallback_fonts.push(fallback_font_handle);
+		text.generate(&fonts);
+
+		// The star isn't in the primary font so it's drawn from the fallback font in slot 1
+		assert_eq!(text.indices().len(), 2 * 6);
+		assert_eq!(text.attributes()[ATTRIBUTES_PER_VERTEX - 1], 0.0);
+		assert_eq!(text.attributes()[5 * ATTRIBUTES_PER_VERTEX - 1], 1.0);
+		assert_eq!(text.attributes()[4 * ATTRIBUTES_PER_VERTEX], 11.0);
+	}
+
+	#[test]
+	fn generate_tofu() {
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(create_font());
+
+		let mut text = Text::new(font_handle, String::from("az"));
+		text.generate(&fonts);
+
+		// No font has the z so it's a tofu box whose atlas bounds start at the origin
+		let attributes = &text.attributes()[4 * ATTRIBUTES_PER_VERTEX..5 * ATTRIBUTES_PER_VERTEX];
+		assert_eq!(attributes[8], TOFU_FONT_SLOT as f32);
+		assert_eq!(&attributes[4..6], &[0.0, 0.0]);
+		assert!(attributes[6] > 0.0 && attributes[7] > 0.0);
+	}
+
+	#[test]
+	fn generate_vertical_anchor() {
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(create_font());
+
+		// The glyphs are 10 tall and sit on the baseline, the second line's baseline is 12 below the first
+		let mut text = Text::new(font_handle, String::from("abc abc abc"));
+		text.max_width = 75.0;
+		let second_line_offset = 6 * 4 * ATTRIBUTES_PER_VERTEX;
+
+		let mut glyph_tops = |anchor| {
+			text.vertical_anchor = anchor;
+			text.generate(&fonts);
+			assert_eq!(text.line_count(), 2);
+			(text.attributes()[1], text.attributes()[second_line_offset + 1])
+		};
+
+		assert_eq!(glyph_tops(VerticalAnchor::Baseline), (-10.0, 2.0));
+		assert_eq!(glyph_tops(VerticalAnchor::Top), (0.0, 12.0));
+		assert_eq!(glyph_tops(VerticalAnchor::Middle), (-11.0, 1.0));
+		assert_eq!(glyph_tops(VerticalAnchor::Bottom), (-22.0, -10.0));
+	}
+
+	#[test]
+	fn generate_newlines() {
+		let text = create_text("ab\nc\n\nabc", f32::INFINITY);
+		assert_eq!(text.line_count(), 4);
+		assert_eq!(text.indices().len(), 6 * 6);
+
+		// The c starts the second line and the last line is three line heights down
+		assert_eq!(&text.attributes()[2 * 4 * ATTRIBUTES_PER_VERTEX..][..2], &[1.0, 2.0]);
+		assert_eq!(&text.attributes()[3 * 4 * ATTRIBUTES_PER_VERTEX..][..2], &[1.0, 26.0]);
+	}
+
+	#[test]
+	fn generate_tabs() {
+		// Tab stops are 4 spaces of 5 apart so the second column starts at 20 on both lines
+		let text = create_text("a\tb\nab\tb", f32::INFINITY);
+		assert_eq!(text.line_count(), 2);
+		assert_eq!(text.indices().len(), 5 * 6);
+		assert_eq!(&text.attributes()[4 * ATTRIBUTES_PER_VERTEX..][..2], &[21.0, -10.0]);
+		assert_eq!(&text.attributes()[4 * 4 * ATTRIBUTES_PER_VERTEX..][..2], &[21.0, 2.0]);
+
+		// A tab past a stop moves to the following one
+		let text = create_text("abc\tb", f32::INFINITY);
+		assert_eq!(text.attributes()[3 * 4 * ATTRIBUTES_PER_VERTEX], 41.0);
+	}
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
-use crate::{Font, pool::Pool};
+use crate::{Font, pool::{Handle, Pool}};
 
-use super::{ComponentList, Text};
+use super::{ComponentList, ComponentStore, Text};
 
 pub struct TextComponentList {
 	component_list: ComponentList<Text>,
@@ -21,6 +21,7 @@ impl TextComponentList {
 	}
 
 	pub fn remove(&mut self, entity: usize) {
+		self.dirty_list.retain(|e| *e != entity);
 		self.component_list.remove(entity);
 	}
 
@@ -28,9 +29,12 @@ impl TextComponentList {
 		self.component_list.borrow(entity)
 	}
 
+	// Any field may be changed so the text is always generated again, prefer set_string and set_font when possible
 	pub fn borrow_mut(&mut self, entity: usize) -> &mut Text {
 		self.dirty_list.push(entity);
-		self.component_list.borrow_mut(entity)
+		let text = self.component_list.borrow_mut(entity);
+		text.generate = true;
+		text
 	}
 
 	pub fn try_borrow(&self, entity: usize) -> Option<&Text> {
@@ -39,7 +43,29 @@ impl TextComponentList {
 
 	pub fn try_borrow_mut(&mut self, entity: usize) -> Option<&mut Text> {
 		self.dirty_list.push(entity);
-		self.component_list.try_borrow_mut(entity)
+		let text = self.component_list.try_borrow_mut(entity)?;
+		text.generate = true;
+		Some(text)
+	}
+
+	pub fn set_string(&mut self, entity: usize, string: String) {
+		let text = self.component_list.borrow_mut(entity);
+		let already_dirty = text.generate;
+		text.set_string(string);
+
+		if text.generate && !already_dirty {
+			self.dirty_list.push(entity);
+		}
+	}
+
+	pub fn set_font(&mut self, entity: usize, font: Handle) {
+		let text = self.component_list.borrow_mut(entity);
+		let already_dirty = text.generate;
+		text.set_font(font);
+
+		if text.generate && !already_dirty {
+			self.dirty_list.push(entity);
+		}
 	}
 
 	pub fn iter(&self) -> impl Iterator<Item = &(usize, Text)> {
@@ -47,46 +73,92 @@ impl TextComponentList {
 	}
 
 	pub fn generate_dirties(&mut self, fonts: &Pool<Font>) {
+		// An entity can be in the list more than once but it's only generated the first time
 		while let Some(entity) = self.dirty_list.pop() {
-			let text = self.component_list.borrow_mut(entity);
-			let font = fonts.borrow(text.font);
-
-			text.indices.clear();
-			text.attributes.clear();
+			if let Some(text) = self.component_list.try_borrow_mut(entity) {
+				if text.generate {
+					text.generate(fonts);
+				}
+			}
+		}
+	}
+}
 
-			let mut char_count = 0;
-			let mut cursor_pos = 0.0;
+impl ComponentStore for TextComponentList {
+	fn try_remove(&mut self, entity: usize) -> bool {
+		if self.component_list.try_borrow(entity).is_none() {
+			return false;
+		}
 
-			for c in text.string.chars() {
-				if c == ' ' {
-					cursor_pos += font.space_advance;
-					continue;
-				}
+		self.remove(entity);
+		true
+	}
+}
 
-				let glyph_index = font.glyphs.binary_search_by_key(&(c as u32), |g| g.char_code).unwrap();
-				let glyph = &font.glyphs[glyph_index];
-
-				let index_offset = char_count * 4;
-				let mut glyph_indices = vec![
-					index_offset, index_offset + 1, index_offset + 2,
-					index_offset, index_offset + 2, index_offset + 3
-				];
-				
-				let screen_pos_x = cursor_pos + glyph.bearing_x;
-
-				let mut glyph_attributes = vec![
-					screen_pos_x, glyph.bearing_y, glyph.position_x, glyph.position_y,
-					screen_pos_x + glyph.width, glyph.bearing_y, glyph.position_x + glyph.width, glyph.position_y,
-					screen_pos_x + glyph.width, glyph.bearing_y + glyph.height, glyph.position_x + glyph.width, glyph.position_y + glyph.height,
-					screen_pos_x, glyph.bearing_y + glyph.height, glyph.position_x, glyph.position_y + glyph.height
-				];
-
-				text.indices.append(&mut glyph_indices);
-				text.attributes.append(&mut glyph_attributes);
-
-				char_count += 1;
-				cursor_pos += glyph.advance;
-			}
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::font::Glyph;
+
+	fn create_font() -> Font {
+		let glyphs = "0123456789".chars().map(|c| Glyph {
+			char_code: c as u32,
+			position_x: 0.0,
+			position_y: 0.0,
+			width: 8.0,
+			height: 10.0,
+			bearing_x: 1.0,
+			bearing_y: -10.0,
+			advance: 10.0
+		}).collect();
+
+		Font {
+			fnt_path: String::new(),
+			atlas_width: 0,
+			atlas_height: 0,
+			space_advance: 5.0,
+			line_height: 12.0,
+			glyphs,
+			kerning_pairs: vec![],
+			submission_info: None
 		}
 	}
+
+	#[test]
+	fn set_string() {
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(create_font());
+
+		let mut text_components = TextComponentList::new();
+		text_components.add(0, Text::new(font_handle, String::from("10")));
+		text_components.generate_dirties(&fonts);
+		assert!(!text_components.borrow(0).generate);
+
+		// Setting the same string doesn't generate the text again
+		text_components.set_string(0, String::from("10"));
+		assert!(text_components.dirty_list.is_empty());
+
+		text_components.set_string(0, String::from("11"));
+		text_components.set_string(0, String::from("11"));
+		assert_eq!(text_components.dirty_list, vec![0]);
+
+		text_components.generate_dirties(&fonts);
+		assert!(!text_components.borrow(0).generate);
+		assert_eq!(text_components.borrow(0).string, "11");
+	}
+
+	#[test]
+	fn borrow_mut_generates_once() {
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(create_font());
+
+		let mut text_components = TextComponentList::new();
+		text_components.add(0, Text::new(font_handle, String::from("1")));
+		text_components.borrow_mut(0).string = String::from("2");
+		assert!(text_components.borrow(0).generate);
+
+		text_components.generate_dirties(&fonts);
+		assert!(text_components.dirty_list.is_empty());
+		assert_eq!(text_components.borrow(0).indices().len(), 6);
+	}
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use super::{ComponentList, Transform2D};
+use super::{ComponentList, ComponentStore, Transform2D};
 
 pub struct Transform2DComponentList {
 	component_list: ComponentList<Transform2D>,
@@ -60,7 +60,33 @@ impl Transform2DComponentList {
 		}
 	}
 
+	pub fn update_dirties(&mut self) {
+		if self.dirty_count == 0 {
+			return;
+		}
+
+		let dirty_entities: Vec<usize> = self.component_list.iter()
+			.filter(|(_, transform)| transform.dirty)
+			.map(|(entity, _)| *entity)
+			.collect();
+
+		for entity in dirty_entities {
+			self.update(entity);
+		}
+	}
+
 	pub fn check_for_dirties(&self) {
 		assert!(self.dirty_count == 0, "{} matrix/matrices have not been calculated", self.dirty_count);
 	}
+}
+
+impl ComponentStore for Transform2DComponentList {
+	fn try_remove(&mut self, entity: usize) -> bool {
+		if self.component_list.try_borrow(entity).is_none() {
+			return false;
+		}
+
+		self.remove(entity);
+		true
+	}
 }
\ No newline at end of file
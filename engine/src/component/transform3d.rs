@@ -3,6 +3,8 @@ use crate::math::{matrix4, Matrix4, Quaternion, Vector3, quaternion, vector3};
 pub struct Transform3D {
 	pub(crate) parent_entity: Option<usize>,
 	pub(crate) child_entities: Vec<usize>,
+	// Set when mutably borrowed through the component list, so assigning the public fields directly still marks it
+	// The local matrix is only recomposed while this is set
 	pub(crate) dirty: bool,
 	pub position: Vector3,
 	pub orientation: Quaternion,
@@ -37,6 +39,25 @@ impl Transform3D {
 		self.local_matrix.compose(&self.position, &self.orientation, &self.scale);
 	}
 
+	// The local +Z, +X and +Y axes rotated by the orientation, scale and parents aren't applied
+	pub fn forward(&self) -> Vector3 {
+		let mut forward = vector3::UNIT_Z;
+		forward.apply_quaternion(&self.orientation);
+		forward
+	}
+
+	pub fn right(&self) -> Vector3 {
+		let mut right = vector3::UNIT_X;
+		right.apply_quaternion(&self.orientation);
+		right
+	}
+
+	pub fn up(&self) -> Vector3 {
+		let mut up = vector3::UNIT_Y;
+		up.apply_quaternion(&self.orientation);
+		up
+	}
+
 	pub fn translate_on_axis(&mut self, mut axis: Vector3, distance: f32) {
 		axis.apply_quaternion(&self.orientation);
 		self.position += axis * distance;
@@ -71,4 +92,134 @@ impl Transform3D {
 	pub fn rotate_z(&mut self, angle: f32) {
 		self.rotate_on_axis(&vector3::UNIT_Z, angle);
 	}
+
+	// Position and scale are interpolated linearly and orientation spherically, t isn't clamped so it can extrapolate
+	pub fn lerp(&self, other: &Transform3D, t: f32) -> Transform3D {
+		let mut transform = Transform3D::new();
+
+		transform.position = self.position;
+		transform.position.lerp(&other.position, t);
+
+		transform.orientation = self.orientation;
+		transform.orientation.slerp(&other.orientation, t);
+
+		transform.scale = self.scale;
+		transform.scale.lerp(&other.scale, t);
+
+		transform
+	}
+
+	// Points the local +Z axis at the target
+	pub fn look_at(&mut self, target: &Vector3, up: &Vector3) {
+		let mut z_axis = target - self.position;
+
+		if z_axis.length_sq() == 0.0 {
+			return;
+		}
+
+		z_axis.normalize();
+
+		let mut x_axis = *up;
+		x_axis.cross(&z_axis);
+
+		// The direction is parallel to up so use whichever unit axis is furthest from the direction instead
+		if x_axis.length_sq() < 1e-12 {
+			x_axis = if z_axis.x.abs() < 0.9 { vector3::UNIT_X } else { vector3::UNIT_Y };
+			x_axis.cross(&z_axis);
+		}
+
+		x_axis.normalize();
+
+		let mut y_axis = z_axis;
+		y_axis.cross(&x_axis);
+
+		let rotation_matrix = Matrix4::new([
+			[x_axis.x, y_axis.x, z_axis.x, 0.0],
+			[x_axis.y, y_axis.y, z_axis.y, 0.0],
+			[x_axis.z, y_axis.z, z_axis.z, 0.0],
+			[0.0, 0.0, 0.0, 1.0]]);
+
+		self.orientation.set_from_rotation_matrix(&rotation_matrix);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::assert_approx_eq;
+	use std::f32::consts::PI;
+
+	#[test]
+	fn lerp_translation() {
+		let a = Transform3D::new();
+		let mut b = Transform3D::new();
+		b.position.set(2.0, 4.0, -6.0);
+		b.scale.set(3.0, 1.0, 1.0);
+
+		assert_eq!(a.lerp(&b, 0.0).position, vector3::ZERO);
+		assert_eq!(a.lerp(&b, 1.0).position, b.position);
+		assert_eq!(a.lerp(&b, 0.5).position, Vector3::new(1.0, 2.0, -3.0));
+		assert_eq!(a.lerp(&b, 0.5).scale, Vector3::new(2.0, 1.0, 1.0));
+		assert_eq!(a.lerp(&b, 2.0).position, Vector3::new(4.0, 8.0, -12.0));
+	}
+
+	#[test]
+	fn lerp_rotation() {
+		let a = Transform3D::new();
+		let mut b = Transform3D::new();
+		b.rotate_y(PI / 2.0);
+
+		assert_approx_eq(&a.lerp(&b, 0.0).orientation, &a.orientation, 1e-6);
+		assert_approx_eq(&a.lerp(&b, 1.0).orientation, &b.orientation, 1e-6);
+
+		let mut expected = quaternion::ZERO;
+		expected.set_from_axis_angle(&vector3::UNIT_Y, PI / 4.0);
+		assert_approx_eq(&a.lerp(&b, 0.5).orientation, &expected, 1e-6);
+		assert_eq!(a.lerp(&b, 0.5).position, vector3::ZERO);
+	}
+
+	#[test]
+	fn look_at() {
+		let mut t = Transform3D::new();
+		t.look_at(&Vector3::new(0.0, 0.0, 1.0), &vector3::UNIT_Y);
+		assert_approx_eq(&t.orientation, &quaternion::ZERO, 1e-6);
+
+		// Looking down -Z is a half turn around Y
+		t.look_at(&Vector3::new(0.0, 0.0, -1.0), &vector3::UNIT_Y);
+		let mut expected = quaternion::ZERO;
+		expected.set_from_axis_angle(&vector3::UNIT_Y, PI);
+		assert_approx_eq(&t.orientation, &expected, 1e-6);
+
+		t.position.set(1.0, 0.0, 0.0);
+		t.look_at(&Vector3::new(3.0, 0.0, 0.0), &vector3::UNIT_Y);
+		assert_approx_eq(&t.forward(), &vector3::UNIT_X, 1e-6);
+	}
+
+	#[test]
+	fn look_at_parallel_to_up() {
+		let mut t = Transform3D::new();
+		t.look_at(&Vector3::new(0.0, 5.0, 0.0), &vector3::UNIT_Y);
+
+		let q = t.orientation;
+		assert!(!q.x.is_nan() && !q.y.is_nan() && !q.z.is_nan() && !q.w.is_nan());
+		assert!((q.length() - 1.0).abs() < 1e-6);
+
+		let mut forward = vector3::UNIT_Z;
+		forward.apply_quaternion(&q);
+		assert_approx_eq(&forward, &vector3::UNIT_Y, 1e-6);
+	}
+
+	#[test]
+	fn basis_vectors() {
+		let mut t = Transform3D::new();
+		assert_eq!(t.forward(), vector3::UNIT_Z);
+		assert_eq!(t.right(), vector3::UNIT_X);
+		assert_eq!(t.up(), vector3::UNIT_Y);
+
+		// A quarter turn around Y points forward along +X and right along -Z
+		t.rotate_y(PI / 2.0);
+		assert_approx_eq(&t.forward(), &vector3::UNIT_X, 1e-6);
+		assert_approx_eq(&t.right(), &Vector3::new(0.0, 0.0, -1.0), 1e-6);
+		assert_approx_eq(&t.up(), &vector3::UNIT_Y, 1e-6);
+	}
 }
\ No newline at end of file
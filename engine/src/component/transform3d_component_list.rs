@@ -1,4 +1,4 @@
-use super::{ComponentList, Transform3D};
+use super::{ComponentList, ComponentStore, Transform3D};
 
 pub struct Transform3DComponentList {
 	component_list: ComponentList<Transform3D>,
@@ -80,13 +80,13 @@ impl Transform3DComponentList {
 			let transform = self.component_list.borrow_mut(entity);
 			entities_to_visit.extend_from_slice(&transform.child_entities);
 
+			// Only a changed transform needs its local matrix recomposed, the global matrix still follows the parent
 			if transform.dirty {
+				transform.update_local_matrix();
 				transform.dirty = false;
 				self.dirty_count -= 1;
 			}
 
-			transform.update_local_matrix();
-
 			if let Some(parent_entity) = transform.parent_entity {
 				let parent_global_matrix = self.component_list.borrow(parent_entity).global_matrix;
 				let child_transform = self.component_list.borrow_mut(entity);
@@ -98,7 +98,152 @@ impl Transform3DComponentList {
 		}
 	}
 
+	// Updates every transform from the roots down so parents are always updated before their children
+	pub fn update_all(&mut self) {
+		let root_entities: Vec<usize> = self.component_list.iter()
+			.filter(|(_, transform)| transform.parent_entity.is_none())
+			.map(|(entity, _)| *entity)
+			.collect();
+
+		for entity in root_entities {
+			self.update(entity);
+		}
+	}
+
+	// Walks the graph from the roots and only recalculates the dirty transforms and everything below them
+	pub fn update_dirties(&mut self) {
+		if self.dirty_count == 0 {
+			return;
+		}
+
+		let mut entities_to_visit: Vec<(usize, bool)> = self.component_list.iter()
+			.filter(|(_, transform)| transform.parent_entity.is_none())
+			.map(|(entity, _)| (*entity, false))
+			.collect();
+
+		while let Some((entity, parent_updated)) = entities_to_visit.pop() {
+			let transform = self.component_list.borrow_mut(entity);
+			let updated = transform.dirty || parent_updated;
+
+			if updated {
+				if transform.dirty {
+					transform.update_local_matrix();
+					transform.dirty = false;
+					self.dirty_count -= 1;
+				}
+
+				if let Some(parent_entity) = transform.parent_entity {
+					let parent_global_matrix = self.component_list.borrow(parent_entity).global_matrix;
+					let child_transform = self.component_list.borrow_mut(entity);
+					child_transform.global_matrix = parent_global_matrix * child_transform.local_matrix;
+				}
+				else {
+					transform.global_matrix = transform.local_matrix;
+				}
+			}
+
+			let transform = self.component_list.borrow(entity);
+			entities_to_visit.extend(transform.child_entities.iter().map(|child_entity| (*child_entity, updated)));
+		}
+	}
+
 	pub fn check_for_dirties(&self) {
 		assert!(self.dirty_count == 0, "{} global matrix/matrices have not been calculated", self.dirty_count);
 	}
+}
+
+impl ComponentStore for Transform3DComponentList {
+	fn try_remove(&mut self, entity: usize) -> bool {
+		if self.component_list.try_borrow(entity).is_none() {
+			return false;
+		}
+
+		self.remove(entity);
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::{Vector3, assert_approx_eq};
+
+	fn create_chain(transform3d_components: &mut Transform3DComponentList) -> [usize; 3] {
+		let mut car = Transform3D::new();
+		car.position.set(10.0, 0.0, 0.0);
+		car.rotate_y(0.5);
+		transform3d_components.add(0, car);
+
+		let mut axle = Transform3D::new();
+		axle.position.set(0.0, -1.0, 2.0);
+		axle.scale.set(2.0, 1.0, 1.0);
+		transform3d_components.add_child(0, 1, axle);
+
+		let mut wheel = Transform3D::new();
+		wheel.position.set(1.0, 0.0, 0.0);
+		wheel.rotate_x(0.3);
+		transform3d_components.add_child(1, 2, wheel);
+
+		[0, 1, 2]
+	}
+
+	#[test]
+	fn global_matrix_chain() {
+		let mut transform3d_components = Transform3DComponentList::new();
+		let [car, axle, wheel] = create_chain(&mut transform3d_components);
+
+		let expected = transform3d_components.borrow(car).local_matrix * transform3d_components.borrow(axle).local_matrix * transform3d_components.borrow(wheel).local_matrix;
+		assert_approx_eq(&transform3d_components.borrow(wheel).global_matrix, &expected, 1e-5);
+	}
+
+	#[test]
+	fn update_moves_children() {
+		let mut transform3d_components = Transform3DComponentList::new();
+		let [car, axle, wheel] = create_chain(&mut transform3d_components);
+
+		transform3d_components.borrow_mut(car).position += Vector3::new(0.0, 0.0, 5.0);
+		transform3d_components.borrow_mut(wheel).rotate_z(1.0);
+		transform3d_components.update_all();
+		transform3d_components.check_for_dirties();
+
+		let expected = transform3d_components.borrow(car).local_matrix * transform3d_components.borrow(axle).local_matrix * transform3d_components.borrow(wheel).local_matrix;
+		assert_approx_eq(&transform3d_components.borrow(wheel).global_matrix, &expected, 1e-5);
+		assert_approx_eq(&transform3d_components.borrow(car).global_matrix, &transform3d_components.borrow(car).local_matrix, 0.0);
+	}
+
+	#[test]
+	fn update_dirties_refreshes_moved_node() {
+		let mut transform3d_components = Transform3DComponentList::new();
+		let [car, axle, wheel] = create_chain(&mut transform3d_components);
+
+		transform3d_components.borrow_mut(axle).position += Vector3::new(0.0, 3.0, 0.0);
+		transform3d_components.update_dirties();
+		transform3d_components.check_for_dirties();
+
+		let axle_expected = transform3d_components.borrow(car).local_matrix * transform3d_components.borrow(axle).local_matrix;
+		assert_approx_eq(&transform3d_components.borrow(axle).global_matrix, &axle_expected, 1e-5);
+
+		let wheel_expected = axle_expected * transform3d_components.borrow(wheel).local_matrix;
+		assert_approx_eq(&transform3d_components.borrow(wheel).global_matrix, &wheel_expected, 1e-5);
+	}
+
+	#[test]
+	fn update_skips_unchanged_local_matrix() {
+		let mut transform3d_components = Transform3DComponentList::new();
+		let [car, axle, _] = create_chain(&mut transform3d_components);
+		let axle_local_matrix = transform3d_components.borrow(axle).local_matrix;
+
+		// Mutating without going through borrow_mut doesn't mark the transform dirty so its local matrix isn't recomposed
+		transform3d_components.component_list.borrow_mut(axle).position.set(5.0, 5.0, 5.0);
+		transform3d_components.borrow_mut(car).position.set(0.0, 0.0, 0.0);
+		transform3d_components.update(car);
+		assert_eq!(transform3d_components.borrow(axle).local_matrix, axle_local_matrix);
+
+		let expected = transform3d_components.borrow(car).local_matrix * axle_local_matrix;
+		assert_approx_eq(&transform3d_components.borrow(axle).global_matrix, &expected, 1e-5);
+
+		transform3d_components.borrow_mut(axle);
+		transform3d_components.update(car);
+		assert_ne!(transform3d_components.borrow(axle).local_matrix, axle_local_matrix);
+	}
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-pub const MAX_ENTITY_COUNT: usize = 500;
+use crate::component::{ComponentList, ComponentStore, Transform3DComponentList};
 
 pub struct EntityManager {
 	free_entities: Vec<usize>,
@@ -14,7 +14,6 @@ impl EntityManager {
 	}
 
 	pub fn create(&mut self) -> usize {
-		assert!(self.alive_entity_count != MAX_ENTITY_COUNT, "Cannot create entity because the limit of {} has been reached", MAX_ENTITY_COUNT);
 		self.alive_entity_count += 1;
 		
 		if let Some(entity) = self.free_entities.pop() {
@@ -25,8 +24,136 @@ impl EntityManager {
 		}
 	}
 
-	pub fn destroy(&mut self, entity: usize) {
+	// Component lists are owned by the user so every list the entity could have a component in must be passed
+	pub fn destroy(&mut self, entity: usize, component_stores: &mut [&mut dyn ComponentStore]) {
+		for component_store in component_stores {
+			component_store.try_remove(entity);
+		}
+
 		self.free_entities.push(entity);
 		self.alive_entity_count -= 1;
 	}
+
+	// Destroys the entity and every entity below it in the transform hierarchy, the entity is detached from its parent
+	pub fn destroy_hierarchy(&mut self, entity: usize, transform3d_components: &mut Transform3DComponentList, component_stores: &mut [&mut dyn ComponentStore]) {
+		let mut entities = vec![];
+		let mut entities_to_visit = vec![entity];
+
+		while let Some(entity) = entities_to_visit.pop() {
+			entities_to_visit.extend_from_slice(&transform3d_components.borrow(entity).child_entities);
+			entities.push(entity);
+		}
+
+		transform3d_components.remove(entity);
+
+		for entity in entities {
+			self.destroy(entity, component_stores);
+		}
+	}
+
+	// Iterates the entities which have both components, the smaller list is iterated and the other is probed
+	pub fn iter2<'a, A, B>(a_components: &'a ComponentList<A>, b_components: &'a ComponentList<B>) -> Box<dyn Iterator<Item = (usize, &'a A, &'a B)> + 'a> {
+		if a_components.len() <= b_components.len() {
+			Box::new(a_components.iter().filter_map(move |(entity, a)| Some((*entity, a, b_components.try_borrow(*entity)?))))
+		}
+		else {
+			Box::new(b_components.iter().filter_map(move |(entity, b)| Some((*entity, a_components.try_borrow(*entity)?, b))))
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::component::Transform3D;
+
+	#[test]
+	fn iter2() {
+		let mut entity_manager = EntityManager::new();
+		let mut positions = ComponentList::<f32>::new();
+		let mut velocities = ComponentList::<i32>::new();
+
+		let moving = entity_manager.create();
+		let stationary = entity_manager.create();
+		let only_velocity = entity_manager.create();
+		let moving_2 = entity_manager.create();
+
+		positions.add(moving, 1.0);
+		positions.add(stationary, 2.0);
+		positions.add(moving_2, 3.0);
+		velocities.add(only_velocity, 10);
+		velocities.add(moving_2, 30);
+		velocities.add(moving, 20);
+
+		let mut joined: Vec<(usize, f32, i32)> = EntityManager::iter2(&positions, &velocities).map(|(e, p, v)| (e, *p, *v)).collect();
+		joined.sort_by_key(|(e, _, _)| *e);
+		assert_eq!(joined, vec![(moving, 1.0, 20), (moving_2, 3.0, 30)]);
+
+		// The same entities are found when the first list is larger
+		velocities.remove(only_velocity);
+		positions.add(only_velocity, 4.0);
+		let mut joined: Vec<usize> = EntityManager::iter2(&positions, &velocities).map(|(e, _, _)| e).collect();
+		joined.sort();
+		assert_eq!(joined, vec![moving, moving_2]);
+	}
+
+	#[test]
+	fn destroy() {
+		let mut entity_manager = EntityManager::new();
+		let mut a_components = ComponentList::<f32>::new();
+		let mut b_components = ComponentList::<i32>::new();
+		let mut c_components = ComponentList::<String>::new();
+		let mut d_components = ComponentList::<bool>::new();
+
+		let other = entity_manager.create();
+		let enemy = entity_manager.create();
+		a_components.add(other, 1.0);
+		a_components.add(enemy, 2.0);
+		b_components.add(enemy, 3);
+		c_components.add(enemy, String::from("enemy"));
+		d_components.add(other, true);
+
+		// The entity doesn't have a d component so that list is skipped
+		entity_manager.destroy(enemy, &mut [&mut a_components, &mut b_components, &mut c_components, &mut d_components]);
+		assert!(a_components.try_borrow(enemy).is_none());
+		assert!(b_components.try_borrow(enemy).is_none());
+		assert!(c_components.try_borrow(enemy).is_none());
+		assert_eq!(*a_components.borrow(other), 1.0);
+		assert!(*d_components.borrow(other));
+
+		assert_eq!(entity_manager.create(), enemy);
+	}
+
+	#[test]
+	fn destroy_hierarchy() {
+		let mut entity_manager = EntityManager::new();
+		let mut transform3d_components = Transform3DComponentList::new();
+		let mut health_components = ComponentList::<u32>::new();
+
+		let world = entity_manager.create();
+		let vehicle = entity_manager.create();
+		let wheel = entity_manager.create();
+		let turret = entity_manager.create();
+		transform3d_components.add(world, Transform3D::new());
+		transform3d_components.add_child(world, vehicle, Transform3D::new());
+		transform3d_components.add_child(vehicle, wheel, Transform3D::new());
+		transform3d_components.add_child(vehicle, turret, Transform3D::new());
+		health_components.add(vehicle, 100);
+		health_components.add(turret, 50);
+
+		entity_manager.destroy_hierarchy(vehicle, &mut transform3d_components, &mut [&mut health_components]);
+
+		for entity in &[vehicle, wheel, turret] {
+			assert!(transform3d_components.try_borrow(*entity).is_none());
+			assert!(health_components.try_borrow(*entity).is_none());
+		}
+
+		assert!(transform3d_components.borrow(world).child_entities.is_empty());
+		assert!(health_components.is_empty());
+
+		// All three ids are recycled
+		let mut recycled = vec![entity_manager.create(), entity_manager.create(), entity_manager.create()];
+		recycled.sort();
+		assert_eq!(recycled, vec![vehicle, wheel, turret]);
+	}
 }
\ No newline at end of file
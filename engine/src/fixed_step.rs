@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+// Accumulates real time and runs an update a whole number of times with the same step so simulation doesn't depend on the frame rate
+// The time left over is carried to the next frame, alpha is how far it is towards the next step for interpolating rendered state
+pub struct FixedStep {
+	step: Duration,
+	accumulator: Duration
+}
+
+impl FixedStep {
+	pub fn new(step: Duration) -> Self {
+		assert!(step > Duration::new(0, 0), "Cannot create fixed step because the step is zero");
+
+		Self {
+			step,
+			accumulator: Duration::new(0, 0)
+		}
+	}
+
+	pub fn step(&self) -> Duration {
+		self.step
+	}
+
+	// Returns the number of times the update was called
+	pub fn advance<F: FnMut(&Duration)>(&mut self, delta: Duration, mut update: F) -> u32 {
+		self.accumulator += delta;
+		let mut steps = 0;
+
+		while self.accumulator >= self.step {
+			update(&self.step);
+			self.accumulator -= self.step;
+			steps += 1;
+		}
+
+		steps
+	}
+
+	pub fn alpha(&self) -> f32 {
+		(self.accumulator.as_secs_f64() / self.step.as_secs_f64()) as f32
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn carries_leftover_time() {
+		let step = Duration::from_millis(20);
+		let mut fixed_step = FixedStep::new(step);
+		let mut updates = vec![];
+
+		// One and a half steps runs one update and carries half a step over
+		assert_eq!(fixed_step.advance(Duration::from_millis(30), |dt| updates.push(*dt)), 1);
+		assert_eq!(updates, vec![step]);
+		assert_eq!(fixed_step.alpha(), 0.5);
+
+		// The carried half completes the next step
+		assert_eq!(fixed_step.advance(Duration::from_millis(10), |dt| updates.push(*dt)), 1);
+		assert_eq!(updates, vec![step, step]);
+		assert_eq!(fixed_step.alpha(), 0.0);
+	}
+
+	#[test]
+	fn multiple_and_no_steps() {
+		let mut fixed_step = FixedStep::new(Duration::from_millis(20));
+		let mut count = 0;
+
+		assert_eq!(fixed_step.advance(Duration::from_millis(65), |_| count += 1), 3);
+		assert_eq!(fixed_step.advance(Duration::from_millis(10), |_| count += 1), 0);
+		assert_eq!(count, 3);
+		assert_eq!(fixed_step.alpha(), 0.75);
+	}
+}
\ No newline at end of file
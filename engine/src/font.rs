@@ -1,6 +1,16 @@
-use std::{path, fs, io, ptr, ffi::CString, slice, io::{Read, Write, Seek}, convert::TryInto};
+use std::{fmt, path, fs, io, ptr, ffi::CString, slice, io::{Read, Write, Seek}, convert::TryInto, ops::RangeInclusive};
 use freetype::freetype::*;
 
+// Printable characters only, the space is handled separately by its advance
+pub const BASIC_LATIN: RangeInclusive<u32> = 33..=126;
+pub const LATIN_1_SUPPLEMENT: RangeInclusive<u32> = 161..=255;
+
+// Cached font files start with the magic and the format version, bump the version whenever the layout changes
+const FNT_MAGIC: [u8; 4] = *b"VEFT";
+const FNT_VERSION: u32 = 1;
+pub(crate) const FNT_ATLAS_OFFSET: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Glyph {
 	pub char_code: u32,
 	pub position_x: f32,
@@ -12,6 +22,13 @@ pub struct Glyph {
 	pub advance: f32
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KerningPair {
+	pub left: u32,
+	pub right: u32,
+	pub amount: f32
+}
+
 struct UnplacedGlyph {
 	char_code: u32,
 	bitmap: Vec<Vec<u8>>,
@@ -22,6 +39,39 @@ struct UnplacedGlyph {
 	advance: f32
 }
 
+#[derive(Debug)]
+pub enum FontError {
+	NotFound(String),
+	Truncated(String),
+	InvalidMagic(String),
+	VersionMismatch { path: String, expected: u32, found: u32 },
+	Io(String, io::Error)
+}
+
+impl FontError {
+	fn from_io(path: &str, error: io::Error) -> Self {
+		match error.kind() {
+			io::ErrorKind::NotFound => Self::NotFound(path.to_owned()),
+			io::ErrorKind::UnexpectedEof => Self::Truncated(path.to_owned()),
+			_ => Self::Io(path.to_owned(), error)
+		}
+	}
+}
+
+impl fmt::Display for FontError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::NotFound(path) => write!(f, "Cannot load font {}, the file does not exist", path),
+			Self::Truncated(path) => write!(f, "Cannot load font {}, the file ends unexpectedly", path),
+			Self::InvalidMagic(path) => write!(f, "Cannot load font {}, the file is not a font file, delete it to regenerate", path),
+			Self::VersionMismatch { path, expected, found } => write!(f, "Cannot load font {}, the file is version {} but version {} is required, delete it to regenerate", path, found, expected),
+			Self::Io(path, error) => write!(f, "Cannot load font {}: {}", path, error)
+		}
+	}
+}
+
+impl std::error::Error for FontError {}
+
 pub(crate) struct SubmissionInfo {
 	pub generation: usize,
 	pub index: usize
@@ -32,51 +82,120 @@ pub struct Font {
 	pub atlas_width: usize,
 	pub atlas_height: usize,
 	pub space_advance: f32,
+	pub line_height: f32,
 	pub glyphs: Vec<Glyph>,
+	pub kerning_pairs: Vec<KerningPair>,
 	pub(crate) submission_info: Option<SubmissionInfo>
 }
 
 impl Font {
-	pub fn new(file_path: &str, size: u32) -> Self {
+	pub fn new(file_path: &str, size: u32) -> Result<Self, FontError> {
+		Self::new_with_char_ranges(file_path, size, &[BASIC_LATIN])
+	}
+
+	pub fn new_with_char_ranges(file_path: &str, size: u32, char_ranges: &[RangeInclusive<u32>]) -> Result<Self, FontError> {
+		assert!(!char_ranges.is_empty(), "Cannot create font because no character ranges were specified");
+
 		let file_path_buf = path::PathBuf::from(file_path);
 		let file_stem = file_path_buf.file_stem().unwrap().to_str().unwrap();
 
-		let fnt_path = format!("target/fonts/{}{}.fnt", file_stem, size);
+		// Fonts with other character ranges are cached separately
+		let fnt_path = if char_ranges == [BASIC_LATIN] {
+			format!("target/fonts/{}{}.fnt", file_stem, size)
+		}
+		else {
+			let ranges: Vec<String> = char_ranges.iter().map(|r| format!("{:x}-{:x}", r.start(), r.end())).collect();
+			format!("target/fonts/{}{}_{}.fnt", file_stem, size, ranges.join("_"))
+		};
 
-		let (atlas_width, atlas_height, space_advance, glyphs) = match fs::File::open(fnt_path.to_owned()) {
+		let (atlas_width, atlas_height, space_advance, glyphs, kerning_pairs) = match fs::File::open(fnt_path.to_owned()) {
 			Ok(file) => {
 				println!("Loading font {} at size {}", file_stem, size);
 
-				Self::load_fnt(file)
+				Self::load_fnt(&fnt_path, file)?
 			},
 			Err(e) => {
 				if e.kind() == io::ErrorKind::NotFound {
+					if !file_path_buf.is_file() {
+						return Err(FontError::NotFound(file_path.to_owned()));
+					}
+
 					println!("Generating font {} at size {}", file_stem, size);
 
 					let ttf_path = CString::new(file_path).unwrap();
-					let (space_advance, unplaced_glyphs) = Self::load_ttf(ttf_path, size);
+					let (space_advance, unplaced_glyphs, kerning_pairs) = Self::load_ttf(ttf_path, size, char_ranges);
 					let (atlas, placed_glyphs) = Self::create_atlas(unplaced_glyphs);
-					Self::save_fnt(&fnt_path, &atlas, space_advance, &placed_glyphs);
+					Self::save_fnt(&fnt_path, &atlas, space_advance, &placed_glyphs, &kerning_pairs).map_err(|error| FontError::Io(fnt_path.clone(), error))?;
 
-					(atlas[0].len(), atlas.len(), space_advance, placed_glyphs)
+					// A preview of the atlas is written next to the font file so it can be inspected in any image viewer
+					Self::save_atlas_png(&path::Path::new(&fnt_path).with_extension("png"), &atlas);
+
+					(atlas[0].len(), atlas.len(), space_advance, placed_glyphs, kerning_pairs)
 				}
 				else {
-					panic!("Cannot load or generate font\n{}", e);
+					return Err(FontError::Io(fnt_path, e));
 				}
 			}
 		};
 
-		Self {
+		let line_height = Self::calculate_line_height(&glyphs);
+
+		Ok(Self {
 			fnt_path,
 			atlas_width,
 			atlas_height,
 			space_advance,
+			line_height,
 			glyphs,
+			kerning_pairs,
 			submission_info: None
+		})
+	}
+
+	// The distance from the highest ascender to the lowest descender
+	// Writes the atlas as it's stored in the font file, which is exactly what gets submitted, to a grayscale PNG
+	pub fn dump_atlas_png(&self, path: &str) -> io::Result<()> {
+		let mut atlas = vec![0u8; self.atlas_width * self.atlas_height];
+		let mut file = fs::File::open(&self.fnt_path)?;
+		file.seek(io::SeekFrom::Start(FNT_ATLAS_OFFSET as u64))?;
+		file.read_exact(&mut atlas)?;
+
+		Self::write_atlas_png(path::Path::new(path), self.atlas_width, self.atlas_height, &atlas)
+	}
+
+	fn calculate_line_height(glyphs: &[Glyph]) -> f32 {
+		let mut ascent: f32 = 0.0;
+		let mut descent: f32 = 0.0;
+
+		for glyph in glyphs {
+			ascent = ascent.max(-glyph.bearing_y);
+			descent = descent.max(glyph.bearing_y + glyph.height);
+		}
+
+		ascent + descent
+	}
+
+	pub fn find_glyph(&self, c: char) -> &Glyph {
+		self.try_find_glyph(c).unwrap_or_else(|| panic!("Cannot find glyph for character {:?} because it's not in the font", c))
+	}
+
+	pub fn try_find_glyph(&self, c: char) -> Option<&Glyph> {
+		let glyph_index = self.glyphs.binary_search_by_key(&(c as u32), |g| g.char_code).ok()?;
+		Some(&self.glyphs[glyph_index])
+	}
+
+	pub fn has_glyph(&self, c: char) -> bool {
+		self.try_find_glyph(c).is_some()
+	}
+
+	pub fn find_kerning(&self, left: char, right: char) -> f32 {
+		match self.kerning_pairs.binary_search_by_key(&(left as u32, right as u32), |p| (p.left, p.right)) {
+			Ok(index) => self.kerning_pairs[index].amount,
+			Err(_) => 0.0
 		}
 	}
 
-	fn load_ttf(ttf_path: CString, size: u32) -> (f32, Vec<UnplacedGlyph>) {
+	fn load_ttf(ttf_path: CString, size: u32, char_ranges: &[RangeInclusive<u32>]) -> (f32, Vec<UnplacedGlyph>, Vec<KerningPair>) {
 		let mut library: FT_Library = ptr::null_mut();
 		let error = unsafe { FT_Init_FreeType(&mut library) };
 		assert_eq!(error, 0, "Cannot initialize Freetype, error code {}", error);
@@ -93,11 +212,16 @@ impl Font {
 		assert_eq!(error, 0, "Cannot load the space glyph, error code {}", error);
 		let space_advance = unsafe { (*(*face).glyph).advance.x / 64 } as f32;
 
-		let char_codes = 33..127;
+		// Code points the font doesn't have a glyph for are skipped
+		let mut char_codes: Vec<u32> = char_ranges.iter().cloned().flatten().collect();
+		char_codes.sort_unstable();
+		char_codes.dedup();
+		char_codes.retain(|&char_code| unsafe { FT_Get_Char_Index(face, char_code as FT_ULong) } != 0);
+
 		let mut unplaced_glyphs: Vec<UnplacedGlyph> = Vec::with_capacity(char_codes.len());
 
-		for char_code in char_codes {
-			let glyph_index = unsafe { FT_Get_Char_Index(face, char_code) };
+		for &char_code in &char_codes {
+			let glyph_index = unsafe { FT_Get_Char_Index(face, char_code as FT_ULong) };
 			let error = unsafe { FT_Load_Glyph(face, glyph_index, 0) };
 			assert_eq!(error, 0, "Cannot load glyph, error code {}", error);
 
@@ -127,7 +251,29 @@ impl Font {
 			});
 		}
 
-		(space_advance, unplaced_glyphs)
+		// Only the pairs which adjust the spacing are kept, they're sorted by left then right char code
+		let mut kerning_pairs = vec![];
+
+		for &left in &char_codes {
+			let left_glyph_index = unsafe { FT_Get_Char_Index(face, left as FT_ULong) };
+
+			for &right in &char_codes {
+				let right_glyph_index = unsafe { FT_Get_Char_Index(face, right as FT_ULong) };
+				let mut kerning = FT_Vector { x: 0, y: 0 };
+				let error = unsafe { FT_Get_Kerning(face, left_glyph_index, right_glyph_index, FT_Kerning_Mode::FT_KERNING_DEFAULT as u32, &mut kerning) };
+				assert_eq!(error, 0, "Cannot get kerning, error code {}", error);
+
+				if kerning.x != 0 {
+					kerning_pairs.push(KerningPair {
+						left,
+						right,
+						amount: (kerning.x / 64) as f32
+					});
+				}
+			}
+		}
+
+		(space_advance, unplaced_glyphs, kerning_pairs)
 	}
 
 	fn create_atlas(unplaced_glyphs: Vec<UnplacedGlyph>) -> (Vec<Vec<u8>>, Vec<Glyph>) {
@@ -243,14 +389,39 @@ impl Font {
 		}
 	}
 
-	fn save_fnt(path: &str, atlas: &[Vec<u8>], space_advance: f32, glyphs: &[Glyph]) {
+	fn save_fnt(path: &str, atlas: &[Vec<u8>], space_advance: f32, glyphs: &[Glyph], kerning_pairs: &[KerningPair]) -> io::Result<()> {
+		let buffer = Self::serialize_fnt(atlas, space_advance, glyphs, kerning_pairs);
+
+		fs::create_dir_all("target/fonts")?;
+		let mut file = fs::File::create(path)?;
+		file.write_all(&buffer)
+	}
+
+	fn save_atlas_png(path: &path::Path, atlas: &[Vec<u8>]) {
+		Self::write_atlas_png(path, atlas[0].len(), atlas.len(), &atlas.concat()).unwrap();
+	}
+
+	fn write_atlas_png(path: &path::Path, width: usize, height: usize, atlas: &[u8]) -> io::Result<()> {
+		let file = fs::File::create(path)?;
+		let mut encoder = png::Encoder::new(io::BufWriter::new(file), width as u32, height as u32);
+		encoder.set_color(png::ColorType::Grayscale);
+		encoder.set_depth(png::BitDepth::Eight);
+
+		let mut writer = encoder.write_header()?;
+		writer.write_image_data(atlas)?;
+		Ok(())
+	}
+
+	fn serialize_fnt(atlas: &[Vec<u8>], space_advance: f32, glyphs: &[Glyph], kerning_pairs: &[KerningPair]) -> Vec<u8> {
 		let atlas_width = atlas[0].len();
 		let atlas_height = atlas.len();
 		let atlas_padding_size = (4 - (atlas_width * atlas_height) % 4) % 4;
 		let glyph_count = glyphs.len();
 
-		let mut buffer: Vec<u8> = Vec::with_capacity(16 + atlas_width * atlas_height + atlas_padding_size + 32 * glyph_count);
+		let mut buffer: Vec<u8> = Vec::with_capacity(FNT_ATLAS_OFFSET + 8 + atlas_width * atlas_height + atlas_padding_size + 32 * glyph_count + 4 + 12 * kerning_pairs.len());
 
+		buffer.extend_from_slice(&FNT_MAGIC);
+		buffer.extend_from_slice(&FNT_VERSION.to_le_bytes());
 		buffer.extend_from_slice(&(atlas_width as u32).to_le_bytes());
 		buffer.extend_from_slice(&(atlas_height as u32).to_le_bytes());
 
@@ -273,32 +444,56 @@ impl Font {
 			buffer.extend_from_slice(&glyph.advance.to_le_bytes());
 		}
 
-		fs::create_dir_all("target/fonts").unwrap();
-		let mut file = fs::File::create(path).unwrap();
-		file.write_all(&buffer).unwrap();
+		// The kerning table follows the glyph table
+		buffer.extend_from_slice(&(kerning_pairs.len() as u32).to_le_bytes());
+
+		for kerning_pair in kerning_pairs {
+			buffer.extend_from_slice(&kerning_pair.left.to_le_bytes());
+			buffer.extend_from_slice(&kerning_pair.right.to_le_bytes());
+			buffer.extend_from_slice(&kerning_pair.amount.to_le_bytes());
+		}
+
+		buffer
 	}
 
-	fn load_fnt(mut file: fs::File) -> (usize, usize, f32, Vec<Glyph>) {
+	fn load_fnt<R: Read + Seek>(fnt_path: &str, mut file: R) -> Result<(usize, usize, f32, Vec<Glyph>, Vec<KerningPair>), FontError> {
+		let read_error = |error| FontError::from_io(fnt_path, error);
 		let mut bytes = [0u8; 4];
 
-		file.read_exact(&mut bytes).unwrap();
+		file.read_exact(&mut bytes).map_err(read_error)?;
+		if bytes != FNT_MAGIC {
+			return Err(FontError::InvalidMagic(fnt_path.to_owned()));
+		}
+
+		file.read_exact(&mut bytes).map_err(read_error)?;
+		let version = u32::from_le_bytes(bytes);
+		if version != FNT_VERSION {
+			return Err(FontError::VersionMismatch { path: fnt_path.to_owned(), expected: FNT_VERSION, found: version });
+		}
+
+		file.read_exact(&mut bytes).map_err(read_error)?;
 		let atlas_width = u32::from_le_bytes(bytes) as usize;
 
-		file.read_exact(&mut bytes).unwrap();
+		file.read_exact(&mut bytes).map_err(read_error)?;
 		let atlas_height = u32::from_le_bytes(bytes) as usize;
 
 		let atlas_padding_size = (4 - (atlas_width * atlas_height) % 4) % 4;
 
-		file.seek(io::SeekFrom::Current((atlas_width * atlas_height + atlas_padding_size) as i64)).unwrap();
+		// Seeking past the end succeeds, a short atlas is caught by the next read
+		file.seek(io::SeekFrom::Current((atlas_width * atlas_height + atlas_padding_size) as i64)).map_err(read_error)?;
 
-		file.read_exact(&mut bytes).unwrap();
+		file.read_exact(&mut bytes).map_err(read_error)?;
 		let space_advance = f32::from_le_bytes(bytes);
 
-		file.read_exact(&mut bytes).unwrap();
+		file.read_exact(&mut bytes).map_err(read_error)?;
 		let glyph_count = u32::from_le_bytes(bytes) as usize;
 
 		let mut buffer: Vec<u8> = Vec::with_capacity(glyph_count * 32);
-		file.read_to_end(&mut buffer).unwrap();
+		file.read_to_end(&mut buffer).map_err(read_error)?;
+
+		if buffer.len() < glyph_count * 32 {
+			return Err(FontError::Truncated(fnt_path.to_owned()));
+		}
 
 		let mut glyphs: Vec<Glyph> = Vec::with_capacity(glyph_count);
 		let get_bytes_at = |offset| buffer[offset..offset + 4].try_into().unwrap();
@@ -327,6 +522,231 @@ impl Font {
 			});
 		}
 
-		(atlas_width, atlas_height, space_advance, glyphs)
+		// Files generated before kerning was supported end after the glyph table
+		let kerning_table_offset = glyph_count * 32;
+		let mut kerning_pairs = vec![];
+
+		if buffer.len() >= kerning_table_offset + 4 {
+			let kerning_pair_count = u32::from_le_bytes(get_bytes_at(kerning_table_offset)) as usize;
+
+			if buffer.len() < kerning_table_offset + 4 + kerning_pair_count * 12 {
+				return Err(FontError::Truncated(fnt_path.to_owned()));
+			}
+
+			kerning_pairs.reserve(kerning_pair_count);
+
+			for kerning_pair_index in 0..kerning_pair_count {
+				let offset = kerning_table_offset + 4 + kerning_pair_index * 12;
+
+				kerning_pairs.push(KerningPair {
+					left: u32::from_le_bytes(get_bytes_at(offset)),
+					right: u32::from_le_bytes(get_bytes_at(offset + 4)),
+					amount: f32::from_le_bytes(get_bytes_at(offset + 8))
+				});
+			}
+		}
+
+		Ok((atlas_width, atlas_height, space_advance, glyphs, kerning_pairs))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fnt_kerning_round_trip() {
+		let atlas = vec![vec![0u8; 3]; 2];
+
+		let glyphs = vec![Glyph {
+			char_code: 'A' as u32,
+			position_x: 0.0,
+			position_y: 0.0,
+			width: 3.0,
+			height: 2.0,
+			bearing_x: 1.0,
+			bearing_y: -2.0,
+			advance: 4.0
+		}];
+
+		let kerning_pairs = vec![
+			KerningPair { left: 'A' as u32, right: 'V' as u32, amount: -2.0 },
+			KerningPair { left: 'T' as u32, right: 'o' as u32, amount: -1.0 },
+			KerningPair { left: 'V' as u32, right: 'A' as u32, amount: -2.0 }
+		];
+
+		let buffer = Font::serialize_fnt(&atlas, 5.0, &glyphs, &kerning_pairs);
+		let (atlas_width, atlas_height, space_advance, loaded_glyphs, loaded_kerning_pairs) = Font::load_fnt("test.fnt", io::Cursor::new(buffer)).unwrap();
+
+		assert_eq!(atlas_width, 3);
+		assert_eq!(atlas_height, 2);
+		assert_eq!(space_advance, 5.0);
+		assert_eq!(loaded_glyphs.len(), 1);
+		assert_eq!(loaded_glyphs[0].advance, 4.0);
+		assert_eq!(loaded_kerning_pairs, kerning_pairs);
+	}
+
+	#[test]
+	fn fnt_is_little_endian() {
+		let atlas = vec![vec![7u8; 3]; 2];
+
+		let glyphs = vec![Glyph {
+			char_code: 0x1F600,
+			position_x: 1.0,
+			position_y: 2.0,
+			width: 3.0,
+			height: 4.0,
+			bearing_x: -1.0,
+			bearing_y: -4.0,
+			advance: 5.5
+		}];
+
+		let buffer = Font::serialize_fnt(&atlas, 6.0, &glyphs, &[]);
+
+		// Magic and version, width and height, then the atlas padded to 4 bytes
+		assert_eq!(&buffer[0..4], b"VEFT");
+		assert_eq!(&buffer[4..8], &[1, 0, 0, 0]);
+		assert_eq!(&buffer[8..16], &[3, 0, 0, 0, 2, 0, 0, 0]);
+		assert_eq!(&buffer[16..24], &[7, 7, 7, 7, 7, 7, 0, 0]);
+		assert_eq!(&buffer[24..28], &6.0f32.to_bits().to_le_bytes());
+		assert_eq!(&buffer[28..32], &[1, 0, 0, 0]);
+		assert_eq!(&buffer[32..36], &[0x00, 0xF6, 0x01, 0x00]);
+
+		let (atlas_width, atlas_height, space_advance, loaded_glyphs, _) = Font::load_fnt("test.fnt", io::Cursor::new(buffer)).unwrap();
+		assert_eq!(atlas_width, 3);
+		assert_eq!(atlas_height, 2);
+		assert_eq!(space_advance, 6.0);
+		assert_eq!(loaded_glyphs, glyphs);
+	}
+
+	#[test]
+	fn atlas_png_dimensions() {
+		let atlas = vec![vec![0u8, 64, 128, 255, 32]; 3];
+		let path = std::env::temp_dir().join("atlas_png_dimensions.png");
+		Font::save_atlas_png(&path, &atlas);
+
+		let decoder = png::Decoder::new(fs::File::open(&path).unwrap());
+		let (info, mut reader) = decoder.read_info().unwrap();
+		assert_eq!(info.width, 5);
+		assert_eq!(info.height, 3);
+		assert_eq!(info.color_type, png::ColorType::Grayscale);
+
+		let mut pixels = vec![0; info.buffer_size()];
+		reader.next_frame(&mut pixels).unwrap();
+		assert_eq!(pixels, atlas.concat());
+
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn dump_atlas_png() {
+		let atlas = vec![vec![0u8, 64, 128, 255, 32], vec![1u8, 2, 3, 4, 5]];
+		let fnt_path = std::env::temp_dir().join("dump_atlas_png.fnt");
+		let png_path = std::env::temp_dir().join("dump_atlas_png.png");
+		fs::write(&fnt_path, Font::serialize_fnt(&atlas, 5.0, &[], &[])).unwrap();
+
+		let font = Font {
+			fnt_path: fnt_path.to_str().unwrap().to_owned(),
+			atlas_width: 5,
+			atlas_height: 2,
+			space_advance: 5.0,
+			line_height: 0.0,
+			glyphs: vec![],
+			kerning_pairs: vec![],
+			submission_info: None
+		};
+
+		font.dump_atlas_png(png_path.to_str().unwrap()).unwrap();
+
+		let decoder = png::Decoder::new(fs::File::open(&png_path).unwrap());
+		let (info, mut reader) = decoder.read_info().unwrap();
+		assert_eq!(info.width as usize, font.atlas_width);
+		assert_eq!(info.height as usize, font.atlas_height);
+
+		let mut pixels = vec![0; info.buffer_size()];
+		reader.next_frame(&mut pixels).unwrap();
+		assert_eq!(pixels, atlas.concat());
+
+		fs::remove_file(fnt_path).unwrap();
+		fs::remove_file(png_path).unwrap();
+	}
+
+	#[test]
+	fn generate_latin_1_supplement() {
+		let font = Font::new_with_char_ranges("../game/res/roboto.ttf", 16, &[BASIC_LATIN, LATIN_1_SUPPLEMENT]).unwrap();
+		assert_eq!(font.glyphs.len(), 94 + 95);
+		assert_eq!(font.find_glyph('é').char_code, 0xE9);
+		assert!(font.find_glyph('é').advance > 0.0);
+	}
+
+	#[test]
+	fn fnt_without_kerning_table() {
+		// Fonts cached before kerning was added end after the glyph table
+		let atlas = vec![vec![0u8; 4]; 1];
+		let mut buffer = Font::serialize_fnt(&atlas, 5.0, &[], &[]);
+		buffer.truncate(buffer.len() - 4);
+
+		let (_, _, _, glyphs, kerning_pairs) = Font::load_fnt("test.fnt", io::Cursor::new(buffer)).unwrap();
+		assert!(glyphs.is_empty());
+		assert!(kerning_pairs.is_empty());
+	}
+
+	#[test]
+	fn truncated_fnt() {
+		let atlas = vec![vec![0u8; 3]; 2];
+		let glyph = Glyph { char_code: 'A' as u32, position_x: 0.0, position_y: 0.0, width: 3.0, height: 2.0, bearing_x: 1.0, bearing_y: -2.0, advance: 4.0 };
+		let kerning_pair = KerningPair { left: 'A' as u32, right: 'V' as u32, amount: -2.0 };
+		let buffer = Font::serialize_fnt(&atlas, 5.0, &[glyph], &[kerning_pair]);
+
+		// Cut inside the header, the atlas, the glyph table and the kerning table
+		for &len in &[2, 14, 20, 48, buffer.len() - 1] {
+			let result = Font::load_fnt("truncated.fnt", io::Cursor::new(&buffer[..len]));
+			assert!(matches!(result, Err(FontError::Truncated(ref path)) if path == "truncated.fnt"), "Expected a truncated error at length {}", len);
+		}
+	}
+
+	#[test]
+	fn missing_ttf() {
+		match Font::new("missing.ttf", 14) {
+			Err(FontError::NotFound(path)) => assert_eq!(path, "missing.ttf"),
+			_ => panic!("Expected a not found error")
+		}
+	}
+
+	#[test]
+	fn fnt_header_round_trip() {
+		let atlas = vec![vec![9u8; 2]; 2];
+		let buffer = Font::serialize_fnt(&atlas, 3.0, &[], &[]);
+		assert_eq!(&buffer[FNT_ATLAS_OFFSET..FNT_ATLAS_OFFSET + 4], &[9, 9, 9, 9]);
+
+		let (atlas_width, atlas_height, space_advance, _, _) = Font::load_fnt("test.fnt", io::Cursor::new(buffer)).unwrap();
+		assert_eq!(atlas_width, 2);
+		assert_eq!(atlas_height, 2);
+		assert_eq!(space_advance, 3.0);
+	}
+
+	#[test]
+	fn fnt_wrong_version() {
+		let atlas = vec![vec![0u8; 2]; 2];
+		let mut buffer = Font::serialize_fnt(&atlas, 3.0, &[], &[]);
+		buffer[4..8].copy_from_slice(&(FNT_VERSION + 1).to_le_bytes());
+
+		match Font::load_fnt("old.fnt", io::Cursor::new(buffer)) {
+			Err(FontError::VersionMismatch { path, expected, found }) => {
+				assert_eq!(path, "old.fnt");
+				assert_eq!(expected, FNT_VERSION);
+				assert_eq!(found, FNT_VERSION + 1);
+			},
+			_ => panic!("Expected a version mismatch error")
+		}
+	}
+
+	#[test]
+	fn fnt_without_header() {
+		// Files cached before the header was added start with the atlas width
+		let atlas = vec![vec![0u8; 2]; 2];
+		let buffer = Font::serialize_fnt(&atlas, 3.0, &[], &[]);
+		let result = Font::load_fnt("old.fnt", io::Cursor::new(&buffer[8..]));
+		assert!(matches!(result, Err(FontError::InvalidMagic(_))));
 	}
 }
\ No newline at end of file
@@ -1,39 +1,302 @@
-use crate::math::{Box3, Vector3};
+use std::{fmt, mem::size_of_val, path::Path, slice};
+use ash::vk;
+use crate::math::{Box3, Matrix4, Vector3, box3, vector3};
 
-#[derive(Clone, Copy)]
+// The number of vertices a 16 bit index can address, geometries with more vertices store 32 bit indices
+pub const MAX_U16_VERTICES: usize = u16::MAX as usize + 1;
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Topology {
 	Triangle,
 	Line
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum Indices {
+	U16(Vec<u16>),
+	U32(Vec<u32>)
+}
+
+impl Indices {
+	pub fn len(&self) -> usize {
+		match self {
+			Self::U16(indices) => indices.len(),
+			Self::U32(indices) => indices.len()
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn get(&self, i: usize) -> Option<u32> {
+		match self {
+			Self::U16(indices) => indices.get(i).map(|&index| index as u32),
+			Self::U32(indices) => indices.get(i).copied()
+		}
+	}
+
+	pub fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+		match self {
+			Self::U16(indices) => Box::new(indices.iter().map(|&index| index as u32)),
+			Self::U32(indices) => Box::new(indices.iter().copied())
+		}
+	}
+
+	// The size in bytes of a single index
+	pub fn index_size(&self) -> usize {
+		match self {
+			Self::U16(_) => 2,
+			Self::U32(_) => 4
+		}
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		match self {
+			Self::U16(indices) => unsafe { slice::from_raw_parts(indices.as_ptr() as *const u8, size_of_val(indices.as_slice())) },
+			Self::U32(indices) => unsafe { slice::from_raw_parts(indices.as_ptr() as *const u8, size_of_val(indices.as_slice())) }
+		}
+	}
+
+	pub(crate) fn index_type(&self) -> vk::IndexType {
+		match self {
+			Self::U16(_) => vk::IndexType::UINT16,
+			Self::U32(_) => vk::IndexType::UINT32
+		}
+	}
+}
+
 pub(crate) struct SubmissionInfo {
 	pub generation: usize,
 	pub index_array_offset: usize,
 	pub attributes_array_offset: usize
 }
 
+#[derive(Debug)]
+pub enum LoadError {
+	Import(gltf::Error),
+	MissingPrimitives(String),
+	UnsupportedMode(String),
+	MissingPositions(String),
+	MissingNormals(String),
+	AttributeCountMismatch(String),
+	IndexOutOfRange(String)
+}
+
+impl fmt::Display for LoadError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Import(error) => write!(f, "Cannot import glTF file: {}", error),
+			Self::MissingPrimitives(name) => write!(f, "Cannot load mesh {}, no primitives found", name),
+			Self::UnsupportedMode(name) => write!(f, "Cannot load mesh {}, only triangle primitives are supported", name),
+			Self::MissingPositions(name) => write!(f, "Cannot load mesh {}, no positions attribute found", name),
+			Self::MissingNormals(name) => write!(f, "Cannot load mesh {}, no normals attribute found", name),
+			Self::AttributeCountMismatch(name) => write!(f, "Cannot load mesh {}, position and normal counts differ", name),
+			Self::IndexOutOfRange(name) => write!(f, "Cannot load mesh {}, an index is out of range for its vertices", name)
+		}
+	}
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<gltf::Error> for LoadError {
+	fn from(error: gltf::Error) -> Self {
+		Self::Import(error)
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GeometryError {
+	MisalignedAttributes { length: usize, stride: usize },
+	IndexOutOfRange { index: u32, vertex_count: usize }
+}
+
+impl fmt::Display for GeometryError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::MisalignedAttributes { length, stride } => write!(f, "Cannot create geometry, {} attributes is not a multiple of the vertex stride {}", length, stride),
+			Self::IndexOutOfRange { index, vertex_count } => write!(f, "Cannot create geometry, index {} is out of range for {} vertices", index, vertex_count)
+		}
+	}
+}
+
+impl std::error::Error for GeometryError {}
+
+// Triangle vertices are a position and normal, followed by an RGB color when the geometry has vertex colors
+// Line vertices are only a position
 pub struct Geometry3D {
-	indices: Vec<u16>,
+	indices: Indices,
 	attributes: Vec<f32>,
 	topology: Topology,
+	vertex_colors: bool,
 	bounding_box: Box3,
+	bounding_sphere: (Vector3, f32),
 	pub(crate) submission_info: Option<SubmissionInfo>
 }
 
 impl Geometry3D {
 	pub fn new(indices: Vec<u16>, attributes: Vec<f32>, topology: Topology) -> Self {
-		let bounding_box = Self::calculate_bounding_box(&attributes, topology);
+		Self::from_parts(Indices::U16(indices), attributes, topology, false)
+	}
+
+	// For geometries with more vertices than a 16 bit index can address
+	pub fn new_u32(indices: Vec<u32>, attributes: Vec<f32>, topology: Topology) -> Self {
+		Self::from_parts(Indices::U32(indices), attributes, topology, false)
+	}
+
+	pub fn new_with_vertex_colors(indices: Vec<u16>, attributes: Vec<f32>) -> Self {
+		Self::from_parts(Indices::U16(indices), attributes, Topology::Triangle, true)
+	}
+
+	// Checks the data up front, a bad index would otherwise only show up as garbage or a device loss when drawn
+	pub fn try_new(indices: Vec<u16>, attributes: Vec<f32>, topology: Topology) -> Result<Self, GeometryError> {
+		let stride = Self::calculate_attribute_stride(topology, false);
+
+		if attributes.len() % stride != 0 {
+			return Err(GeometryError::MisalignedAttributes { length: attributes.len(), stride });
+		}
+
+		let vertex_count = attributes.len() / stride;
+
+		if let Some(&index) = indices.iter().find(|&&index| index as usize >= vertex_count) {
+			return Err(GeometryError::IndexOutOfRange { index: index as u32, vertex_count });
+		}
+
+		Ok(Self::new(indices, attributes, topology))
+	}
+
+	fn from_parts(indices: Indices, attributes: Vec<f32>, topology: Topology, vertex_colors: bool) -> Self {
+		let stride = Self::calculate_attribute_stride(topology, vertex_colors);
+		let bounding_box = Self::calculate_bounding_box(&attributes, stride);
+		let bounding_sphere = Self::calculate_bounding_sphere(&attributes, stride);
 
 		Self {
 			indices,
 			attributes,
 			topology,
+			vertex_colors,
 			bounding_box,
+			bounding_sphere,
 			submission_info: None
 		}
 	}
 
-	pub fn indices(&self) -> &[u16] {
+	// Each primitive becomes a geometry, 32 bit indices are kept when the accessor uses them or there are more than MAX_U16_VERTICES vertices
+	pub fn load_gltf<P: AsRef<Path>>(path: P) -> Result<Vec<Self>, LoadError> {
+		let (document, buffers, _) = gltf::import(path)?;
+		let mut geometries = vec![];
+
+		for mesh in document.meshes() {
+			let name = mesh.name().unwrap_or("unnamed");
+
+			for primitive in mesh.primitives() {
+				geometries.push(Self::from_gltf_primitive(name, &primitive, &buffers)?);
+			}
+		}
+
+		Ok(geometries)
+	}
+
+	pub(crate) fn from_gltf_primitive(name: &str, primitive: &gltf::Primitive, buffers: &[gltf::buffer::Data]) -> Result<Self, LoadError> {
+		if primitive.mode() != gltf::mesh::Mode::Triangles {
+			return Err(LoadError::UnsupportedMode(name.to_owned()));
+		}
+
+		let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+		// Attributes
+		let positions: Vec<[f32; 3]> = reader.read_positions()
+			.ok_or_else(|| LoadError::MissingPositions(name.to_owned()))?
+			.collect();
+
+		// Indices, non indexed primitives draw every vertex in order
+		let indices = match reader.read_indices() {
+			Some(gltf::mesh::util::ReadIndices::U32(indices)) => Indices::U32(indices.collect()),
+			Some(indices) if positions.len() > MAX_U16_VERTICES => Indices::U32(indices.into_u32().collect()),
+			Some(indices) => Indices::U16(indices.into_u32().map(|index| index as u16).collect()),
+			None if positions.len() > MAX_U16_VERTICES => Indices::U32((0..positions.len() as u32).collect()),
+			None => Indices::U16((0..positions.len() as u16).collect())
+		};
+
+		if indices.iter().any(|index| index as usize >= positions.len()) {
+			return Err(LoadError::IndexOutOfRange(name.to_owned()));
+		}
+
+		let normals: Vec<[f32; 3]> = reader.read_normals()
+			.ok_or_else(|| LoadError::MissingNormals(name.to_owned()))?
+			.collect();
+
+		if positions.len() != normals.len() {
+			return Err(LoadError::AttributeCountMismatch(name.to_owned()));
+		}
+
+		let mut attributes: Vec<f32> = Vec::with_capacity(positions.len() * 6);
+
+		for (position, normal) in positions.iter().zip(normals.iter()) {
+			attributes.extend_from_slice(position);
+			attributes.extend_from_slice(normal);
+		}
+
+		Ok(Self::from_parts(indices, attributes, Topology::Triangle, false))
+	}
+
+	// Positions are transformed by each matrix and normals by the inverse transpose of its upper 3x3 so they stay perpendicular to surfaces
+	pub fn merge(geometries: &[(&Geometry3D, &Matrix4)]) -> Self {
+		assert!(!geometries.is_empty(), "Cannot merge geometries because none were given");
+
+		let topology = *geometries[0].0.topology();
+		let vertex_colors = geometries[0].0.has_vertex_colors();
+		let stride = Self::calculate_attribute_stride(topology, vertex_colors);
+
+		// The merged indices only need 32 bits when one of the geometries already uses them or there are too many vertices for 16
+		let vertex_count: usize = geometries.iter().map(|(geometry, _)| geometry.attributes.len() / stride).sum();
+		let wide = vertex_count > MAX_U16_VERTICES || geometries.iter().any(|(geometry, _)| matches!(geometry.indices, Indices::U32(_)));
+
+		let mut indices: Vec<u32> = vec![];
+		let mut attributes = vec![];
+
+		for (geometry, matrix) in geometries {
+			assert!(*geometry.topology() == topology, "Cannot merge geometries because their topologies differ");
+			assert!(geometry.has_vertex_colors() == vertex_colors, "Cannot merge geometries because only some have vertex colors");
+
+			let index_offset = (attributes.len() / stride) as u32;
+			indices.extend(geometry.indices.iter().map(|index| index + index_offset));
+
+			let mut normal_matrix = matrix.truncate();
+			normal_matrix.invert();
+			normal_matrix.transpose();
+			let n = &normal_matrix.elements;
+
+			for vertex in geometry.attributes.chunks_exact(stride) {
+				let position = matrix.transform_point(&Vector3::new(vertex[0], vertex[1], vertex[2]));
+				attributes.extend_from_slice(&[position.x, position.y, position.z]);
+
+				if topology == Topology::Triangle {
+					let mut normal = Vector3::new(
+						n[0][0] * vertex[3] + n[0][1] * vertex[4] + n[0][2] * vertex[5],
+						n[1][0] * vertex[3] + n[1][1] * vertex[4] + n[1][2] * vertex[5],
+						n[2][0] * vertex[3] + n[2][1] * vertex[4] + n[2][2] * vertex[5]);
+					normal.normalize();
+					attributes.extend_from_slice(&[normal.x, normal.y, normal.z]);
+				}
+
+				if vertex_colors {
+					attributes.extend_from_slice(&vertex[6..9]);
+				}
+			}
+		}
+
+		let indices = if wide {
+			Indices::U32(indices)
+		}
+		else {
+			Indices::U16(indices.into_iter().map(|index| index as u16).collect())
+		};
+
+		Self::from_parts(indices, attributes, topology, vertex_colors)
+	}
+
+	pub fn indices(&self) -> &Indices {
 		&self.indices
 	}
 
@@ -45,42 +308,85 @@ impl Geometry3D {
 		&self.topology
 	}
 
+	pub fn has_vertex_colors(&self) -> bool {
+		self.vertex_colors
+	}
+
+	// The number of floats per vertex
+	pub fn attribute_stride(&self) -> usize {
+		Self::calculate_attribute_stride(self.topology, self.vertex_colors)
+	}
+
 	pub fn bounding_box(&self) -> &Box3 {
 		&self.bounding_box
 	}
 
+	// The center and radius, calculated along with the bounding box whenever the attributes change
+	pub fn bounding_sphere(&self) -> (Vector3, f32) {
+		self.bounding_sphere
+	}
+
+	// The new attributes don't have vertex colors
 	pub fn set(&mut self, indices: Vec<u16>, attributes: Vec<f32>, topology: Topology) {
-		self.indices = indices;
+		self.indices = Indices::U16(indices);
 		self.attributes = attributes;
 		self.topology = topology;
-		self.bounding_box = Self::calculate_bounding_box(&self.attributes, self.topology);
+		self.vertex_colors = false;
+
+		let stride = self.attribute_stride();
+		self.bounding_box = Self::calculate_bounding_box(&self.attributes, stride);
+		self.bounding_sphere = Self::calculate_bounding_sphere(&self.attributes, stride);
 		self.submission_info = None;
 	}
 
-	fn calculate_bounding_box(attributes: &[f32], topology: Topology) -> Box3 {
-		let mut min = Vector3::from_scalar(f32::INFINITY);
-		let mut max = Vector3::from_scalar(f32::NEG_INFINITY);
+	fn calculate_attribute_stride(topology: Topology, vertex_colors: bool) -> usize {
+		match (topology, vertex_colors) {
+			(Topology::Triangle, false) => 6,
+			(Topology::Triangle, true) => 9,
+			(Topology::Line, _) => 3
+		}
+	}
+
+	fn calculate_bounding_box(attributes: &[f32], stride: usize) -> Box3 {
+		let mut bounding_box = box3::EMPTY;
+
+		for position in attributes.chunks_exact(stride) {
+			bounding_box.expand_by_point(&Vector3::new(position[0], position[1], position[2]));
+		}
+
+		bounding_box
+	}
+
+	// Ritter's algorithm, the sphere isn't minimal but it's at most around 5% larger
+	fn calculate_bounding_sphere(attributes: &[f32], stride: usize) -> (Vector3, f32) {
+		let positions: Vec<Vector3> = attributes.chunks_exact(stride).map(|p| Vector3::new(p[0], p[1], p[2])).collect();
 
-		let stride = match topology {
-			Topology::Triangle => 6,
-			Topology::Line => 3
+		if positions.is_empty() {
+			return (vector3::ZERO, 0.0);
+		}
+
+		let farthest_from = |point: &Vector3| -> Vector3 {
+			*positions.iter().max_by(|a, b| (*a - point).length_sq().partial_cmp(&(*b - point).length_sq()).unwrap()).unwrap()
 		};
 
-		for i in 0..(attributes.len() / stride) {
-			let x = attributes[i * stride];
-			let y = attributes[i * stride + 1];
-			let z = attributes[i * stride + 2];
+		// Start with the sphere around two points which are roughly the farthest apart
+		let a = farthest_from(&positions[0]);
+		let b = farthest_from(&a);
+		let mut center = (a + b) * 0.5;
+		let mut radius = (b - a).length() * 0.5;
 
-			min.x = min.x.min(x);
-			min.y = min.y.min(y);
-			min.z = min.z.min(z);
+		// Grow the sphere just enough to include each point outside of it
+		for position in &positions {
+			let distance = (position - center).length();
 
-			max.x = max.x.max(x);
-			max.y = max.y.max(y);
-			max.z = max.z.max(z);
+			if distance > radius {
+				let new_radius = (radius + distance) * 0.5;
+				center += (position - center) * ((new_radius - radius) / distance);
+				radius = new_radius;
+			}
 		}
 
-		Box3::new(min, max)
+		(center, radius)
 	}
 
 	pub fn create_plane() -> Self {
@@ -197,4 +503,180 @@ impl Geometry3D {
 
 		self.set(indices, attributes, Topology::Line);
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::{matrix4, quaternion};
+
+	#[test]
+	fn bounding_box() {
+		let geometry = Geometry3D::create_box();
+		assert_eq!(geometry.bounding_box(), &Box3::new(Vector3::from_scalar(-1.0), Vector3::from_scalar(1.0)));
+
+		let geometry = Geometry3D::create_plane();
+		assert_eq!(geometry.bounding_box(), &Box3::new(Vector3::new(-1.0, 0.0, -1.0), Vector3::new(1.0, 0.0, 1.0)));
+	}
+
+	#[test]
+	fn try_new() {
+		let attributes = vec![0.0; 18];
+		assert!(Geometry3D::try_new(vec![0, 1, 2], attributes.clone(), Topology::Triangle).is_ok());
+
+		let result = Geometry3D::try_new(vec![0, 1, 3], attributes, Topology::Triangle);
+		assert_eq!(result.err(), Some(GeometryError::IndexOutOfRange { index: 3, vertex_count: 3 }));
+
+		let result = Geometry3D::try_new(vec![0, 1], vec![0.0; 10], Topology::Triangle);
+		assert_eq!(result.err(), Some(GeometryError::MisalignedAttributes { length: 10, stride: 6 }));
+
+		let result = Geometry3D::try_new(vec![0, 1], vec![0.0; 6], Topology::Line);
+		assert!(result.is_ok());
+	}
+
+	#[test]
+	fn bounding_sphere() {
+		let geometry = Geometry3D::create_box();
+		let (center, radius) = geometry.bounding_sphere();
+
+		// The smallest sphere around the box has a radius of sqrt(3)
+		assert!(radius >= 3.0f32.sqrt() - 1e-5);
+		assert!(radius <= 3.0f32.sqrt() * 1.05);
+
+		for x in &[-1.0, 1.0] {
+			for y in &[-1.0, 1.0] {
+				for z in &[-1.0, 1.0] {
+					let corner = Vector3::new(*x, *y, *z);
+					assert!((corner - center).length() <= radius + 1e-5);
+				}
+			}
+		}
+
+		let geometry = Geometry3D::new(vec![], vec![], Topology::Triangle);
+		assert_eq!(geometry.bounding_sphere(), (vector3::ZERO, 0.0));
+	}
+
+	#[test]
+	fn merge() {
+		let box_geometry = Geometry3D::create_box();
+		let first_matrix = matrix4::IDENTITY;
+		let mut second_matrix = matrix4::IDENTITY;
+		second_matrix.compose(&Vector3::new(10.0, 0.0, 0.0), &quaternion::ZERO, &Vector3::new(1.0, 2.0, 1.0));
+
+		let merged = Geometry3D::merge(&[(&box_geometry, &first_matrix), (&box_geometry, &second_matrix)]);
+		assert_eq!(merged.indices().len(), 2 * box_geometry.indices().len());
+		assert_eq!(merged.attributes().len(), 2 * box_geometry.attributes().len());
+
+		// The second box's indices are offset past the first box's 24 vertices
+		assert!(merged.indices().iter().zip(box_geometry.indices().iter()).all(|(a, b)| a == b));
+		assert_eq!(merged.indices().get(36), box_geometry.indices().get(0).map(|index| index + 24));
+		assert!(matches!(merged.indices(), Indices::U16(_)));
+
+		// The second box's first vertex is (1, 1, 1) with the normal (0, 1, 0) before the transform
+		let second = &merged.attributes()[24 * 6..];
+		assert_eq!(&second[..6], &[11.0, 2.0, 1.0, 0.0, 1.0, 0.0]);
+
+		assert_eq!(merged.bounding_box(), &Box3::new(Vector3::new(-1.0, -2.0, -1.0), Vector3::new(11.0, 2.0, 1.0)));
+	}
+
+	#[test]
+	fn merge_normals() {
+		// Scaling x by 2 turns a diagonal normal toward x less than the surface does
+		let geometry = Geometry3D::new(vec![0, 0, 0], vec![0.0, 0.0, 0.0, 0.6, 0.8, 0.0], Topology::Triangle);
+		let mut matrix = matrix4::IDENTITY;
+		matrix.compose(&vector3::ZERO, &quaternion::ZERO, &Vector3::new(2.0, 1.0, 1.0));
+
+		let merged = Geometry3D::merge(&[(&geometry, &matrix)]);
+		let normal = Vector3::new(merged.attributes()[3], merged.attributes()[4], merged.attributes()[5]);
+		let mut expected = Vector3::new(0.3, 0.8, 0.0);
+		expected.normalize();
+
+		assert!((normal - expected).length() < 1e-6);
+	}
+
+	#[test]
+	fn merge_u32() {
+		let small = Geometry3D::new(vec![0, 1, 2], vec![0.0; 18], Topology::Triangle);
+		let wide = Geometry3D::new_u32(vec![2, 1, 0], vec![0.0; 18], Topology::Triangle);
+
+		let merged = Geometry3D::merge(&[(&small, &matrix4::IDENTITY), (&wide, &matrix4::IDENTITY)]);
+		assert_eq!(merged.indices(), &Indices::U32(vec![0, 1, 2, 5, 4, 3]));
+
+		// Too many vertices for 16 bit indices between them
+		let large = Geometry3D::new(vec![0, 1, 2], vec![0.0; MAX_U16_VERTICES * 6], Topology::Triangle);
+		let merged = Geometry3D::merge(&[(&small, &matrix4::IDENTITY), (&large, &matrix4::IDENTITY)]);
+		assert_eq!(merged.indices(), &Indices::U32(vec![0, 1, 2, 3, 4, 5]));
+	}
+
+	#[test]
+	fn indices_as_bytes() {
+		let indices = Indices::U16(vec![1, 2, 3]);
+		assert_eq!(indices.as_bytes().len(), 6);
+		assert_eq!(indices.index_type(), ash::vk::IndexType::UINT16);
+
+		let indices = Indices::U32(vec![1, 2, 3]);
+		assert_eq!(indices.as_bytes().len(), 12);
+		assert_eq!(indices.index_type(), ash::vk::IndexType::UINT32);
+	}
+
+	#[test]
+	fn vertex_colors() {
+		let attributes = vec![
+			0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0,
+			2.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0,
+			0.0, 2.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0
+		];
+
+		let geometry = Geometry3D::new_with_vertex_colors(vec![0, 1, 2], attributes);
+		assert!(geometry.has_vertex_colors());
+		assert_eq!(geometry.attribute_stride(), 9);
+
+		// The colors aren't mistaken for positions
+		assert_eq!(geometry.bounding_box(), &Box3::new(vector3::ZERO, Vector3::new(2.0, 2.0, 0.0)));
+
+		// Merging keeps the colors after the transformed normals
+		let mut matrix = matrix4::IDENTITY;
+		matrix.compose(&Vector3::new(0.0, 0.0, 5.0), &quaternion::ZERO, &Vector3::from_scalar(1.0));
+		let merged = Geometry3D::merge(&[(&geometry, &matrix), (&geometry, &matrix4::IDENTITY)]);
+		assert!(merged.has_vertex_colors());
+		assert_eq!(&merged.attributes()[9..18], &[2.0, 0.0, 5.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+		assert_eq!(merged.indices(), &Indices::U16(vec![0, 1, 2, 3, 4, 5]));
+
+		assert_eq!(Geometry3D::create_box().attribute_stride(), 6);
+		assert_eq!(Geometry3D::create_axis_helper().attribute_stride(), 3);
+	}
+
+	#[test]
+	fn load_gltf() {
+		let geometries = Geometry3D::load_gltf("../game/res/monkey.gltf").unwrap();
+		assert_eq!(geometries.len(), 1);
+		assert_eq!(geometries[0].indices().len(), 2904);
+		assert_eq!(geometries[0].attributes().len(), 1966 * 6);
+	}
+
+	#[test]
+	fn load_gltf_missing_file() {
+		assert!(matches!(Geometry3D::load_gltf("missing.gltf"), Err(LoadError::Import(_))));
+	}
+
+	#[test]
+	fn load_gltf_non_indexed() {
+		let geometries = Geometry3D::load_gltf("tests/res/non_indexed.gltf").unwrap();
+		assert_eq!(geometries.len(), 1);
+		assert_eq!(geometries[0].indices(), &Indices::U16(vec![0, 1, 2]));
+		assert_eq!(geometries[0].attributes().len(), 3 * 6);
+	}
+
+	#[test]
+	fn load_gltf_u32_indices() {
+		let geometries = Geometry3D::load_gltf("tests/res/u32_indices.gltf").unwrap();
+		assert_eq!(geometries.len(), 1);
+		assert_eq!(geometries[0].attributes().len(), (MAX_U16_VERTICES + 1) * 6);
+
+		// The last triangle uses the vertex past what a 16 bit index can address
+		match geometries[0].indices() {
+			Indices::U32(indices) => assert_eq!(indices.last(), Some(&(MAX_U16_VERTICES as u32))),
+			Indices::U16(_) => panic!("Expected 32 bit indices")
+		}
+	}
 }
\ No newline at end of file
@@ -0,0 +1,194 @@
+use std::path::Path;
+use crate::{
+	EntityManager,
+	Geometry3D,
+	component::{MultiComponentList, Mesh, NameComponentList, Transform3D, Transform3DComponentList, mesh::Material},
+	geometry3d::LoadError,
+	math::Vector3,
+	pool::{Handle, Pool}
+};
+
+pub fn load_scene<P: AsRef<Path>>(
+	path: P,
+	entity_manager: &mut EntityManager,
+	geometries: &mut Pool<Geometry3D>,
+	transform3d_components: &mut Transform3DComponentList,
+	mesh_components: &mut MultiComponentList<Mesh>,
+	name_components: &mut NameComponentList) -> Result<Vec<usize>, LoadError>
+{
+	let (document, buffers, _) = gltf::import(path)?;
+
+	// Create a mesh component for each primitive of each glTF mesh so nodes sharing a mesh share the components
+	let mut mesh_component_indices: Vec<Vec<usize>> = Vec::with_capacity(document.meshes().len());
+
+	for mesh in document.meshes() {
+		let name = mesh.name().unwrap_or("unnamed");
+		let mut indices = Vec::new();
+
+		for primitive in mesh.primitives() {
+			let geometry = Geometry3D::from_gltf_primitive(name, &primitive, &buffers)?;
+			let geometry_handle = geometries.add(geometry);
+			indices.push(mesh_components.add(create_mesh(geometry_handle, &primitive.material())));
+		}
+
+		if indices.is_empty() {
+			return Err(LoadError::MissingPrimitives(name.to_owned()));
+		}
+
+		mesh_component_indices.push(indices);
+	}
+
+	// Walk the node hierarchy, adding parents before their children
+	let scene = match document.default_scene() {
+		Some(scene) => scene,
+		None => match document.scenes().next() {
+			Some(scene) => scene,
+			None => return Ok(vec![])
+		}
+	};
+
+	let mut root_entities = vec![];
+	let mut nodes_to_visit: Vec<(gltf::Node, Option<usize>)> = scene.nodes().map(|node| (node, None)).collect();
+
+	while let Some((node, parent_entity)) = nodes_to_visit.pop() {
+		let entity = entity_manager.create();
+		let transform = create_transform(&node);
+
+		if let Some(parent_entity) = parent_entity {
+			transform3d_components.add_child(parent_entity, entity, transform);
+		}
+		else {
+			transform3d_components.add(entity, transform);
+			root_entities.push(entity);
+		}
+
+		if let Some(name) = node.name() {
+			name_components.add(entity, name.to_owned());
+		}
+
+		// An entity only has one mesh so every primitive after the first is given a child entity which follows the node
+		if let Some(mesh) = node.mesh() {
+			let indices = &mesh_component_indices[mesh.index()];
+			mesh_components.assign(entity, indices[0]);
+
+			for index in &indices[1..] {
+				let primitive_entity = entity_manager.create();
+				transform3d_components.add_child(entity, primitive_entity, Transform3D::new());
+				mesh_components.assign(primitive_entity, *index);
+			}
+		}
+
+		nodes_to_visit.extend(node.children().map(|child| (child, Some(entity))));
+	}
+
+	Ok(root_entities)
+}
+
+// Primitives without a material keep the normal material, otherwise the base color factor is used
+// Lit materials are Lambert tinted by the base color and unlit ones are the basic material
+fn create_mesh(geometry_handle: Handle, material: &gltf::Material) -> Mesh {
+	if material.index().is_none() {
+		return Mesh::new(geometry_handle, Material::Normal);
+	}
+
+	let [r, g, b, _] = material.pbr_metallic_roughness().base_color_factor();
+	let color = Vector3::new(r, g, b);
+
+	if material.unlit() {
+		return Mesh::new(geometry_handle, Material::Basic { color });
+	}
+
+	let mut mesh = Mesh::new(geometry_handle, Material::Lambert);
+	mesh.tint = color;
+	mesh.tint_strength = 1.0;
+	mesh
+}
+
+fn create_transform(node: &gltf::Node) -> Transform3D {
+	let (translation, rotation, scale) = node.transform().decomposed();
+	let mut transform = Transform3D::new();
+	transform.position.set(translation[0], translation[1], translation[2]);
+	transform.orientation.set(rotation[0], rotation[1], rotation[2], rotation[3]);
+	transform.scale.set(scale[0], scale[1], scale[2]);
+	transform
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::assert_approx_eq;
+
+	#[test]
+	fn load_scene_hierarchy() {
+		let mut entity_manager = EntityManager::new();
+		let mut geometries = Pool::<Geometry3D>::new();
+		let mut transform3d_components = Transform3DComponentList::new();
+		let mut mesh_components = MultiComponentList::<Mesh>::new();
+		let mut name_components = NameComponentList::new();
+
+		let root_entities = load_scene("tests/res/hierarchy.gltf", &mut entity_manager, &mut geometries, &mut transform3d_components, &mut mesh_components, &mut name_components).unwrap();
+		assert_eq!(root_entities.len(), 1);
+
+		let parent_transform = transform3d_components.borrow(root_entities[0]);
+		assert_eq!(parent_transform.child_entities.len(), 1);
+
+		let child_entity = parent_transform.child_entities[0];
+		let child_transform = transform3d_components.borrow(child_entity);
+		assert_eq!(child_transform.parent_entity, Some(root_entities[0]));
+
+		let expected = parent_transform.global_matrix() * child_transform.local_matrix();
+		assert_approx_eq(child_transform.global_matrix(), &expected, 1e-6);
+		assert_eq!(geometries.len(), 1);
+		assert!(mesh_components.try_borrow(child_entity).is_some());
+		assert_eq!(name_components.find_by_name("Parent"), Some(root_entities[0]));
+		assert_eq!(name_components.find_by_name("Child"), Some(child_entity));
+	}
+
+	#[test]
+	fn load_scene_multiple_primitives() {
+		let mut entity_manager = EntityManager::new();
+		let mut geometries = Pool::<Geometry3D>::new();
+		let mut transform3d_components = Transform3DComponentList::new();
+		let mut mesh_components = MultiComponentList::<Mesh>::new();
+		let mut name_components = NameComponentList::new();
+
+		let root_entities = load_scene("tests/res/two_primitives.gltf", &mut entity_manager, &mut geometries, &mut transform3d_components, &mut mesh_components, &mut name_components).unwrap();
+		assert_eq!(root_entities.len(), 1);
+		assert_eq!(geometries.len(), 2);
+
+		// The second primitive is on a child entity with an identity transform
+		let transform = transform3d_components.borrow(root_entities[0]);
+		assert_eq!(transform.child_entities.len(), 1);
+
+		let primitive_entity = transform.child_entities[0];
+		assert_approx_eq(transform3d_components.borrow(primitive_entity).global_matrix(), transform.global_matrix(), 1e-6);
+
+		let geometry_handle = mesh_components.borrow(root_entities[0]).geometry_handle;
+		let primitive_geometry_handle = mesh_components.borrow(primitive_entity).geometry_handle;
+		assert_ne!(geometry_handle, primitive_geometry_handle);
+		assert_eq!(geometries.borrow(primitive_geometry_handle).indices().len(), 3);
+	}
+
+	#[test]
+	fn load_scene_base_color() {
+		let mut entity_manager = EntityManager::new();
+		let mut geometries = Pool::<Geometry3D>::new();
+		let mut transform3d_components = Transform3DComponentList::new();
+		let mut mesh_components = MultiComponentList::<Mesh>::new();
+		let mut name_components = NameComponentList::new();
+
+		load_scene("tests/res/materials.gltf", &mut entity_manager, &mut geometries, &mut transform3d_components, &mut mesh_components, &mut name_components).unwrap();
+
+		let red = mesh_components.borrow(name_components.find_by_name("Red").unwrap());
+		assert!(matches!(red.material, Material::Lambert));
+		assert_eq!(red.tint, Vector3::new(1.0, 0.0, 0.0));
+		assert_eq!(red.tint_strength, 1.0);
+
+		let green = mesh_components.borrow(name_components.find_by_name("Green").unwrap());
+		assert!(matches!(green.material, Material::Basic { color } if color == Vector3::new(0.0, 1.0, 0.0)));
+
+		let plain = mesh_components.borrow(name_components.find_by_name("Plain").unwrap());
+		assert!(matches!(plain.material, Material::Normal));
+		assert_eq!(plain.tint_strength, 0.0);
+	}
+}
\ No newline at end of file
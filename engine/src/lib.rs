@@ -4,11 +4,16 @@ pub(crate) mod vulkan;
 pub mod math;
 pub mod pool;
 
+pub mod window;
+pub use window::Window;
+
 pub mod geometry3d;
 pub use geometry3d::Geometry3D;
 
+pub mod gltf_loader;
+
 pub mod camera;
-pub use camera::Camera;
+pub use camera::{Camera, Projection};
 
 pub mod font;
 pub use font::Font;
@@ -16,5 +21,11 @@ pub use font::Font;
 pub mod entity_manager;
 pub use entity_manager::EntityManager;
 
+pub mod clock;
+pub use clock::Clock;
+
+pub mod fixed_step;
+pub use fixed_step::FixedStep;
+
 pub mod component;
 pub mod system;
\ No newline at end of file
@@ -1,10 +1,16 @@
-use super::Vector3;
+use super::{Matrix4, Vector3};
 
 pub const DEFAULT_SQUARE: Box3 = Box3 {
 	min: Vector3 { x: -1.0, y: -1.0, z: -1.0 },
 	max: Vector3 { x:  1.0, y:  1.0, z:  1.0 }
 };
 
+// Expanding an empty box by a point gives a box containing only that point
+pub const EMPTY: Box3 = Box3 {
+	min: Vector3 { x: f32::INFINITY, y: f32::INFINITY, z: f32::INFINITY },
+	max: Vector3 { x: f32::NEG_INFINITY, y: f32::NEG_INFINITY, z: f32::NEG_INFINITY }
+};
+
 #[derive(Default, Copy, Clone, Debug, PartialEq)]
 pub struct Box3 {
 	pub min: Vector3,
@@ -21,6 +27,25 @@ impl Box3 {
 		self.max = max;
 	}
 
+	pub fn expand_by_point(&mut self, point: &Vector3) {
+		self.min.min(point);
+		self.max.max(point);
+	}
+
+	pub fn center(&self) -> Vector3 {
+		(self.min + self.max) * 0.5
+	}
+
+	// The result is the box which contains the transformed corners so it's only as tight as the original box
+	pub fn apply_matrix(&mut self, m: &Matrix4) {
+		let vertices = self.as_vertices();
+		*self = EMPTY;
+
+		for vertex in &vertices {
+			self.expand_by_point(&m.transform_point(vertex));
+		}
+	}
+
 	pub fn as_vertices(&self) -> [Vector3; 8] {
 		let min = &self.min;
 		let max = &self.max;
@@ -41,6 +66,8 @@ impl Box3 {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::math::{Quaternion, assert_approx_eq};
+	use std::f32::consts::FRAC_1_SQRT_2;
 
 	#[test]
 	fn new() {
@@ -61,6 +88,34 @@ mod tests {
 		assert_eq!(b, Box3 { min, max });
 	}
 
+	#[test]
+	fn expand_by_point() {
+		let mut b = EMPTY;
+		b.expand_by_point(&Vector3::new(1.0, -2.0, 3.0));
+		assert_eq!(b, Box3 { min: Vector3::new(1.0, -2.0, 3.0), max: Vector3::new(1.0, -2.0, 3.0) });
+
+		b.expand_by_point(&Vector3::new(-1.0, 2.0, 4.0));
+		assert_eq!(b, Box3 { min: Vector3::new(-1.0, -2.0, 3.0), max: Vector3::new(1.0, 2.0, 4.0) });
+	}
+
+	#[test]
+	fn center() {
+		let b = Box3::new(Vector3::new(-1.0, 0.0, 2.0), Vector3::new(3.0, 4.0, 4.0));
+		assert_eq!(b.center(), Vector3::new(1.0, 2.0, 3.0));
+	}
+
+	#[test]
+	fn apply_matrix() {
+		let mut m = Matrix4::default();
+		m.compose(&Vector3::new(10.0, 0.0, 0.0), &Quaternion::new(0.0, 0.0, FRAC_1_SQRT_2, FRAC_1_SQRT_2), &Vector3::new(2.0, 1.0, 1.0));
+
+		// Scaled along x, rotated a quarter turn around z then translated
+		let mut b = DEFAULT_SQUARE;
+		b.apply_matrix(&m);
+		assert_approx_eq(&b.min, &Vector3::new(9.0, -2.0, -1.0), 1e-6);
+		assert_approx_eq(&b.max, &Vector3::new(11.0, 2.0, 1.0), 1e-6);
+	}
+
 	#[test]
 	fn as_vertices() {
 		let expected = [
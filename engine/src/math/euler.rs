@@ -41,12 +41,18 @@ impl Euler {
 		self.order = order;
 	}
 
+	pub fn set_from_rotation_matrix(&mut self, m: &Matrix4, order: Order) {
+		self.order = order;
+		self.set_from_orientation_matrix(m);
+	}
+
+	// The upper 3x3 of the matrix must be a pure rotation, the asin terms are clamped since rounding can push them past 1
 	pub fn set_from_orientation_matrix(&mut self, m: &Matrix4) {
 		let e = &m.elements;
 
 		match self.order {
 			Order::Xyz => {
-				self.y = e[0][2].asin();
+				self.y = e[0][2].clamp(-1.0, 1.0).asin();
 
 				if e[0][2] > SINGULARITY_THRESHOLD {
 					self.x = e[1][0].atan2(e[1][1]);
@@ -62,7 +68,7 @@ impl Euler {
 				}
 			},
 			Order::Xzy => {
-				self.z = (-e[0][1]).asin();
+				self.z = (-e[0][1]).clamp(-1.0, 1.0).asin();
 
 				if e[0][1] > SINGULARITY_THRESHOLD {
 					self.x = (-e[2][0]).atan2(e[2][2]);
@@ -78,7 +84,7 @@ impl Euler {
 				}
 			},
 			Order::Yxz => {
-				self.x = -e[1][2].asin();
+				self.x = -e[1][2].clamp(-1.0, 1.0).asin();
 
 				if e[1][2] > SINGULARITY_THRESHOLD {
 					self.y = (-e[0][1]).atan2(e[0][0]);
@@ -94,7 +100,7 @@ impl Euler {
 				}
 			},
 			Order::Yzx => {
-				self.z = e[1][0].asin();
+				self.z = e[1][0].clamp(-1.0, 1.0).asin();
 
 				if e[1][0] > SINGULARITY_THRESHOLD {
 					self.x = 0.0;
@@ -110,7 +116,7 @@ impl Euler {
 				}
 			},
 			Order::Zxy => {
-				self.x = e[2][1].asin();
+				self.x = e[2][1].clamp(-1.0, 1.0).asin();
 
 				if e[2][1] > SINGULARITY_THRESHOLD {
 					self.y = 0.0;
@@ -126,7 +132,7 @@ impl Euler {
 				}
 			},
 			Order::Zyx => {
-				self.y = -e[2][0].asin();
+				self.y = -e[2][0].clamp(-1.0, 1.0).asin();
 
 				if e[2][0] > SINGULARITY_THRESHOLD {
 					self.x = 0.0;
@@ -377,6 +383,31 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn set_from_rotation_matrix() {
+		let orders = [Order::Xyz, Order::Xzy, Order::Yxz, Order::Yzx, Order::Zxy, Order::Zyx];
+		let mut m = matrix4::IDENTITY;
+		let mut e = ZERO;
+
+		for order in &orders {
+			let expected = Euler::new(0.3, -0.7, 1.1, *order);
+			m.make_orientation_from_euler(&expected);
+			e.set_from_rotation_matrix(&m, *order);
+			assert_approx_eq(&e, &expected, 1e-5);
+		}
+
+		// Gimbal lock in the order the camera uses, the yaw is kept and the roll is zeroed
+		let expected = Euler::new(FRAC_PI_2, 0.4, 0.0, Order::Yxz);
+		m.make_orientation_from_euler(&expected);
+		e.set_from_rotation_matrix(&m, Order::Yxz);
+		assert_approx_eq(&e, &expected, 1e-3);
+
+		// Rounding past 1 doesn't produce NaN
+		m.elements[1][2] = -1.0000001;
+		e.set_from_rotation_matrix(&m, Order::Yxz);
+		assert!((e.x - FRAC_PI_2).abs() < 1e-6);
+	}
+
 	#[test]
 	fn set_from_quaternion() {
 		let mut e = ZERO;
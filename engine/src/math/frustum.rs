@@ -0,0 +1,136 @@
+use super::{Box3, Matrix4, Vector3};
+
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct Plane {
+	pub normal: Vector3,
+	pub constant: f32
+}
+
+impl Plane {
+	pub fn new(normal: Vector3, constant: f32) -> Self {
+		Self { normal, constant }
+	}
+
+	// Positive in front of the plane, the side the normal points to
+	pub fn distance_to_point(&self, point: &Vector3) -> f32 {
+		self.normal.dot(point) + self.constant
+	}
+
+	fn from_coefficients(a: f32, b: f32, c: f32, d: f32) -> Self {
+		let normal = Vector3::new(a, b, c);
+		let length = normal.length();
+
+		Self {
+			normal: normal / length,
+			constant: d / length
+		}
+	}
+}
+
+// The plane normals point into the frustum
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct Frustum {
+	pub planes: [Plane; 6]
+}
+
+impl Frustum {
+	// The clip volume is -w <= x <= w, -w <= y <= w and 0 <= z <= w
+	pub fn from_matrix(m: &Matrix4) -> Self {
+		let e = &m.elements;
+		let row = |i: usize| (e[i][0], e[i][1], e[i][2], e[i][3]);
+		let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+		Self {
+			planes: [
+				Plane::from_coefficients(w.0 + x.0, w.1 + x.1, w.2 + x.2, w.3 + x.3),
+				Plane::from_coefficients(w.0 - x.0, w.1 - x.1, w.2 - x.2, w.3 - x.3),
+				Plane::from_coefficients(w.0 + y.0, w.1 + y.1, w.2 + y.2, w.3 + y.3),
+				Plane::from_coefficients(w.0 - y.0, w.1 - y.1, w.2 - y.2, w.3 - y.3),
+				Plane::from_coefficients(z.0, z.1, z.2, z.3),
+				Plane::from_coefficients(w.0 - z.0, w.1 - z.1, w.2 - z.2, w.3 - z.3)
+			]
+		}
+	}
+
+	// Conservative, a box near a corner of the frustum can be reported as intersecting when it isn't
+	pub fn intersects_box(&self, b: &Box3) -> bool {
+		for plane in &self.planes {
+			// The corner of the box furthest along the plane normal
+			let corner = Vector3::new(
+				if plane.normal.x >= 0.0 { b.max.x } else { b.min.x },
+				if plane.normal.y >= 0.0 { b.max.y } else { b.min.y },
+				if plane.normal.z >= 0.0 { b.max.z } else { b.min.z });
+
+			if plane.distance_to_point(&corner) < 0.0 {
+				return false;
+			}
+		}
+
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::matrix4;
+
+	fn create_frustum() -> Frustum {
+		// Looking down +Z from the origin
+		let mut projection_matrix = matrix4::IDENTITY;
+		projection_matrix.make_perspective(1.0, 90.0, 0.1, 50.0);
+		Frustum::from_matrix(&projection_matrix)
+	}
+
+	fn create_box(x: f32, y: f32, z: f32) -> Box3 {
+		Box3::new(Vector3::new(x - 1.0, y - 1.0, z - 1.0), Vector3::new(x + 1.0, y + 1.0, z + 1.0))
+	}
+
+	#[test]
+	fn plane_distance_to_point() {
+		let plane = Plane::new(Vector3::new(0.0, 1.0, 0.0), -2.0);
+		assert_eq!(plane.distance_to_point(&Vector3::new(5.0, 3.0, 1.0)), 1.0);
+		assert_eq!(plane.distance_to_point(&Vector3::new(5.0, 0.0, 1.0)), -2.0);
+	}
+
+	#[test]
+	fn intersects_box() {
+		let frustum = create_frustum();
+		assert!(frustum.intersects_box(&create_box(0.0, 0.0, 10.0)));
+		assert!(frustum.intersects_box(&create_box(0.0, 0.0, 0.0)));
+		assert!(frustum.intersects_box(&create_box(10.0, 0.0, 10.0)));
+		assert!(frustum.intersects_box(&create_box(0.0, 0.0, 50.5)));
+	}
+
+	#[test]
+	fn intersects_box_culled() {
+		let frustum = create_frustum();
+
+		// Behind the camera
+		assert!(!frustum.intersects_box(&create_box(0.0, 0.0, -10.0)));
+
+		// Beyond the far plane
+		assert!(!frustum.intersects_box(&create_box(0.0, 0.0, 60.0)));
+
+		// Off to the sides
+		assert!(!frustum.intersects_box(&create_box(20.0, 0.0, 10.0)));
+		assert!(!frustum.intersects_box(&create_box(0.0, -20.0, 10.0)));
+	}
+
+	#[test]
+	fn intersects_box_moved_camera() {
+		// The camera is moved back and turned around so it looks down -Z
+		let mut camera_matrix = matrix4::IDENTITY;
+		let mut orientation = crate::math::Quaternion::default();
+		orientation.set_from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), std::f32::consts::PI);
+		camera_matrix.compose(&Vector3::new(0.0, 0.0, 20.0), &orientation, &Vector3::from_scalar(1.0));
+		camera_matrix.invert();
+
+		let mut projection_matrix = matrix4::IDENTITY;
+		projection_matrix.make_perspective(1.0, 90.0, 0.1, 50.0);
+		let frustum = Frustum::from_matrix(&(projection_matrix * camera_matrix));
+
+		assert!(frustum.intersects_box(&create_box(0.0, 0.0, 0.0)));
+		assert!(!frustum.intersects_box(&create_box(0.0, 0.0, 30.0)));
+	}
+}
\ No newline at end of file
@@ -33,6 +33,53 @@ impl Matrix3 {
 		]
 	}
 
+	pub fn transpose(&mut self) {
+		let e = &mut self.elements;
+		let mut temp;
+
+		temp = e[1][0]; e[1][0] = e[0][1]; e[0][1] = temp;
+		temp = e[2][0]; e[2][0] = e[0][2]; e[0][2] = temp;
+		temp = e[2][1]; e[2][1] = e[1][2]; e[1][2] = temp;
+	}
+
+	pub fn determinant(&self) -> f32 {
+		let e = &self.elements;
+
+		e[0][0] * (e[1][1] * e[2][2] - e[1][2] * e[2][1]) -
+		e[0][1] * (e[1][0] * e[2][2] - e[1][2] * e[2][0]) +
+		e[0][2] * (e[1][0] * e[2][1] - e[1][1] * e[2][0])
+	}
+
+	// Returns false and leaves the matrix unchanged if it's singular
+	pub fn invert(&mut self) -> bool {
+		let det = self.determinant();
+
+		if det == 0.0 {
+			return false;
+		}
+
+		let det_rec = 1.0 / det;
+		let m = &mut self.elements;
+
+		let (m00, m01, m02) = (m[0][0], m[0][1], m[0][2]);
+		let (m10, m11, m12) = (m[1][0], m[1][1], m[1][2]);
+		let (m20, m21, m22) = (m[2][0], m[2][1], m[2][2]);
+
+		m[0][0] = (m11 * m22 - m12 * m21) * det_rec;
+		m[0][1] = (m02 * m21 - m01 * m22) * det_rec;
+		m[0][2] = (m01 * m12 - m02 * m11) * det_rec;
+
+		m[1][0] = (m12 * m20 - m10 * m22) * det_rec;
+		m[1][1] = (m00 * m22 - m02 * m20) * det_rec;
+		m[1][2] = (m02 * m10 - m00 * m12) * det_rec;
+
+		m[2][0] = (m10 * m21 - m11 * m20) * det_rec;
+		m[2][1] = (m01 * m20 - m00 * m21) * det_rec;
+		m[2][2] = (m00 * m11 - m01 * m10) * det_rec;
+
+		true
+	}
+
 	pub fn compose(&mut self, position: &Vector2, orientation: f32, scale: &Vector2) {
 		let se = &mut self.elements;
 
@@ -198,6 +245,70 @@ mod tests {
 		assert_eq!(m.to_padded_array(), expected);
 	}
 
+	#[test]
+	fn transpose() {
+		let mut m = Matrix3::new([
+			[1.0, 2.0, 3.0],
+			[4.0, 5.0, 6.0],
+			[7.0, 8.0, 9.0]]);
+
+		m.transpose();
+
+		let expected = Matrix3::new([
+			[1.0, 4.0, 7.0],
+			[2.0, 5.0, 8.0],
+			[3.0, 6.0, 9.0]]);
+
+		assert_eq!(m, expected);
+	}
+
+	#[test]
+	fn determinant() {
+		assert_eq!(IDENTITY.determinant(), 1.0);
+
+		let m = Matrix3::new([
+			[2.0, 0.0, 1.0],
+			[1.0, 1.0, 0.0],
+			[0.0, 3.0, 1.0]]);
+
+		assert_eq!(m.determinant(), 5.0);
+	}
+
+	#[test]
+	fn invert() {
+		let mut m = IDENTITY;
+		assert!(m.invert());
+		assert_eq!(m, IDENTITY);
+
+		let original = Matrix3::new([
+			[2.0, 0.0, 1.0],
+			[1.0, 1.0, 0.0],
+			[0.0, 3.0, 1.0]]);
+
+		let mut m = original;
+		assert!(m.invert());
+
+		let expected = Matrix3::new([
+			[0.2, 0.6, -0.2],
+			[-0.2, 0.4, 0.2],
+			[0.6, -1.2, 0.4]]);
+
+		assert_approx_eq(&m, &expected, 1e-6);
+		assert_approx_eq(&(original * m), &IDENTITY, 1e-6);
+	}
+
+	#[test]
+	fn invert_singular() {
+		let singular = Matrix3::new([
+			[1.0, 2.0, 3.0],
+			[4.0, 5.0, 6.0],
+			[7.0, 8.0, 9.0]]);
+
+		let mut m = singular;
+		assert!(!m.invert());
+		assert_eq!(m, singular);
+	}
+
 	#[test]
 	fn compose() {
 		let pos = Vector2::new(100.0, 200.0);
@@ -48,6 +48,12 @@ impl Matrix4 {
 		temp = e[3][2]; e[3][2] = e[2][3]; e[2][3] = temp;
 	}
 
+	pub fn transposed(&self) -> Self {
+		let mut m = *self;
+		m.transpose();
+		m
+	}
+
 	pub fn invert(&mut self) {
 		let m = &mut self.elements;
 
@@ -124,6 +130,38 @@ impl Matrix4 {
 		se[3][3] = 1.0;
 	}
 
+	// A negative determinant means the scale is mirrored, which is attributed to the x axis
+	pub fn decompose(&self) -> (Vector3, Quaternion, Vector3) {
+		let e = &self.elements;
+
+		let mut scale = Vector3::new(
+			Vector3::new(e[0][0], e[1][0], e[2][0]).length(),
+			Vector3::new(e[0][1], e[1][1], e[2][1]).length(),
+			Vector3::new(e[0][2], e[1][2], e[2][2]).length());
+
+		let det =
+			e[0][0] * (e[1][1] * e[2][2] - e[1][2] * e[2][1]) -
+			e[0][1] * (e[1][0] * e[2][2] - e[1][2] * e[2][0]) +
+			e[0][2] * (e[1][0] * e[2][1] - e[1][1] * e[2][0]);
+
+		if det < 0.0 {
+			scale.x = -scale.x;
+		}
+
+		let mut rotation_matrix = IDENTITY;
+
+		for row in 0..3 {
+			rotation_matrix.elements[row][0] = e[row][0] / scale.x;
+			rotation_matrix.elements[row][1] = e[row][1] / scale.y;
+			rotation_matrix.elements[row][2] = e[row][2] / scale.z;
+		}
+
+		let mut orientation = Quaternion::default();
+		orientation.set_from_rotation_matrix(&rotation_matrix);
+
+		(self.extract_position(), orientation, scale)
+	}
+
 	pub fn extract_position(&self) -> Vector3 {
 		let se = &self.elements;
 		Vector3::new(se[0][3], se[1][3], se[2][3])
@@ -155,6 +193,33 @@ impl Matrix4 {
 		se[3][3] = 0.0;
 	}
 
+	pub fn make_orthographic(&mut self, left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) {
+		let w = right - left;
+		let h = top - bottom;
+		let d = far - near;
+		let se = &mut self.elements;
+
+		se[0][0] = -2.0 / w;
+		se[0][1] = 0.0;
+		se[0][2] = 0.0;
+		se[0][3] = (right + left) / w;
+
+		se[1][0] = 0.0;
+		se[1][1] = -2.0 / h;
+		se[1][2] = 0.0;
+		se[1][3] = (top + bottom) / h;
+
+		se[2][0] = 0.0;
+		se[2][1] = 0.0;
+		se[2][2] = 1.0 / d;
+		se[2][3] = -near / d;
+
+		se[3][0] = 0.0;
+		se[3][1] = 0.0;
+		se[3][2] = 0.0;
+		se[3][3] = 1.0;
+	}
+
 	pub fn make_orientation_from_quaternion(&mut self, q: &Quaternion) {
 		self.compose(&vector3::ZERO, q, &vector3::ONE);
 	}
@@ -266,6 +331,27 @@ impl Matrix4 {
 			]
 		}
 	}
+
+	// Treats the point as having a w of 1 then divides by the resulting w so projection matrices work too
+	pub fn transform_point(&self, point: &Vector3) -> Vector3 {
+		let e = &self.elements;
+		let w = e[3][0] * point.x + e[3][1] * point.y + e[3][2] * point.z + e[3][3];
+
+		Vector3::new(
+			(e[0][0] * point.x + e[0][1] * point.y + e[0][2] * point.z + e[0][3]) / w,
+			(e[1][0] * point.x + e[1][1] * point.y + e[1][2] * point.z + e[1][3]) / w,
+			(e[2][0] * point.x + e[2][1] * point.y + e[2][2] * point.z + e[2][3]) / w)
+	}
+
+	// Treats the direction as having a w of 0 so the translation is ignored, the result isn't normalized
+	pub fn transform_direction(&self, direction: &Vector3) -> Vector3 {
+		let e = &self.elements;
+
+		Vector3::new(
+			e[0][0] * direction.x + e[0][1] * direction.y + e[0][2] * direction.z,
+			e[1][0] * direction.x + e[1][1] * direction.y + e[1][2] * direction.z,
+			e[2][0] * direction.x + e[2][1] * direction.y + e[2][2] * direction.z)
+	}
 }
 
 impl_op_ex!(+ |a: &Matrix4, b: &Matrix4| -> Matrix4 {
@@ -466,6 +552,23 @@ mod tests {
 		assert_eq!(m, expected);
 	}
 
+	#[test]
+	fn transposed() {
+		let m = Matrix4::new([
+			[0.0, 0.1, 0.2, 0.3],
+			[1.0, 1.1, 1.2, 1.3],
+			[2.0, 2.1, 2.2, 2.3],
+			[3.0, 3.1, 3.2, 3.3]]);
+		let original = m;
+
+		let mut expected = m;
+		expected.transpose();
+
+		assert_eq!(m.transposed(), expected);
+		assert_eq!(m, original);
+		assert_eq!(m.transposed().transposed(), original);
+	}
+
 	#[test]
 	fn invert() {
 		let mut m = Matrix4::new([
@@ -501,6 +604,43 @@ mod tests {
 		assert_eq!(m, expected);
 	}
 
+	#[test]
+	fn decompose() {
+		let position = Vector3::new(1.0, -2.0, 3.0);
+		let mut orientation = Quaternion::default();
+		orientation.set_from_axis_angle(&Vector3::new(0.0, 0.6, 0.8), 1.2);
+		let scale = Vector3::new(2.0, 0.5, 3.0);
+
+		let mut m = IDENTITY;
+		m.compose(&position, &orientation, &scale);
+		let (p, q, s) = m.decompose();
+
+		assert_approx_eq(&p, &position, 1e-6);
+		assert_approx_eq(&q, &orientation, 1e-6);
+		assert_approx_eq(&s, &scale, 1e-5);
+	}
+
+	#[test]
+	fn decompose_mirrored() {
+		let position = Vector3::new(4.0, 5.0, 6.0);
+		let mut orientation = Quaternion::default();
+		orientation.set_from_axis_angle(&Vector3::new(1.0, 0.0, 0.0), 0.5);
+		let scale = Vector3::new(-2.0, 1.0, 1.0);
+
+		let mut m = IDENTITY;
+		m.compose(&position, &orientation, &scale);
+		let (p, q, s) = m.decompose();
+
+		assert_approx_eq(&p, &position, 1e-6);
+		assert_approx_eq(&q, &orientation, 1e-6);
+		assert_approx_eq(&s, &scale, 1e-6);
+
+		// Recomposing gives back the same matrix
+		let mut recomposed = IDENTITY;
+		recomposed.compose(&p, &q, &s);
+		assert_approx_eq(&recomposed, &m, 1e-6);
+	}
+
 	#[test]
 	fn extract_position() {
 		let m = Matrix4::new([
@@ -526,6 +666,26 @@ mod tests {
 		assert_eq!(m, expected);
 	}
 
+	#[test]
+	fn make_orthographic() {
+		let mut m = IDENTITY;
+		m.make_orthographic(-2.0, 2.0, -1.0, 1.0, 1.0, 5.0);
+
+		let expected = Matrix4::new([
+			[-0.5, 0.0, 0.0, 0.0],
+			[0.0, -1.0, 0.0, 0.0],
+			[0.0, 0.0, 0.25, -0.25],
+			[0.0, 0.0, 0.0, 1.0]]);
+
+		assert_eq!(m, expected);
+
+		let near_corner = m * Vector4::new(2.0, 1.0, 1.0, 1.0);
+		assert_eq!(near_corner, Vector4::new(-1.0, -1.0, 0.0, 1.0));
+
+		let far_corner = m * Vector4::new(-2.0, -1.0, 5.0, 1.0);
+		assert_eq!(far_corner, Vector4::new(1.0, 1.0, 1.0, 1.0));
+	}
+
 	#[test]
 	fn make_orientation_from_quaternion() {
 		let mut m = IDENTITY;
@@ -756,6 +916,25 @@ mod tests {
 		assert_eq!(a, expected);
 	}
 
+	#[test]
+	fn transform_point() {
+		let mut m = IDENTITY;
+		m.compose(&Vector3::new(1.0, 2.0, 3.0), &Quaternion::new(0.0, 0.0, 0.0, 1.0), &vector3::ONE);
+		assert_eq!(m.transform_point(&Vector3::new(1.0, -1.0, 2.0)), Vector3::new(2.0, 1.0, 5.0));
+
+		// Perspective divide
+		let mut projection = IDENTITY;
+		projection.make_perspective(0.5, 90.0, 1.0, 5.0);
+		assert_approx_eq(&projection.transform_point(&Vector3::new(1.0, 1.0, 3.0)), &Vector3::new(-2.0 / 3.0, -1.0 / 3.0, 2.5 / 3.0), 1e-6);
+	}
+
+	#[test]
+	fn transform_direction() {
+		let mut m = IDENTITY;
+		m.compose(&Vector3::new(1.0, 2.0, 3.0), &Quaternion::new(0.0, 0.0, 0.0, 1.0), &Vector3::new(2.0, 2.0, 2.0));
+		assert_eq!(m.transform_direction(&Vector3::new(1.0, -1.0, 2.0)), Vector3::new(2.0, -2.0, 4.0));
+	}
+
 	#[test]
 	fn mul_vec_4() {
 		let a = Matrix4::new([
@@ -770,6 +949,19 @@ mod tests {
 		assert_eq!(a * b, expected);
 	}
 
+	#[test]
+	fn mul_vec_4_inverse_projection() {
+		let mut projection = IDENTITY;
+		projection.make_perspective(0.5, 90.0, 1.0, 5.0);
+
+		let clip = projection * Vector4::new(1.0, 1.0, 3.0, 1.0);
+		assert_eq!(clip, Vector4::new(-2.0, -1.0, 2.5, 3.0));
+
+		let mut inverse_projection = projection;
+		inverse_projection.invert();
+		assert_approx_eq(&(inverse_projection * clip), &Vector4::new(1.0, 1.0, 3.0, 1.0), 1e-5);
+	}
+
 	#[test]
 	fn approx_eq() {
 		let a = Matrix4::new([
@@ -785,5 +977,7 @@ mod tests {
 			[2.0, 4.0, 7.0, 2.0]]);
 
 		assert_approx_eq(&a, &b, 1.0);
+		assert!(!a.approx_eq(&b, 0.9));
+		assert_approx_eq(&a, &a, 0.0);
 	}
 }
\ No newline at end of file
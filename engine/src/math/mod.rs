@@ -23,6 +23,12 @@ pub use euler::Euler;
 pub mod box3;
 pub use box3::Box3;
 
+pub mod frustum;
+pub use frustum::Frustum;
+
+pub mod ray;
+pub use ray::Ray;
+
 use std::fmt::Debug;
 
 pub trait ApproxEq {
@@ -1,5 +1,5 @@
 use std::fmt::Display;
-use super::{Vector3, Euler, Order, ApproxEq};
+use super::{Vector3, Euler, Order, Matrix4, ApproxEq};
 use auto_ops::impl_op_ex;
 
 pub const ZERO: Quaternion = Quaternion { x: 0.0, y: 0.0, z: 0.0, w: 1.0 };
@@ -78,6 +78,35 @@ impl Quaternion {
 		}
 	}
 
+	// The upper 3x3 of the matrix must be a pure rotation
+	pub fn set_from_rotation_matrix(&mut self, m: &Matrix4) {
+		let e = &m.elements;
+
+		let (m00, m01, m02) = (e[0][0], e[0][1], e[0][2]);
+		let (m10, m11, m12) = (e[1][0], e[1][1], e[1][2]);
+		let (m20, m21, m22) = (e[2][0], e[2][1], e[2][2]);
+
+		let trace = m00 + m11 + m22;
+
+		// Divide by the largest of the diagonal terms for stability
+		if trace > 0.0 {
+			let s = 0.5 / (trace + 1.0).sqrt();
+			self.set((m21 - m12) * s, (m02 - m20) * s, (m10 - m01) * s, 0.25 / s);
+		}
+		else if m00 > m11 && m00 > m22 {
+			let s = 2.0 * (1.0 + m00 - m11 - m22).sqrt();
+			self.set(0.25 * s, (m01 + m10) / s, (m02 + m20) / s, (m21 - m12) / s);
+		}
+		else if m11 > m22 {
+			let s = 2.0 * (1.0 + m11 - m00 - m22).sqrt();
+			self.set((m01 + m10) / s, 0.25 * s, (m12 + m21) / s, (m02 - m20) / s);
+		}
+		else {
+			let s = 2.0 * (1.0 + m22 - m00 - m11).sqrt();
+			self.set((m02 + m20) / s, (m12 + m21) / s, 0.25 * s, (m10 - m01) / s);
+		}
+	}
+
 	pub fn conjigate(&mut self) {
 		self.x = -self.x;
 		self.y = -self.y;
@@ -109,6 +138,34 @@ impl Quaternion {
 			self.w /= l;
 		}
 	}
+
+	// Values of t outside of [0, 1] continue along the same arc
+	pub fn slerp(&mut self, other: &Self, t: f32) {
+		let mut cos_half_theta = self.dot(other);
+		let mut other = *other;
+
+		// Take the shortest path
+		if cos_half_theta < 0.0 {
+			other.set(-other.x, -other.y, -other.z, -other.w);
+			cos_half_theta = -cos_half_theta;
+		}
+
+		let (a, b) = if cos_half_theta > 0.9995 {
+			// The quaternions are nearly identical so fall back to a normalized linear interpolation
+			(1.0 - t, t)
+		}
+		else {
+			let half_theta = cos_half_theta.acos();
+			let sin_half_theta = half_theta.sin();
+			(((1.0 - t) * half_theta).sin() / sin_half_theta, (t * half_theta).sin() / sin_half_theta)
+		};
+
+		self.x = self.x * a + other.x * b;
+		self.y = self.y * a + other.y * b;
+		self.z = self.z * a + other.z * b;
+		self.w = self.w * a + other.w * b;
+		self.normalize();
+	}
 }
 
 impl_op_ex!(* |a: &Quaternion, b: &Quaternion| -> Quaternion {
@@ -151,7 +208,7 @@ impl Display for Quaternion {
 mod tests {
 	use super::*;
 	use crate::math::assert_approx_eq;
-	use std::f32::consts::{PI, FRAC_PI_2, FRAC_1_SQRT_2};
+	use std::f32::consts::{PI, FRAC_PI_2, FRAC_PI_4, FRAC_1_SQRT_2};
 
 	#[test]
 	fn new() {
@@ -201,6 +258,40 @@ mod tests {
 		assert_approx_eq(&q, &Quaternion { x: 0.0, y: FRAC_1_SQRT_2, z: 0.0, w: FRAC_1_SQRT_2 }, 1e-6);
 	}
 
+	#[test]
+	fn set_from_rotation_matrix() {
+		let mut m = Matrix4::default();
+		let mut q = ZERO;
+		let mut expected = ZERO;
+
+		// Identity
+		m.make_orientation_from_quaternion(&ZERO);
+		q.set_from_rotation_matrix(&m);
+		assert_approx_eq(&q, &ZERO, 1e-6);
+
+		// 90 degrees around Y, the trace is positive
+		expected.set_from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), FRAC_PI_2);
+		m.make_orientation_from_quaternion(&expected);
+		q.set_from_rotation_matrix(&m);
+		assert_approx_eq(&q, &expected, 1e-6);
+
+		// 180 degrees around each axis, the trace is -1 so each largest diagonal branch is taken
+		for axis in &[Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)] {
+			expected.set_from_axis_angle(axis, PI);
+			m.make_orientation_from_quaternion(&expected);
+			q.set_from_rotation_matrix(&m);
+			assert_approx_eq(&q, &expected, 1e-6);
+		}
+
+		// Arbitrary rotation
+		let mut axis = Vector3::new(1.0, -2.0, 3.0);
+		axis.normalize();
+		expected.set_from_axis_angle(&axis, 2.5);
+		m.make_orientation_from_quaternion(&expected);
+		q.set_from_rotation_matrix(&m);
+		assert_approx_eq(&q, &expected, 1e-6);
+	}
+
 	#[test]
 	fn conjigate() {
 		let mut q = Quaternion::new(1.0, 2.0, 3.0, 4.0);
@@ -236,6 +327,40 @@ mod tests {
 		assert_approx_eq(&q, &Quaternion { x: 0.833, y: 0.5, z: 0.166, w: -0.166 }, 0.001);
 	}
 
+	#[test]
+	fn slerp() {
+		let mut a = ZERO;
+		let mut b = ZERO;
+		b.set_from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), FRAC_PI_2);
+
+		let mut q = a;
+		q.slerp(&b, 0.0);
+		assert_approx_eq(&q, &a, 1e-6);
+
+		q = a;
+		q.slerp(&b, 1.0);
+		assert_approx_eq(&q, &b, 1e-6);
+
+		q = a;
+		q.slerp(&b, 0.5);
+		let mut expected = ZERO;
+		expected.set_from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), FRAC_PI_4);
+		assert_approx_eq(&q, &expected, 1e-6);
+
+		// Extrapolates past the end
+		q = a;
+		q.slerp(&b, 2.0);
+		expected.set_from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), PI);
+		assert_approx_eq(&q, &expected, 1e-6);
+
+		// Takes the shortest path when the other quaternion is negated
+		a.set(-b.x, -b.y, -b.z, -b.w);
+		q = ZERO;
+		q.slerp(&a, 0.5);
+		expected.set_from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), FRAC_PI_4);
+		assert_approx_eq(&q, &expected, 1e-6);
+	}
+
 	#[test]
 	fn mul() {
 		let a = Quaternion::new(3.0, 1.0, 2.0, 4.0);
@@ -255,5 +380,12 @@ mod tests {
 		let a = Quaternion::new(1.0, 2.0, 3.0, 4.0);
 		let b = Quaternion::new(1.0, 2.0, 3.0, 4.0);
 		assert_approx_eq(&a, &b, 0.0);
+
+		let c = Quaternion::new(1.0 + 1e-7, 2.0, 3.0 - 1e-7, 4.0);
+		assert_approx_eq(&a, &c, 1e-6);
+
+		let d = Quaternion::new(1.5, 1.5, 3.5, 3.5);
+		assert_approx_eq(&a, &d, 0.5);
+		assert!(!a.approx_eq(&d, 0.4));
 	}
 }
\ No newline at end of file
@@ -0,0 +1,200 @@
+use super::{Box3, Matrix4, Vector3, Vector4};
+
+const EPSILON: f32 = 1e-7;
+
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+pub struct Ray {
+	pub origin: Vector3,
+	pub direction: Vector3
+}
+
+impl Ray {
+	pub fn new(origin: Vector3, direction: Vector3) -> Self {
+		Self { origin, direction }
+	}
+
+	// The screen coordinates are in pixels from the top left corner
+	pub fn from_screen_coordinates(x: f32, y: f32, width: f32, height: f32, inverse_projection_view_matrix: &Matrix4) -> Self {
+		let ndc_x = 2.0 * x / width - 1.0;
+		let ndc_y = 2.0 * y / height - 1.0;
+
+		let unproject = |z: f32| {
+			let v = inverse_projection_view_matrix * Vector4::new(ndc_x, ndc_y, z, 1.0);
+			Vector3::new(v.x / v.w, v.y / v.w, v.z / v.w)
+		};
+
+		let near = unproject(0.0);
+		let far = unproject(1.0);
+
+		let mut direction = far - near;
+		direction.normalize();
+
+		Self::new(near, direction)
+	}
+
+	pub fn at(&self, t: f32) -> Vector3 {
+		self.origin + self.direction * t
+	}
+
+	// Returns 0 if the origin is inside of the box
+	pub fn intersect_box(&self, b: &Box3) -> Option<f32> {
+		let mut t_min = f32::NEG_INFINITY;
+		let mut t_max = f32::INFINITY;
+
+		for axis in 0..3 {
+			let origin = self.origin.get_from_index(axis);
+			let inverse_direction = 1.0 / self.direction.get_from_index(axis);
+
+			let t1 = (b.min.get_from_index(axis) - origin) * inverse_direction;
+			let t2 = (b.max.get_from_index(axis) - origin) * inverse_direction;
+
+			t_min = t_min.max(t1.min(t2));
+			t_max = t_max.min(t1.max(t2));
+		}
+
+		if t_max < t_min.max(0.0) {
+			None
+		}
+		else {
+			Some(t_min.max(0.0))
+		}
+	}
+
+	// Both sides of the triangle are hit
+	pub fn intersect_triangle(&self, a: &Vector3, b: &Vector3, c: &Vector3) -> Option<f32> {
+		let edge_1 = b - a;
+		let edge_2 = c - a;
+
+		let mut p = self.direction;
+		p.cross(&edge_2);
+		let det = edge_1.dot(&p);
+
+		// The ray is parallel to the triangle
+		if det.abs() < EPSILON {
+			return None;
+		}
+
+		let inverse_det = 1.0 / det;
+		let s = self.origin - a;
+		let u = s.dot(&p) * inverse_det;
+
+		if !(0.0..=1.0).contains(&u) {
+			return None;
+		}
+
+		let mut q = s;
+		q.cross(&edge_1);
+		let v = self.direction.dot(&q) * inverse_det;
+
+		if v < 0.0 || u + v > 1.0 {
+			return None;
+		}
+
+		let t = edge_2.dot(&q) * inverse_det;
+
+		if t > EPSILON {
+			Some(t)
+		}
+		else {
+			None
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::math::{assert_approx_eq, box3, matrix4, vector3};
+
+	#[test]
+	fn new() {
+		let origin = Vector3::new(1.0, 2.0, 3.0);
+		let direction = Vector3::new(0.0, 0.0, 1.0);
+		assert_eq!(Ray::new(origin, direction), Ray { origin, direction });
+	}
+
+	#[test]
+	fn from_screen_coordinates() {
+		let mut projection_matrix = matrix4::IDENTITY;
+		projection_matrix.make_perspective(1.0, 90.0, 0.1, 50.0);
+		let mut view_matrix = matrix4::IDENTITY;
+		view_matrix.compose(&Vector3::new(1.0, 2.0, 3.0), &Default::default(), &Vector3::from_scalar(1.0));
+		view_matrix.invert();
+
+		let mut inverse_projection_view_matrix = projection_matrix * view_matrix;
+		inverse_projection_view_matrix.invert();
+
+		// The center of the screen looks straight down the camera's +Z axis
+		let ray = Ray::from_screen_coordinates(400.0, 300.0, 800.0, 600.0, &inverse_projection_view_matrix);
+		assert_approx_eq(&ray.origin, &Vector3::new(1.0, 2.0, 3.1), 1e-5);
+		assert_approx_eq(&ray.direction, &Vector3::new(0.0, 0.0, 1.0), 1e-5);
+
+		// A 90 degree field of view puts the edges of the screen at 45 degrees
+		let ray = Ray::from_screen_coordinates(400.0, 0.0, 800.0, 600.0, &inverse_projection_view_matrix);
+		assert!((ray.direction.y.abs() - ray.direction.z).abs() < 1e-5);
+	}
+
+	#[test]
+	fn at() {
+		let ray = Ray::new(Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+		assert_eq!(ray.at(2.0), Vector3::new(1.0, 2.0, 0.0));
+	}
+
+	#[test]
+	fn intersect_box() {
+		let ray = Ray::new(Vector3::new(0.5, 0.5, -5.0), Vector3::new(0.0, 0.0, 1.0));
+		assert_eq!(ray.intersect_box(&box3::DEFAULT_SQUARE), Some(4.0));
+
+		let mut direction = Vector3::new(-1.0, -1.0, -1.0);
+		direction.normalize();
+		let ray = Ray::new(Vector3::new(3.0, 3.0, 3.0), direction);
+		assert_approx_eq(&ray.at(ray.intersect_box(&box3::DEFAULT_SQUARE).unwrap()), &Vector3::new(1.0, 1.0, 1.0), 1e-5);
+
+		// Inside the box
+		let ray = Ray::new(vector3::ZERO, Vector3::new(1.0, 0.0, 0.0));
+		assert_eq!(ray.intersect_box(&box3::DEFAULT_SQUARE), Some(0.0));
+	}
+
+	#[test]
+	fn intersect_box_miss() {
+		// Passes beside the box
+		let ray = Ray::new(Vector3::new(2.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+		assert_eq!(ray.intersect_box(&box3::DEFAULT_SQUARE), None);
+
+		// Points away from the box
+		let ray = Ray::new(Vector3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, -1.0));
+		assert_eq!(ray.intersect_box(&box3::DEFAULT_SQUARE), None);
+	}
+
+	#[test]
+	fn intersect_triangle() {
+		let a = Vector3::new(-1.0, -1.0, 2.0);
+		let b = Vector3::new(1.0, -1.0, 2.0);
+		let c = Vector3::new(0.0, 1.0, 2.0);
+
+		let ray = Ray::new(vector3::ZERO, Vector3::new(0.0, 0.0, 1.0));
+		assert_eq!(ray.intersect_triangle(&a, &b, &c), Some(2.0));
+
+		// The winding doesn't matter
+		assert_eq!(ray.intersect_triangle(&a, &c, &b), Some(2.0));
+	}
+
+	#[test]
+	fn intersect_triangle_miss() {
+		let a = Vector3::new(-1.0, -1.0, 2.0);
+		let b = Vector3::new(1.0, -1.0, 2.0);
+		let c = Vector3::new(0.0, 1.0, 2.0);
+
+		// Outside of the edges
+		let ray = Ray::new(Vector3::new(0.9, 0.9, 0.0), Vector3::new(0.0, 0.0, 1.0));
+		assert_eq!(ray.intersect_triangle(&a, &b, &c), None);
+
+		// Behind the origin
+		let ray = Ray::new(vector3::ZERO, Vector3::new(0.0, 0.0, -1.0));
+		assert_eq!(ray.intersect_triangle(&a, &b, &c), None);
+
+		// Parallel
+		let ray = Ray::new(vector3::ZERO, Vector3::new(1.0, 0.0, 0.0));
+		assert_eq!(ray.intersect_triangle(&a, &b, &c), None);
+	}
+}
\ No newline at end of file
@@ -1,4 +1,5 @@
 use auto_ops::impl_op_ex;
+use super::ApproxEq;
 
 pub const ZERO: Vector2 = Vector2 { x: 0.0, y: 0.0 };
 
@@ -21,6 +22,26 @@ impl Vector2 {
 		self.x = x;
 		self.y = y;
 	}
+
+	pub fn length(&self) -> f32 {
+		(self.x * self.x + self.y * self.y).sqrt()
+	}
+
+	pub fn length_sq(&self) -> f32 {
+		self.x * self.x + self.y * self.y
+	}
+
+	pub fn normalize(&mut self) {
+		let length = self.length();
+
+		if length != 0.0 {
+			*self /= length;
+		}
+	}
+
+	pub fn dot(&self, other: &Self) -> f32 {
+		self.x * other.x + self.y * other.y
+	}
 }
 
 impl_op_ex!(+ |a: &Vector2, b: &Vector2| -> Vector2 {
@@ -118,9 +139,19 @@ impl_op_ex!(- |a: &Vector2| -> Vector2 {
 	}
 });
 
+impl ApproxEq for Vector2 {
+	fn approx_eq(&self, other: &Self, tol: f32) -> bool {
+		let x_diff = (self.x - other.x).abs();
+		let y_diff = (self.y - other.y).abs();
+
+		x_diff <= tol && y_diff <= tol
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::math::assert_approx_eq;
 
 	#[test]
 	fn from() {
@@ -139,6 +170,35 @@ mod tests {
 		assert_eq!(v, Vector2 { x: 1.0, y: 2.0 });
 	}
 
+	#[test]
+	fn length() {
+		assert_eq!(Vector2::new(3.0, 4.0).length(), 5.0);
+	}
+
+	#[test]
+	fn length_sq() {
+		assert_eq!(Vector2::new(1.0, 2.0).length_sq(), 5.0);
+	}
+
+	#[test]
+	fn normalize() {
+		let mut v = Vector2::new(3.0, 4.0);
+		v.normalize();
+		assert_eq!(v, Vector2 { x: 0.6, y: 0.8 });
+		assert_eq!(v.length(), 1.0);
+
+		v = ZERO;
+		v.normalize();
+		assert_eq!(v, Vector2 { x: 0.0, y: 0.0 });
+	}
+
+	#[test]
+	fn dot() {
+		let a = Vector2::new(1.0, -2.0);
+		let b = Vector2::new(4.0, -3.0);
+		assert_eq!(a.dot(&b), 10.0);
+	}
+
 	#[test]
 	fn add_vector() {
 		let a = Vector2::new(1.0, -2.0);
@@ -252,4 +312,12 @@ mod tests {
 		let v = Vector2::new(1.0, 2.0);
 		assert_eq!(-v, Vector2 { x: -1.0, y: -2.0 });
 	}
+	#[test]
+	fn approx_eq() {
+		let a = Vector2::new(1.0, 2.0);
+		let b = Vector2::new(1.5, 1.5);
+		assert_approx_eq(&a, &a, 0.0);
+		assert_approx_eq(&a, &b, 0.5);
+		assert!(!a.approx_eq(&b, 0.4));
+	}
 }
\ No newline at end of file
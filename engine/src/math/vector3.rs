@@ -89,6 +89,12 @@ impl Vector3 {
 		self.z = self_x * other.y - self_y * other.x;
 	}
 
+	pub fn lerp(&mut self, other: &Self, t: f32) {
+		self.x += (other.x - self.x) * t;
+		self.y += (other.y - self.y) * t;
+		self.z += (other.z - self.z) * t;
+	}
+
 	pub fn apply_quaternion(&mut self, q: &Quaternion) {
 		let ix = q.w * self.x + q.y * self.z - q.z * self.y;
 		let iy = q.w * self.y + q.z * self.x - q.x * self.z;
@@ -111,6 +117,12 @@ impl Vector3 {
 		self.y = self.y.max(other.y);
 		self.z = self.z.max(other.z);
 	}
+
+	// Component-wise, e.g. keeping a position inside a box's min and max corners
+	pub fn clamp(&mut self, min: &Self, max: &Self) {
+		self.max(min);
+		self.min(max);
+	}
 }
 
 impl_op_ex!(+ |a: &Vector3, b: &Vector3| -> Vector3 {
@@ -334,6 +346,16 @@ mod tests {
 		assert_eq!(v, Vector3 { x: 7.0, y: 11.0, z: 5.0 });
 	}
 
+	#[test]
+	fn lerp() {
+		let mut v = Vector3::new(1.0, 2.0, 3.0);
+		v.lerp(&Vector3::new(3.0, 2.0, -1.0), 0.5);
+		assert_eq!(v, Vector3 { x: 2.0, y: 2.0, z: 1.0 });
+
+		v.lerp(&Vector3::new(3.0, 2.0, -1.0), 2.0);
+		assert_eq!(v, Vector3 { x: 4.0, y: 2.0, z: -3.0 });
+	}
+
 	#[test]
 	fn apply_quaternion() {
 		let mut v = Vector3::new(0.0, 0.0, 1.0);
@@ -357,6 +379,20 @@ mod tests {
 		assert_eq!(a, Vector3 { x: 1.0, y: 2.0, z: 0.0 });
 	}
 
+	#[test]
+	fn clamp() {
+		let min = Vector3::new(-10.0, -1.0, 2.0);
+		let max = Vector3::new(10.0, 1.0, 5.0);
+
+		let mut a = Vector3::new(-12.0, 0.5, 7.0);
+		a.clamp(&min, &max);
+		assert_eq!(a, Vector3 { x: -10.0, y: 0.5, z: 5.0 });
+
+		let mut b = Vector3::new(3.0, -4.0, -3.0);
+		b.clamp(&min, &max);
+		assert_eq!(b, Vector3 { x: 3.0, y: -1.0, z: 2.0 });
+	}
+
 	#[test]
 	fn add_vector() {
 		let a = Vector3::new(1.0, -2.0, 3.0);
@@ -476,5 +512,12 @@ mod tests {
 		let a = Vector3::new(1.0, 2.0, 3.0);
 		let b = Vector3::new(1.0, 2.0, 3.0);
 		assert_approx_eq(&a, &b, 0.0);
+
+		let c = Vector3::new(1.0 + 1e-7, 2.0 - 1e-7, 3.0);
+		assert_approx_eq(&a, &c, 1e-6);
+
+		let d = Vector3::new(1.5, 1.5, 3.5);
+		assert_approx_eq(&a, &d, 0.5);
+		assert!(!a.approx_eq(&d, 0.4));
 	}
 }
\ No newline at end of file
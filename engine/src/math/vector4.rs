@@ -1,3 +1,6 @@
+use super::{Vector3, ApproxEq};
+use auto_ops::impl_op_ex;
+
 pub const ZERO: Vector4 = Vector4 { x: 0.0, y: 0.0, z: 0.0, w: 0.0 };
 
 #[derive(Default, Copy, Clone, Debug, PartialEq)]
@@ -13,14 +16,314 @@ impl Vector4 {
 	pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
 		Self { x, y, z, w }
 	}
+
+	pub fn from_scalar(scalar: f32) -> Self {
+		Self { x: scalar, y: scalar, z: scalar, w: scalar }
+	}
+
+	pub fn set(&mut self, x: f32, y: f32, z: f32, w: f32) {
+		self.x = x;
+		self.y = y;
+		self.z = z;
+		self.w = w;
+	}
+
+	// Drops w without dividing by it
+	pub fn truncate(&self) -> Vector3 {
+		Vector3::new(self.x, self.y, self.z)
+	}
+
+	pub fn length(&self) -> f32 {
+		self.dot(self).sqrt()
+	}
+
+	pub fn dot(&self, other: &Self) -> f32 {
+		self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+	}
+}
+
+impl_op_ex!(+ |a: &Vector4, b: &Vector4| -> Vector4 {
+	let mut r = *a;
+	r += b;
+	r
+});
+
+impl_op_ex!(- |a: &Vector4, b: &Vector4| -> Vector4 {
+	let mut r = *a;
+	r -= b;
+	r
+});
+
+impl_op_ex!(* |a: &Vector4, b: &Vector4| -> Vector4 {
+	let mut r = *a;
+	r *= b;
+	r
+});
+
+impl_op_ex!(/ |a: &Vector4, b: &Vector4| -> Vector4 {
+	let mut r = *a;
+	r /= b;
+	r
+});
+
+impl_op_ex!(+= |a: &mut Vector4, b: &Vector4| {
+	a.x += b.x;
+	a.y += b.y;
+	a.z += b.z;
+	a.w += b.w;
+});
+
+impl_op_ex!(-= |a: &mut Vector4, b: &Vector4| {
+	a.x -= b.x;
+	a.y -= b.y;
+	a.z -= b.z;
+	a.w -= b.w;
+});
+
+impl_op_ex!(*= |a: &mut Vector4, b: &Vector4| {
+	a.x *= b.x;
+	a.y *= b.y;
+	a.z *= b.z;
+	a.w *= b.w;
+});
+
+impl_op_ex!(/= |a: &mut Vector4, b: &Vector4| {
+	a.x /= b.x;
+	a.y /= b.y;
+	a.z /= b.z;
+	a.w /= b.w;
+});
+
+impl_op_ex!(+ |a: &Vector4, b: f32| -> Vector4 {
+	let mut r = *a;
+	r += b;
+	r
+});
+
+impl_op_ex!(- |a: &Vector4, b: f32| -> Vector4 {
+	let mut r = *a;
+	r -= b;
+	r
+});
+
+impl_op_ex!(* |a: &Vector4, b: f32| -> Vector4 {
+	let mut r = *a;
+	r *= b;
+	r
+});
+
+impl_op_ex!(/ |a: &Vector4, b: f32| -> Vector4 {
+	let mut r = *a;
+	r /= b;
+	r
+});
+
+impl_op_ex!(+= |a: &mut Vector4, b: f32| {
+	a.x += b;
+	a.y += b;
+	a.z += b;
+	a.w += b;
+});
+
+impl_op_ex!(-= |a: &mut Vector4, b: f32| {
+	a.x -= b;
+	a.y -= b;
+	a.z -= b;
+	a.w -= b;
+});
+
+impl_op_ex!(*= |a: &mut Vector4, b: f32| {
+	a.x *= b;
+	a.y *= b;
+	a.z *= b;
+	a.w *= b;
+});
+
+impl_op_ex!(/= |a: &mut Vector4, b: f32| {
+	a.x /= b;
+	a.y /= b;
+	a.z /= b;
+	a.w /= b;
+});
+
+impl_op_ex!(- |a: &Vector4| -> Vector4 {
+	Vector4 {
+		x: -a.x,
+		y: -a.y,
+		z: -a.z,
+		w: -a.w
+	}
+});
+
+impl ApproxEq for Vector4 {
+	fn approx_eq(&self, other: &Self, tol: f32) -> bool {
+		let x_diff = (self.x - other.x).abs();
+		let y_diff = (self.y - other.y).abs();
+		let z_diff = (self.z - other.z).abs();
+		let w_diff = (self.w - other.w).abs();
+
+		x_diff <= tol && y_diff <= tol && z_diff <= tol && w_diff <= tol
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::math::assert_approx_eq;
 
 	#[test]
 	fn new() {
 		assert_eq!(Vector4::new(1.0, 2.0, 3.0, 4.0), Vector4 { x: 1.0, y: 2.0, z: 3.0, w: 4.0 });
 	}
+
+	#[test]
+	fn from_scalar() {
+		assert_eq!(Vector4::from_scalar(1.0), Vector4 { x: 1.0, y: 1.0, z: 1.0, w: 1.0 });
+	}
+
+	#[test]
+	fn set() {
+		let mut v = ZERO;
+		v.set(1.0, 2.0, 3.0, 4.0);
+		assert_eq!(v, Vector4 { x: 1.0, y: 2.0, z: 3.0, w: 4.0 });
+	}
+
+	#[test]
+	fn truncate() {
+		assert_eq!(Vector4::new(1.0, 2.0, 3.0, 4.0).truncate(), Vector3::new(1.0, 2.0, 3.0));
+	}
+
+	#[test]
+	fn length() {
+		assert_eq!(Vector4::new(1.0, 2.0, 2.0, 4.0).length(), 5.0);
+	}
+
+	#[test]
+	fn dot() {
+		let a = Vector4::new(1.0, -2.0, 3.0, 2.0);
+		let b = Vector4::new(4.0, -3.0, 1.0, -1.0);
+		assert_eq!(a.dot(&b), 11.0);
+	}
+
+	#[test]
+	fn add_vector() {
+		let a = Vector4::new(1.0, -2.0, 3.0, 0.5);
+		let b = Vector4::new(-3.0, 1.0, 2.0, 1.5);
+		assert_eq!(a + b, Vector4 { x: -2.0, y: -1.0, z: 5.0, w: 2.0 });
+	}
+
+	#[test]
+	fn sub_vector() {
+		let a = Vector4::new(1.0, -2.0, 3.0, 0.5);
+		let b = Vector4::new(-3.0, 1.0, 2.0, 1.5);
+		assert_eq!(a - b, Vector4 { x: 4.0, y: -3.0, z: 1.0, w: -1.0 });
+	}
+
+	#[test]
+	fn mul_vector() {
+		let a = Vector4::new(1.0, -2.0, 3.0, 0.5);
+		let b = Vector4::new(-3.0, 1.0, 2.0, 4.0);
+		assert_eq!(a * b, Vector4 { x: -3.0, y: -2.0, z: 6.0, w: 2.0 });
+	}
+
+	#[test]
+	fn div_vector() {
+		let a = Vector4::new(-3.0, 4.0, 6.0, 1.0);
+		let b = Vector4::new(1.0, -2.0, 3.0, 4.0);
+		assert_eq!(a / b, Vector4 { x: -3.0, y: -2.0, z: 2.0, w: 0.25 });
+	}
+
+	#[test]
+	fn add_assign_vector() {
+		let mut v = Vector4::new(1.0, -2.0, 3.0, 0.5);
+		v += Vector4::new(-3.0, 1.0, 2.0, 1.5);
+		assert_eq!(v, Vector4 { x: -2.0, y: -1.0, z: 5.0, w: 2.0 });
+	}
+
+	#[test]
+	fn sub_assign_vector() {
+		let mut v = Vector4::new(1.0, -2.0, 3.0, 0.5);
+		v -= Vector4::new(-3.0, 1.0, 2.0, 1.5);
+		assert_eq!(v, Vector4 { x: 4.0, y: -3.0, z: 1.0, w: -1.0 });
+	}
+
+	#[test]
+	fn mul_assign_vector() {
+		let mut v = Vector4::new(1.0, -2.0, 3.0, 0.5);
+		v *= Vector4::new(-3.0, 1.0, 2.0, 4.0);
+		assert_eq!(v, Vector4 { x: -3.0, y: -2.0, z: 6.0, w: 2.0 });
+	}
+
+	#[test]
+	fn div_assign_vector() {
+		let mut v = Vector4::new(-3.0, 4.0, 6.0, 1.0);
+		v /= Vector4::new(1.0, -2.0, 3.0, 4.0);
+		assert_eq!(v, Vector4 { x: -3.0, y: -2.0, z: 2.0, w: 0.25 });
+	}
+
+	#[test]
+	fn add_scalar() {
+		let v = Vector4::new(1.0, -2.0, 3.0, 0.0);
+		assert_eq!(v + 3.0, Vector4 { x: 4.0, y: 1.0, z: 6.0, w: 3.0 });
+	}
+
+	#[test]
+	fn sub_scalar() {
+		let v = Vector4::new(1.0, -2.0, 3.0, 0.0);
+		assert_eq!(v - 3.0, Vector4 { x: -2.0, y: -5.0, z: 0.0, w: -3.0 });
+	}
+
+	#[test]
+	fn mul_scalar() {
+		let v = Vector4::new(1.0, -2.0, 3.0, 0.0);
+		assert_eq!(v * 3.0, Vector4 { x: 3.0, y: -6.0, z: 9.0, w: 0.0 });
+	}
+
+	#[test]
+	fn div_scalar() {
+		let v = Vector4::new(-2.0, 4.0, 6.0, 1.0);
+		assert_eq!(v / 2.0, Vector4 { x: -1.0, y: 2.0, z: 3.0, w: 0.5 });
+	}
+
+	#[test]
+	fn add_assign_scalar() {
+		let mut v = Vector4::new(1.0, -2.0, 3.0, 0.0);
+		v += 3.0;
+		assert_eq!(v, Vector4 { x: 4.0, y: 1.0, z: 6.0, w: 3.0 });
+	}
+
+	#[test]
+	fn sub_assign_scalar() {
+		let mut v = Vector4::new(1.0, -2.0, 3.0, 0.0);
+		v -= 3.0;
+		assert_eq!(v, Vector4 { x: -2.0, y: -5.0, z: 0.0, w: -3.0 });
+	}
+
+	#[test]
+	fn mul_assign_scalar() {
+		let mut v = Vector4::new(1.0, -2.0, 3.0, 0.0);
+		v *= 3.0;
+		assert_eq!(v, Vector4 { x: 3.0, y: -6.0, z: 9.0, w: 0.0 });
+	}
+
+	#[test]
+	fn div_assign_scalar() {
+		let mut v = Vector4::new(-2.0, 4.0, 6.0, 1.0);
+		v /= 2.0;
+		assert_eq!(v, Vector4 { x: -1.0, y: 2.0, z: 3.0, w: 0.5 });
+	}
+
+	#[test]
+	fn neg() {
+		let v = Vector4::new(1.0, 2.0, -3.0, 4.0);
+		assert_eq!(-v, Vector4 { x: -1.0, y: -2.0, z: 3.0, w: -4.0 });
+	}
+
+	#[test]
+	fn approx_eq() {
+		let a = Vector4::new(1.0, 2.0, 3.0, 4.0);
+		let b = Vector4::new(1.5, 1.5, 3.5, 3.5);
+		assert_approx_eq(&a, &b, 0.5);
+		assert!(!a.approx_eq(&b, 0.4));
+	}
 }
\ No newline at end of file
@@ -125,12 +125,22 @@ impl<T> Pool<T> {
 		self.records.len()
 	}
 
-	pub fn occupied_record_count(&self) -> usize {
+	pub fn len(&self) -> usize {
 		self.records.len() - self.vacant_record_indices.len()
 	}
 
 	pub fn is_empty(&self) -> bool {
-		self.occupied_record_count() == 0
+		self.len() == 0
+	}
+
+	pub fn clear(&mut self) {
+		self.vacant_record_indices.clear();
+
+		// Records are kept so their generations carry over and old handles stay invalid
+		for (index, record) in self.records.iter_mut().enumerate().rev() {
+			record.payload = None;
+			self.vacant_record_indices.push(index);
+		}
 	}
 
 	pub fn iter(&self) -> Iter<T> {
@@ -174,7 +184,7 @@ pub struct Iter<'a, T> {
 }
 
 impl<'a, T> Iterator for Iter<'a, T> {
-	type Item = &'a T;
+	type Item = (Handle, &'a T);
 
 	fn next(&mut self) -> Option<Self::Item> {
 		if self.current_index == self.records.len() {
@@ -193,9 +203,14 @@ impl<'a, T> Iterator for Iter<'a, T> {
 			current_record = &self.records[self.current_index];
 		}
 
+		let handle = Handle {
+			index: self.current_index,
+			generation: current_record.generation
+		};
+
 		self.current_index += 1;
 
-		current_record.payload.as_ref()
+		Some((handle, current_record.payload.as_ref().unwrap()))
 	}
 }
 
@@ -205,7 +220,7 @@ pub struct IterMut<'a, T> {
 }
 
 impl<'a, T> Iterator for IterMut<'a, T> {
-	type Item = &'a mut T;
+	type Item = (Handle, &'a mut T);
 
 	fn next(&mut self) -> Option<Self::Item> {
 		if self.current_index == self.records.len() {
@@ -224,9 +239,16 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 			current_record = &mut self.records[self.current_index];
 		}
 
+		let current_record = unsafe { &mut *current_record };
+
+		let handle = Handle {
+			index: self.current_index,
+			generation: current_record.generation
+		};
+
 		self.current_index += 1;
 
-		unsafe { &mut *current_record }.payload.as_mut()
+		Some((handle, current_record.payload.as_mut().unwrap()))
 	}
 }
 
@@ -279,6 +301,23 @@ mod tests {
 		assert_eq!(pool.vacant_record_indices[0], 0);
 	}
 
+	#[test]
+	fn stale_handle() {
+		let mut pool = Pool::<u32>::new();
+		let old_handle = pool.add(4);
+		pool.remove(old_handle);
+		let new_handle = pool.add(5);
+
+		assert_eq!(old_handle.index, new_handle.index);
+		assert!(!pool.valid_handle(old_handle));
+		assert!(pool.try_borrow(old_handle).is_none());
+		assert!(pool.try_borrow_mut(old_handle).is_none());
+		assert_eq!(pool.try_borrow(new_handle), Some(&5));
+
+		let result = panic::catch_unwind(|| pool.borrow(old_handle));
+		assert!(result.is_err());
+	}
+
 	#[test]
 	fn borrow() {
 		let mut pool = Pool::<u32>::new();
@@ -336,13 +375,42 @@ mod tests {
 	}
 
 	#[test]
-	fn occupied_record_count() {
+	fn len() {
 		let mut pool = Pool::<u32>::new();
+		assert_eq!(pool.len(), 0);
+		assert!(pool.is_empty());
+
 		let handle = pool.add(4);
 		pool.add(6);
+		assert_eq!(pool.len(), 2);
+
 		pool.remove(handle);
+		assert_eq!(pool.len(), 1);
+		assert!(!pool.is_empty());
 
-		assert_eq!(pool.occupied_record_count(), 1);
+		pool.add(8);
+		assert_eq!(pool.len(), 2);
+	}
+
+	#[test]
+	fn clear() {
+		let mut pool = Pool::<u32>::new();
+		let handle_1 = pool.add(4);
+		let handle_2 = pool.add(6);
+		pool.remove(handle_1);
+		pool.clear();
+
+		assert_eq!(pool.len(), 0);
+		assert!(pool.is_empty());
+		assert_eq!(pool.capacity(), 2);
+		assert!(pool.try_borrow(handle_1).is_none());
+		assert!(pool.try_borrow(handle_2).is_none());
+		assert_eq!(pool.iter().count(), 0);
+
+		let handle_3 = pool.add(8);
+		assert_eq!(handle_3.index, 0);
+		assert!(pool.try_borrow(handle_1).is_none());
+		assert_eq!(pool.borrow(handle_3), &8);
 	}
 
 	#[test]
@@ -367,17 +435,17 @@ mod tests {
 	fn iter() {
 		let mut pool = Pool::<u32>::new();
 		
-		pool.add(0);
+		let handle_0 = pool.add(0);
 		let handle_1 = pool.add(1);
 		let handle_2 = pool.add(2);
-		pool.add(3);
+		let handle_3 = pool.add(3);
 
 		pool.remove(handle_1);
 		pool.remove(handle_2);
 
 		let mut iter = pool.iter();
-		assert_eq!(iter.next(), Some(&0));
-		assert_eq!(iter.next(), Some(&3));
+		assert_eq!(iter.next(), Some((handle_0, &0)));
+		assert_eq!(iter.next(), Some((handle_3, &3)));
 		assert_eq!(iter.next(), None);
 	}
 
@@ -385,17 +453,39 @@ mod tests {
 	fn iter_mut() {
 		let mut pool = Pool::<u32>::new();
 		
-		pool.add(0);
+		let handle_0 = pool.add(0);
 		let handle_1 = pool.add(1);
 		let handle_2 = pool.add(2);
-		pool.add(3);
+		let handle_3 = pool.add(3);
 
 		pool.remove(handle_1);
 		pool.remove(handle_2);
 
 		let mut iter = pool.iter_mut();
-		assert_eq!(iter.next(), Some(&mut 0));
-		assert_eq!(iter.next(), Some(&mut 3));
+		assert_eq!(iter.next(), Some((handle_0, &mut 0)));
+		assert_eq!(iter.next(), Some((handle_3, &mut 3)));
 		assert_eq!(iter.next(), None);
 	}
+
+	#[test]
+	fn iter_reused_record() {
+		let mut pool = Pool::<u32>::new();
+
+		let handle_0 = pool.add(0);
+		let handle_1 = pool.add(1);
+		pool.remove(handle_0);
+		let handle_2 = pool.add(2);
+
+		assert_ne!(handle_0, handle_2);
+
+		let entries: Vec<(Handle, u32)> = pool.iter().map(|(handle, payload)| (handle, *payload)).collect();
+		assert_eq!(entries, vec![(handle_2, 2), (handle_1, 1)]);
+
+		for (_, payload) in pool.iter_mut() {
+			*payload += 10;
+		}
+
+		assert_eq!(pool.borrow(handle_1), &11);
+		assert_eq!(pool.borrow(handle_2), &12);
+	}
 }
\ No newline at end of file
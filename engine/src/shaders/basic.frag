@@ -1,10 +1,16 @@
 #version 450
 #extension GL_ARB_separate_shader_objects : enable
 
+layout(constant_id = 0) const bool gammaCorrection = false;
+
 layout(location = 0) in vec3 fragColor;
+layout(location = 1) flat in vec4 fragTint;
 
 layout(location = 0) out vec4 outColor;
 
 void main() {
-	outColor = vec4(fragColor, 1.0);
+	// The tint's alpha is its strength, 0 leaves the color unchanged
+	vec3 tintedColor = fragColor * mix(vec3(1.0), fragTint.rgb, fragTint.a);
+	vec3 color = gammaCorrection ? pow(tintedColor, vec3(1.0 / 2.2)) : tintedColor;
+	outColor = vec4(color, 1.0);
 }
\ No newline at end of file
@@ -6,20 +6,24 @@ layout(set = 0, binding = 0, std140, row_major) uniform FrameData {
 	mat4 viewMatrix;
 };
 
+struct Instance {
+	mat4 modelMatrix;
+	vec3 color;
+	vec4 tint;
+};
+
 layout(set = 1, binding = 0, std140, row_major) buffer InstanceData {
-	mat4 modelMatrix[];
+	Instance instances[];
 };
 
+invariant gl_Position;
+
 layout(location = 0) in vec3 inPosition;
 layout(location = 0) out vec3 fragColor;
-
-vec3 colors[3] = vec3[](
-	vec3(1.0, 0.0, 0.0),
-	vec3(0.0, 1.0, 0.0),
-	vec3(0.0, 0.0, 1.0)
-);
+layout(location = 1) flat out vec4 fragTint;
 
 void main() {
-	gl_Position = projectionMatrix * viewMatrix * modelMatrix[gl_InstanceIndex] * vec4(inPosition, 1.0);
-	fragColor = vec3(0.1, 0.1, 0.1);
+	gl_Position = projectionMatrix * viewMatrix * (instances[gl_InstanceIndex].modelMatrix * vec4(inPosition, 1.0));
+	fragColor = instances[gl_InstanceIndex].color;
+	fragTint = instances[gl_InstanceIndex].tint;
 }
\ No newline at end of file
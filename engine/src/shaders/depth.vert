@@ -0,0 +1,27 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(set = 0, binding = 0, std140, row_major) uniform FrameData {
+	mat4 projectionMatrix;
+	mat4 viewMatrix;
+};
+
+// Laid out like the shadow pass instance data so every material's instances can be read
+layout(set = 1, binding = 0, std140) buffer InstanceData {
+	vec4 instanceData[];
+};
+
+layout(push_constant) uniform PushConstants {
+	uint instanceStride;
+};
+
+layout(location = 0) in vec3 inPosition;
+
+// The color pass tests for equal depth so the position must be calculated exactly like the mesh shaders
+invariant gl_Position;
+
+void main() {
+	uint base = gl_InstanceIndex * instanceStride;
+	mat4 modelMatrix = transpose(mat4(instanceData[base], instanceData[base + 1], instanceData[base + 2], instanceData[base + 3]));
+	gl_Position = projectionMatrix * viewMatrix * (modelMatrix * vec4(inPosition, 1.0));
+}
\ No newline at end of file
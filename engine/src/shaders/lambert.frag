@@ -1,10 +1,82 @@
 #version 450
 #extension GL_ARB_separate_shader_objects : enable
 
+#define MAX_POINT_LIGHTS 5
+#define SHADOW_BIAS 0.002
+
+layout(constant_id = 0) const bool gammaCorrection = false;
+
+struct PointLight {
+	vec3 position;
+	vec3 color;
+};
+
+layout(set = 0, binding = 0, std140, row_major) uniform FrameData {
+	mat4 projectionMatrix;
+	mat4 viewMatrix;
+	vec3 ambientLight;
+	uint pointLightCount;
+	PointLight pointLights[MAX_POINT_LIGHTS];
+	vec3 directionalLightDirection;
+	uint shadowsEnabled;
+	vec3 directionalLightColor;
+	mat4 lightSpaceMatrix;
+	vec3 cameraPosition;
+	vec3 fogColor;
+	float fogDensity;
+	uint fogEnabled;
+};
+
+layout(set = 2, binding = 0) uniform sampler2DShadow shadowMap;
+
 layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec3 fragDirectionalColor;
+layout(location = 2) in vec4 fragLightSpacePosition;
+layout(location = 3) flat in uint fragShadowsEnabled;
+layout(location = 4) in float fragViewDepth;
+layout(location = 5) flat in vec4 fragTint;
 
 layout(location = 0) out vec4 outColor;
 
+float calculateShadow() {
+	vec3 position = fragLightSpacePosition.xyz / fragLightSpacePosition.w;
+
+	// Fragments beyond the far plane of the shadow volume are lit
+	if (position.z > 1.0) {
+		return 1.0;
+	}
+
+	// Average a 3x3 area of depth comparisons to soften the edges
+	vec2 uv = position.xy * 0.5 + 0.5;
+	vec2 texelSize = 1.0 / vec2(textureSize(shadowMap, 0));
+	float shadow = 0.0;
+
+	for (int x = -1; x <= 1; x++) {
+		for (int y = -1; y <= 1; y++) {
+			shadow += texture(shadowMap, vec3(uv + vec2(x, y) * texelSize, position.z - SHADOW_BIAS));
+		}
+	}
+
+	return shadow / 9.0;
+}
+
 void main() {
-	outColor = vec4(fragColor, 1.0);
+	float shadow = fragShadowsEnabled == 1u ? calculateShadow() : 1.0;
+	vec3 color = fragColor + fragDirectionalColor * shadow;
+
+	// The tint's alpha is its strength, 0 leaves the color unchanged
+	color *= mix(vec3(1.0), fragTint.rgb, fragTint.a);
+
+	// Fog is blended before gamma correction since the colors are linear
+	if (fogEnabled == 1u) {
+		float fogFactor = exp(-fogDensity * fragViewDepth);
+		color = mix(fogColor, color, fogFactor);
+	}
+
+	// Lighting is calculated in linear space so it's encoded before being written to a non sRGB image
+	if (gammaCorrection) {
+		color = pow(color, vec3(1.0 / 2.2));
+	}
+
+	outColor = vec4(color, 1.0);
 }
\ No newline at end of file
@@ -14,23 +14,46 @@ layout(set = 0, binding = 0, std140, row_major) uniform FrameData {
 	vec3 ambientLight;
 	uint pointLightCount;
 	PointLight pointLights[MAX_POINT_LIGHTS];
+	vec3 directionalLightDirection;
+	uint shadowsEnabled;
+	vec3 directionalLightColor;
+	mat4 lightSpaceMatrix;
+	vec3 cameraPosition;
+	vec3 fogColor;
+	float fogDensity;
+	uint fogEnabled;
+};
+
+struct Instance {
+	mat4 modelMatrix;
+	mat3 normalMatrix;
+	vec4 tint;
 };
 
 layout(set = 1, binding = 0, std140, row_major) buffer InstanceData {
-	mat4 modelMatrix[];
+	Instance instances[];
 };
 
+invariant gl_Position;
+
 layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec3 inNormal;
 
 layout(location = 0) out vec3 fragColor;
+layout(location = 1) out vec3 fragDirectionalColor;
+layout(location = 2) out vec4 fragLightSpacePosition;
+layout(location = 3) flat out uint fragShadowsEnabled;
+layout(location = 4) out float fragViewDepth;
+layout(location = 5) flat out vec4 fragTint;
 
 void main() {
-	vec4 vertexPositionObjectSpaceVec4 = modelMatrix[gl_InstanceIndex] * vec4(inPosition, 1.0);
+	vec4 vertexPositionObjectSpaceVec4 = instances[gl_InstanceIndex].modelMatrix * vec4(inPosition, 1.0);
 	vec3 vertexPositionObjectSpaceVec3 = vec3(vertexPositionObjectSpaceVec4);
-	vec3 vertexNormalObjectSpace = mat3(transpose(inverse(modelMatrix[gl_InstanceIndex]))) * inNormal;
+	vec3 vertexNormalObjectSpace = normalize(instances[gl_InstanceIndex].normalMatrix * inNormal);
 	
-	gl_Position = projectionMatrix * viewMatrix * vertexPositionObjectSpaceVec4;
+	vec4 vertexPositionViewSpace = viewMatrix * vertexPositionObjectSpaceVec4;
+	gl_Position = projectionMatrix * vertexPositionViewSpace;
+	fragViewDepth = -vertexPositionViewSpace.z;
 
 	fragColor = ambientLight;
 
@@ -39,4 +62,11 @@ void main() {
 		float diffuse = max(dot(vertexNormalObjectSpace, lightDirection), 0.0f);
 		fragColor += pointLights[i].color * diffuse;
 	}
+
+	// The directional light is kept separate so the fragment shader can shadow it
+	float directionalDiffuse = max(dot(normalize(vertexNormalObjectSpace), -directionalLightDirection), 0.0f);
+	fragDirectionalColor = directionalLightColor * directionalDiffuse;
+	fragLightSpacePosition = lightSpaceMatrix * vertexPositionObjectSpaceVec4;
+	fragShadowsEnabled = shadowsEnabled;
+	fragTint = instances[gl_InstanceIndex].tint;
 }
\ No newline at end of file
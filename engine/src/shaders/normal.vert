@@ -6,15 +6,24 @@ layout(set = 0, binding = 0, std140, row_major) uniform FrameData {
 	mat4 viewMatrix;
 };
 
+struct Instance {
+	mat4 modelMatrix;
+	vec4 tint;
+};
+
 layout(set = 1, binding = 0, std140, row_major) buffer InstanceData {
-	mat4 modelMatrix[];
+	Instance instances[];
 };
 
+invariant gl_Position;
+
 layout(location = 0) in vec3 inPosition;
 layout(location = 1) in vec3 inNormal;
 layout(location = 0) out vec3 fragColor;
+layout(location = 1) flat out vec4 fragTint;
 
 void main() {
-	gl_Position = projectionMatrix * viewMatrix * modelMatrix[gl_InstanceIndex] * vec4(inPosition, 1.0);
+	gl_Position = projectionMatrix * viewMatrix * (instances[gl_InstanceIndex].modelMatrix * vec4(inPosition, 1.0));
 	fragColor = inNormal * 0.5 + 0.5;
+	fragTint = instances[gl_InstanceIndex].tint;
 }
\ No newline at end of file
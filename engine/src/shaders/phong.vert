@@ -0,0 +1,60 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+#define MAX_POINT_LIGHTS 5
+
+struct PointLight {
+	vec3 position;
+	vec3 color;
+};
+
+layout(set = 0, binding = 0, std140, row_major) uniform FrameData {
+	mat4 projectionMatrix;
+	mat4 viewMatrix;
+	vec3 ambientLight;
+	uint pointLightCount;
+	PointLight pointLights[MAX_POINT_LIGHTS];
+	vec3 directionalLightDirection;
+	uint shadowsEnabled;
+	vec3 directionalLightColor;
+	mat4 lightSpaceMatrix;
+	vec3 cameraPosition;
+};
+
+struct Instance {
+	mat4 modelMatrix;
+	mat3 normalMatrix;
+	vec3 specular;
+	float shininess;
+	vec4 tint;
+};
+
+layout(set = 1, binding = 0, std140, row_major) buffer InstanceData {
+	Instance instances[];
+};
+
+invariant gl_Position;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inNormal;
+
+layout(location = 0) out vec3 fragPosition;
+layout(location = 1) out vec3 fragNormal;
+layout(location = 2) out vec4 fragLightSpacePosition;
+layout(location = 3) flat out vec3 fragSpecular;
+layout(location = 4) flat out float fragShininess;
+layout(location = 5) flat out vec4 fragTint;
+
+void main() {
+	vec4 vertexPositionWorldSpace = instances[gl_InstanceIndex].modelMatrix * vec4(inPosition, 1.0);
+
+	gl_Position = projectionMatrix * viewMatrix * vertexPositionWorldSpace;
+
+	// The specular term depends on the view direction so lighting is calculated per fragment
+	fragPosition = vec3(vertexPositionWorldSpace);
+	fragNormal = instances[gl_InstanceIndex].normalMatrix * inNormal;
+	fragLightSpacePosition = lightSpaceMatrix * vertexPositionWorldSpace;
+	fragSpecular = instances[gl_InstanceIndex].specular;
+	fragShininess = instances[gl_InstanceIndex].shininess;
+	fragTint = instances[gl_InstanceIndex].tint;
+}
\ No newline at end of file
@@ -0,0 +1,39 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+#define MAX_POINT_LIGHTS 5
+
+struct PointLight {
+	vec3 position;
+	vec3 color;
+};
+
+layout(set = 0, binding = 0, std140, row_major) uniform FrameData {
+	mat4 projectionMatrix;
+	mat4 viewMatrix;
+	vec3 ambientLight;
+	uint pointLightCount;
+	PointLight pointLights[MAX_POINT_LIGHTS];
+	vec3 directionalLightDirection;
+	uint shadowsEnabled;
+	vec3 directionalLightColor;
+	mat4 lightSpaceMatrix;
+};
+
+// The instance data of each material starts with the row major model matrix but the instance sizes differ
+layout(set = 1, binding = 0, std140) buffer InstanceData {
+	vec4 instanceData[];
+};
+
+// The number of vec4s per instance
+layout(push_constant) uniform PushConstants {
+	uint instanceStride;
+};
+
+layout(location = 0) in vec3 inPosition;
+
+void main() {
+	uint base = gl_InstanceIndex * instanceStride;
+	mat4 modelMatrix = transpose(mat4(instanceData[base], instanceData[base + 1], instanceData[base + 2], instanceData[base + 3]));
+	gl_Position = lightSpaceMatrix * modelMatrix * vec4(inPosition, 1.0);
+}
\ No newline at end of file
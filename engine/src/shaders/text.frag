@@ -1,15 +1,53 @@
 #version 450
 #extension GL_ARB_separate_shader_objects : enable
 
+layout(constant_id = 0) const bool gammaCorrection = false;
+
 layout(set = 1, binding = 0) uniform sampler samp;
 layout(set = 2, binding = 0) uniform texture2D atlases[10];
 
 layout(location = 0) in vec2 fragTexPosition;
 layout(location = 1) in flat uint atlasIndex;
+layout(location = 2) in flat vec3 color;
+layout(location = 3) in flat vec4 glyphBounds;
+layout(location = 4) in flat vec3 outlineColor;
+layout(location = 5) in flat float outlineWidth;
+layout(location = 6) in flat uint tofu;
 
 layout(location = 0) out vec4 outColor;
 
+// Texels outside of the glyph belong to other glyphs in the atlas
+float sampleCoverage(vec2 position) {
+	if (any(lessThan(position, glyphBounds.xy)) || any(greaterThan(position, glyphBounds.zw))) {
+		return 0.0;
+	}
+
+	// A tofu box is a 1 pixel border around the glyph bounds rather than a texel in an atlas
+	if (tofu == 1) {
+		vec2 edgeDistance = min(position - glyphBounds.xy, glyphBounds.zw - position);
+		return min(edgeDistance.x, edgeDistance.y) <= 1.0 ? 1.0 : 0.0;
+	}
+
+	return texture(sampler2D(atlases[atlasIndex], samp), position).r;
+}
+
 void main() {
-	float alpha = texture(sampler2D(atlases[atlasIndex], samp), fragTexPosition).r;
-	outColor = vec4(1, 1, 1, alpha);
+	float fill = sampleCoverage(fragTexPosition);
+	float outline = 0.0;
+
+	// The outline is a band around the glyph found by taking the max coverage within the outline width
+	int radius = int(ceil(outlineWidth));
+
+	for (int x = -radius; x <= radius; x++) {
+		for (int y = -radius; y <= radius; y++) {
+			float distance = length(vec2(x, y));
+			float weight = 1.0 - smoothstep(outlineWidth - 0.5, outlineWidth + 0.5, distance);
+			outline = max(outline, sampleCoverage(fragTexPosition + vec2(x, y)) * weight);
+		}
+	}
+
+	// The fill is composited over the outline
+	float alpha = max(fill, outline);
+	vec3 rgb = alpha > 0.0 ? mix(outlineColor, color, fill / alpha) : color;
+	outColor = vec4(gammaCorrection ? pow(rgb, vec3(1.0 / 2.2)) : rgb, alpha);
 }
\ No newline at end of file
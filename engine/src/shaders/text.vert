@@ -3,25 +3,54 @@
 
 struct InstanceData {
 	mat3 matrix;
+	vec3 color;
 	uint atlasIndex;
+	vec3 outlineColor;
+	float outlineWidth;
+	uvec3 fallbackAtlasIndices;
 };
 
+// Glyphs no font has are drawn as a box, slot 0 is the primary font followed by the fallback fonts
+const uint TOFU_FONT_SLOT = 4;
+
 layout(set = 0, binding = 0, std140, row_major) buffer InstanceDataBlock {
 	InstanceData instanceData[];
 };
 
 layout(location = 0) in vec2 inPosition;
 layout(location = 1) in vec2 inTexPosition;
+layout(location = 2) in vec4 inGlyphBounds;
+layout(location = 3) in float inFontSlot;
+// All text is merged into one draw so the instance data is looked up per vertex
+layout(location = 4) in uint inInstanceIndex;
 
 layout(location = 0) out vec2 fragTexPosition;
 layout(location = 1) out flat uint outAtlasIndex;
+layout(location = 2) out flat vec3 outColor;
+layout(location = 3) out flat vec4 outGlyphBounds;
+layout(location = 4) out flat vec3 outOutlineColor;
+layout(location = 5) out flat float outOutlineWidth;
+layout(location = 6) out flat uint outTofu;
 
 void main() {
-	InstanceData currentInstanceData = instanceData[gl_InstanceIndex];
+	InstanceData currentInstanceData = instanceData[inInstanceIndex];
 
 	vec3 normalized_position = currentInstanceData.matrix * vec3(inPosition, 1.0);
 	gl_Position = vec4(normalized_position.xy, 0.0, 1.0);
 
-	outAtlasIndex = currentInstanceData.atlasIndex;
+	uint fontSlot = uint(inFontSlot);
+
+	if (fontSlot == 0 || fontSlot == TOFU_FONT_SLOT) {
+		outAtlasIndex = currentInstanceData.atlasIndex;
+	}
+	else {
+		outAtlasIndex = currentInstanceData.fallbackAtlasIndices[fontSlot - 1];
+	}
+
+	outTofu = fontSlot == TOFU_FONT_SLOT ? 1 : 0;
+	outColor = currentInstanceData.color;
+	outGlyphBounds = inGlyphBounds;
+	outOutlineColor = currentInstanceData.outlineColor;
+	outOutlineWidth = currentInstanceData.outlineWidth;
 	fragTexPosition = inTexPosition;
 }
\ No newline at end of file
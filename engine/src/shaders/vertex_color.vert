@@ -0,0 +1,29 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(set = 0, binding = 0, std140, row_major) uniform FrameData {
+	mat4 projectionMatrix;
+	mat4 viewMatrix;
+};
+
+struct Instance {
+	mat4 modelMatrix;
+	vec4 tint;
+};
+
+layout(set = 1, binding = 0, std140, row_major) buffer InstanceData {
+	Instance instances[];
+};
+
+invariant gl_Position;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inColor;
+layout(location = 0) out vec3 fragColor;
+layout(location = 1) flat out vec4 fragTint;
+
+void main() {
+	gl_Position = projectionMatrix * viewMatrix * (instances[gl_InstanceIndex].modelMatrix * vec4(inPosition, 1.0));
+	fragColor = inColor;
+	fragTint = instances[gl_InstanceIndex].tint;
+}
\ No newline at end of file
@@ -1,5 +1,5 @@
 pub mod render_system;
-pub use render_system::RenderSystem;
+pub use render_system::{RenderSystem, RenderSystemError, ScreenshotError, SampleCount, CullMode, FrontFace, Viewport};
 
 pub mod mesh_bounds_helper_system;
 pub use mesh_bounds_helper_system::MeshBoundsHelperSystem;
\ No newline at end of file
@@ -1,22 +1,31 @@
-use std::{mem::{MaybeUninit, transmute}, cmp::{min, max}};
+use std::{cmp::{min, max}, ptr};
 use ash::{vk, version::DeviceV1_0, version::InstanceV1_0, extensions::khr};
 use crate::vulkan::{Context, Buffer};
-use super::{Swapchain, DepthImageResources, SwapchainFrame, InFlightFrame, InstanceDataResources, IN_FLIGHT_FRAMES_COUNT, FRAME_DATA_MEMORY_SIZE, MAX_FONTS};
+use super::{RenderSystemError, SampleCount, Swapchain, ImageResources, SwapchainFrame, InFlightFrame, InstanceDataResources, FRAME_DATA_MEMORY_SIZE, MAX_FONTS, MAX_VIEWPORTS, RENDER_TARGET_FORMAT, frame_data_stride};
+
+// Line, basic, normal, lambert, phong, vertex color and text instance data then shadow, depth prepass and debug
+const SECONDARY_COMMAND_BUFFERS_PER_FRAME: usize = 10;
+
+// The final layout is PRESENT_SRC_KHR for the swapchain and SHADER_READ_ONLY_OPTIMAL for render targets
+// The swapchain and scaled render target passes only differ in their layouts so they're compatible and share the same pipelines
+// Render targets use RENDER_TARGET_FORMAT so their pass isn't compatible and has its own pipelines
+pub fn create_render_pass(context: &Context, format: vk::Format, sample_count: vk::SampleCountFlags, final_layout: vk::ImageLayout) -> Result<vk::RenderPass, RenderSystemError> {
+	// When multisampling, the color attachment is resolved into a third single sampled attachment which is presented
+	let multisampled = sample_count != vk::SampleCountFlags::TYPE_1;
 
-pub fn create_render_pass(context: &Context) -> vk::RenderPass {
 	let color_attachment_description = vk::AttachmentDescription::builder()
-		.format(context.surface.format.format)
-		.samples(vk::SampleCountFlags::TYPE_1)
+		.format(format)
+		.samples(sample_count)
 		.load_op(vk::AttachmentLoadOp::CLEAR)
-		.store_op(vk::AttachmentStoreOp::STORE)
+		.store_op(if multisampled { vk::AttachmentStoreOp::DONT_CARE } else { vk::AttachmentStoreOp::STORE })
 		.stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
 		.stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
 		.initial_layout(vk::ImageLayout::UNDEFINED)
-		.final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+		.final_layout(if multisampled { vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL } else { final_layout });
 
 	let depth_attachment_description = vk::AttachmentDescription::builder()
 		.format(vk::Format::D32_SFLOAT)
-		.samples(vk::SampleCountFlags::TYPE_1)
+		.samples(sample_count)
 		.load_op(vk::AttachmentLoadOp::CLEAR)
 		.store_op(vk::AttachmentStoreOp::DONT_CARE)
 		.stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
@@ -24,7 +33,21 @@ pub fn create_render_pass(context: &Context) -> vk::RenderPass {
 		.initial_layout(vk::ImageLayout::UNDEFINED)
 		.final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
 
-	let attachment_descriptions = [color_attachment_description.build(), depth_attachment_description.build()];
+	let resolve_attachment_description = vk::AttachmentDescription::builder()
+		.format(format)
+		.samples(vk::SampleCountFlags::TYPE_1)
+		.load_op(vk::AttachmentLoadOp::DONT_CARE)
+		.store_op(vk::AttachmentStoreOp::STORE)
+		.stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+		.stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+		.initial_layout(vk::ImageLayout::UNDEFINED)
+		.final_layout(final_layout);
+
+	let mut attachment_descriptions = vec![color_attachment_description.build(), depth_attachment_description.build()];
+
+	if multisampled {
+		attachment_descriptions.push(resolve_attachment_description.build());
+	}
 	
 	let color_attachment_ref = vk::AttachmentReference::builder()
 		.attachment(0)
@@ -34,11 +57,21 @@ pub fn create_render_pass(context: &Context) -> vk::RenderPass {
 	let depth_attachment_ref = vk::AttachmentReference::builder()
 		.attachment(1)
 		.layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+	let resolve_attachment_ref = vk::AttachmentReference::builder()
+		.attachment(2)
+		.layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+	let resolve_attachment_refs = [resolve_attachment_ref.build()];
 	
-	let subpass_description = vk::SubpassDescription::builder()
+	let mut subpass_description = vk::SubpassDescription::builder()
 		.pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
 		.color_attachments(&color_attachment_refs)
 		.depth_stencil_attachment(&depth_attachment_ref);
+
+	if multisampled {
+		subpass_description = subpass_description.resolve_attachments(&resolve_attachment_refs);
+	}
+
 	let subpass_descriptions = [subpass_description.build()];
 
 	let subpass_dependency = vk::SubpassDependency::builder()
@@ -48,24 +81,57 @@ pub fn create_render_pass(context: &Context) -> vk::RenderPass {
 		.src_access_mask(vk::AccessFlags::empty())
 		.dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
 		.dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE);
-	let subpass_dependencies = [subpass_dependency.build()];
+
+	// Render targets are read after the render pass by shaders or transfers
+	let render_target_subpass_dependency = vk::SubpassDependency::builder()
+		.src_subpass(0)
+		.dst_subpass(vk::SUBPASS_EXTERNAL)
+		.src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+		.src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+		.dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::TRANSFER)
+		.dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::TRANSFER_READ);
+
+	let subpass_dependencies = [subpass_dependency.build(), render_target_subpass_dependency.build()];
 	
 	let render_pass_create_info = vk::RenderPassCreateInfo::builder()
 		.attachments(&attachment_descriptions)
 		.subpasses(&subpass_descriptions)
 		.dependencies(&subpass_dependencies);
 	
-	unsafe { context.logical_device.create_render_pass(&render_pass_create_info, None).unwrap() }
+	Ok(unsafe { context.logical_device.create_render_pass(&render_pass_create_info, None) }?)
+}
+
+pub fn choose_sample_count(context: &Context, requested_sample_count: SampleCount) -> vk::SampleCountFlags {
+	let candidates = [
+		vk::SampleCountFlags::TYPE_8,
+		vk::SampleCountFlags::TYPE_4,
+		vk::SampleCountFlags::TYPE_2
+	];
+
+	let requested = requested_sample_count.as_flags();
+	let supported = context.physical_device.supported_sample_counts;
+
+	candidates.iter()
+		.find(|&&candidate| candidate.as_raw() <= requested.as_raw() && supported.contains(candidate))
+		.copied()
+		.unwrap_or(vk::SampleCountFlags::TYPE_1)
+}
+
+// The format the swapchain pipelines and render pass draw in, without a surface that's the render target format
+pub(super) fn color_format(context: &Context) -> vk::Format {
+	context.surface.as_ref().map_or(RENDER_TARGET_FORMAT, |surface| surface.format.format)
 }
 
-pub(super) fn create_swapchain(context: &Context, framebuffer_width: u32, framebuffer_height: u32, render_pass: vk::RenderPass) -> Swapchain {
+pub(super) fn create_swapchain(context: &Context, framebuffer_width: u32, framebuffer_height: u32, render_pass: vk::RenderPass, sample_count: vk::SampleCountFlags) -> Result<Swapchain, RenderSystemError> {
+	let surface = context.surface.as_ref().ok_or(RenderSystemError::Headless)?;
+
 	// Get present mode
-	let present_modes = unsafe { context.surface.extension.get_physical_device_surface_present_modes(context.physical_device.handle, context.surface.handle).unwrap() };
+	let present_modes = unsafe { surface.extension.get_physical_device_surface_present_modes(context.physical_device.handle, surface.handle) }?;
 	let present_mode_option = present_modes.iter().find(|&&m| m == vk::PresentModeKHR::FIFO);
 	let present_mode = *present_mode_option.unwrap_or_else(|| &present_modes[0]);
 
 	// Create extent
-	let capabilities = unsafe { context.surface.extension.get_physical_device_surface_capabilities(context.physical_device.handle, context.surface.handle).unwrap() };
+	let capabilities = unsafe { surface.extension.get_physical_device_surface_capabilities(context.physical_device.handle, surface.handle) }?;
 	let extent = if capabilities.current_extent.width == u32::MAX {
 		vk::Extent2D::builder()
 			.width(max(capabilities.min_image_extent.width, min(capabilities.max_image_extent.width, framebuffer_width)))
@@ -76,20 +142,31 @@ pub(super) fn create_swapchain(context: &Context, framebuffer_width: u32, frameb
 		capabilities.current_extent
 	};
 
-	// Create swapchain extension, handle & images
+	// Ensure D32_SFLOAT format is supported for depth buffering
+	let format_properties = unsafe { context.instance.get_physical_device_format_properties(context.physical_device.handle, vk::Format::D32_SFLOAT) };
+	let required_format_feature = vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT;
+	if format_properties.optimal_tiling_features & required_format_feature != required_format_feature {
+		return Err(RenderSystemError::UnsupportedDepthFormat(vk::Format::D32_SFLOAT));
+	}
+
+	// Create swapchain extension & handle
 	let mut image_count = capabilities.min_image_count + 1;
 	if capabilities.max_image_count > 0 && image_count > capabilities.max_image_count {
 		image_count = capabilities.max_image_count;
 	}
 
+	// Only color attachment usage is guaranteed, screenshots need transfer source and the render scale blit needs transfer destination
+	let optional_usage = vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST;
+	let usage = vk::ImageUsageFlags::COLOR_ATTACHMENT | (capabilities.supported_usage_flags & optional_usage);
+
 	let mut swapchain_create_info = vk::SwapchainCreateInfoKHR::builder()
-		.surface(context.surface.handle)
+		.surface(surface.handle)
 		.min_image_count(image_count)
-		.image_format(context.surface.format.format)
-		.image_color_space(context.surface.format.color_space)
+		.image_format(surface.format.format)
+		.image_color_space(surface.format.color_space)
 		.image_extent(extent)
 		.image_array_layers(1)
-		.image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+		.image_usage(usage)
 		.pre_transform(capabilities.current_transform)
 		.composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
 		.present_mode(present_mode)
@@ -108,77 +185,63 @@ pub(super) fn create_swapchain(context: &Context, framebuffer_width: u32, frameb
 	}
 
 	let extension = khr::Swapchain::new(&context.instance, &context.logical_device);
-	let handle = unsafe { extension.create_swapchain(&swapchain_create_info, None).unwrap() };
-	let images = unsafe { extension.get_swapchain_images(handle).unwrap() };
+	let handle = unsafe { extension.create_swapchain(&swapchain_create_info, None) }?;
 
-	// Ensure D32_SFLOAT format is supported for depth buffering
-	let required_format = vk::Format::D32_SFLOAT;
-	let format_properties = unsafe { context.instance.get_physical_device_format_properties(context.physical_device.handle, required_format) };
-	let required_format_feature = vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT;
-	if format_properties.optimal_tiling_features & required_format_feature != required_format_feature {
-		panic!("Required format for depth buffering not supported");
+	// The rest is filled in by create_swapchain_resources, null handles are ignored when a failure destroys what was created so far
+	let mut swapchain = Swapchain {
+		extension,
+		handle,
+		extent,
+		depth_image_resources: ImageResources::default(),
+		color_image_resources: None,
+		frames: vec![],
+		usage
+	};
+
+	match create_swapchain_resources(context, &mut swapchain, render_pass, sample_count) {
+		Ok(()) => Ok(swapchain),
+		Err(error) => {
+			swapchain.drop(&context.logical_device);
+			Err(error)
+		}
 	}
+}
 
-	// Create depth image
-	let image_create_info = vk::ImageCreateInfo::builder()
-		.image_type(vk::ImageType::TYPE_2D)
-		.extent(vk::Extent3D::builder()
-			.width(extent.width)
-			.height(extent.height)
-			.depth(1)
-			.build())
-		.mip_levels(1)
-		.array_layers(1)
-		.format(required_format)
-		.tiling(vk::ImageTiling::OPTIMAL)
-		.initial_layout(vk::ImageLayout::UNDEFINED)
-		.usage(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT)
-		.samples(vk::SampleCountFlags::TYPE_1)
-		.sharing_mode(vk::SharingMode::EXCLUSIVE);
+fn create_swapchain_resources(context: &Context, swapchain: &mut Swapchain, render_pass: vk::RenderPass, sample_count: vk::SampleCountFlags) -> Result<(), RenderSystemError> {
+	let extent = swapchain.extent;
 
-	let depth_image = unsafe { context.logical_device.create_image(&image_create_info, None).unwrap() };
+	create_image_resources(context, &mut swapchain.depth_image_resources, extent, vk::Format::D32_SFLOAT, vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT, sample_count, vk::ImageAspectFlags::DEPTH)?;
+	context.set_object_name(swapchain.depth_image_resources.image, "depth_image");
 
-	// Allocate depth image memory and bind it to the image
-	let memory_requirements = unsafe { context.logical_device.get_image_memory_requirements(depth_image) };
-	let memory_type_index = context.physical_device.find_memory_type_index(memory_requirements.memory_type_bits, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+	// Create the multisampled color image which is resolved into the swapchain image
+	if sample_count != vk::SampleCountFlags::TYPE_1 {
+		let color_image_resources = swapchain.color_image_resources.get_or_insert_with(ImageResources::default);
+		let usage = vk::ImageUsageFlags::TRANSIENT_ATTACHMENT | vk::ImageUsageFlags::COLOR_ATTACHMENT;
+		create_image_resources(context, color_image_resources, extent, color_format(context), usage, sample_count, vk::ImageAspectFlags::COLOR)?;
+		context.set_object_name(color_image_resources.image, "multisampled_color_image");
+	}
 
-	let allocate_info = vk::MemoryAllocateInfo::builder()
-		.allocation_size(memory_requirements.size)
-		.memory_type_index(memory_type_index as u32);
+	// Create swapchain frames
+	let images = unsafe { swapchain.extension.get_swapchain_images(swapchain.handle) }?;
+	swapchain.frames.reserve(images.len());
 
-	let depth_image_memory = unsafe { context.logical_device.allocate_memory(&allocate_info, None).unwrap() };
-	unsafe { context.logical_device.bind_image_memory(depth_image, depth_image_memory, 0).unwrap() };
+	for (index, image) in images.into_iter().enumerate() {
+		context.set_object_name(image, &format!("swapchain_image_{}", index));
 
-	// Create depth image view
-	let image_view_create_info = vk::ImageViewCreateInfo::builder()
-		.image(depth_image)
-		.view_type(vk::ImageViewType::TYPE_2D)
-		.format(required_format)
-		.subresource_range(vk::ImageSubresourceRange::builder()
-			.aspect_mask(vk::ImageAspectFlags::DEPTH)
-			.base_mip_level(0)
-			.level_count(1)
-			.base_array_layer(0)
-			.layer_count(1)
-			.build());
-	
-	let depth_image_view = unsafe { context.logical_device.create_image_view(&image_view_create_info, None).unwrap() };
-	
-	// Create the container struct
-	let depth_image_resources = DepthImageResources {
-		image: depth_image,
-		image_view: depth_image_view,
-		memory: depth_image_memory
-	};
+		swapchain.frames.push(SwapchainFrame {
+			image,
+			image_view: vk::ImageView::null(),
+			framebuffer: vk::Framebuffer::null(),
+			fence: vk::Fence::null()
+		});
+
+		let frame = swapchain.frames.last_mut().unwrap();
 
-	// Create swapchain frames
-	let mut frames = Vec::with_capacity(images.len());
-	for image in images {
 		// Create image view
 		let image_view_create_info = vk::ImageViewCreateInfo::builder()
 			.image(image)
 			.view_type(vk::ImageViewType::TYPE_2D)
-			.format(context.surface.format.format)
+			.format(color_format(context))
 			.components(vk::ComponentMapping::builder()
 				.r(vk::ComponentSwizzle::IDENTITY)
 				.g(vk::ComponentSwizzle::IDENTITY)
@@ -193,10 +256,13 @@ pub(super) fn create_swapchain(context: &Context, framebuffer_width: u32, frameb
 				.layer_count(1)
 				.build());
 
-		let image_view = unsafe { context.logical_device.create_image_view(&image_view_create_info, None).unwrap() };
+		frame.image_view = unsafe { context.logical_device.create_image_view(&image_view_create_info, None) }?;
 
 		// Create framebuffer
-		let attachments = [image_view, depth_image_view];
+		let attachments = match &swapchain.color_image_resources {
+			Some(color_image_resources) => vec![color_image_resources.image_view, swapchain.depth_image_resources.image_view, frame.image_view],
+			None => vec![frame.image_view, swapchain.depth_image_resources.image_view]
+		};
 
 		let create_info = vk::FramebufferCreateInfo::builder()
 			.render_pass(render_pass)
@@ -205,36 +271,89 @@ pub(super) fn create_swapchain(context: &Context, framebuffer_width: u32, frameb
 			.height(extent.height)
 			.layers(1);
 		
-		let framebuffer = unsafe { context.logical_device.create_framebuffer(&create_info, None).unwrap() };
+		frame.framebuffer = unsafe { context.logical_device.create_framebuffer(&create_info, None) }?;
+	}
 
-		// Create fence
-		let fence = vk::Fence::null();
+	Ok(())
+}
 
-		frames.push(SwapchainFrame {
-			image_view,
-			framebuffer,
-			fence
-		});
-	}
+// Creates a single sampled or multisampled attachment, each handle is stored as soon as it's created so a failure doesn't leak the earlier ones
+fn create_image_resources(
+	context: &Context,
+	resources: &mut ImageResources,
+	extent: vk::Extent2D,
+	format: vk::Format,
+	usage: vk::ImageUsageFlags,
+	sample_count: vk::SampleCountFlags,
+	aspect_mask: vk::ImageAspectFlags)
+	-> Result<(), RenderSystemError>
+{
+	let image_create_info = vk::ImageCreateInfo::builder()
+		.image_type(vk::ImageType::TYPE_2D)
+		.extent(vk::Extent3D::builder()
+			.width(extent.width)
+			.height(extent.height)
+			.depth(1)
+			.build())
+		.mip_levels(1)
+		.array_layers(1)
+		.format(format)
+		.tiling(vk::ImageTiling::OPTIMAL)
+		.initial_layout(vk::ImageLayout::UNDEFINED)
+		.usage(usage)
+		.samples(sample_count)
+		.sharing_mode(vk::SharingMode::EXCLUSIVE);
 
-	Swapchain {
-		extension,
-		handle,
-		extent,
-		depth_image_resources,
-		frames
-	}
+	resources.image = unsafe { context.logical_device.create_image(&image_create_info, None) }?;
+
+	// Allocate image memory and bind it to the image
+	let memory_requirements = unsafe { context.logical_device.get_image_memory_requirements(resources.image) };
+	let memory_type_index = context.physical_device.find_memory_type_index(memory_requirements.memory_type_bits, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+	let allocate_info = vk::MemoryAllocateInfo::builder()
+		.allocation_size(memory_requirements.size)
+		.memory_type_index(memory_type_index as u32);
+
+	resources.memory = unsafe { context.logical_device.allocate_memory(&allocate_info, None) }?;
+	unsafe { context.logical_device.bind_image_memory(resources.image, resources.memory, 0) }?;
+
+	// Create image view
+	let image_view_create_info = vk::ImageViewCreateInfo::builder()
+		.image(resources.image)
+		.view_type(vk::ImageViewType::TYPE_2D)
+		.format(format)
+		.subresource_range(vk::ImageSubresourceRange::builder()
+			.aspect_mask(aspect_mask)
+			.base_mip_level(0)
+			.level_count(1)
+			.base_array_layer(0)
+			.layer_count(1)
+			.build());
+	
+	resources.image_view = unsafe { context.logical_device.create_image_view(&image_view_create_info, None) }?;
+	Ok(())
+}
+
+pub fn is_srgb_format(format: vk::Format) -> bool {
+	matches!(format,
+		vk::Format::R8_SRGB |
+		vk::Format::R8G8_SRGB |
+		vk::Format::R8G8B8_SRGB |
+		vk::Format::B8G8R8_SRGB |
+		vk::Format::R8G8B8A8_SRGB |
+		vk::Format::B8G8R8A8_SRGB |
+		vk::Format::A8B8G8R8_SRGB_PACK32)
 }
 
-pub fn create_descriptor_pool(context: &Context) -> vk::DescriptorPool {
-	let frames_count = IN_FLIGHT_FRAMES_COUNT as u32;
+pub fn create_descriptor_pool(context: &Context, in_flight_frames_count: usize) -> Result<vk::DescriptorPool, RenderSystemError> {
+	let frames_count = in_flight_frames_count as u32;
 
 	let storage_buffer_pool_size = vk::DescriptorPoolSize::builder()
 		.ty(vk::DescriptorType::STORAGE_BUFFER)
-		.descriptor_count(frames_count * 5 + 4);
+		.descriptor_count(frames_count * 7 + 6);
 	
 	let uniform_buffer_pool_size = vk::DescriptorPoolSize::builder()
-		.ty(vk::DescriptorType::UNIFORM_BUFFER)
+		.ty(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
 		.descriptor_count(frames_count);
 	
 	let sampler_pool_size = vk::DescriptorPoolSize::builder()
@@ -245,43 +364,61 @@ pub fn create_descriptor_pool(context: &Context) -> vk::DescriptorPool {
 		.ty(vk::DescriptorType::SAMPLED_IMAGE)
 		.descriptor_count(MAX_FONTS as u32);
 	
+	let shadow_map_pool_size = vk::DescriptorPoolSize::builder()
+		.ty(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+		.descriptor_count(1);
+	
 	let pool_sizes = [
 		storage_buffer_pool_size.build(),
 		uniform_buffer_pool_size.build(),
 		sampler_pool_size.build(),
-		sampled_image_pool_size.build()
+		sampled_image_pool_size.build(),
+		shadow_map_pool_size.build()
 	];
 	
 	let create_info = vk::DescriptorPoolCreateInfo::builder()
 		.pool_sizes(&pool_sizes)
-		.max_sets(frames_count * 6 + 6);
+		.max_sets(frames_count * 8 + 9);
 	
-	unsafe { context.logical_device.create_descriptor_pool(&create_info, None) }.unwrap()
+	Ok(unsafe { context.logical_device.create_descriptor_pool(&create_info, None) }?)
 }
 
-pub fn create_command_pool(context: &Context) -> vk::CommandPool {
+pub fn create_timestamp_query_pool(context: &Context, in_flight_frames_count: usize) -> Result<vk::QueryPool, RenderSystemError> {
+	// Each in flight frame writes a timestamp at the start and end of its primary command buffer
+	if context.physical_device.timestamp_period.is_none() {
+		return Ok(vk::QueryPool::null());
+	}
+
+	let create_info = vk::QueryPoolCreateInfo::builder()
+		.query_type(vk::QueryType::TIMESTAMP)
+		.query_count(in_flight_frames_count as u32 * 2);
+
+	Ok(unsafe { context.logical_device.create_query_pool(&create_info, None) }?)
+}
+
+pub fn create_command_pool(context: &Context) -> Result<vk::CommandPool, RenderSystemError> {
 	let create_info = vk::CommandPoolCreateInfo::builder()
 		.queue_family_index(context.physical_device.graphics_queue_family)
 		.flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
 
-	unsafe { context.logical_device.create_command_pool(&create_info, None) }.unwrap()
+	Ok(unsafe { context.logical_device.create_command_pool(&create_info, None) }?)
 }
 
-pub fn create_frame_data_descriptor_set_layout(logical_device: &ash::Device) -> vk::DescriptorSetLayout {
+pub fn create_frame_data_descriptor_set_layout(logical_device: &ash::Device) -> Result<vk::DescriptorSetLayout, RenderSystemError> {
 	let layout_binding = vk::DescriptorSetLayoutBinding::builder()
 		.binding(0)
-		.descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+		.descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
 		.descriptor_count(1)
-		.stage_flags(vk::ShaderStageFlags::VERTEX);
+		.stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
 	let layout_bindings = [layout_binding.build()];
 
 	let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
 		.bindings(&layout_bindings);
 
-	unsafe { logical_device.create_descriptor_set_layout(&create_info, None) }.unwrap()
+	Ok(unsafe { logical_device.create_descriptor_set_layout(&create_info, None) }?)
 }
 
-pub fn create_instance_data_descriptor_set_layout(logical_device: &ash::Device) -> vk::DescriptorSetLayout {
+pub fn create_instance_data_descriptor_set_layout(logical_device: &ash::Device) -> Result<vk::DescriptorSetLayout, RenderSystemError> {
 	let layout_binding = vk::DescriptorSetLayoutBinding::builder()
 		.binding(0)
 		.descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
@@ -293,16 +430,42 @@ pub fn create_instance_data_descriptor_set_layout(logical_device: &ash::Device)
 	let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
 		.bindings(&layout_bindings);
 
-	unsafe { logical_device.create_descriptor_set_layout(&create_info, None) }.unwrap()
+	Ok(unsafe { logical_device.create_descriptor_set_layout(&create_info, None) }?)
 }
 
 pub(super) fn create_in_flight_frames(
 	context: &Context,
+	in_flight_frames_count: usize,
+	descriptor_pool: vk::DescriptorPool,
+	command_pool: vk::CommandPool,
+	frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
+	instance_data_descriptor_set_layout: vk::DescriptorSetLayout)
+	-> Result<Vec<InFlightFrame>, RenderSystemError>
+{
+	let mut frames = Vec::with_capacity(in_flight_frames_count);
+
+	// Command buffers and descriptor sets are freed with their pools so only the frames' own objects are destroyed on failure
+	match push_in_flight_frames(context, &mut frames, in_flight_frames_count, descriptor_pool, command_pool, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout) {
+		Ok(()) => Ok(frames),
+		Err(error) => {
+			for frame in &frames {
+				frame.drop(&context.logical_device);
+			}
+
+			Err(error)
+		}
+	}
+}
+
+fn push_in_flight_frames(
+	context: &Context,
+	frames: &mut Vec<InFlightFrame>,
+	in_flight_frames_count: usize,
 	descriptor_pool: vk::DescriptorPool,
 	command_pool: vk::CommandPool,
 	frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
 	instance_data_descriptor_set_layout: vk::DescriptorSetLayout)
-	-> [InFlightFrame; IN_FLIGHT_FRAMES_COUNT]
+	-> Result<(), RenderSystemError>
 {
 	let semaphore_create_info = vk::SemaphoreCreateInfo::builder();
 
@@ -312,16 +475,16 @@ pub(super) fn create_in_flight_frames(
 	let primary_command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
 		.command_pool(command_pool)
 		.level(vk::CommandBufferLevel::PRIMARY)
-		.command_buffer_count(IN_FLIGHT_FRAMES_COUNT as u32);
+		.command_buffer_count(in_flight_frames_count as u32);
 	
-	let primary_command_buffers = unsafe { context.logical_device.allocate_command_buffers(&primary_command_buffer_allocate_info) }.unwrap();
+	let primary_command_buffers = unsafe { context.logical_device.allocate_command_buffers(&primary_command_buffer_allocate_info) }?;
 
 	let secondary_command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
 		.command_pool(command_pool)
 		.level(vk::CommandBufferLevel::SECONDARY)
-		.command_buffer_count(IN_FLIGHT_FRAMES_COUNT as u32 * 5);
+		.command_buffer_count((in_flight_frames_count * SECONDARY_COMMAND_BUFFERS_PER_FRAME) as u32);
 	
-	let secondary_command_buffers = unsafe { context.logical_device.allocate_command_buffers(&secondary_command_buffer_allocate_info) }.unwrap();
+	let secondary_command_buffers = unsafe { context.logical_device.allocate_command_buffers(&secondary_command_buffer_allocate_info) }?;
 
 	let descriptor_set_layouts = [
 		frame_data_descriptor_set_layout,
@@ -329,6 +492,8 @@ pub(super) fn create_in_flight_frames(
 		instance_data_descriptor_set_layout,
 		instance_data_descriptor_set_layout,
 		instance_data_descriptor_set_layout,
+		instance_data_descriptor_set_layout,
+		instance_data_descriptor_set_layout,
 		instance_data_descriptor_set_layout
 	];
 
@@ -336,89 +501,119 @@ pub(super) fn create_in_flight_frames(
 		.descriptor_pool(descriptor_pool)
 		.set_layouts(&descriptor_set_layouts);
 
-	let mut frames: [MaybeUninit<InFlightFrame>; IN_FLIGHT_FRAMES_COUNT] = unsafe { MaybeUninit::uninit().assume_init() };
+	let frame_command_buffers = primary_command_buffers.iter().zip(secondary_command_buffers.chunks_exact(SECONDARY_COMMAND_BUFFERS_PER_FRAME));
 	
-	for (index, frame) in frames.iter_mut().enumerate() {
-		let image_available = unsafe { context.logical_device.create_semaphore(&semaphore_create_info, None) }.unwrap();
-		let render_finished = unsafe { context.logical_device.create_semaphore(&semaphore_create_info, None) }.unwrap();
-		let fence = unsafe { context.logical_device.create_fence(&fence_create_info, None) }.unwrap();
-		let descriptor_sets = unsafe { context.logical_device.allocate_descriptor_sets(&descriptor_set_allocate_info) }.unwrap();
+	for (index, (&primary_command_buffer, secondary_command_buffers)) in frame_command_buffers.enumerate() {
+		let descriptor_sets = unsafe { context.logical_device.allocate_descriptor_sets(&descriptor_set_allocate_info) }?;
 		let frame_data_descriptor_set = descriptor_sets[0];
-		let primary_command_buffer = primary_command_buffers[index];
 
-		let frame_data_buffer = Buffer::new(context, FRAME_DATA_MEMORY_SIZE as u64, vk::BufferUsageFlags::UNIFORM_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE);
+		let frame_data_buffer = Buffer::new(context, (frame_data_stride(context) * MAX_VIEWPORTS) as u64, vk::BufferUsageFlags::UNIFORM_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE)?;
+		context.set_object_name(frame_data_buffer.handle, &format!("frame_data_buffer_{}", index));
 
 		let instance_data_buffer = Buffer::null(
 			vk::BufferUsageFlags::INDEX_BUFFER | vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::STORAGE_BUFFER,
 			vk::MemoryPropertyFlags::HOST_VISIBLE);
 		
-		let frame_data_descriptor_buffer_info = vk::DescriptorBufferInfo::builder()
-			.buffer(frame_data_buffer.handle)
-			.offset(0)
-			.range(vk::WHOLE_SIZE);
-		let frame_data_descriptor_buffer_infos = [frame_data_descriptor_buffer_info.build()];
-
-		let frame_data_write_descriptor_set = vk::WriteDescriptorSet::builder()
-			.dst_set(frame_data_descriptor_set)
-			.dst_binding(0)
-			.dst_array_element(0)
-			.descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
-			.buffer_info(&frame_data_descriptor_buffer_infos);
-		
-		let write_descriptor_sets = [frame_data_write_descriptor_set.build()];
-		unsafe { context.logical_device.update_descriptor_sets(&write_descriptor_sets, &[]) };
-
 		let line_instance_data_resources = InstanceDataResources {
 			descriptor_set: descriptor_sets[1],
-			secondary_command_buffer: secondary_command_buffers[5 * index],
+			secondary_command_buffer: secondary_command_buffers[0],
 			array_offset: 0,
 			array_size: 0
 		};
 
 		let basic_instance_data_resources = InstanceDataResources {
 			descriptor_set: descriptor_sets[2],
-			secondary_command_buffer: secondary_command_buffers[5 * index + 1],
+			secondary_command_buffer: secondary_command_buffers[1],
 			array_offset: 0,
 			array_size: 0
 		};
 
 		let normal_instance_data_resources = InstanceDataResources {
 			descriptor_set: descriptor_sets[3],
-			secondary_command_buffer: secondary_command_buffers[5 * index + 2],
+			secondary_command_buffer: secondary_command_buffers[2],
 			array_offset: 0,
 			array_size: 0
 		};
 
 		let lambert_instance_data_resources = InstanceDataResources {
 			descriptor_set: descriptor_sets[4],
-			secondary_command_buffer: secondary_command_buffers[5 * index + 3],
+			secondary_command_buffer: secondary_command_buffers[3],
 			array_offset: 0,
 			array_size: 0
 		};
 
-		let text_instance_data_resources = InstanceDataResources {
+		let phong_instance_data_resources = InstanceDataResources {
 			descriptor_set: descriptor_sets[5],
-			secondary_command_buffer: secondary_command_buffers[5 * index + 4],
+			secondary_command_buffer: secondary_command_buffers[4],
+			array_offset: 0,
+			array_size: 0
+		};
+
+		let vertex_color_instance_data_resources = InstanceDataResources {
+			descriptor_set: descriptor_sets[6],
+			secondary_command_buffer: secondary_command_buffers[5],
 			array_offset: 0,
 			array_size: 0
 		};
 
-		*frame = MaybeUninit::new(InFlightFrame {
-			image_available,
-			render_finished,
-			fence,
+		let text_instance_data_resources = InstanceDataResources {
+			descriptor_set: descriptor_sets[7],
+			secondary_command_buffer: secondary_command_buffers[6],
+			array_offset: 0,
+			array_size: 0
+		};
+
+		let shadow_secondary_command_buffer = secondary_command_buffers[7];
+		let depth_prepass_secondary_command_buffer = secondary_command_buffers[8];
+		let debug_secondary_command_buffer = secondary_command_buffers[9];
+
+		// The frame is pushed before its semaphores, fence and mapping are created so a failure destroys the ones that were
+		frames.push(InFlightFrame {
+			image_available: vk::Semaphore::null(),
+			render_finished: vk::Semaphore::null(),
+			fence: vk::Fence::null(),
 			frame_data_descriptor_set,
 			primary_command_buffer,
 			frame_data_buffer,
+			frame_data_buffer_ptr: ptr::null_mut(),
 			instance_data_buffer,
+			instance_data_buffer_ptr: ptr::null_mut(),
 			line_instance_data_resources,
 			basic_instance_data_resources,
 			normal_instance_data_resources,
 			lambert_instance_data_resources,
+			phong_instance_data_resources,
+			vertex_color_instance_data_resources,
 			text_instance_data_resources,
+			shadow_secondary_command_buffer,
+			depth_prepass_secondary_command_buffer,
+			debug_secondary_command_buffer,
+			timestamps_written: false,
 			index_arrays_offset: 0
 		});
+
+		let frame = frames.last_mut().unwrap();
+		frame.image_available = unsafe { context.logical_device.create_semaphore(&semaphore_create_info, None) }?;
+		frame.render_finished = unsafe { context.logical_device.create_semaphore(&semaphore_create_info, None) }?;
+		frame.fence = unsafe { context.logical_device.create_fence(&fence_create_info, None) }?;
+		frame.frame_data_buffer_ptr = unsafe { context.logical_device.map_memory(frame.frame_data_buffer.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) }?;
+
+		let frame_data_descriptor_buffer_info = vk::DescriptorBufferInfo::builder()
+			.buffer(frame.frame_data_buffer.handle)
+			.offset(0)
+			.range(FRAME_DATA_MEMORY_SIZE as u64);
+		let frame_data_descriptor_buffer_infos = [frame_data_descriptor_buffer_info.build()];
+
+		let frame_data_write_descriptor_set = vk::WriteDescriptorSet::builder()
+			.dst_set(frame_data_descriptor_set)
+			.dst_binding(0)
+			.dst_array_element(0)
+			.descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+			.buffer_info(&frame_data_descriptor_buffer_infos);
+		
+		let write_descriptor_sets = [frame_data_write_descriptor_set.build()];
+		unsafe { context.logical_device.update_descriptor_sets(&write_descriptor_sets, &[]) };
 	}
 
-	unsafe { transmute::<_, [InFlightFrame; IN_FLIGHT_FRAMES_COUNT]>(frames) }
+	Ok(())
 }
\ No newline at end of file
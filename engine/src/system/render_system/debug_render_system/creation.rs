@@ -0,0 +1,150 @@
+use std::ffi::CString;
+use ash::{vk, version::DeviceV1_0};
+use super::VERTEX_SIZE;
+use super::super::{create_pipeline_per_target, create_shader_module, PipelineTarget, RenderSystemError};
+
+pub fn create_pipeline_layout(logical_device: &ash::Device, frame_data_descriptor_set_layout: vk::DescriptorSetLayout) -> vk::PipelineLayout {
+	let descriptor_set_layouts = [frame_data_descriptor_set_layout];
+
+	let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::builder()
+		.set_layouts(&descriptor_set_layouts);
+
+	unsafe { logical_device.create_pipeline_layout(&pipeline_layout_create_info, None) }.unwrap()
+}
+
+pub fn create_pipelines(logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, pipeline_layout: vk::PipelineLayout, pipeline_targets: &[PipelineTarget]) -> Result<Vec<vk::Pipeline>, RenderSystemError> {
+	create_pipeline_per_target(logical_device, pipeline_targets, |target| {
+		create_pipeline(logical_device, pipeline_cache, pipeline_layout, target.render_pass, target.sample_count, target.gamma_correction)
+	})
+}
+
+pub fn create_pipeline(
+	logical_device: &ash::Device,
+	pipeline_cache: vk::PipelineCache,
+	pipeline_layout: vk::PipelineLayout,
+	render_pass: vk::RenderPass,
+	sample_count: vk::SampleCountFlags,
+	gamma_correction: bool)
+	-> Result<vk::Pipeline, RenderSystemError>
+{
+	let entry_point = CString::new("main").unwrap();
+	let entry_point_cstr = entry_point.as_c_str();
+
+	let vert_module = create_shader_module(logical_device, "debug.vert.spv")?;
+	let vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::VERTEX)
+		.module(vert_module)
+		.name(entry_point_cstr);
+
+	// The fragment shader's gamma correction is a bool specialization constant which is 4 bytes
+	let gamma_correction_data = (gamma_correction as u32).to_ne_bytes();
+	let specialization_map_entry = vk::SpecializationMapEntry::builder()
+		.constant_id(0)
+		.offset(0)
+		.size(4);
+	let specialization_map_entries = [specialization_map_entry.build()];
+
+	let frag_specialization_info = vk::SpecializationInfo::builder()
+		.map_entries(&specialization_map_entries)
+		.data(&gamma_correction_data);
+
+	// The basic fragment shader outputs the interpolated color
+	let frag_module = create_shader_module(logical_device, "basic.frag.spv")?;
+	let frag_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::FRAGMENT)
+		.module(frag_module)
+		.name(entry_point_cstr)
+		.specialization_info(&frag_specialization_info);
+
+	let stage_create_infos = [vert_stage_create_info.build(), frag_stage_create_info.build()];
+
+	let input_binding_description = vk::VertexInputBindingDescription::builder()
+		.binding(0)
+		.stride(VERTEX_SIZE as u32)
+		.input_rate(vk::VertexInputRate::VERTEX);
+	let input_binding_descriptions = [input_binding_description.build()];
+
+	let input_attribute_description_position = vk::VertexInputAttributeDescription::builder()
+		.binding(0)
+		.location(0)
+		.format(vk::Format::R32G32B32_SFLOAT)
+		.offset(0);
+
+	let input_attribute_description_color = vk::VertexInputAttributeDescription::builder()
+		.binding(0)
+		.location(1)
+		.format(vk::Format::R32G32B32_SFLOAT)
+		.offset(12);
+
+	let input_attribute_descriptions = [input_attribute_description_position.build(), input_attribute_description_color.build()];
+
+	let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::builder()
+		.vertex_binding_descriptions(&input_binding_descriptions)
+		.vertex_attribute_descriptions(&input_attribute_descriptions);
+
+	let input_assembly_state_create_info = vk::PipelineInputAssemblyStateCreateInfo::builder()
+		.topology(vk::PrimitiveTopology::LINE_LIST)
+		.primitive_restart_enable(false);
+
+	// The viewport and scissor are dynamic since the swapchain and render targets can be different sizes
+	let viewport_state_create_info = vk::PipelineViewportStateCreateInfo::builder()
+		.viewport_count(1)
+		.scissor_count(1);
+
+	let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+	let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo::builder()
+		.dynamic_states(&dynamic_states);
+
+	let rasterization_state_create_info = vk::PipelineRasterizationStateCreateInfo::builder()
+		.depth_clamp_enable(false)
+		.rasterizer_discard_enable(false)
+		.polygon_mode(vk::PolygonMode::FILL)
+		.line_width(1.0)
+		.cull_mode(vk::CullModeFlags::NONE)
+		.front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+		.depth_bias_enable(false);
+
+	let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo::builder()
+		.sample_shading_enable(false)
+		.rasterization_samples(sample_count);
+
+	// Lines are hidden behind meshes but don't write depth so they never hide each other
+	let depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo::builder()
+		.depth_test_enable(true)
+		.depth_write_enable(false)
+		.depth_compare_op(vk::CompareOp::LESS_OR_EQUAL)
+		.depth_bounds_test_enable(false)
+		.stencil_test_enable(false);
+
+	let color_blend_attachment_state = vk::PipelineColorBlendAttachmentState::builder()
+		.color_write_mask(vk::ColorComponentFlags::all())
+		.blend_enable(false);
+	let color_blend_attachment_states = [color_blend_attachment_state.build()];
+
+	let color_blend_state_create_info = vk::PipelineColorBlendStateCreateInfo::builder()
+		.logic_op_enable(false)
+		.attachments(&color_blend_attachment_states);
+
+	let pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
+		.stages(&stage_create_infos)
+		.vertex_input_state(&vertex_input_state_create_info)
+		.input_assembly_state(&input_assembly_state_create_info)
+		.viewport_state(&viewport_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
+		.rasterization_state(&rasterization_state_create_info)
+		.multisample_state(&multisample_state_create_info)
+		.depth_stencil_state(&depth_stencil_state_create_info)
+		.color_blend_state(&color_blend_state_create_info)
+		.layout(pipeline_layout)
+		.render_pass(render_pass)
+		.subpass(0);
+
+	let pipeline = unsafe { logical_device.create_graphics_pipelines(pipeline_cache, &[pipeline_create_info.build()], None) }.map_err(|(_, result)| result)?[0];
+
+	unsafe {
+		logical_device.destroy_shader_module(vert_module, None);
+		logical_device.destroy_shader_module(frag_module, None);
+	}
+
+	Ok(pipeline)
+}
\ No newline at end of file
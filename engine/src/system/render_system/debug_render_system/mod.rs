@@ -0,0 +1,178 @@
+use ash::{vk, version::DeviceV1_0};
+use crate::math::{Box3, Vector3};
+use super::{PipelineTarget, RenderSystemError};
+
+mod creation;
+use creation::*;
+
+// Each vertex is a vec3 position followed by a vec3 color
+pub const VERTEX_SIZE: usize = 6 * 4;
+const AABB_EDGE_INDICES: [usize; 24] = [0, 1, 1, 2, 2, 3, 3, 0, 0, 4, 1, 5, 2, 6, 3, 7, 4, 5, 5, 6, 6, 7, 7, 4];
+
+// Lines are queued in world space during the frame, drawn by the next render then cleared
+pub struct DebugRenderSystem {
+	pub pipeline_layout: vk::PipelineLayout,
+	pub pipelines: Vec<vk::Pipeline>,
+	vertices: Vec<f32>
+}
+
+impl DebugRenderSystem {
+	pub fn new(logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, frame_data_descriptor_set_layout: vk::DescriptorSetLayout, pipeline_targets: &[PipelineTarget]) -> Result<Self, RenderSystemError> {
+		let pipeline_layout = create_pipeline_layout(logical_device, frame_data_descriptor_set_layout);
+		let pipelines = match create_pipelines(logical_device, pipeline_cache, pipeline_layout, pipeline_targets) {
+			Ok(pipelines) => pipelines,
+			Err(error) => {
+				unsafe { logical_device.destroy_pipeline_layout(pipeline_layout, None) };
+				return Err(error);
+			}
+		};
+
+		Ok(Self {
+			pipeline_layout,
+			pipelines,
+			vertices: vec![]
+		})
+	}
+
+	pub fn line(&mut self, a: &Vector3, b: &Vector3, color: &Vector3) {
+		self.vertices.extend_from_slice(&[a.x, a.y, a.z, color.x, color.y, color.z]);
+		self.vertices.extend_from_slice(&[b.x, b.y, b.z, color.x, color.y, color.z]);
+	}
+
+	pub fn aabb(&mut self, aabb: &Box3, color: &Vector3) {
+		let corners = aabb.as_vertices();
+
+		for edge in AABB_EDGE_INDICES.chunks_exact(2) {
+			self.line(&corners[edge[0]], &corners[edge[1]], color);
+		}
+	}
+
+	// A square grid on the XZ plane centered on the origin, the lines through the origin are halfway to white
+	pub fn grid(&mut self, size: f32, spacing: f32, color: &Vector3) {
+		assert!(spacing > 0.0, "Cannot draw grid because the spacing {} is not positive", spacing);
+
+		let half_size = size * 0.5;
+		let half_line_count = (half_size / spacing).floor() as i32;
+
+		let mut center_color = *color;
+		center_color.lerp(&Vector3::from_scalar(1.0), 0.5);
+
+		for i in -half_line_count..=half_line_count {
+			let offset = i as f32 * spacing;
+			let line_color = if i == 0 { &center_color } else { color };
+
+			self.line(&Vector3::new(-half_size, 0.0, offset), &Vector3::new(half_size, 0.0, offset), line_color);
+			self.line(&Vector3::new(offset, 0.0, -half_size), &Vector3::new(offset, 0.0, half_size), line_color);
+		}
+	}
+
+	pub fn vertices(&self) -> &[f32] {
+		&self.vertices
+	}
+
+	pub fn vertex_count(&self) -> usize {
+		self.vertices.len() * 4 / VERTEX_SIZE
+	}
+
+	pub fn clear(&mut self) {
+		self.vertices.clear();
+	}
+
+	// The caller ensures the device is idle, the old pipelines are kept if the new ones can't be created
+	pub fn recreate_pipelines(&mut self, logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, pipeline_targets: &[PipelineTarget]) -> Result<(), RenderSystemError> {
+		let pipelines = create_pipelines(logical_device, pipeline_cache, self.pipeline_layout, pipeline_targets)?;
+		self.destroy_pipelines(logical_device);
+		self.pipelines = pipelines;
+		Ok(())
+	}
+
+	fn destroy_pipelines(&self, logical_device: &ash::Device) {
+		for pipeline in &self.pipelines {
+			unsafe { logical_device.destroy_pipeline(*pipeline, None) };
+		}
+	}
+
+	pub fn drop(&self, logical_device: &ash::Device) {
+		self.destroy_pipelines(logical_device);
+
+		unsafe {
+			logical_device.destroy_pipeline_layout(self.pipeline_layout, None);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn create_debug_render_system() -> DebugRenderSystem {
+		DebugRenderSystem {
+			pipeline_layout: vk::PipelineLayout::null(),
+			pipelines: vec![],
+			vertices: vec![]
+		}
+	}
+
+	#[test]
+	fn line() {
+		let mut debug_render_system = create_debug_render_system();
+		debug_render_system.line(&Vector3::new(1.0, 2.0, 3.0), &Vector3::new(4.0, 5.0, 6.0), &Vector3::new(1.0, 0.0, 0.0));
+
+		assert_eq!(debug_render_system.vertex_count(), 2);
+		assert_eq!(debug_render_system.vertices(), &[
+			1.0, 2.0, 3.0, 1.0, 0.0, 0.0,
+			4.0, 5.0, 6.0, 1.0, 0.0, 0.0]);
+	}
+
+	#[test]
+	fn aabb() {
+		let mut debug_render_system = create_debug_render_system();
+		let aabb = Box3::new(Vector3::from_scalar(-1.0), Vector3::from_scalar(1.0));
+		debug_render_system.aabb(&aabb, &Vector3::new(0.0, 1.0, 0.0));
+		debug_render_system.line(&Vector3::from_scalar(0.0), &Vector3::from_scalar(1.0), &Vector3::new(0.0, 1.0, 0.0));
+
+		// 12 edges then the line
+		assert_eq!(debug_render_system.vertex_count(), 26);
+
+		// Every edge is parallel to an axis
+		for edge in debug_render_system.vertices()[..24 * 6].chunks_exact(12) {
+			let differing_axes = (0..3).filter(|axis| edge[*axis] != edge[6 + axis]).count();
+			assert_eq!(differing_axes, 1);
+		}
+
+		debug_render_system.clear();
+		assert_eq!(debug_render_system.vertex_count(), 0);
+	}
+
+	#[test]
+	fn grid() {
+		let mut debug_render_system = create_debug_render_system();
+		debug_render_system.grid(2.0, 1.0, &Vector3::new(0.2, 0.2, 0.2));
+
+		// Three lines along each axis at -1, 0 and 1
+		assert_eq!(debug_render_system.vertex_count(), 12);
+
+		let endpoints: Vec<(Vector3, Vector3)> = debug_render_system.vertices().chunks_exact(12)
+			.map(|l| (Vector3::new(l[0], l[1], l[2]), Vector3::new(l[6], l[7], l[8])))
+			.collect();
+
+		assert_eq!(endpoints, vec![
+			(Vector3::new(-1.0, 0.0, -1.0), Vector3::new(1.0, 0.0, -1.0)),
+			(Vector3::new(-1.0, 0.0, -1.0), Vector3::new(-1.0, 0.0, 1.0)),
+			(Vector3::new(-1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0)),
+			(Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 0.0, 1.0)),
+			(Vector3::new(-1.0, 0.0, 1.0), Vector3::new(1.0, 0.0, 1.0)),
+			(Vector3::new(1.0, 0.0, -1.0), Vector3::new(1.0, 0.0, 1.0))
+		]);
+
+		// The center cross is brighter
+		let vertices = debug_render_system.vertices();
+		assert_eq!(vertices[3], 0.2);
+		assert!((vertices[2 * 12 + 3] - 0.6).abs() < 1e-6);
+
+		// Finer spacing adds more lines
+		debug_render_system.clear();
+		debug_render_system.grid(2.0, 0.5, &Vector3::new(0.2, 0.2, 0.2));
+		assert_eq!(debug_render_system.vertex_count(), 20);
+	}
+}
\ No newline at end of file
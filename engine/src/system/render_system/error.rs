@@ -0,0 +1,82 @@
+use std::{fmt, io};
+use ash::vk;
+
+#[derive(Debug)]
+pub enum RenderSystemError {
+	Loading(String),
+	MissingInstanceExtension(String),
+	SurfaceCreation(vk::Result),
+	NoSuitableDevice,
+	UnsupportedDepthFormat(vk::Format),
+	UnsupportedSwapchainUsage(vk::ImageUsageFlags),
+	Headless,
+	ShaderFile(String, io::Error),
+	FontFile(String, io::Error),
+	Vulkan(vk::Result)
+}
+
+impl fmt::Display for RenderSystemError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Loading(message) => write!(f, "Cannot load Vulkan: {}", message),
+			Self::MissingInstanceExtension(name) => write!(f, "Cannot create Vulkan instance, the required extension {} is not supported", name),
+			Self::SurfaceCreation(result) => write!(f, "Cannot create window surface: {}", result),
+			Self::NoSuitableDevice => write!(f, "Cannot find a discrete GPU with graphics and present queues, swapchain support and geometry shaders"),
+			Self::UnsupportedDepthFormat(format) => write!(f, "Cannot create depth buffer, the device does not support {:?} as a depth attachment", format),
+			Self::UnsupportedSwapchainUsage(usage) => write!(f, "Cannot use the swapchain images for {:?}, the surface does not support it", usage),
+			Self::Headless => write!(f, "Cannot use the swapchain, the render system is headless"),
+			Self::ShaderFile(path, error) => write!(f, "Cannot read shader {}: {}", path, error),
+			Self::FontFile(path, error) => write!(f, "Cannot read font atlas {}: {}", path, error),
+			Self::Vulkan(result) => write!(f, "Vulkan call failed: {}", result)
+		}
+	}
+}
+
+impl std::error::Error for RenderSystemError {}
+
+impl From<vk::Result> for RenderSystemError {
+	fn from(result: vk::Result) -> Self {
+		Self::Vulkan(result)
+	}
+}
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+	Unsupported,
+	NothingPresented,
+	Vulkan(vk::Result),
+	Io(io::Error),
+	Encoding(png::EncodingError)
+}
+
+impl fmt::Display for ScreenshotError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Unsupported => write!(f, "Cannot capture screenshot, the surface does not support copying from swapchain images"),
+			Self::NothingPresented => write!(f, "Cannot capture screenshot, nothing has been presented since the swapchain was created"),
+			Self::Vulkan(result) => write!(f, "Cannot capture screenshot, Vulkan call failed: {}", result),
+			Self::Io(error) => write!(f, "Cannot save screenshot: {}", error),
+			Self::Encoding(error) => write!(f, "Cannot encode screenshot: {}", error)
+		}
+	}
+}
+
+impl std::error::Error for ScreenshotError {}
+
+impl From<vk::Result> for ScreenshotError {
+	fn from(result: vk::Result) -> Self {
+		Self::Vulkan(result)
+	}
+}
+
+impl From<io::Error> for ScreenshotError {
+	fn from(error: io::Error) -> Self {
+		Self::Io(error)
+	}
+}
+
+impl From<png::EncodingError> for ScreenshotError {
+	fn from(error: png::EncodingError) -> Self {
+		Self::Encoding(error)
+	}
+}
\ No newline at end of file
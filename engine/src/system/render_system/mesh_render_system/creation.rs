@@ -1,14 +1,19 @@
 use std::ffi::CString;
 use ash::{vk, version::DeviceV1_0};
-use super::super::create_shader_module;
+use super::{MeshPipelines, super::{create_shader_module, CullMode, FrontFace, PipelineTarget, RenderSystemError, MATERIALS_COUNT}};
+
+// Vertex color vertices are a position and normal followed by an RGB color
+pub const VERTEX_COLOR_VERTEX_STRIDE: u32 = 36;
+pub const VERTEX_COLOR_COLOR_OFFSET: u32 = 24;
 
 pub fn create_pipeline_layout(
 	logical_device: &ash::Device,
 	frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
-	instance_data_descriptor_set_layout: vk::DescriptorSetLayout)
+	instance_data_descriptor_set_layout: vk::DescriptorSetLayout,
+	shadow_map_descriptor_set_layout: vk::DescriptorSetLayout)
 	-> vk::PipelineLayout
 {
-	let descriptor_set_layouts = [frame_data_descriptor_set_layout, instance_data_descriptor_set_layout];
+	let descriptor_set_layouts = [frame_data_descriptor_set_layout, instance_data_descriptor_set_layout, shadow_map_descriptor_set_layout];
 
 	let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::builder()
 		.set_layouts(&descriptor_set_layouts);
@@ -16,11 +21,57 @@ pub fn create_pipeline_layout(
 	unsafe { logical_device.create_pipeline_layout(&pipeline_layout_create_info, None) }.unwrap()
 }
 
-pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipeline_layout: vk::PipelineLayout, render_pass: vk::RenderPass) -> Vec<vk::Pipeline> {
+// With the depth pre-pass the depth buffer already holds the closest surfaces so only those fragments are shaded
+pub fn mesh_depth_state(depth_prepass_enabled: bool) -> (vk::CompareOp, bool) {
+	if depth_prepass_enabled {
+		(vk::CompareOp::EQUAL, false)
+	}
+	else {
+		(vk::CompareOp::LESS, true)
+	}
+}
+
+// Shared by the mesh pipelines and the depth pre-pass pipeline so the pre-pass writes depth for the same faces that are shaded
+pub fn mesh_rasterization_state(cull_mode: CullMode, front_face: FrontFace) -> vk::PipelineRasterizationStateCreateInfo {
+	vk::PipelineRasterizationStateCreateInfo::builder()
+		.depth_clamp_enable(false)
+		.rasterizer_discard_enable(false)
+		.polygon_mode(vk::PolygonMode::FILL)
+		.line_width(1.0)
+		.cull_mode(cull_mode.as_flags())
+		.front_face(front_face.as_vk())
+		.depth_bias_enable(false)
+		.build()
+}
+
+#[allow(clippy::clippy::too_many_arguments)]
+pub fn create_pipelines(
+	logical_device: &ash::Device,
+	pipeline_cache: vk::PipelineCache,
+	pipeline_layout: vk::PipelineLayout,
+	render_pass: vk::RenderPass,
+	sample_count: vk::SampleCountFlags,
+	gamma_correction: bool,
+	depth_prepass_enabled: bool,
+	rasterization_state_create_info: &vk::PipelineRasterizationStateCreateInfo)
+	-> Result<Vec<vk::Pipeline>, RenderSystemError>
+{
 	// Shared
 	let entry_point = CString::new("main").unwrap();
 	let entry_point_cstr = entry_point.as_c_str();
 
+	// The fragment shaders' gamma correction is a bool specialization constant which is 4 bytes
+	let gamma_correction_data = (gamma_correction as u32).to_ne_bytes();
+	let specialization_map_entry = vk::SpecializationMapEntry::builder()
+		.constant_id(0)
+		.offset(0)
+		.size(4);
+	let specialization_map_entries = [specialization_map_entry.build()];
+
+	let frag_specialization_info = vk::SpecializationInfo::builder()
+		.map_entries(&specialization_map_entries)
+		.data(&gamma_correction_data);
+
 	let input_binding_description = vk::VertexInputBindingDescription::builder()
 		.binding(0)
 		.stride(24)
@@ -45,41 +96,25 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.topology(vk::PrimitiveTopology::TRIANGLE_LIST)
 		.primitive_restart_enable(false);
 
-	let viewport = vk::Viewport::builder()
-		.x(0.0)
-		.y(0.0)
-		.width(extent.width as f32)
-		.height(extent.height as f32)
-		.min_depth(0.0)
-		.max_depth(1.0);
-	let viewports = [viewport.build()];
-
-	let scissor = vk::Rect2D::builder()
-		.offset(vk::Offset2D::builder().x(0).y(0).build())
-		.extent(extent);
-	let scissors = [scissor.build()];
-
+	// The viewport and scissor are dynamic since the swapchain and render targets can be different sizes
 	let viewport_state_create_info = vk::PipelineViewportStateCreateInfo::builder()
-		.viewports(&viewports)
-		.scissors(&scissors);
+		.viewport_count(1)
+		.scissor_count(1);
 
-	let rasterization_state_create_info = vk::PipelineRasterizationStateCreateInfo::builder()
-		.depth_clamp_enable(false)
-		.rasterizer_discard_enable(false)
-		.polygon_mode(vk::PolygonMode::FILL)
-		.line_width(1.0)
-		.cull_mode(vk::CullModeFlags::BACK)
-		.front_face(vk::FrontFace::COUNTER_CLOCKWISE)
-		.depth_bias_enable(false);
+	let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+	let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo::builder()
+		.dynamic_states(&dynamic_states);
 
 	let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo::builder()
 		.sample_shading_enable(false)
-		.rasterization_samples(vk::SampleCountFlags::TYPE_1);
+		.rasterization_samples(sample_count);
+
+	let (depth_compare_op, depth_write_enable) = mesh_depth_state(depth_prepass_enabled);
 
 	let depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo::builder()
 		.depth_test_enable(true)
-		.depth_write_enable(true)
-		.depth_compare_op(vk::CompareOp::LESS)
+		.depth_write_enable(depth_write_enable)
+		.depth_compare_op(depth_compare_op)
 		.depth_bounds_test_enable(false)
 		.stencil_test_enable(false);
 
@@ -93,17 +128,18 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.attachments(&color_blend_attachment_states);
 	
 	// Line
-	let line_vert_module = create_shader_module(logical_device, "basic.vert.spv");
+	let line_vert_module = create_shader_module(logical_device, "line.vert.spv")?;
 	let line_vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::VERTEX)
 		.module(line_vert_module)
 		.name(entry_point_cstr);
 	
-	let line_frag_module = create_shader_module(logical_device, "basic.frag.spv");
+	let line_frag_module = create_shader_module(logical_device, "basic.frag.spv")?;
 	let line_frag_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::FRAGMENT)
 		.module(line_frag_module)
-		.name(entry_point_cstr);
+		.name(entry_point_cstr)
+		.specialization_info(&frag_specialization_info);
 	
 	let line_stage_create_infos = [line_vert_stage_create_info.build(), line_frag_stage_create_info.build()];
 
@@ -131,32 +167,42 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.cull_mode(vk::CullModeFlags::NONE)
 		.front_face(vk::FrontFace::COUNTER_CLOCKWISE)
 		.depth_bias_enable(false);
+
+	// Lines aren't drawn in the depth pre-pass
+	let line_depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo::builder()
+		.depth_test_enable(true)
+		.depth_write_enable(true)
+		.depth_compare_op(vk::CompareOp::LESS)
+		.depth_bounds_test_enable(false)
+		.stencil_test_enable(false);
 	
 	let line_pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
 		.stages(&line_stage_create_infos)
 		.vertex_input_state(&line_vertex_input_state_create_info)
 		.input_assembly_state(&line_input_assembly_state_create_info)
 		.viewport_state(&viewport_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
 		.rasterization_state(&line_rasterization_state_create_info)
 		.multisample_state(&multisample_state_create_info)
-		.depth_stencil_state(&depth_stencil_state_create_info)
+		.depth_stencil_state(&line_depth_stencil_state_create_info)
 		.color_blend_state(&color_blend_state_create_info)
 		.layout(pipeline_layout)
 		.render_pass(render_pass)
 		.subpass(0);
 
 	// Basic
-	let basic_vert_module = create_shader_module(logical_device, "basic.vert.spv");
+	let basic_vert_module = create_shader_module(logical_device, "basic.vert.spv")?;
 	let basic_vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::VERTEX)
 		.module(basic_vert_module)
 		.name(entry_point_cstr);
 	
-	let basic_frag_module = create_shader_module(logical_device, "basic.frag.spv");
+	let basic_frag_module = create_shader_module(logical_device, "basic.frag.spv")?;
 	let basic_frag_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::FRAGMENT)
 		.module(basic_frag_module)
-		.name(entry_point_cstr);
+		.name(entry_point_cstr)
+		.specialization_info(&frag_specialization_info);
 	
 	let basic_stage_create_infos = [basic_vert_stage_create_info.build(), basic_frag_stage_create_info.build()];
 	let basic_input_attribute_descriptions = [input_attribute_description_position];
@@ -170,7 +216,8 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.vertex_input_state(&basic_vertex_input_state_create_info)
 		.input_assembly_state(&input_assembly_state_create_info)
 		.viewport_state(&viewport_state_create_info)
-		.rasterization_state(&rasterization_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
+		.rasterization_state(rasterization_state_create_info)
 		.multisample_state(&multisample_state_create_info)
 		.depth_stencil_state(&depth_stencil_state_create_info)
 		.color_blend_state(&color_blend_state_create_info)
@@ -179,17 +226,18 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.subpass(0);
 	
 	// Normal
-	let normal_vert_module = create_shader_module(logical_device, "normal.vert.spv");
+	let normal_vert_module = create_shader_module(logical_device, "normal.vert.spv")?;
 	let normal_vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::VERTEX)
 		.module(normal_vert_module)
 		.name(entry_point_cstr);
 
-	let normal_frag_module =  create_shader_module(logical_device, "normal.frag.spv");
+	let normal_frag_module =  create_shader_module(logical_device, "normal.frag.spv")?;
 	let normal_frag_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::FRAGMENT)
 		.module(normal_frag_module)
-		.name(entry_point_cstr);
+		.name(entry_point_cstr)
+		.specialization_info(&frag_specialization_info);
 	
 	let normal_stage_create_infos = [normal_vert_stage_create_info.build(), normal_frag_stage_create_info.build()];
 	let normal_input_attribute_descriptions = [input_attribute_description_position, input_attribute_description_normal];
@@ -203,7 +251,8 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.vertex_input_state(&normal_vert_input_state_create_info)
 		.input_assembly_state(&input_assembly_state_create_info)
 		.viewport_state(&viewport_state_create_info)
-		.rasterization_state(&rasterization_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
+		.rasterization_state(rasterization_state_create_info)
 		.multisample_state(&multisample_state_create_info)
 		.depth_stencil_state(&depth_stencil_state_create_info)
 		.color_blend_state(&color_blend_state_create_info)
@@ -212,17 +261,18 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.subpass(0);
 	
 	// Lambert
-	let lambert_vert_module = create_shader_module(logical_device, "lambert.vert.spv");
+	let lambert_vert_module = create_shader_module(logical_device, "lambert.vert.spv")?;
 	let lambert_vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::VERTEX)
 		.module(lambert_vert_module)
 		.name(entry_point_cstr);
 
-	let lambert_frag_module =  create_shader_module(logical_device, "lambert.frag.spv");
+	let lambert_frag_module =  create_shader_module(logical_device, "lambert.frag.spv")?;
 	let lambert_frag_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::FRAGMENT)
 		.module(lambert_frag_module)
-		.name(entry_point_cstr);
+		.name(entry_point_cstr)
+		.specialization_info(&frag_specialization_info);
 
 	let lambert_stage_create_infos = [lambert_vert_stage_create_info.build(), lambert_frag_stage_create_info.build()];
 	let lambert_input_attribute_descriptions = [input_attribute_description_position, input_attribute_description_normal];
@@ -236,7 +286,43 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.vertex_input_state(&lambert_vert_input_state_create_info)
 		.input_assembly_state(&input_assembly_state_create_info)
 		.viewport_state(&viewport_state_create_info)
-		.rasterization_state(&rasterization_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
+		.rasterization_state(rasterization_state_create_info)
+		.multisample_state(&multisample_state_create_info)
+		.depth_stencil_state(&depth_stencil_state_create_info)
+		.color_blend_state(&color_blend_state_create_info)
+		.layout(pipeline_layout)
+		.render_pass(render_pass)
+		.subpass(0);
+	
+	// Phong
+	let phong_vert_module = create_shader_module(logical_device, "phong.vert.spv")?;
+	let phong_vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::VERTEX)
+		.module(phong_vert_module)
+		.name(entry_point_cstr);
+
+	let phong_frag_module = create_shader_module(logical_device, "phong.frag.spv")?;
+	let phong_frag_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::FRAGMENT)
+		.module(phong_frag_module)
+		.name(entry_point_cstr)
+		.specialization_info(&frag_specialization_info);
+
+	let phong_stage_create_infos = [phong_vert_stage_create_info.build(), phong_frag_stage_create_info.build()];
+	let phong_input_attribute_descriptions = [input_attribute_description_position, input_attribute_description_normal];
+
+	let phong_vert_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::builder()
+		.vertex_binding_descriptions(&input_binding_descriptions)
+		.vertex_attribute_descriptions(&phong_input_attribute_descriptions);
+
+	let phong_pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
+		.stages(&phong_stage_create_infos)
+		.vertex_input_state(&phong_vert_input_state_create_info)
+		.input_assembly_state(&input_assembly_state_create_info)
+		.viewport_state(&viewport_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
+		.rasterization_state(rasterization_state_create_info)
 		.multisample_state(&multisample_state_create_info)
 		.depth_stencil_state(&depth_stencil_state_create_info)
 		.color_blend_state(&color_blend_state_create_info)
@@ -244,14 +330,66 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 		.render_pass(render_pass)
 		.subpass(0);
 	
+	// Vertex color
+	let vertex_color_vert_module = create_shader_module(logical_device, "vertex_color.vert.spv")?;
+	let vertex_color_vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::VERTEX)
+		.module(vertex_color_vert_module)
+		.name(entry_point_cstr);
+
+	let vertex_color_frag_module = create_shader_module(logical_device, "basic.frag.spv")?;
+	let vertex_color_frag_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::FRAGMENT)
+		.module(vertex_color_frag_module)
+		.name(entry_point_cstr)
+		.specialization_info(&frag_specialization_info);
+
+	let vertex_color_stage_create_infos = [vertex_color_vert_stage_create_info.build(), vertex_color_frag_stage_create_info.build()];
+
+	let vertex_color_input_binding_description = vk::VertexInputBindingDescription::builder()
+		.binding(0)
+		.stride(VERTEX_COLOR_VERTEX_STRIDE)
+		.input_rate(vk::VertexInputRate::VERTEX);
+	let vertex_color_input_binding_descriptions = [vertex_color_input_binding_description.build()];
+
+	let input_attribute_description_color = vk::VertexInputAttributeDescription::builder()
+		.binding(0)
+		.location(1)
+		.format(vk::Format::R32G32B32_SFLOAT)
+		.offset(VERTEX_COLOR_COLOR_OFFSET)
+		.build();
+
+	let vertex_color_input_attribute_descriptions = [input_attribute_description_position, input_attribute_description_color];
+
+	let vertex_color_vert_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::builder()
+		.vertex_binding_descriptions(&vertex_color_input_binding_descriptions)
+		.vertex_attribute_descriptions(&vertex_color_input_attribute_descriptions);
+
+	// The depth pre-pass and shadow pipelines expect the 24 byte vertex layout so these aren't drawn in either
+	let vertex_color_pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
+		.stages(&vertex_color_stage_create_infos)
+		.vertex_input_state(&vertex_color_vert_input_state_create_info)
+		.input_assembly_state(&input_assembly_state_create_info)
+		.viewport_state(&viewport_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
+		.rasterization_state(rasterization_state_create_info)
+		.multisample_state(&multisample_state_create_info)
+		.depth_stencil_state(&line_depth_stencil_state_create_info)
+		.color_blend_state(&color_blend_state_create_info)
+		.layout(pipeline_layout)
+		.render_pass(render_pass)
+		.subpass(0);
+	
 	// Create pipelines
 	let pipeline_create_infos = [
 		line_pipeline_create_info.build(),
 		basic_pipeline_create_info.build(),
 		normal_pipeline_create_info.build(),
-		lambert_pipeline_create_info.build()];
+		lambert_pipeline_create_info.build(),
+		phong_pipeline_create_info.build(),
+		vertex_color_pipeline_create_info.build()];
 	
-	let pipelines = unsafe { logical_device.create_graphics_pipelines(vk::PipelineCache::null(), &pipeline_create_infos, None) }.unwrap();
+	let pipelines = unsafe { logical_device.create_graphics_pipelines(pipeline_cache, &pipeline_create_infos, None) }.map_err(|(_, result)| result)?;
 
 	// Destroy shader modules
 	unsafe {
@@ -266,16 +404,200 @@ pub fn create_pipelines(logical_device: &ash::Device, extent: vk::Extent2D, pipe
 
 		logical_device.destroy_shader_module(lambert_vert_module, None);
 		logical_device.destroy_shader_module(lambert_frag_module, None);
+
+		logical_device.destroy_shader_module(phong_vert_module, None);
+		logical_device.destroy_shader_module(phong_frag_module, None);
+
+		logical_device.destroy_shader_module(vertex_color_vert_module, None);
+		logical_device.destroy_shader_module(vertex_color_frag_module, None);
 	}
 
-	pipelines
+	Ok(pipelines)
+}
+
+pub fn create_depth_prepass_pipeline_layout(
+	logical_device: &ash::Device,
+	frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
+	instance_data_descriptor_set_layout: vk::DescriptorSetLayout)
+	-> vk::PipelineLayout
+{
+	let descriptor_set_layouts = [frame_data_descriptor_set_layout, instance_data_descriptor_set_layout];
+
+	// The instance stride
+	let push_constant_range = vk::PushConstantRange::builder()
+		.stage_flags(vk::ShaderStageFlags::VERTEX)
+		.offset(0)
+		.size(4);
+	let push_constant_ranges = [push_constant_range.build()];
+
+	let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::builder()
+		.set_layouts(&descriptor_set_layouts)
+		.push_constant_ranges(&push_constant_ranges);
+
+	unsafe { logical_device.create_pipeline_layout(&pipeline_layout_create_info, None) }.unwrap()
+}
+
+pub fn create_depth_prepass_pipeline(
+	logical_device: &ash::Device,
+	pipeline_cache: vk::PipelineCache,
+	pipeline_layout: vk::PipelineLayout,
+	render_pass: vk::RenderPass,
+	sample_count: vk::SampleCountFlags,
+	rasterization_state_create_info: &vk::PipelineRasterizationStateCreateInfo)
+	-> Result<vk::Pipeline, RenderSystemError>
+{
+	let entry_point = CString::new("main").unwrap();
+	let entry_point_cstr = entry_point.as_c_str();
+
+	// There's no fragment stage since only depth is written
+	let vert_module = create_shader_module(logical_device, "depth.vert.spv")?;
+	let vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::VERTEX)
+		.module(vert_module)
+		.name(entry_point_cstr);
+	let stage_create_infos = [vert_stage_create_info.build()];
+
+	let input_binding_description = vk::VertexInputBindingDescription::builder()
+		.binding(0)
+		.stride(24)
+		.input_rate(vk::VertexInputRate::VERTEX);
+	let input_binding_descriptions = [input_binding_description.build()];
+
+	let input_attribute_description_position = vk::VertexInputAttributeDescription::builder()
+		.binding(0)
+		.location(0)
+		.format(vk::Format::R32G32B32_SFLOAT)
+		.offset(0);
+	let input_attribute_descriptions = [input_attribute_description_position.build()];
+
+	let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::builder()
+		.vertex_binding_descriptions(&input_binding_descriptions)
+		.vertex_attribute_descriptions(&input_attribute_descriptions);
+
+	let input_assembly_state_create_info = vk::PipelineInputAssemblyStateCreateInfo::builder()
+		.topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+		.primitive_restart_enable(false);
+
+	// The viewport and scissor are dynamic since the swapchain and render targets can be different sizes
+	let viewport_state_create_info = vk::PipelineViewportStateCreateInfo::builder()
+		.viewport_count(1)
+		.scissor_count(1);
+
+	let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+	let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo::builder()
+		.dynamic_states(&dynamic_states);
+
+	let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo::builder()
+		.sample_shading_enable(false)
+		.rasterization_samples(sample_count);
+
+	let depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo::builder()
+		.depth_test_enable(true)
+		.depth_write_enable(true)
+		.depth_compare_op(vk::CompareOp::LESS)
+		.depth_bounds_test_enable(false)
+		.stencil_test_enable(false);
+
+	// The subpass has a color attachment so it needs a blend state but nothing is written to it
+	let color_blend_attachment_state = vk::PipelineColorBlendAttachmentState::builder()
+		.color_write_mask(vk::ColorComponentFlags::empty())
+		.blend_enable(false);
+	let color_blend_attachment_states = [color_blend_attachment_state.build()];
+
+	let color_blend_state_create_info = vk::PipelineColorBlendStateCreateInfo::builder()
+		.logic_op_enable(false)
+		.attachments(&color_blend_attachment_states);
+
+	let pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
+		.stages(&stage_create_infos)
+		.vertex_input_state(&vertex_input_state_create_info)
+		.input_assembly_state(&input_assembly_state_create_info)
+		.viewport_state(&viewport_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
+		.rasterization_state(rasterization_state_create_info)
+		.multisample_state(&multisample_state_create_info)
+		.depth_stencil_state(&depth_stencil_state_create_info)
+		.color_blend_state(&color_blend_state_create_info)
+		.layout(pipeline_layout)
+		.render_pass(render_pass)
+		.subpass(0);
+
+	let pipeline = unsafe { logical_device.create_graphics_pipelines(pipeline_cache, &[pipeline_create_info.build()], None) }.map_err(|(_, result)| result)?[0];
+
+	unsafe { logical_device.destroy_shader_module(vert_module, None) };
+
+	Ok(pipeline)
 }
 
 pub fn create_static_descriptor_sets(logical_device: &ash::Device, descriptor_pool: vk::DescriptorPool, instance_data_descriptor_set_layout: vk::DescriptorSetLayout) -> Vec<vk::DescriptorSet> {
-	let descriptor_set_layouts = [instance_data_descriptor_set_layout, instance_data_descriptor_set_layout, instance_data_descriptor_set_layout, instance_data_descriptor_set_layout];
+	let descriptor_set_layouts = [instance_data_descriptor_set_layout; MATERIALS_COUNT];
 	let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo::builder()
 		.descriptor_pool(descriptor_pool)
 		.set_layouts(&descriptor_set_layouts);
 	
 	unsafe { logical_device.allocate_descriptor_sets(&descriptor_set_allocate_info) }.unwrap()
+}
+
+// Creates a set of mesh pipelines for each target, the sets which were already created are destroyed if a later one fails
+pub fn create_mesh_pipelines_per_target(
+	logical_device: &ash::Device,
+	pipeline_cache: vk::PipelineCache,
+	pipeline_layout: vk::PipelineLayout,
+	depth_prepass_pipeline_layout: vk::PipelineLayout,
+	depth_prepass_enabled: bool,
+	rasterization_state_create_info: &vk::PipelineRasterizationStateCreateInfo,
+	pipeline_targets: &[PipelineTarget])
+	-> Result<Vec<MeshPipelines>, RenderSystemError>
+{
+	let mut target_pipelines: Vec<MeshPipelines> = Vec::with_capacity(pipeline_targets.len());
+
+	for target in pipeline_targets {
+		match create_mesh_pipelines(logical_device, pipeline_cache, pipeline_layout, depth_prepass_pipeline_layout, depth_prepass_enabled, rasterization_state_create_info, target) {
+			Ok(pipelines) => target_pipelines.push(pipelines),
+			Err(error) => {
+				for pipelines in &target_pipelines {
+					pipelines.destroy(logical_device);
+				}
+
+				return Err(error);
+			}
+		}
+	}
+
+	Ok(target_pipelines)
+}
+
+fn create_mesh_pipelines(
+	logical_device: &ash::Device,
+	pipeline_cache: vk::PipelineCache,
+	pipeline_layout: vk::PipelineLayout,
+	depth_prepass_pipeline_layout: vk::PipelineLayout,
+	depth_prepass_enabled: bool,
+	rasterization_state_create_info: &vk::PipelineRasterizationStateCreateInfo,
+	target: &PipelineTarget)
+	-> Result<MeshPipelines, RenderSystemError>
+{
+	let pipelines = create_pipelines(logical_device, pipeline_cache, pipeline_layout, target.render_pass, target.sample_count, target.gamma_correction, depth_prepass_enabled, rasterization_state_create_info)?;
+
+	let mut mesh_pipelines = MeshPipelines {
+		line: pipelines[0],
+		basic: pipelines[1],
+		normal: pipelines[2],
+		lambert: pipelines[3],
+		phong: pipelines[4],
+		vertex_color: pipelines[5],
+		depth_prepass: vk::Pipeline::null()
+	};
+
+	if depth_prepass_enabled {
+		match create_depth_prepass_pipeline(logical_device, pipeline_cache, depth_prepass_pipeline_layout, target.render_pass, target.sample_count, rasterization_state_create_info) {
+			Ok(pipeline) => mesh_pipelines.depth_prepass = pipeline,
+			Err(error) => {
+				mesh_pipelines.destroy(logical_device);
+				return Err(error);
+			}
+		}
+	}
+
+	Ok(mesh_pipelines)
 }
\ No newline at end of file
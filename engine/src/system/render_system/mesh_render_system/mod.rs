@@ -1,21 +1,46 @@
 use std::{mem::size_of_val, ptr::copy_nonoverlapping};
 use ash::{vk, version::DeviceV1_0};
-use crate::{component::mesh::Material, geometry3d::{Geometry3D, SubmissionInfo}, pool::{Pool, Handle}, vulkan::{Buffer, Context}};
-use super::MATERIALS_COUNT;
+use crate::{component::mesh::Material, geometry3d::{Geometry3D, SubmissionInfo}, math::{Matrix4, Vector3}, pool::{Pool, Handle}, vulkan::{Buffer, Context, StagingBuffer}};
+use super::{CullMode, FrontFace, PipelineTarget, RenderSystemError, MATERIALS_COUNT};
 
 mod creation;
 use creation::*;
 
+// Instance data layout (std140)
+// - mat4 model matrix at 0
+// - Basic instances follow with the vec3 color at 64
+// - Lambert instances follow with the mat3 normal matrix at 64, each row is padded to a vec4
+// - Phong instances follow the normal matrix with the vec3 specular color at 112 and the float shininess at 124
+// - Every instance ends with the vec4 tint, the color in xyz and the strength in w
+pub const MESH_INSTANCE_DATA_SIZE: usize = 20 * 4;
+pub const BASIC_INSTANCE_DATA_SIZE: usize = 24 * 4;
+const BASIC_INSTANCE_DATA_COLOR_OFFSET: usize = 16 * 4;
+pub const LAMBERT_INSTANCE_DATA_SIZE: usize = 32 * 4;
+const LAMBERT_INSTANCE_DATA_NORMAL_MATRIX_OFFSET: usize = 16 * 4;
+pub const PHONG_INSTANCE_DATA_SIZE: usize = 36 * 4;
+const PHONG_INSTANCE_DATA_SPECULAR_OFFSET: usize = 28 * 4;
+const PHONG_INSTANCE_DATA_SHININESS_OFFSET: usize = 31 * 4;
+const INSTANCE_DATA_TINT_SIZE: usize = 4 * 4;
+
+// Each material's instances are packed in one array and each instance group is drawn with a single instanced draw call
+// The group's first instance is the draw's first instance so gl_InstanceIndex indexes the whole array
+pub fn instance_data_offset(array_offset: usize, instance_data_size: usize, first_instance: usize, instance_index: usize) -> usize {
+	array_offset + instance_data_size * (first_instance + instance_index)
+}
+
 pub struct MeshRenderSystem {
 	pub pipeline_layout: vk::PipelineLayout,
-	pub line_pipeline: vk::Pipeline,
-	pub basic_pipeline: vk::Pipeline,
-	pub normal_pipeline: vk::Pipeline,
-	pub lambert_pipeline: vk::Pipeline,
+	pub depth_prepass_pipeline_layout: vk::PipelineLayout,
+	pub pipelines: Vec<MeshPipelines>,
+	pub depth_prepass_enabled: bool,
+	pub cull_mode: CullMode,
+	pub front_face: FrontFace,
 	pub line_static_descriptor_set: vk::DescriptorSet,
 	pub basic_static_descriptor_set: vk::DescriptorSet,
 	pub normal_static_descriptor_set: vk::DescriptorSet,
 	pub lambert_static_descriptor_set: vk::DescriptorSet,
+	pub phong_static_descriptor_set: vk::DescriptorSet,
+	pub vertex_color_static_descriptor_set: vk::DescriptorSet,
 	pub static_geometry_buffer: Buffer,
 	pub static_geometry_infos: Vec<StaticGeometryInfo>,
 	pub static_instance_groups: Vec<StaticInstanceGroup>,
@@ -23,6 +48,32 @@ pub struct MeshRenderSystem {
 	static_geometry_submission_generation: usize
 }
 
+// One set for each pipeline target, the depth pre-pass pipeline is null while the pre-pass is disabled
+#[derive(Clone, Copy)]
+pub struct MeshPipelines {
+	pub line: vk::Pipeline,
+	pub basic: vk::Pipeline,
+	pub normal: vk::Pipeline,
+	pub lambert: vk::Pipeline,
+	pub phong: vk::Pipeline,
+	pub vertex_color: vk::Pipeline,
+	pub depth_prepass: vk::Pipeline
+}
+
+impl MeshPipelines {
+	fn destroy(&self, logical_device: &ash::Device) {
+		unsafe {
+			logical_device.destroy_pipeline(self.depth_prepass, None);
+			logical_device.destroy_pipeline(self.vertex_color, None);
+			logical_device.destroy_pipeline(self.phong, None);
+			logical_device.destroy_pipeline(self.lambert, None);
+			logical_device.destroy_pipeline(self.normal, None);
+			logical_device.destroy_pipeline(self.basic, None);
+			logical_device.destroy_pipeline(self.line, None);
+		}
+	}
+}
+
 #[derive(Clone)]
 pub struct StaticGeometryInfo {
 	pub index_array_offset: usize,
@@ -40,64 +91,97 @@ pub struct StaticInstanceGroup {
 impl MeshRenderSystem {
 	pub fn new(
 		logical_device: &ash::Device,
+		pipeline_cache: vk::PipelineCache,
 		frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
 		instance_data_descriptor_set_layout: vk::DescriptorSetLayout,
-		extent: vk::Extent2D,
-		render_pass: vk::RenderPass,
+		shadow_map_descriptor_set_layout: vk::DescriptorSetLayout,
+		pipeline_targets: &[PipelineTarget],
 		descriptor_pool: vk::DescriptorPool)
-		-> Self
+		-> Result<Self, RenderSystemError>
 	{
-		let pipeline_layout = create_pipeline_layout(logical_device, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout);
-		let pipelines = create_pipelines(logical_device, extent, pipeline_layout, render_pass);
+		let pipeline_layout = create_pipeline_layout(logical_device, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout, shadow_map_descriptor_set_layout);
+		let depth_prepass_pipeline_layout = create_depth_prepass_pipeline_layout(logical_device, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout);
+		let rasterization_state_create_info = mesh_rasterization_state(CullMode::Back, FrontFace::CounterClockwise);
+		let pipelines = match create_mesh_pipelines_per_target(logical_device, pipeline_cache, pipeline_layout, depth_prepass_pipeline_layout, false, &rasterization_state_create_info, pipeline_targets) {
+			Ok(pipelines) => pipelines,
+			Err(error) => {
+				unsafe {
+					logical_device.destroy_pipeline_layout(depth_prepass_pipeline_layout, None);
+					logical_device.destroy_pipeline_layout(pipeline_layout, None);
+				}
+
+				return Err(error);
+			}
+		};
+
 		let static_descriptor_sets = create_static_descriptor_sets(logical_device, descriptor_pool, instance_data_descriptor_set_layout);
 
-		let static_geometry_buffer = Buffer::null(
+		let static_geometry_buffer = Buffer::null_concurrent(
 			vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER | vk::BufferUsageFlags::VERTEX_BUFFER,
 			vk::MemoryPropertyFlags::DEVICE_LOCAL);
 
-		Self {
+		Ok(Self {
 			pipeline_layout,
-			line_pipeline: pipelines[0],
-			basic_pipeline: pipelines[1],
-			normal_pipeline: pipelines[2],
-			lambert_pipeline: pipelines[3],
+			depth_prepass_pipeline_layout,
+			pipelines,
+			depth_prepass_enabled: false,
+			cull_mode: CullMode::Back,
+			front_face: FrontFace::CounterClockwise,
 			line_static_descriptor_set: static_descriptor_sets[0],
 			basic_static_descriptor_set: static_descriptor_sets[1],
 			normal_static_descriptor_set: static_descriptor_sets[2],
 			lambert_static_descriptor_set: static_descriptor_sets[3],
+			phong_static_descriptor_set: static_descriptor_sets[4],
+			vertex_color_static_descriptor_set: static_descriptor_sets[5],
 			static_geometry_buffer,
 			static_geometry_infos: vec![],
 			static_instance_groups: vec![],
 			static_material_counts: [0; MATERIALS_COUNT],
 			static_geometry_submission_generation: 0
-		}
+		})
 	}
 
-	pub fn handle_swapchain_recreation(&mut self, logical_device: &ash::Device, extent: vk::Extent2D, render_pass: vk::RenderPass) {
-		unsafe {
-			logical_device.destroy_pipeline(self.lambert_pipeline, None);
-			logical_device.destroy_pipeline(self.normal_pipeline, None);
-			logical_device.destroy_pipeline(self.basic_pipeline, None);
-			logical_device.destroy_pipeline(self.line_pipeline, None);
-		}
+	// The mesh pipelines' depth state depends on the pre-pass so they're recreated along with it
+	pub fn set_depth_prepass_enabled(&mut self, logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, enabled: bool, pipeline_targets: &[PipelineTarget]) -> Result<(), RenderSystemError> {
+		self.depth_prepass_enabled = enabled;
+		self.recreate_pipelines(logical_device, pipeline_cache, pipeline_targets)
+	}
+
+	// The cull mode is baked into the pipelines so they're recreated, the swapchain doesn't own them so it persists across resizes
+	pub fn set_cull_mode(&mut self, logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, cull_mode: CullMode, pipeline_targets: &[PipelineTarget]) -> Result<(), RenderSystemError> {
+		self.cull_mode = cull_mode;
+		self.recreate_pipelines(logical_device, pipeline_cache, pipeline_targets)
+	}
+
+	pub fn set_front_face(&mut self, logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, front_face: FrontFace, pipeline_targets: &[PipelineTarget]) -> Result<(), RenderSystemError> {
+		self.front_face = front_face;
+		self.recreate_pipelines(logical_device, pipeline_cache, pipeline_targets)
+	}
 
-		let pipelines = create_pipelines(logical_device, extent, self.pipeline_layout, render_pass);
+	// The old pipelines are only destroyed once the new ones are created so they're still valid if creation fails
+	pub fn recreate_pipelines(&mut self, logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, pipeline_targets: &[PipelineTarget]) -> Result<(), RenderSystemError> {
+		let rasterization_state_create_info = mesh_rasterization_state(self.cull_mode, self.front_face);
+		let pipelines = create_mesh_pipelines_per_target(logical_device, pipeline_cache, self.pipeline_layout, self.depth_prepass_pipeline_layout, self.depth_prepass_enabled, &rasterization_state_create_info, pipeline_targets)?;
 
-		self.line_pipeline = pipelines[0];
-		self.basic_pipeline = pipelines[1];
-		self.normal_pipeline = pipelines[2];
-		self.lambert_pipeline = pipelines[3];
+		for pipelines in &self.pipelines {
+			pipelines.destroy(logical_device);
+		}
+
+		self.pipelines = pipelines;
+		Ok(())
 	}
 
-	pub fn submit_static_geometries(&mut self, context: &Context, command_pool: vk::CommandPool, geometries: &mut Pool<Geometry3D>, handles: &[Handle]) {
-		// Don't forget to increment the submission generation
+	// The caller ensures no in flight frame is still reading the static geometry buffer
+	pub fn submit_static_geometries(&mut self, context: &Context, command_pool: vk::CommandPool, staging_buffer: &mut StagingBuffer, geometries: &mut Pool<Geometry3D>, handles: &[Handle]) -> Result<(), RenderSystemError> {
+		// Geometries from earlier submissions are no longer in the buffer
+		self.static_geometry_submission_generation += 1;
 		let logical_device = &context.logical_device;
 
 		let mut buffer_size = 0;
 
 		for handle in handles {
 			let geometry = geometries.borrow_mut(*handle);
-			let index_array_size = size_of_val(geometry.indices());
+			let index_array_size = geometry.indices().as_bytes().len();
 			let attributes_array_size = size_of_val(geometry.attributes());
 
 			let index_array_offset = buffer_size;
@@ -114,91 +198,317 @@ impl MeshRenderSystem {
 			buffer_size += index_array_size + attributes_array_padding + attributes_array_size;
 		}
 		
-		let buffer_size = buffer_size as u64;
+		// There's nothing to copy so the previous submission's memory is released instead of making a zero sized copy
+		if buffer_size == 0 {
+			self.clear_static_geometries();
+			return Ok(());
+		}
 
-		// Create a host visible staging buffer
-		let staging_buffer = Buffer::new(&context, buffer_size, vk::BufferUsageFlags::TRANSFER_SRC, vk::MemoryPropertyFlags::HOST_VISIBLE);
+		let buffer_size = buffer_size as u64;
 
 		// Allocate larger device local buffer if necessary and update descriptor sets to reference new buffer
 		if buffer_size > self.static_geometry_buffer.capacity {
-			unsafe { logical_device.queue_wait_idle(context.graphics_queue) }.unwrap();
-			self.static_geometry_buffer.reallocate(&context, buffer_size);
+			self.static_geometry_buffer.reserve(&context, buffer_size)?;
+			context.set_object_name(self.static_geometry_buffer.handle, "static_geometry_buffer");
 			println!("Static mesh buffer reallocated");
 		}
 
-		// Copy mesh data into staging buffer and save draw information
-		let buffer_ptr = unsafe { logical_device.map_memory(staging_buffer.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) }.unwrap();
-
-		for handle in handles {
-			let geometry = geometries.borrow(*handle);
-			let submission_info = geometry.submission_info.as_ref().unwrap();
-			let indices = geometry.indices();
-			let attributes = geometry.attributes();
+		// Copy mesh data into the staging buffer
+		staging_buffer.write(context, buffer_size, |buffer_ptr| {
+			for handle in handles {
+				let geometry = geometries.borrow(*handle);
+				let submission_info = geometry.submission_info.as_ref().unwrap();
+				let indices = geometry.indices();
+				let attributes = geometry.attributes();
 
-			unsafe {
-				let index_array_dst_ptr = buffer_ptr.add(submission_info.index_array_offset) as *mut u16;
-				copy_nonoverlapping(indices.as_ptr(), index_array_dst_ptr, indices.len());
+				unsafe {
+					let index_array_dst_ptr = buffer_ptr.add(submission_info.index_array_offset) as *mut u8;
+					copy_nonoverlapping(indices.as_bytes().as_ptr(), index_array_dst_ptr, indices.as_bytes().len());
 
-				let attribute_array_dst_ptr = buffer_ptr.add(submission_info.attributes_array_offset) as *mut f32;
-				copy_nonoverlapping(attributes.as_ptr(), attribute_array_dst_ptr, attributes.len());
+					let attribute_array_dst_ptr = buffer_ptr.add(submission_info.attributes_array_offset) as *mut f32;
+					copy_nonoverlapping(attributes.as_ptr(), attribute_array_dst_ptr, attributes.len());
+				}
 			}
-		}
+		})?;
 
-		// Flush and unmap staging buffer
-		let range = vk::MappedMemoryRange::builder()
-			.memory(staging_buffer.memory)
-			.offset(0)
-			.size(vk::WHOLE_SIZE);
+		// Copy the data from the staging buffer to the device local buffer, the next frame waits for the copy to finish
+		let region = vk::BufferCopy::builder()
+			.size(buffer_size);
 
-		unsafe {
-			logical_device.flush_mapped_memory_ranges(&[range.build()]).unwrap();
-			logical_device.unmap_memory(staging_buffer.memory);
-		}
+		staging_buffer.submit_transfer(context, command_pool, |command_buffer, staging_buffer_handle| {
+			unsafe { logical_device.cmd_copy_buffer(command_buffer, staging_buffer_handle, self.static_geometry_buffer.handle, &[region.build()]) };
+		})?;
 
-		// Record a command buffer to copy the data from the staging buffer to the device local buffer
-		let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
-			.level(vk::CommandBufferLevel::PRIMARY)
-			.command_pool(command_pool)
-			.command_buffer_count(1);
+		Ok(())
+	}
 
-		let command_buffer = unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }.unwrap()[0];
+	// The caller ensures no in flight frame or transfer is still using the static geometry buffer
+	pub fn clear_static_geometries(&mut self) {
+		self.static_geometry_submission_generation += 1;
+		self.static_geometry_infos.clear();
+		self.static_instance_groups.clear();
+		self.static_material_counts = [0; MATERIALS_COUNT];
+		self.static_geometry_buffer.free();
+	}
 
-		let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
-			.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
-		
-		let region = vk::BufferCopy::builder()
-			.size(buffer_size);
+	pub fn drop(&self, logical_device: &ash::Device) {
+		for pipelines in &self.pipelines {
+			pipelines.destroy(logical_device);
+		}
 		
 		unsafe {
-			logical_device.begin_command_buffer(command_buffer, &command_buffer_begin_info).unwrap();
-			logical_device.cmd_copy_buffer(command_buffer, staging_buffer.handle, self.static_geometry_buffer.handle, &[region.build()]);
-			logical_device.end_command_buffer(command_buffer).unwrap();
+			logical_device.destroy_pipeline_layout(self.depth_prepass_pipeline_layout, None);
+			logical_device.destroy_pipeline_layout(self.pipeline_layout, None);
 		}
+	}
+}
 
-		// Submit the command buffer
-		let command_buffers = [command_buffer];
-		let submit_info = vk::SubmitInfo::builder()
-			.command_buffers(&command_buffers);
-		
-		unsafe {
-			logical_device.queue_wait_idle(context.graphics_queue).unwrap();
-			logical_device.queue_submit(context.graphics_queue, &[submit_info.build()], vk::Fence::null()).unwrap();
-			logical_device.queue_wait_idle(context.graphics_queue).unwrap();
-			logical_device.free_command_buffers(command_pool, &command_buffers);
+// Line, normal and vertex color instances are only the model matrix and tint
+pub fn copy_mesh_instance_data(dst: &mut [u8], model_matrix: &Matrix4, tint: &Vector3, tint_strength: f32) {
+	assert_eq!(dst.len(), MESH_INSTANCE_DATA_SIZE, "Cannot copy mesh instance data because the destination is {} bytes instead of {}", dst.len(), MESH_INSTANCE_DATA_SIZE);
+	copy_matrix4(dst, model_matrix);
+	copy_tint(dst, tint, tint_strength);
+}
+
+pub fn copy_basic_instance_data(dst: &mut [u8], model_matrix: &Matrix4, color: &Vector3, tint: &Vector3, tint_strength: f32) {
+	assert_eq!(dst.len(), BASIC_INSTANCE_DATA_SIZE, "Cannot copy basic instance data because the destination is {} bytes instead of {}", dst.len(), BASIC_INSTANCE_DATA_SIZE);
+	copy_matrix4(dst, model_matrix);
+
+	copy_f32(dst, BASIC_INSTANCE_DATA_COLOR_OFFSET, color.x);
+	copy_f32(dst, BASIC_INSTANCE_DATA_COLOR_OFFSET + 4, color.y);
+	copy_f32(dst, BASIC_INSTANCE_DATA_COLOR_OFFSET + 8, color.z);
+	copy_tint(dst, tint, tint_strength);
+}
+
+// The normal matrix is the inverse transpose of the upper 3x3 so normals stay perpendicular to surfaces under non uniform scale
+pub fn copy_lambert_instance_data(dst: &mut [u8], model_matrix: &Matrix4, tint: &Vector3, tint_strength: f32) {
+	assert_eq!(dst.len(), LAMBERT_INSTANCE_DATA_SIZE, "Cannot copy lambert instance data because the destination is {} bytes instead of {}", dst.len(), LAMBERT_INSTANCE_DATA_SIZE);
+	copy_matrix4(dst, model_matrix);
+	copy_normal_matrix(dst, model_matrix);
+	copy_tint(dst, tint, tint_strength);
+}
+
+// Phong instances are lambert instances with the specular color and shininess inserted before the tint
+pub fn copy_phong_instance_data(dst: &mut [u8], model_matrix: &Matrix4, specular: &Vector3, shininess: f32, tint: &Vector3, tint_strength: f32) {
+	assert_eq!(dst.len(), PHONG_INSTANCE_DATA_SIZE, "Cannot copy phong instance data because the destination is {} bytes instead of {}", dst.len(), PHONG_INSTANCE_DATA_SIZE);
+	copy_matrix4(dst, model_matrix);
+	copy_normal_matrix(dst, model_matrix);
+
+	copy_f32(dst, PHONG_INSTANCE_DATA_SPECULAR_OFFSET, specular.x);
+	copy_f32(dst, PHONG_INSTANCE_DATA_SPECULAR_OFFSET + 4, specular.y);
+	copy_f32(dst, PHONG_INSTANCE_DATA_SPECULAR_OFFSET + 8, specular.z);
+	copy_f32(dst, PHONG_INSTANCE_DATA_SHININESS_OFFSET, shininess);
+	copy_tint(dst, tint, tint_strength);
+}
+
+fn copy_tint(dst: &mut [u8], tint: &Vector3, tint_strength: f32) {
+	let offset = dst.len() - INSTANCE_DATA_TINT_SIZE;
+	copy_f32(dst, offset, tint.x);
+	copy_f32(dst, offset + 4, tint.y);
+	copy_f32(dst, offset + 8, tint.z);
+	copy_f32(dst, offset + 12, tint_strength);
+}
+
+fn copy_normal_matrix(dst: &mut [u8], model_matrix: &Matrix4) {
+	let mut normal_matrix = model_matrix.truncate();
+	normal_matrix.invert();
+	normal_matrix.transpose();
+
+	for (row_index, row) in normal_matrix.to_padded_array().iter().enumerate() {
+		for (col_index, element) in row.iter().enumerate() {
+			copy_f32(dst, LAMBERT_INSTANCE_DATA_NORMAL_MATRIX_OFFSET + 16 * row_index + 4 * col_index, *element);
 		}
+	}
+}
 
-		staging_buffer.drop(logical_device);
+fn copy_matrix4(dst: &mut [u8], matrix: &Matrix4) {
+	for (row_index, row) in matrix.elements.iter().enumerate() {
+		for (col_index, element) in row.iter().enumerate() {
+			copy_f32(dst, 16 * row_index + 4 * col_index, *element);
+		}
 	}
+}
 
-	pub fn drop(&self, logical_device: &ash::Device) {
-		self.static_geometry_buffer.drop(logical_device);
-		
-		unsafe {
-			logical_device.destroy_pipeline(self.lambert_pipeline, None);
-			logical_device.destroy_pipeline(self.normal_pipeline, None);
-			logical_device.destroy_pipeline(self.basic_pipeline, None);
-			logical_device.destroy_pipeline(self.line_pipeline, None);
-			logical_device.destroy_pipeline_layout(self.pipeline_layout, None);
+fn copy_f32(dst: &mut [u8], offset: usize, value: f32) {
+	dst[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::convert::TryInto;
+	use crate::math::{Vector3, vector3, matrix4, quaternion};
+
+	fn read_f32(src: &[u8], offset: usize) -> f32 {
+		f32::from_ne_bytes(src[offset..offset + 4].try_into().unwrap())
+	}
+
+	#[test]
+	fn instance_data_offsets() {
+		// Two groups of 3 and 2 instances packed after another material's array
+		let array_offset = 512;
+		let group_offsets: Vec<usize> = (0..3).map(|i| instance_data_offset(array_offset, MESH_INSTANCE_DATA_SIZE, 0, i)).collect();
+		assert_eq!(group_offsets, vec![512, 592, 672]);
+
+		let group_offsets: Vec<usize> = (0..2).map(|i| instance_data_offset(array_offset, MESH_INSTANCE_DATA_SIZE, 3, i)).collect();
+		assert_eq!(group_offsets, vec![752, 832]);
+
+		// The last lambert instance ends exactly at the end of the array
+		let instance_count = 5;
+		let last_offset = instance_data_offset(array_offset, LAMBERT_INSTANCE_DATA_SIZE, 3, 1);
+		assert_eq!(last_offset + LAMBERT_INSTANCE_DATA_SIZE, array_offset + LAMBERT_INSTANCE_DATA_SIZE * instance_count);
+	}
+
+	#[test]
+	fn depth_prepass_depth_state() {
+		assert_eq!(mesh_depth_state(false), (vk::CompareOp::LESS, true));
+
+		// Fragments are only shaded where the pre-pass left their depth and the depth buffer isn't written again
+		assert_eq!(mesh_depth_state(true), (vk::CompareOp::EQUAL, false));
+	}
+
+	#[test]
+	fn rasterization_state() {
+		// glTF and the built in geometries wind front faces counter clockwise
+		let rasterization_state = mesh_rasterization_state(CullMode::Back, FrontFace::CounterClockwise);
+		assert_eq!(rasterization_state.front_face, vk::FrontFace::COUNTER_CLOCKWISE);
+		assert_eq!(rasterization_state.cull_mode, vk::CullModeFlags::BACK);
+
+		let rasterization_state = mesh_rasterization_state(CullMode::None, FrontFace::Clockwise);
+		assert_eq!(rasterization_state.front_face, vk::FrontFace::CLOCKWISE);
+		assert_eq!(rasterization_state.cull_mode, vk::CullModeFlags::NONE);
+	}
+
+	#[test]
+	fn basic_instance_data_layout() {
+		assert_eq!(BASIC_INSTANCE_DATA_COLOR_OFFSET, MESH_INSTANCE_DATA_SIZE - INSTANCE_DATA_TINT_SIZE);
+		assert_eq!(BASIC_INSTANCE_DATA_SIZE, BASIC_INSTANCE_DATA_COLOR_OFFSET + 16 + INSTANCE_DATA_TINT_SIZE);
+		assert_eq!(BASIC_INSTANCE_DATA_SIZE % 16, 0);
+
+		// Two instances with different colors packed in one array
+		let mut dst = [0u8; 2 * BASIC_INSTANCE_DATA_SIZE];
+		let mut model_matrix = matrix4::IDENTITY;
+		model_matrix.compose(&Vector3::new(5.0, 6.0, 7.0), &quaternion::ZERO, &Vector3::from_scalar(1.0));
+
+		for (instance_index, color) in [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)].iter().enumerate() {
+			let offset = instance_data_offset(0, BASIC_INSTANCE_DATA_SIZE, 0, instance_index);
+			copy_basic_instance_data(&mut dst[offset..offset + BASIC_INSTANCE_DATA_SIZE], &model_matrix, color, &vector3::ONE, 0.0);
 		}
+
+		assert_eq!(read_f32(&dst, 12), 5.0);
+		assert_eq!(read_f32(&dst, BASIC_INSTANCE_DATA_SIZE + 12), 5.0);
+
+		assert_eq!(read_f32(&dst, 64), 1.0);
+		assert_eq!(read_f32(&dst, 68), 0.0);
+		assert_eq!(read_f32(&dst, BASIC_INSTANCE_DATA_SIZE + 64), 0.0);
+		assert_eq!(read_f32(&dst, BASIC_INSTANCE_DATA_SIZE + 68), 1.0);
+	}
+
+	#[test]
+	fn lambert_instance_data_layout() {
+		// std140 aligns a mat3 to 16 bytes and the array stride to 16 bytes
+		assert_eq!(LAMBERT_INSTANCE_DATA_NORMAL_MATRIX_OFFSET, MESH_INSTANCE_DATA_SIZE - INSTANCE_DATA_TINT_SIZE);
+		assert_eq!(LAMBERT_INSTANCE_DATA_SIZE, LAMBERT_INSTANCE_DATA_NORMAL_MATRIX_OFFSET + 3 * 16 + INSTANCE_DATA_TINT_SIZE);
+		assert_eq!(LAMBERT_INSTANCE_DATA_SIZE % 16, 0);
+
+		let mut model_matrix = matrix4::IDENTITY;
+		model_matrix.compose(&Vector3::new(5.0, 6.0, 7.0), &quaternion::ZERO, &Vector3::new(2.0, 1.0, 4.0));
+
+		let mut dst = [0u8; LAMBERT_INSTANCE_DATA_SIZE];
+		copy_lambert_instance_data(&mut dst, &model_matrix, &vector3::ONE, 0.0);
+
+		assert_eq!(read_f32(&dst, 0), 2.0);
+		assert_eq!(read_f32(&dst, 12), 5.0);
+		assert_eq!(read_f32(&dst, 28), 6.0);
+		assert_eq!(read_f32(&dst, 40), 4.0);
+		assert_eq!(read_f32(&dst, 60), 1.0);
+
+		// The non uniform scale is inverted in the normal matrix
+		assert_eq!(read_f32(&dst, 64), 0.5);
+		assert_eq!(read_f32(&dst, 64 + 16 + 4), 1.0);
+		assert_eq!(read_f32(&dst, 64 + 32 + 8), 0.25);
+		assert_eq!(read_f32(&dst, 64 + 12), 0.0);
+	}
+
+	#[test]
+	fn lambert_normal_matrix_rotation() {
+		// A pure rotation is its own normal matrix
+		let mut orientation = quaternion::ZERO;
+		orientation.set_from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), 0.7);
+		let mut model_matrix = matrix4::IDENTITY;
+		model_matrix.make_orientation_from_quaternion(&orientation);
+
+		let mut dst = [0u8; LAMBERT_INSTANCE_DATA_SIZE];
+		copy_lambert_instance_data(&mut dst, &model_matrix, &vector3::ONE, 0.0);
+
+		for row in 0..3 {
+			for col in 0..3 {
+				let expected = model_matrix.elements[row][col];
+				assert!((read_f32(&dst, 64 + 16 * row + 4 * col) - expected).abs() < 1e-6);
+			}
+		}
+	}
+
+	#[test]
+	fn phong_instance_data_layout() {
+		// The specular color and shininess share the vec4 after the normal matrix
+		assert_eq!(PHONG_INSTANCE_DATA_SPECULAR_OFFSET, LAMBERT_INSTANCE_DATA_NORMAL_MATRIX_OFFSET + 3 * 16);
+		assert_eq!(PHONG_INSTANCE_DATA_SHININESS_OFFSET, PHONG_INSTANCE_DATA_SPECULAR_OFFSET + 12);
+		assert_eq!(PHONG_INSTANCE_DATA_SIZE, PHONG_INSTANCE_DATA_SPECULAR_OFFSET + 16 + INSTANCE_DATA_TINT_SIZE);
+		assert_eq!(PHONG_INSTANCE_DATA_SIZE % 16, 0);
+
+		let mut model_matrix = matrix4::IDENTITY;
+		model_matrix.compose(&Vector3::new(5.0, 6.0, 7.0), &quaternion::ZERO, &Vector3::new(2.0, 1.0, 4.0));
+
+		let mut dst = [0u8; PHONG_INSTANCE_DATA_SIZE];
+		copy_phong_instance_data(&mut dst, &model_matrix, &Vector3::new(0.9, 0.8, 0.7), 32.0, &vector3::ONE, 0.0);
+
+		assert_eq!(read_f32(&dst, 12), 5.0);
+		assert_eq!(read_f32(&dst, 64), 0.5);
+		assert_eq!(read_f32(&dst, 112), 0.9);
+		assert_eq!(read_f32(&dst, 116), 0.8);
+		assert_eq!(read_f32(&dst, 120), 0.7);
+		assert_eq!(read_f32(&dst, 124), 32.0);
+	}
+
+	#[test]
+	fn tint_instance_data_layout() {
+		// The tint is the last vec4 of every material's instance data
+		let mut model_matrix = matrix4::IDENTITY;
+		model_matrix.compose(&Vector3::new(5.0, 6.0, 7.0), &quaternion::ZERO, &Vector3::from_scalar(1.0));
+		let tint = Vector3::new(1.0, 0.2, 0.1);
+
+		let mut mesh_dst = [0u8; MESH_INSTANCE_DATA_SIZE];
+		copy_mesh_instance_data(&mut mesh_dst, &model_matrix, &tint, 0.75);
+		let mut basic_dst = [0u8; BASIC_INSTANCE_DATA_SIZE];
+		copy_basic_instance_data(&mut basic_dst, &model_matrix, &Vector3::new(0.5, 0.5, 0.5), &tint, 0.75);
+		let mut lambert_dst = [0u8; LAMBERT_INSTANCE_DATA_SIZE];
+		copy_lambert_instance_data(&mut lambert_dst, &model_matrix, &tint, 0.75);
+		let mut phong_dst = [0u8; PHONG_INSTANCE_DATA_SIZE];
+		copy_phong_instance_data(&mut phong_dst, &model_matrix, &Vector3::new(0.9, 0.8, 0.7), 32.0, &tint, 0.75);
+
+		for (dst, tint_offset) in [(&mesh_dst[..], 64), (&basic_dst[..], 80), (&lambert_dst[..], 112), (&phong_dst[..], 128)].iter() {
+			assert_eq!(dst.len(), tint_offset + INSTANCE_DATA_TINT_SIZE);
+			assert_eq!(dst.len() % 16, 0);
+			assert_eq!(read_f32(dst, 12), 5.0);
+			assert_eq!(read_f32(dst, *tint_offset), 1.0);
+			assert_eq!(read_f32(dst, tint_offset + 4), 0.2);
+			assert_eq!(read_f32(dst, tint_offset + 8), 0.1);
+			assert_eq!(read_f32(dst, tint_offset + 12), 0.75);
+		}
+
+		// The tint doesn't overwrite the material's own data
+		assert_eq!(read_f32(&basic_dst, 64), 0.5);
+		assert_eq!(read_f32(&phong_dst, 112), 0.9);
+		assert_eq!(read_f32(&phong_dst, 124), 32.0);
+	}
+
+	#[test]
+	fn vertex_color_vertex_layout() {
+		let geometry = Geometry3D::new_with_vertex_colors(vec![], vec![]);
+		assert_eq!(VERTEX_COLOR_VERTEX_STRIDE as usize, geometry.attribute_stride() * 4);
+
+		// The color follows the position and normal
+		assert_eq!(VERTEX_COLOR_COLOR_OFFSET, 6 * 4);
 	}
 }
\ No newline at end of file
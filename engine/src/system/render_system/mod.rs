@@ -1,12 +1,12 @@
-use std::{cmp::max, fs::File, mem::size_of_val, ptr::copy_nonoverlapping};
+use std::{cmp::max, ffi::c_void, fs::File, mem::{self, size_of_val}, ptr::{self, copy_nonoverlapping}, slice, thread, time::Duration};
 use crate::{
 	Camera,
-	component::{ComponentList, MultiComponentList, Light, Mesh, TextComponentList, Transform2DComponentList, Transform3DComponentList, mesh::Material, Text},
+	component::{ComponentList, MultiComponentList, Light, Mesh, TextComponentList, Transform2DComponentList, Transform3DComponentList, mesh::Material, Text, text::MAX_FALLBACK_FONTS},
 	Font,
 	Geometry3D,
-	math::{vector3, Vector3},
+	math::{vector3, Box3, Frustum, Matrix4, Vector3},
 	pool::{Pool, Handle},
-	vulkan::{Context, Buffer}
+	vulkan::{Context, Buffer, StagingBuffer}
 };
 use ash::{vk, version::DeviceV1_0, extensions::khr};
 
@@ -19,41 +19,200 @@ use mesh_render_system::*;
 mod text_render_system;
 use text_render_system::*;
 
-const IN_FLIGHT_FRAMES_COUNT: usize = 2;
-const FRAME_DATA_MEMORY_SIZE: usize = 76 * 4;
-const MATERIALS_COUNT: usize = 4;
+mod shadow_render_system;
+use shadow_render_system::*;
+
+mod debug_render_system;
+use debug_render_system::DebugRenderSystem;
+
+mod render_target;
+use render_target::{RenderTarget, scaled_extent, RENDER_TARGET_FORMAT};
+
+mod readback;
+
+mod viewport;
+pub use viewport::Viewport;
+
+mod error;
+pub use error::{RenderSystemError, ScreenshotError};
+
+const SHADER_RELOAD_ATTEMPTS: u32 = 3;
+const SHADER_RELOAD_RETRY_DELAY: Duration = Duration::from_millis(100);
+// Frame data layout (std140), each viewport has its own copy
+// - mat4 projection matrix at 0
+// - mat4 inverse view matrix at 64
+// - vec3 ambient light at 128 and uint point light count at 140
+// - point lights at 144, each is a vec3 position and vec3 color padded to 32 bytes
+// - vec3 directional light direction at 304 and uint shadows enabled flag at 316
+// - vec3 directional light color at 320
+// - mat4 light space matrix at 336
+// - vec3 camera world position at 400
+// - vec3 fog color at 416, float fog density at 428 and uint fog enabled flag at 432
+const FRAME_DATA_MEMORY_SIZE: usize = 112 * 4;
+const FRAME_DATA_INVERSE_VIEW_MATRIX_OFFSET: usize = 16 * 4;
+const FRAME_DATA_AMBIENT_LIGHT_OFFSET: usize = 32 * 4;
+const FRAME_DATA_POINT_LIGHT_COUNT_OFFSET: usize = 35 * 4;
+const FRAME_DATA_POINT_LIGHTS_OFFSET: usize = 36 * 4;
+const FRAME_DATA_POINT_LIGHT_STRIDE: usize = 8 * 4;
+const FRAME_DATA_DIRECTIONAL_LIGHT_DIRECTION_OFFSET: usize = 76 * 4;
+const FRAME_DATA_SHADOWS_ENABLED_OFFSET: usize = 79 * 4;
+const FRAME_DATA_DIRECTIONAL_LIGHT_COLOR_OFFSET: usize = 80 * 4;
+const FRAME_DATA_LIGHT_SPACE_MATRIX_OFFSET: usize = 84 * 4;
+const FRAME_DATA_CAMERA_POSITION_OFFSET: usize = 100 * 4;
+const FRAME_DATA_FOG_COLOR_OFFSET: usize = 104 * 4;
+const FRAME_DATA_FOG_DENSITY_OFFSET: usize = 107 * 4;
+const FRAME_DATA_FOG_ENABLED_OFFSET: usize = 108 * 4;
+const MATERIALS_COUNT: usize = 6;
 const MAX_POINT_LIGHTS: usize = 5;
 const MAX_FONTS: usize = 10;
+const MAX_VIEWPORTS: usize = 4;
+
+// Indices of the mesh and debug pipelines created for each pipeline target
+const SWAPCHAIN_PIPELINES: usize = 0;
+const RENDER_TARGET_PIPELINES: usize = 1;
+const PIPELINE_NAME_PREFIXES: [&str; 2] = ["", "render_target_"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleCount {
+	X1,
+	X2,
+	X4,
+	X8
+}
+
+impl SampleCount {
+	fn as_flags(self) -> vk::SampleCountFlags {
+		match self {
+			Self::X1 => vk::SampleCountFlags::TYPE_1,
+			Self::X2 => vk::SampleCountFlags::TYPE_2,
+			Self::X4 => vk::SampleCountFlags::TYPE_4,
+			Self::X8 => vk::SampleCountFlags::TYPE_8
+		}
+	}
+}
+
+// Which faces of the meshes are discarded
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CullMode {
+	None,
+	Back,
+	Front
+}
+
+impl CullMode {
+	fn as_flags(self) -> vk::CullModeFlags {
+		match self {
+			Self::None => vk::CullModeFlags::NONE,
+			Self::Back => vk::CullModeFlags::BACK,
+			Self::Front => vk::CullModeFlags::FRONT
+		}
+	}
+}
+
+// The winding order of the meshes' front faces as seen by the camera, glTF and the built in geometries are counter clockwise
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrontFace {
+	Clockwise,
+	CounterClockwise
+}
+
+impl FrontFace {
+	fn as_vk(self) -> vk::FrontFace {
+		match self {
+			Self::Clockwise => vk::FrontFace::CLOCKWISE,
+			Self::CounterClockwise => vk::FrontFace::COUNTER_CLOCKWISE
+		}
+	}
+}
 
 pub struct RenderSystem {
-	context: Context,
+	sample_count: vk::SampleCountFlags,
 	render_pass: vk::RenderPass,
-	swapchain: Swapchain,
+	offscreen_render_pass: vk::RenderPass,
+	scaled_render_pass: vk::RenderPass,
+	// None when headless
+	swapchain: Option<Swapchain>,
 	descriptor_pool: vk::DescriptorPool,
 	command_pool: vk::CommandPool,
+	staging_buffer: StagingBuffer,
+	timestamp_query_pool: vk::QueryPool,
+	last_gpu_frame_time: Duration,
 	frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
 	instance_data_descriptor_set_layout: vk::DescriptorSetLayout,
-	in_flight_frames: [InFlightFrame; IN_FLIGHT_FRAMES_COUNT],
+	in_flight_frames: Vec<InFlightFrame>,
 	current_in_flight_frame_index: usize,
 	mesh_resources: MeshRenderSystem,
-	text_resources: TextRenderSystem
+	text_resources: TextRenderSystem,
+	shadow_resources: ShadowRenderSystem,
+	debug_resources: DebugRenderSystem,
+	render_targets: Pool<RenderTarget>,
+	render_scale: f32,
+	scaled_render_target: Option<RenderTarget>,
+	last_presented_image_index: Option<u32>,
+	framebuffer_size: (i32, i32),
+	fog: Option<(Vector3, f32)>,
+	// Fields are dropped in order so the device is destroyed after the buffers free their memory
+	context: Context
 }
 
 struct Swapchain {
 	extension: khr::Swapchain,
 	handle: vk::SwapchainKHR,
 	extent: vk::Extent2D,
-	depth_image_resources: DepthImageResources,
-	frames: Vec<SwapchainFrame>
+	depth_image_resources: ImageResources,
+	color_image_resources: Option<ImageResources>,
+	frames: Vec<SwapchainFrame>,
+	usage: vk::ImageUsageFlags
+}
+
+// Render targets have a fixed format so their render pass isn't compatible with the swapchain's, pipelines are created once for each target
+// The scaled render target has the swapchain's format so it's drawn with the swapchain's pipelines
+#[derive(Clone, Copy)]
+pub struct PipelineTarget {
+	pub render_pass: vk::RenderPass,
+	pub sample_count: vk::SampleCountFlags,
+	pub gamma_correction: bool
+}
+
+// Indexed by SWAPCHAIN_PIPELINES and RENDER_TARGET_PIPELINES
+fn pipeline_targets(render_pass: vk::RenderPass, offscreen_render_pass: vk::RenderPass, sample_count: vk::SampleCountFlags, gamma_correction: bool) -> [PipelineTarget; 2] {
+	[
+		PipelineTarget { render_pass, sample_count, gamma_correction },
+		PipelineTarget { render_pass: offscreen_render_pass, sample_count, gamma_correction: !is_srgb_format(RENDER_TARGET_FORMAT) }
+	]
 }
 
-struct DepthImageResources {
+// The pipelines which were already created are destroyed if a later one fails
+fn create_pipeline_per_target<F>(logical_device: &ash::Device, pipeline_targets: &[PipelineTarget], create_pipeline: F) -> Result<Vec<vk::Pipeline>, RenderSystemError>
+	where F: Fn(&PipelineTarget) -> Result<vk::Pipeline, RenderSystemError>
+{
+	let mut pipelines = Vec::with_capacity(pipeline_targets.len());
+
+	for target in pipeline_targets {
+		match create_pipeline(target) {
+			Ok(pipeline) => pipelines.push(pipeline),
+			Err(error) => {
+				for pipeline in pipelines {
+					unsafe { logical_device.destroy_pipeline(pipeline, None) };
+				}
+
+				return Err(error);
+			}
+		}
+	}
+
+	Ok(pipelines)
+}
+
+#[derive(Default)]
+struct ImageResources {
 	image: vk::Image,
 	image_view: vk::ImageView,
 	memory: vk::DeviceMemory
 }
 
 struct SwapchainFrame {
+	image: vk::Image,
 	image_view: vk::ImageView,
 	framebuffer: vk::Framebuffer,
 	fence: vk::Fence
@@ -66,12 +225,20 @@ struct InFlightFrame {
 	frame_data_descriptor_set: vk::DescriptorSet,
 	primary_command_buffer: vk::CommandBuffer,
 	frame_data_buffer: Buffer,
+	frame_data_buffer_ptr: *mut c_void,
 	instance_data_buffer: Buffer,
+	instance_data_buffer_ptr: *mut c_void,
 	line_instance_data_resources: InstanceDataResources,
 	basic_instance_data_resources: InstanceDataResources,
 	normal_instance_data_resources: InstanceDataResources,
 	lambert_instance_data_resources: InstanceDataResources,
+	phong_instance_data_resources: InstanceDataResources,
+	vertex_color_instance_data_resources: InstanceDataResources,
 	text_instance_data_resources: InstanceDataResources,
+	shadow_secondary_command_buffer: vk::CommandBuffer,
+	depth_prepass_secondary_command_buffer: vk::CommandBuffer,
+	debug_secondary_command_buffer: vk::CommandBuffer,
+	timestamps_written: bool,
 	index_arrays_offset: usize,
 }
 
@@ -82,17 +249,132 @@ struct InstanceDataResources {
 	array_size: usize
 }
 
-fn create_shader_module(logical_device: &ash::Device, filename: &str) -> vk::ShaderModule {
+// Rounds the offset up to the next multiple of the alignment
+fn align_offset(offset: usize, alignment: usize) -> usize {
+	offset + (alignment - offset % alignment) % alignment
+}
+
+// Sizes in bytes of everything after the instance data arrays in an in flight frame's instance data buffer
+struct TrailingArraySizes {
+	index_arrays: usize,
+	attribute_arrays: usize,
+	debug_vertices: usize,
+	text_indices: usize,
+	text_attributes: usize,
+	text_instance_indices: usize
+}
+
+// Everything after the instance data arrays is placed from one base so the buffer is sized for exactly what's written
+struct TrailingArrayLayout {
+	index_arrays: usize,
+	attribute_arrays: usize,
+	debug_vertices: usize,
+	text_indices: usize,
+	text_attributes: usize,
+	text_instance_indices: usize,
+	end: usize
+}
+
+impl TrailingArrayLayout {
+	fn new(index_arrays_offset: usize, sizes: &TrailingArraySizes) -> Self {
+		let attribute_arrays = align_offset(index_arrays_offset + sizes.index_arrays, 4);
+		let debug_vertices = attribute_arrays + sizes.attribute_arrays;
+		let text_indices = align_offset(debug_vertices + sizes.debug_vertices, 4);
+		let text_attributes = text_indices + sizes.text_indices;
+		let text_instance_indices = text_attributes + sizes.text_attributes;
+
+		Self {
+			index_arrays: index_arrays_offset,
+			attribute_arrays,
+			debug_vertices,
+			text_indices,
+			text_attributes,
+			text_instance_indices,
+			end: text_instance_indices + sizes.text_instance_indices
+		}
+	}
+}
+
+// Each viewport has its own frame data which is bound with a dynamic offset so it has to be aligned
+fn frame_data_stride(context: &Context) -> usize {
+	align_offset(FRAME_DATA_MEMORY_SIZE, context.physical_device.min_uniform_buffer_offset_alignment as usize)
+}
+
+// The camera position is in world space so fragment shaders can calculate view dependent shading
+fn copy_camera_frame_data(dst: &mut [u8], camera: &Camera, inverse_view_matrix: &Matrix4) {
+	assert_eq!(dst.len(), FRAME_DATA_MEMORY_SIZE, "Cannot copy camera frame data because the destination is {} bytes instead of {}", dst.len(), FRAME_DATA_MEMORY_SIZE);
+
+	for (row_index, row) in camera.projection_matrix.elements.iter().enumerate() {
+		for (col_index, element) in row.iter().enumerate() {
+			copy_f32(dst, 16 * row_index + 4 * col_index, *element);
+		}
+	}
+
+	for (row_index, row) in inverse_view_matrix.elements.iter().enumerate() {
+		for (col_index, element) in row.iter().enumerate() {
+			copy_f32(dst, FRAME_DATA_INVERSE_VIEW_MATRIX_OFFSET + 16 * row_index + 4 * col_index, *element);
+		}
+	}
+
+	let position = camera.transform.global_matrix.extract_position();
+	copy_f32(dst, FRAME_DATA_CAMERA_POSITION_OFFSET, position.x);
+	copy_f32(dst, FRAME_DATA_CAMERA_POSITION_OFFSET + 4, position.y);
+	copy_f32(dst, FRAME_DATA_CAMERA_POSITION_OFFSET + 8, position.z);
+}
+
+fn copy_f32(dst: &mut [u8], offset: usize, value: f32) {
+	dst[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+}
+
+// Shaders are compiled to SPIR-V by the build script
+fn read_shader_file(filename: &str) -> Result<Vec<u32>, RenderSystemError> {
 	let mut file_path = String::from("target/shaders/");
 	file_path.push_str(filename);
 
-	let mut file = File::open(file_path).unwrap();
-	let file_contents = ash::util::read_spv(&mut file).unwrap();
+	File::open(&file_path)
+		.and_then(|mut file| ash::util::read_spv(&mut file))
+		.map_err(|error| RenderSystemError::ShaderFile(file_path, error))
+}
+
+fn create_shader_module(logical_device: &ash::Device, filename: &str) -> Result<vk::ShaderModule, RenderSystemError> {
+	let file_contents = read_shader_file(filename)?;
 
 	let create_info = vk::ShaderModuleCreateInfo::builder()
 		.code(&file_contents);
 
-	unsafe { logical_device.create_shader_module(&create_info, None) }.unwrap()
+	Ok(unsafe { logical_device.create_shader_module(&create_info, None) }?)
+}
+
+impl Swapchain {
+	// Null handles left by a failed creation are ignored by the destroy calls
+	fn drop(&self, logical_device: &ash::Device) {
+		unsafe {
+			self.extension.destroy_swapchain(self.handle, None);
+			logical_device.destroy_image(self.depth_image_resources.image, None);
+			logical_device.destroy_image_view(self.depth_image_resources.image_view, None);
+			logical_device.free_memory(self.depth_image_resources.memory, None);
+
+			if let Some(color_image_resources) = &self.color_image_resources {
+				logical_device.destroy_image(color_image_resources.image, None);
+				logical_device.destroy_image_view(color_image_resources.image_view, None);
+				logical_device.free_memory(color_image_resources.memory, None);
+			}
+
+			for frame in &self.frames {
+				logical_device.destroy_image_view(frame.image_view, None);
+				logical_device.destroy_framebuffer(frame.framebuffer, None);
+			}
+		}
+	}
+
+	// Destroys everything and leaves null handles so dropping it again does nothing
+	fn release(&mut self, logical_device: &ash::Device) {
+		self.drop(logical_device);
+		self.handle = vk::SwapchainKHR::null();
+		self.depth_image_resources = ImageResources::default();
+		self.color_image_resources = None;
+		self.frames.clear();
+	}
 }
 
 impl InFlightFrame {
@@ -108,6 +390,10 @@ impl InFlightFrame {
 		normal_instance_data_array_size: usize,
 		lambert_instance_data_array_offset: usize,
 		lambert_instance_data_array_size: usize,
+		phong_instance_data_array_offset: usize,
+		phong_instance_data_array_size: usize,
+		vertex_color_instance_data_array_offset: usize,
+		vertex_color_instance_data_array_size: usize,
 		text_instance_data_array_offset: usize,
 		text_instance_data_array_size: usize,
 		index_arrays_offset: usize)
@@ -168,6 +454,34 @@ impl InFlightFrame {
 			.descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
 			.buffer_info(&lambert_descriptor_buffer_infos);
 		
+		// Phong
+		let phong_descriptor_buffer_info = vk::DescriptorBufferInfo::builder()
+			.buffer(self.instance_data_buffer.handle)
+			.offset(phong_instance_data_array_offset as u64)
+			.range(max(1, phong_instance_data_array_size) as u64);
+		let phong_descriptor_buffer_infos = [phong_descriptor_buffer_info.build()];
+
+		let phong_write_descriptor_set = vk::WriteDescriptorSet::builder()
+			.dst_set(self.phong_instance_data_resources.descriptor_set)
+			.dst_binding(0)
+			.dst_array_element(0)
+			.descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+			.buffer_info(&phong_descriptor_buffer_infos);
+		
+		// Vertex color
+		let vertex_color_descriptor_buffer_info = vk::DescriptorBufferInfo::builder()
+			.buffer(self.instance_data_buffer.handle)
+			.offset(vertex_color_instance_data_array_offset as u64)
+			.range(max(1, vertex_color_instance_data_array_size) as u64);
+		let vertex_color_descriptor_buffer_infos = [vertex_color_descriptor_buffer_info.build()];
+
+		let vertex_color_write_descriptor_set = vk::WriteDescriptorSet::builder()
+			.dst_set(self.vertex_color_instance_data_resources.descriptor_set)
+			.dst_binding(0)
+			.dst_array_element(0)
+			.descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+			.buffer_info(&vertex_color_descriptor_buffer_infos);
+		
 		// Text
 		let text_descriptor_buffer_info = vk::DescriptorBufferInfo::builder()
 			.buffer(self.instance_data_buffer.handle)
@@ -188,6 +502,8 @@ impl InFlightFrame {
 			basic_write_descriptor_set.build(),
 			normal_write_descriptor_set.build(),
 			lambert_write_descriptor_set.build(),
+			phong_write_descriptor_set.build(),
+			vertex_color_write_descriptor_set.build(),
 			text_write_descriptor_set.build()
 		];
 		
@@ -206,148 +522,680 @@ impl InFlightFrame {
 		self.lambert_instance_data_resources.array_offset = lambert_instance_data_array_offset;
 		self.lambert_instance_data_resources.array_size = lambert_instance_data_array_size;
 
+		self.phong_instance_data_resources.array_offset = phong_instance_data_array_offset;
+		self.phong_instance_data_resources.array_size = phong_instance_data_array_size;
+
+		self.vertex_color_instance_data_resources.array_offset = vertex_color_instance_data_array_offset;
+		self.vertex_color_instance_data_resources.array_size = vertex_color_instance_data_array_size;
+
 		self.text_instance_data_resources.array_offset = text_instance_data_array_offset;
 		self.text_instance_data_resources.array_size = text_instance_data_array_size;
 
 		self.index_arrays_offset = index_arrays_offset;
 	}
+
+	// The command buffers and descriptor sets are freed with their pools and the buffers free themselves
+	fn drop(&self, logical_device: &ash::Device) {
+		unsafe {
+			logical_device.destroy_semaphore(self.image_available, None);
+			logical_device.destroy_semaphore(self.render_finished, None);
+			logical_device.destroy_fence(self.fence, None);
+
+			if !self.frame_data_buffer_ptr.is_null() {
+				logical_device.unmap_memory(self.frame_data_buffer.memory);
+			}
+
+			if !self.instance_data_buffer_ptr.is_null() {
+				logical_device.unmap_memory(self.instance_data_buffer.memory);
+			}
+		}
+	}
 }
 
-impl RenderSystem {
-	pub fn new(glfw: &glfw::Glfw, window: &glfw::Window) -> Self {
-		let context = Context::new(glfw, window);
-		let render_pass = create_render_pass(&context);
-		let (framebuffer_width, framebuffer_height) = window.get_framebuffer_size();
-		let swapchain = create_swapchain(&context, framebuffer_width as u32, framebuffer_height as u32, render_pass);
-		let descriptor_pool = create_descriptor_pool(&context);
-		let command_pool = create_command_pool(&context);
-		let frame_data_descriptor_set_layout = create_frame_data_descriptor_set_layout(&context.logical_device);
-		let instance_data_descriptor_set_layout = create_instance_data_descriptor_set_layout(&context.logical_device);
-		let in_flight_frames = create_in_flight_frames(&context, descriptor_pool, command_pool, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout);
-		let mesh_resources = MeshRenderSystem::new(&context.logical_device, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout, swapchain.extent, render_pass, descriptor_pool);
-		let text_renderer = TextRenderSystem::new(&context.logical_device, instance_data_descriptor_set_layout, swapchain.extent, render_pass, descriptor_pool);
+// Owns what RenderSystem::new has created so far so a failed step destroys it before the context destroys the device
+// Destroying a null handle does nothing so the handles which haven't been created yet are left null
+struct PartialRenderSystem {
+	logical_device: ash::Device,
+	render_pass: vk::RenderPass,
+	offscreen_render_pass: vk::RenderPass,
+	scaled_render_pass: vk::RenderPass,
+	swapchain: Option<Swapchain>,
+	descriptor_pool: vk::DescriptorPool,
+	command_pool: vk::CommandPool,
+	staging_buffer: Option<StagingBuffer>,
+	timestamp_query_pool: vk::QueryPool,
+	frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
+	instance_data_descriptor_set_layout: vk::DescriptorSetLayout,
+	in_flight_frames: Vec<InFlightFrame>,
+	shadow_resources: Option<ShadowRenderSystem>,
+	mesh_resources: Option<MeshRenderSystem>,
+	debug_resources: Option<DebugRenderSystem>
+}
 
+impl PartialRenderSystem {
+	fn new(logical_device: &ash::Device) -> Self {
 		Self {
-			context,
-			render_pass,
-			swapchain,
-			descriptor_pool,
-			command_pool,
-			frame_data_descriptor_set_layout,
-			instance_data_descriptor_set_layout,
-			in_flight_frames,
+			logical_device: logical_device.clone(),
+			render_pass: vk::RenderPass::null(),
+			offscreen_render_pass: vk::RenderPass::null(),
+			scaled_render_pass: vk::RenderPass::null(),
+			swapchain: None,
+			descriptor_pool: vk::DescriptorPool::null(),
+			command_pool: vk::CommandPool::null(),
+			staging_buffer: None,
+			timestamp_query_pool: vk::QueryPool::null(),
+			frame_data_descriptor_set_layout: vk::DescriptorSetLayout::null(),
+			instance_data_descriptor_set_layout: vk::DescriptorSetLayout::null(),
+			in_flight_frames: vec![],
+			shadow_resources: None,
+			mesh_resources: None,
+			debug_resources: None
+		}
+	}
+}
+
+impl Drop for PartialRenderSystem {
+	fn drop(&mut self) {
+		let logical_device = &self.logical_device;
+
+		if let Some(debug_resources) = &self.debug_resources {
+			debug_resources.drop(logical_device);
+		}
+
+		if let Some(mesh_resources) = &mut self.mesh_resources {
+			mesh_resources.drop(logical_device);
+		}
+
+		if let Some(shadow_resources) = &self.shadow_resources {
+			shadow_resources.drop(logical_device);
+		}
+
+		for frame in &self.in_flight_frames {
+			frame.drop(logical_device);
+		}
+
+		if let Some(staging_buffer) = &self.staging_buffer {
+			staging_buffer.drop(logical_device);
+		}
+
+		if let Some(swapchain) = &self.swapchain {
+			swapchain.drop(logical_device);
+		}
+
+		unsafe {
+			logical_device.destroy_descriptor_set_layout(self.instance_data_descriptor_set_layout, None);
+			logical_device.destroy_descriptor_set_layout(self.frame_data_descriptor_set_layout, None);
+			logical_device.destroy_query_pool(self.timestamp_query_pool, None);
+			logical_device.destroy_command_pool(self.command_pool, None);
+			logical_device.destroy_descriptor_pool(self.descriptor_pool, None);
+			logical_device.destroy_render_pass(self.scaled_render_pass, None);
+			logical_device.destroy_render_pass(self.offscreen_render_pass, None);
+			logical_device.destroy_render_pass(self.render_pass, None);
+		}
+	}
+}
+
+impl RenderSystem {
+	// More in flight frames trade latency for throughput, 1 is the lowest latency and 2 or 3 keep the GPU busier
+	pub fn new(glfw: &glfw::Glfw, window: &glfw::Window, enable_validation: bool, sample_count: SampleCount, in_flight_frames_count: usize) -> Result<Self, RenderSystemError> {
+		let context = Context::new(glfw, window, enable_validation)?;
+		Self::from_context(context, Some(window.get_framebuffer_size()), sample_count, in_flight_frames_count)
+	}
+
+	// Renders without a window, e.g. for tests or offline rendering, only render targets can be drawn to and read back
+	pub fn new_headless(enable_validation: bool, sample_count: SampleCount, in_flight_frames_count: usize) -> Result<Self, RenderSystemError> {
+		let context = Context::new_headless(enable_validation)?;
+		Self::from_context(context, None, sample_count, in_flight_frames_count)
+	}
+
+	fn from_context(context: Context, framebuffer_size: Option<(i32, i32)>, sample_count: SampleCount, in_flight_frames_count: usize) -> Result<Self, RenderSystemError> {
+		assert!(in_flight_frames_count > 0, "Cannot create render system because there must be at least one in flight frame");
+
+		let sample_count = choose_sample_count(&context, sample_count);
+
+		// Everything is handed to the partial render system as soon as it's created so an error destroys it before the context
+		// Without a swapchain nothing is presented so the swapchain render pass only has to stay compatible with its pipelines
+		let mut partial = PartialRenderSystem::new(&context.logical_device);
+		let color_format = color_format(&context);
+		let final_layout = if framebuffer_size.is_some() { vk::ImageLayout::PRESENT_SRC_KHR } else { vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL };
+		partial.render_pass = create_render_pass(&context, color_format, sample_count, final_layout)?;
+		partial.offscreen_render_pass = create_render_pass(&context, RENDER_TARGET_FORMAT, sample_count, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)?;
+		partial.scaled_render_pass = create_render_pass(&context, color_format, sample_count, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)?;
+		let render_pass = partial.render_pass;
+
+		// Writes to an sRGB image are encoded by the hardware, otherwise the fragment shaders have to do it
+		let gamma_correction = !is_srgb_format(color_format);
+
+		if let Some((framebuffer_width, framebuffer_height)) = framebuffer_size {
+			partial.swapchain = Some(create_swapchain(&context, framebuffer_width as u32, framebuffer_height as u32, render_pass, sample_count)?);
+		}
+
+		let swapchain_extent = partial.swapchain.as_ref().map_or_else(vk::Extent2D::default, |swapchain| swapchain.extent);
+
+		partial.descriptor_pool = create_descriptor_pool(&context, in_flight_frames_count)?;
+		partial.command_pool = create_command_pool(&context)?;
+		partial.staging_buffer = Some(StagingBuffer::new(&context)?);
+		partial.timestamp_query_pool = create_timestamp_query_pool(&context, in_flight_frames_count)?;
+		partial.frame_data_descriptor_set_layout = create_frame_data_descriptor_set_layout(&context.logical_device)?;
+		partial.instance_data_descriptor_set_layout = create_instance_data_descriptor_set_layout(&context.logical_device)?;
+		let descriptor_pool = partial.descriptor_pool;
+		let frame_data_descriptor_set_layout = partial.frame_data_descriptor_set_layout;
+		let instance_data_descriptor_set_layout = partial.instance_data_descriptor_set_layout;
+
+		let pipeline_targets = pipeline_targets(render_pass, partial.offscreen_render_pass, sample_count, gamma_correction);
+		partial.in_flight_frames = create_in_flight_frames(&context, in_flight_frames_count, descriptor_pool, partial.command_pool, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout)?;
+		let shadow_resources = ShadowRenderSystem::new(&context, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout, descriptor_pool)?;
+		let shadow_map_descriptor_set_layout = shadow_resources.descriptor_set_layout;
+		partial.shadow_resources = Some(shadow_resources);
+		partial.mesh_resources = Some(MeshRenderSystem::new(&context.logical_device, context.pipeline_cache, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout, shadow_map_descriptor_set_layout, &pipeline_targets, descriptor_pool)?);
+		partial.debug_resources = Some(DebugRenderSystem::new(&context.logical_device, context.pipeline_cache, frame_data_descriptor_set_layout, &pipeline_targets)?);
+		let text_resources = TextRenderSystem::new(&context.logical_device, context.pipeline_cache, instance_data_descriptor_set_layout, swapchain_extent, render_pass, sample_count, gamma_correction, descriptor_pool)?;
+
+		// Taking everything out leaves the partial render system with nothing to destroy
+		let render_system = Self {
+			sample_count,
+			render_pass: mem::take(&mut partial.render_pass),
+			offscreen_render_pass: mem::take(&mut partial.offscreen_render_pass),
+			scaled_render_pass: mem::take(&mut partial.scaled_render_pass),
+			swapchain: partial.swapchain.take(),
+			descriptor_pool: mem::take(&mut partial.descriptor_pool),
+			command_pool: mem::take(&mut partial.command_pool),
+			staging_buffer: partial.staging_buffer.take().unwrap(),
+			timestamp_query_pool: mem::take(&mut partial.timestamp_query_pool),
+			last_gpu_frame_time: Duration::new(0, 0),
+			frame_data_descriptor_set_layout: mem::take(&mut partial.frame_data_descriptor_set_layout),
+			instance_data_descriptor_set_layout: mem::take(&mut partial.instance_data_descriptor_set_layout),
+			in_flight_frames: mem::take(&mut partial.in_flight_frames),
 			current_in_flight_frame_index: 0,
-			mesh_resources,
-			text_resources: text_renderer
+			mesh_resources: partial.mesh_resources.take().unwrap(),
+			text_resources,
+			shadow_resources: partial.shadow_resources.take().unwrap(),
+			debug_resources: partial.debug_resources.take().unwrap(),
+			render_targets: Pool::new(),
+			render_scale: 1.0,
+			scaled_render_target: None,
+			last_presented_image_index: None,
+			framebuffer_size: framebuffer_size.unwrap_or((0, 0)),
+			fog: None,
+			context
+		};
+
+		render_system.name_pipelines();
+		Ok(render_system)
+	}
+
+	fn name_pipelines(&self) {
+		for (prefix, pipelines) in PIPELINE_NAME_PREFIXES.iter().zip(&self.mesh_resources.pipelines) {
+			self.context.set_object_name(pipelines.line, &format!("{}line_pipeline", prefix));
+			self.context.set_object_name(pipelines.basic, &format!("{}basic_pipeline", prefix));
+			self.context.set_object_name(pipelines.normal, &format!("{}normal_pipeline", prefix));
+			self.context.set_object_name(pipelines.lambert, &format!("{}lambert_pipeline", prefix));
+			self.context.set_object_name(pipelines.phong, &format!("{}phong_pipeline", prefix));
+			self.context.set_object_name(pipelines.vertex_color, &format!("{}vertex_color_pipeline", prefix));
+
+			if self.mesh_resources.depth_prepass_enabled {
+				self.context.set_object_name(pipelines.depth_prepass, &format!("{}depth_prepass_pipeline", prefix));
+			}
+		}
+
+		for (prefix, pipeline) in PIPELINE_NAME_PREFIXES.iter().zip(&self.debug_resources.pipelines) {
+			self.context.set_object_name(*pipeline, &format!("{}debug_pipeline", prefix));
 		}
+
+		self.context.set_object_name(self.text_resources.pipeline, "text_pipeline");
+		self.context.set_object_name(self.shadow_resources.pipeline, "shadow_pipeline");
 	}
 
+	// Measured from the most recently completed frame which lags behind by the number of in flight frames
+	pub fn last_gpu_frame_time(&self) -> Duration {
+		self.last_gpu_frame_time
+	}
+
+	// True when the swapchain format isn't sRGB so the fragment shaders gamma correct their output
+	pub fn gamma_correction(&self) -> bool {
+		!is_srgb_format(color_format(&self.context))
+	}
+
+	pub fn is_headless(&self) -> bool {
+		self.swapchain.is_none()
+	}
+
+	// Headless render systems have no swapchain so its extent is zero
 	pub fn get_swapchain_extent(&self) -> (u32, u32) {
-		let extent = &self.swapchain.extent;
-		(extent.width, extent.height)
+		self.swapchain.as_ref().map_or((0, 0), |swapchain| (swapchain.extent.width, swapchain.extent.height))
+	}
+
+	// Only the swapchain dependent resources are recreated, the pipelines, layouts and descriptor pool don't depend on the extent
+	pub fn recreate_swapchain(&mut self, framebuffer_width: i32, framebuffer_height: i32) -> Result<(u32, u32), RenderSystemError> {
+		if self.swapchain.is_none() {
+			return Err(RenderSystemError::Headless);
+		}
+
+		// Every submission using the old framebuffers and image views signals an in flight fence so there's no need to idle the whole device
+		// The fences don't cover presentation though, so the present queue is idled before the old swapchain images are released
+		self.wait_for_in_flight_frames()?;
+		unsafe { self.context.logical_device.queue_wait_idle(self.context.present_queue) }?;
+		let swapchain = self.swapchain.as_mut().unwrap();
+		swapchain.release(&self.context.logical_device);
+
+		// On failure only null handles are left behind, the caller can try to recreate the swapchain again
+		*swapchain = create_swapchain(&self.context, framebuffer_width as u32, framebuffer_height as u32, self.render_pass, self.sample_count)?;
+		let extent = swapchain.extent;
+
+		self.text_resources.handle_swapchain_recreation(extent);
+		self.recreate_scaled_render_target();
+		self.last_presented_image_index = None;
+		self.framebuffer_size = (framebuffer_width, framebuffer_height);
+		println!("Swapchain recreated");
+
+		Ok((extent.width, extent.height))
+	}
+
+	// The surface size is decided by the swapchain when the current extent is undefined so it always matches in that case
+	fn surface_extent(&self, swapchain: &Swapchain) -> Result<vk::Extent2D, RenderSystemError> {
+		let surface = self.context.surface.as_ref().ok_or(RenderSystemError::Headless)?;
+		let capabilities = unsafe { surface.extension.get_physical_device_surface_capabilities(self.context.physical_device.handle, surface.handle) }?;
+
+		if capabilities.current_extent.width == u32::MAX {
+			Ok(swapchain.extent)
+		}
+		else {
+			Ok(capabilities.current_extent)
+		}
+	}
+
+	fn wait_for_in_flight_frames(&self) -> Result<(), RenderSystemError> {
+		let fences: Vec<vk::Fence> = self.in_flight_frames.iter().map(|frame| frame.fence).collect();
+		unsafe { self.context.logical_device.wait_for_fences(&fences, true, std::u64::MAX) }?;
+		Ok(())
+	}
+
+	pub fn enable_shadows(&mut self, resolution: u32) -> Result<(), RenderSystemError> {
+		self.shadow_resources.enable(&self.context, resolution)?;
+		self.context.set_object_name(self.shadow_resources.pipeline, "shadow_pipeline");
+		println!("Shadows enabled with a {}x{} shadow map", resolution, resolution);
+		Ok(())
 	}
 
-	pub fn recreate_swapchain(&mut self, framebuffer_width: i32, framebuffer_height: i32) -> (u32, u32) {
+	// Opaque meshes are drawn depth only first so the mesh pipelines only shade the closest fragments
+	pub fn enable_depth_prepass(&mut self, enabled: bool) -> Result<(), RenderSystemError> {
+		if enabled == self.mesh_resources.depth_prepass_enabled {
+			return Ok(());
+		}
+
+		unsafe { self.context.logical_device.device_wait_idle() }?;
+		let pipeline_targets = self.pipeline_targets();
+		self.mesh_resources.set_depth_prepass_enabled(&self.context.logical_device, self.context.pipeline_cache, enabled, &pipeline_targets)?;
+		self.name_pipelines();
+		println!("Depth pre-pass {}", if enabled { "enabled" } else { "disabled" });
+		Ok(())
+	}
+
+	// Disabling culling shows both sides of double sided geometry or meshes with inverted winding
+	pub fn set_cull_mode(&mut self, cull_mode: CullMode) -> Result<(), RenderSystemError> {
+		if cull_mode == self.mesh_resources.cull_mode {
+			return Ok(());
+		}
+
+		unsafe { self.context.logical_device.device_wait_idle() }?;
+		let pipeline_targets = self.pipeline_targets();
+		self.mesh_resources.set_cull_mode(&self.context.logical_device, self.context.pipeline_cache, cull_mode, &pipeline_targets)?;
+		self.name_pipelines();
+		println!("Cull mode set to {:?}", cull_mode);
+		Ok(())
+	}
+
+	// Rebuilds every pipeline from the SPIR-V in target/shaders so edited shaders show up without restarting
+	// The shader compiler may still be writing a file so reading is retried, a pipeline which can't be rebuilt keeps its previous version
+	pub fn reload_shaders(&mut self) -> Result<(), RenderSystemError> {
+		unsafe { self.context.logical_device.device_wait_idle() }?;
+
+		let mut attempt = 1;
+		let result = loop {
+			match self.recreate_pipelines() {
+				Err(RenderSystemError::ShaderFile(..)) if attempt < SHADER_RELOAD_ATTEMPTS => {
+					attempt += 1;
+					thread::sleep(SHADER_RELOAD_RETRY_DELAY);
+				},
+				result => break result
+			}
+		};
+
+		self.name_pipelines();
+		result?;
+		println!("Shaders reloaded");
+		Ok(())
+	}
+
+	fn pipeline_targets(&self) -> [PipelineTarget; 2] {
+		pipeline_targets(self.render_pass, self.offscreen_render_pass, self.sample_count, self.gamma_correction())
+	}
+
+	// The caller ensures the device is idle
+	fn recreate_pipelines(&mut self) -> Result<(), RenderSystemError> {
 		let logical_device = &self.context.logical_device;
+		let pipeline_cache = self.context.pipeline_cache;
+		let pipeline_targets = self.pipeline_targets();
 
-		unsafe {
-			logical_device.device_wait_idle().unwrap();
+		self.mesh_resources.recreate_pipelines(logical_device, pipeline_cache, &pipeline_targets)?;
+		self.text_resources.recreate_pipeline(logical_device, pipeline_cache, self.render_pass, self.sample_count, self.gamma_correction())?;
+		self.debug_resources.recreate_pipelines(logical_device, pipeline_cache, &pipeline_targets)?;
+		self.shadow_resources.recreate_pipeline(logical_device, pipeline_cache)
+	}
 
-			self.swapchain.extension.destroy_swapchain(self.swapchain.handle, None);
-			logical_device.destroy_image(self.swapchain.depth_image_resources.image, None);
-			logical_device.destroy_image_view(self.swapchain.depth_image_resources.image_view, None);
-			logical_device.free_memory(self.swapchain.depth_image_resources.memory, None);
+	pub fn cull_mode(&self) -> CullMode {
+		self.mesh_resources.cull_mode
+	}
 
-			for frame in &self.swapchain.frames {
-				logical_device.destroy_image_view(frame.image_view, None);
-				logical_device.destroy_framebuffer(frame.framebuffer, None);
+	// Meshes authored with clockwise winding are drawn inside out with the default so they can be flipped here
+	pub fn set_front_face(&mut self, front_face: FrontFace) -> Result<(), RenderSystemError> {
+		if front_face == self.mesh_resources.front_face {
+			return Ok(());
+		}
+
+		unsafe { self.context.logical_device.device_wait_idle() }?;
+		let pipeline_targets = self.pipeline_targets();
+		self.mesh_resources.set_front_face(&self.context.logical_device, self.context.pipeline_cache, front_face, &pipeline_targets)?;
+		self.name_pipelines();
+		println!("Front face set to {:?}", front_face);
+		Ok(())
+	}
+
+	pub fn front_face(&self) -> FrontFace {
+		self.mesh_resources.front_face
+	}
+
+	// Lit meshes are blended toward the fog color by exp(-density * depth) where depth is the fragment's view space depth
+	pub fn set_fog(&mut self, color: Vector3, density: f32) {
+		assert!(density >= 0.0, "Cannot set fog because the density {} is negative", density);
+		self.fog = Some((color, density));
+	}
+
+	pub fn disable_fog(&mut self) {
+		self.fog = None;
+	}
+
+	// Debug lines are in world space and drawn by the next render, render_to_texture also draws them but doesn't consume them
+	pub fn debug_line(&mut self, a: &Vector3, b: &Vector3, color: &Vector3) {
+		self.debug_resources.line(a, b, color);
+	}
+
+	pub fn debug_aabb(&mut self, aabb: &Box3, color: &Vector3) {
+		self.debug_resources.aabb(aabb, color);
+	}
+
+	// The grid is made of debug lines so it has to be drawn every frame
+	pub fn draw_grid(&mut self, size: f32, spacing: f32, color: &Vector3) {
+		self.debug_resources.grid(size, spacing, color);
+	}
+
+	// Below 1.0 the scene is rendered at a lower resolution then upscaled to the swapchain image
+	// Upscaling blits into the swapchain image so it's unavailable when the surface doesn't support transfer destination usage
+	pub fn set_render_scale(&mut self, render_scale: f32) -> Result<(), RenderSystemError> {
+		assert!((0.25..=1.0).contains(&render_scale), "Cannot set render scale because {} is not between 0.25 and 1.0", render_scale);
+
+		if render_scale == self.render_scale {
+			return Ok(());
+		}
+
+		let swapchain = self.swapchain.as_ref().ok_or(RenderSystemError::Headless)?;
+
+		if render_scale < 1.0 && !swapchain.usage.contains(vk::ImageUsageFlags::TRANSFER_DST) {
+			return Err(RenderSystemError::UnsupportedSwapchainUsage(vk::ImageUsageFlags::TRANSFER_DST));
+		}
+
+		unsafe { self.context.logical_device.device_wait_idle() }?;
+		self.render_scale = render_scale;
+		self.recreate_scaled_render_target();
+		Ok(())
+	}
+
+	pub fn render_scale(&self) -> f32 {
+		self.render_scale
+	}
+
+	// The caller ensures the device is idle
+	fn recreate_scaled_render_target(&mut self) {
+		if let Some(scaled_render_target) = self.scaled_render_target.take() {
+			scaled_render_target.drop(&self.context.logical_device);
+		}
+
+		// A recreated swapchain could have lost transfer destination usage, the scene is then rendered at full resolution
+		if let Some(swapchain) = &self.swapchain {
+			if self.render_scale < 1.0 && swapchain.usage.contains(vk::ImageUsageFlags::TRANSFER_DST) {
+				let extent = scaled_extent(swapchain.extent, self.render_scale);
+				self.scaled_render_target = Some(RenderTarget::new(&self.context, extent.width, extent.height, color_format(&self.context), self.scaled_render_pass, self.sample_count));
 			}
 		}
+	}
 
-		self.swapchain = create_swapchain(&self.context, framebuffer_width as u32, framebuffer_height as u32, self.render_pass);
-		self.mesh_resources.handle_swapchain_recreation(&self.context.logical_device, self.swapchain.extent, self.render_pass);
-		self.text_resources.handle_swapchain_recreation(&self.context.logical_device, self.swapchain.extent, self.render_pass);
-		println!("Swapchain recreated");
+	pub fn create_render_target(&mut self, width: u32, height: u32) -> Handle {
+		let render_target = RenderTarget::new(&self.context, width, height, RENDER_TARGET_FORMAT, self.offscreen_render_pass, self.sample_count);
+		self.render_targets.add(render_target)
+	}
+
+	// The render target may still be in use by an in flight frame so wait before destroying it
+	pub fn resize_render_target(&mut self, handle: Handle, width: u32, height: u32) {
+		let render_target = self.render_targets.borrow_mut(handle);
 
-		let extent = &self.swapchain.extent;
-		(extent.width, extent.height)
+		unsafe { self.context.logical_device.device_wait_idle() }.unwrap();
+		render_target.drop(&self.context.logical_device);
+		*render_target = RenderTarget::new(&self.context, width, height, RENDER_TARGET_FORMAT, self.offscreen_render_pass, self.sample_count);
 	}
 
-	pub fn submit_static_geometries(&mut self, geometries: &mut Pool<Geometry3D>, handles: &[Handle]) {
-		self.mesh_resources.submit_static_geometries(&self.context, self.command_pool, geometries, handles);
+	pub fn destroy_render_target(&mut self, handle: Handle) {
+		unsafe { self.context.logical_device.device_wait_idle() }.unwrap();
+		self.render_targets.borrow(handle).drop(&self.context.logical_device);
+		self.render_targets.remove(handle);
+	}
+
+	// Only the in flight frames reading the old static geometry are waited on, the copy itself runs on the transfer queue when there is one
+	pub fn submit_static_geometries(&mut self, geometries: &mut Pool<Geometry3D>, handles: &[Handle]) -> Result<(), RenderSystemError> {
+		self.wait_for_in_flight_frames()?;
+		self.mesh_resources.submit_static_geometries(&self.context, self.command_pool, &mut self.staging_buffer, geometries, handles)?;
 		println!("Static meshes submitted");
+		Ok(())
 	}
 
-	pub fn submit_fonts(&mut self, fonts: &mut Pool<Font>) {
-		self.text_resources.submit_fonts(&self.context, self.command_pool, fonts);
+	// Frees the static geometry buffer, e.g. when unloading a level, submitting an empty slice does the same
+	pub fn clear_static_geometries(&mut self) -> Result<(), RenderSystemError> {
+		self.wait_for_in_flight_frames()?;
+		self.staging_buffer.wait_for_transfer(&self.context.logical_device)?;
+		self.mesh_resources.clear_static_geometries();
+		println!("Static meshes cleared");
+		Ok(())
+	}
+
+	pub fn submit_fonts(&mut self, fonts: &mut Pool<Font>) -> Result<(), RenderSystemError> {
+		self.text_resources.submit_fonts(&self.context, self.command_pool, &mut self.staging_buffer, fonts)?;
 		println!("Fonts submitted");
+		Ok(())
 	}
 
+	// Each camera is rendered into its viewport of the swapchain image, headless render systems return an error
+	// An out of date swapchain is recreated here, returns true when that happened so projections can be updated from the new extent
+	// Errors such as a lost device are returned, the render system can only be dropped afterwards
 	pub fn render(&mut self,
-		camera: &Camera,
+		views: &[(&Camera, Viewport)],
 		light_components: &ComponentList<Light>,
 		geometries: &Pool<Geometry3D>,
 		mesh_components: &MultiComponentList<Mesh>,
 		transform3d_components: &Transform3DComponentList,
 		fonts: &Pool<Font>,
 		text_components: &TextComponentList,
-		transform2d_components: &Transform2DComponentList) -> bool
+		transform2d_components: &Transform2DComponentList) -> Result<bool, RenderSystemError>
 	{
+		if self.swapchain.is_none() {
+			return Err(RenderSystemError::Headless);
+		}
+
+		let text = Some((fonts, text_components, transform2d_components));
+		self.draw(None, views, light_components, geometries, mesh_components, transform3d_components, text)
+	}
+
+	// Renders the scene from the camera into the render target's color image which can then be sampled
+	// Text is screen space so it's only drawn to the swapchain
+	pub fn render_to_texture(&mut self,
+		render_target: Handle,
+		camera: &Camera,
+		light_components: &ComponentList<Light>,
+		geometries: &Pool<Geometry3D>,
+		mesh_components: &MultiComponentList<Mesh>,
+		transform3d_components: &Transform3DComponentList) -> Result<(), RenderSystemError>
+	{
+		assert!(self.render_targets.valid_handle(render_target), "Cannot render to texture because the render target handle is invalid");
+		self.draw(Some(render_target), &[(camera, Viewport::FULL)], light_components, geometries, mesh_components, transform3d_components, None)?;
+		Ok(())
+	}
+
+	// Saves the most recently presented swapchain image as a PNG
+	pub fn capture_screenshot(&self, path: &str) -> Result<(), ScreenshotError> {
+		let swapchain = match &self.swapchain {
+			Some(swapchain) if swapchain.usage.contains(vk::ImageUsageFlags::TRANSFER_SRC) => swapchain,
+			_ => return Err(ScreenshotError::Unsupported)
+		};
+
+		let image_index = self.last_presented_image_index.ok_or(ScreenshotError::NothingPresented)?;
+
+		unsafe { self.context.logical_device.device_wait_idle() }?;
+
+		let image = swapchain.frames[image_index as usize].image;
+		let extent = swapchain.extent;
+		let pixels = readback::read_image_pixels(&self.context, self.command_pool, image, vk::ImageLayout::PRESENT_SRC_KHR, color_format(&self.context), extent)?;
+		readback::save_png(path, extent.width, extent.height, &pixels)?;
+
+		println!("Screenshot saved to {}", path);
+		Ok(())
+	}
+
+	// Returns the render target's pixels as R8G8B8A8 rows, top row first, regardless of the surface format
+	// Waits for any in flight frames so the most recent render to texture is included
+	pub fn read_pixels(&self, render_target: Handle) -> Result<Vec<u8>, RenderSystemError> {
+		assert!(self.render_targets.valid_handle(render_target), "Cannot read pixels because the render target handle is invalid");
+
+		unsafe { self.context.logical_device.device_wait_idle() }?;
+		Ok(self.render_targets.borrow(render_target).read_pixels(&self.context, self.command_pool)?)
+	}
+
+	#[allow(clippy::clippy::too_many_arguments)]
+	fn draw(&mut self,
+		render_target: Option<Handle>,
+		views: &[(&Camera, Viewport)],
+		light_components: &ComponentList<Light>,
+		geometries: &Pool<Geometry3D>,
+		mesh_components: &MultiComponentList<Mesh>,
+		transform3d_components: &Transform3DComponentList,
+		text: Option<(&Pool<Font>, &TextComponentList, &Transform2DComponentList)>) -> Result<bool, RenderSystemError>
+	{
+		assert!(!views.is_empty(), "Cannot render scene because there are no cameras");
+		assert!(views.len() <= MAX_VIEWPORTS, "Cannot render scene because {} viewports is more than the limit {}", views.len(), MAX_VIEWPORTS);
+
+		let mut swapchain_recreated = false;
+		let mut acquire_suboptimal = false;
 		let logical_device = &self.context.logical_device;
 		let in_flight_frame = &mut self.in_flight_frames[self.current_in_flight_frame_index];
 		
 		// Wait for this in flight frame to become available
-		unsafe { logical_device.wait_for_fences(&[in_flight_frame.fence], true, std::u64::MAX) }.unwrap();
+		unsafe { logical_device.wait_for_fences(&[in_flight_frame.fence], true, std::u64::MAX) }?;
+
+		// The fence guarantees the timestamps this in flight frame last wrote are available so reading them won't stall
+		if in_flight_frame.timestamps_written {
+			let timestamp_period = self.context.physical_device.timestamp_period.unwrap();
+			let mut timestamps = [0u64; 2];
+			let first_query = 2 * self.current_in_flight_frame_index as u32;
+
+			unsafe {
+				logical_device.get_query_pool_results(
+					self.timestamp_query_pool,
+					first_query,
+					2,
+					&mut timestamps,
+					vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WAIT)
+			}?;
+
+			let ticks = timestamps[1].wrapping_sub(timestamps[0]);
+			self.last_gpu_frame_time = Duration::from_nanos((ticks as f64 * timestamp_period as f64) as u64);
+		}
 		
-		// Acquire a swapchain image to render to
-		let result = unsafe {
-			self.swapchain.extension.acquire_next_image(self.swapchain.handle,
-				std::u64::MAX,
-				in_flight_frame.image_available,
-				vk::Fence::null())
+		// Acquire a swapchain image to render to unless rendering to a render target
+		let acquired_image = match render_target {
+			Some(_) => None,
+			None => {
+				let image_available = in_flight_frame.image_available;
+
+				let acquired_image = acquire_with_recreation(self,
+					|render_system| unsafe {
+						let swapchain = render_system.swapchain.as_ref().unwrap();
+						swapchain.extension.acquire_next_image(swapchain.handle,
+							std::u64::MAX,
+							image_available,
+							vk::Fence::null())
+					},
+					|render_system| {
+						let (framebuffer_width, framebuffer_height) = render_system.framebuffer_size;
+						render_system.recreate_swapchain(framebuffer_width, framebuffer_height).map(|_| ())
+					})?;
+
+				Some(acquired_image)
+			}
 		};
 
-		match result {
-			Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => return true,
-			Err(e) => panic!("Could not aquire a swapchain image: {}", e),
-			_ => ()
-		}
+		// Recreating the swapchain borrowed the whole render system
+		let logical_device = &self.context.logical_device;
+		let in_flight_frame = &mut self.in_flight_frames[self.current_in_flight_frame_index];
 
-		let image_index = result.unwrap().0;
-		let swapchain_frame = &mut self.swapchain.frames[image_index as usize];
+		// When the render scale is below 1.0 the scene is rendered to the scaled render target then blitted to the swapchain image
+		let (render_pass, framebuffer, extent, image_index) = match render_target {
+			Some(handle) => {
+				let render_target = self.render_targets.borrow(handle);
+				(self.offscreen_render_pass, render_target.framebuffer, render_target.extent, None)
+			},
+			None => {
+				let acquired_image = acquired_image.unwrap();
+				swapchain_recreated = acquired_image.recreated;
+				acquire_suboptimal = acquired_image.suboptimal;
+
+				let image_index = acquired_image.index;
+				let swapchain = self.swapchain.as_mut().unwrap();
+				let swapchain_extent = swapchain.extent;
+				let swapchain_frame = &mut swapchain.frames[image_index as usize];
+
+				// Wait for swapchain frame to become available
+				if swapchain_frame.fence != vk::Fence::null() {
+					unsafe { logical_device.wait_for_fences(&[swapchain_frame.fence], true, std::u64::MAX) }?;
+				}
 
-		// Wait for swapchain frame to become available
-		if swapchain_frame.fence != vk::Fence::null() {
-			unsafe { logical_device.wait_for_fences(&[swapchain_frame.fence], true, std::u64::MAX) }.unwrap();
-		}
+				swapchain_frame.fence = in_flight_frame.fence;
 
-		swapchain_frame.fence = in_flight_frame.fence;
+				match &self.scaled_render_target {
+					Some(scaled_render_target) => (self.scaled_render_pass, scaled_render_target.framebuffer, scaled_render_target.extent, Some(image_index)),
+					None => (self.render_pass, swapchain_frame.framebuffer, swapchain_extent, Some(image_index))
+				}
+			}
+		};
 
-		// Map frame data buffer
-		let frame_data_buffer_ptr = unsafe { logical_device.map_memory(in_flight_frame.frame_data_buffer.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) }.unwrap();
-		
-		// Copy camera data into frame data buffer
-		let projection_matrix = &camera.projection_matrix.elements;
-		let projection_matrix_dst_ptr = frame_data_buffer_ptr as *mut [f32; 4];
-		unsafe { copy_nonoverlapping(projection_matrix.as_ptr(), projection_matrix_dst_ptr, 4) };
-
-		let mut inverse_view_matrix = camera.transform.global_matrix;
-		inverse_view_matrix.invert();
-		unsafe {
-			let inverse_view_matrix_dst_ptr = frame_data_buffer_ptr.add(16 * 4) as *mut [f32; 4];
-			copy_nonoverlapping(inverse_view_matrix.elements.as_ptr(), inverse_view_matrix_dst_ptr, 4);
-		}
+		let pipeline_index = if render_target.is_some() { RENDER_TARGET_PIPELINES } else { SWAPCHAIN_PIPELINES };
+		let mesh_pipelines = self.mesh_resources.pipelines[pipeline_index];
 
+		// The frame data buffer is persistently mapped
+		let frame_data_buffer_ptr = in_flight_frame.frame_data_buffer_ptr;
+		
 		// Iterate over lights to
 		// - Calculate the total ambient light color and intensity
-		// - Copy the point light data into the frame data buffer
+		// - Copy the point and directional light data into the frame data buffer
 		let mut total_ambient_light_color = vector3::ZERO;
 		let mut total_ambient_light_intensity = 0.0;
 
-		let mut point_light_count = 0;
-		let position_base_offest = 36 * 4;
-		let color_base_offest = 40 * 4;
-		let stride = 8 * 4;
+		// Checked up front so the point lights can't be copied past the end of the frame data
+		let point_light_count = light_components.point_light_count();
+		assert!(point_light_count <= MAX_POINT_LIGHTS, "Cannot render scene because {} point lights is more than the limit {}", point_light_count, MAX_POINT_LIGHTS);
+
+		let mut point_light_index = 0;
+
+		let mut directional_light_count = 0;
+		let mut directional_light_direction = vector3::ZERO;
+		let mut directional_light_intensified_color = vector3::ZERO;
+		let mut light_space_matrix = None;
 
 		for (entity, light) in light_components.iter() {
 			match light {
@@ -360,48 +1208,119 @@ impl RenderSystem {
 					let position = transform3d_components.borrow(*entity).global_matrix.extract_position();
 
 					unsafe {
-						let position_dst_ptr = frame_data_buffer_ptr.add(position_base_offest + stride * point_light_count) as *mut Vector3;
+						let position_offset = FRAME_DATA_POINT_LIGHTS_OFFSET + FRAME_DATA_POINT_LIGHT_STRIDE * point_light_index;
+						let position_dst_ptr = frame_data_buffer_ptr.add(position_offset) as *mut Vector3;
 						copy_nonoverlapping(&position as *const Vector3, position_dst_ptr, 1);
 
-						let color_dst_ptr = frame_data_buffer_ptr.add(color_base_offest + stride * point_light_count) as *mut Vector3;
+						let color_dst_ptr = frame_data_buffer_ptr.add(position_offset + 16) as *mut Vector3;
 						copy_nonoverlapping(&intensified_color as *const Vector3, color_dst_ptr, 1);
 					}
 
-					point_light_count += 1;
+					point_light_index += 1;
+				},
+				Light::DirectionalLight(directional_light) => {
+					// The light shines along the +Z axis of its transform
+					let global_matrix = &transform3d_components.borrow(*entity).global_matrix;
+					let elements = &global_matrix.elements;
+					directional_light_direction.set(elements[0][2], elements[1][2], elements[2][2]);
+					directional_light_direction.normalize();
+					directional_light_intensified_color = directional_light.color * directional_light.intensity;
+					light_space_matrix = Some(ShadowRenderSystem::light_space_matrix(global_matrix));
+
+					directional_light_count += 1;
 				}
 			}
 		}
 
 		// Copy point light count into frame data buffer
-		assert!(point_light_count <= MAX_POINT_LIGHTS, "Cannot render scene because {} point lights is more than the limit {}", point_light_count, MAX_POINT_LIGHTS);
 		unsafe {
-			let point_light_count_dst_ptr = frame_data_buffer_ptr.add(35 * 4) as *mut u32;
+			let point_light_count_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_POINT_LIGHT_COUNT_OFFSET) as *mut u32;
 			copy_nonoverlapping(&(point_light_count as u32) as *const u32, point_light_count_dst_ptr, 1);
 		}
 
 		// Copy total intensified ambient light color into frame data buffer
 		let total_ambient_light_intensified_color = total_ambient_light_color * total_ambient_light_intensity;
 		unsafe {
-			let ambient_light_dst_ptr = frame_data_buffer_ptr.add(32 * 4) as *mut Vector3;
+			let ambient_light_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_AMBIENT_LIGHT_OFFSET) as *mut Vector3;
 			copy_nonoverlapping(&total_ambient_light_intensified_color as *const Vector3, ambient_light_dst_ptr, 1);
 		}
 
-		// Flush and unmap frame data buffer
+		// Copy directional light data into frame data buffer, shadows are only cast when there is a directional light
+		assert!(directional_light_count <= 1, "Cannot render scene because {} directional lights is more than the limit 1", directional_light_count);
+		let shadows_enabled = self.shadow_resources.enabled && light_space_matrix.is_some();
+
+		unsafe {
+			let direction_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_DIRECTIONAL_LIGHT_DIRECTION_OFFSET) as *mut Vector3;
+			copy_nonoverlapping(&directional_light_direction as *const Vector3, direction_dst_ptr, 1);
+
+			let shadows_enabled_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_SHADOWS_ENABLED_OFFSET) as *mut u32;
+			copy_nonoverlapping(&(shadows_enabled as u32) as *const u32, shadows_enabled_dst_ptr, 1);
+
+			let color_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_DIRECTIONAL_LIGHT_COLOR_OFFSET) as *mut Vector3;
+			copy_nonoverlapping(&directional_light_intensified_color as *const Vector3, color_dst_ptr, 1);
+
+			if let Some(light_space_matrix) = &light_space_matrix {
+				let light_space_matrix_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_LIGHT_SPACE_MATRIX_OFFSET) as *mut [f32; 4];
+				copy_nonoverlapping(light_space_matrix.elements.as_ptr(), light_space_matrix_dst_ptr, 4);
+			}
+		}
+
+		// Copy fog data into frame data buffer
+		let (fog_color, fog_density) = self.fog.unwrap_or((vector3::ZERO, 0.0));
+
+		unsafe {
+			let fog_color_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_FOG_COLOR_OFFSET) as *mut Vector3;
+			copy_nonoverlapping(&fog_color as *const Vector3, fog_color_dst_ptr, 1);
+
+			let fog_density_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_FOG_DENSITY_OFFSET) as *mut f32;
+			copy_nonoverlapping(&fog_density as *const f32, fog_density_dst_ptr, 1);
+
+			let fog_enabled_dst_ptr = frame_data_buffer_ptr.add(FRAME_DATA_FOG_ENABLED_OFFSET) as *mut u32;
+			copy_nonoverlapping(&(self.fog.is_some() as u32) as *const u32, fog_enabled_dst_ptr, 1);
+		}
+
+		// Each viewport's frame data is a copy of the first's light data with its own camera data
+		let frame_data_stride = frame_data_stride(&self.context);
+		let mut camera_frustums = Vec::with_capacity(views.len());
+
+		for (view_index, (camera, _)) in views.iter().enumerate() {
+			let view_frame_data_ptr = unsafe { frame_data_buffer_ptr.add(frame_data_stride * view_index) };
+
+			if view_index > 0 {
+				unsafe { copy_nonoverlapping(frame_data_buffer_ptr as *const u8, view_frame_data_ptr as *mut u8, FRAME_DATA_MEMORY_SIZE) };
+			}
+
+			// Copy camera data into frame data buffer
+			let mut inverse_view_matrix = camera.transform.global_matrix;
+			inverse_view_matrix.invert();
+
+			let view_frame_data = unsafe { slice::from_raw_parts_mut(view_frame_data_ptr as *mut u8, FRAME_DATA_MEMORY_SIZE) };
+			copy_camera_frame_data(view_frame_data, camera, &inverse_view_matrix);
+
+			camera_frustums.push(Frustum::from_matrix(&(camera.projection_matrix * inverse_view_matrix)));
+		}
+
+		// Flush frame data buffer
 		let range = vk::MappedMemoryRange::builder()
 			.memory(in_flight_frame.frame_data_buffer.memory)
 			.offset(0)
 			.size(vk::WHOLE_SIZE);
 		
-		unsafe {		
-			logical_device.flush_mapped_memory_ranges(&[range.build()]).unwrap();
-			logical_device.unmap_memory(in_flight_frame.frame_data_buffer.memory);
-		}
+		unsafe { logical_device.flush_mapped_memory_ranges(&[range.build()]) }?;
+
+		// Meshes outside of every camera frustum are culled, when shadows are enabled meshes inside the shadow volume are kept so they still cast shadows
+		let shadow_frustum = match &light_space_matrix {
+			Some(light_space_matrix) if shadows_enabled => Some(Frustum::from_matrix(light_space_matrix)),
+			_ => None
+		};
 
 		// Iterate over meshes to
+		// - Cull the instances which aren't visible
 		// - Calculate the offsets and size of the data
 		// - Count the number of entities of each material to render
 		struct InstanceGroupInfo<'a> {
-			tuple: &'a (Vec<usize>, Mesh),
+			mesh: &'a Mesh,
+			instances: Vec<usize>,
 			index_array_relative_offset: usize,
 			attribute_array_relative_offset: usize
 		}
@@ -411,87 +1330,125 @@ impl RenderSystem {
 		let mut attribute_arrays_size = 0;
 		let mut material_counts = [0; MATERIALS_COUNT];
 
-		for tuple in mesh_components.iter() {
-			instance_group_infos.push(InstanceGroupInfo {
-				tuple,
-				index_array_relative_offset: index_arrays_size,
-				attribute_array_relative_offset: attribute_arrays_size
-			});
-			
-			let (instances, mesh) = tuple;
+		for (instances, mesh) in mesh_components.iter() {
 			let geometry = geometries.borrow(mesh.geometry_handle);
 
-			index_arrays_size += size_of_val(geometry.indices());
-			attribute_arrays_size += size_of_val(geometry.attributes());
-			material_counts[mesh.material as usize] += instances.len();
-		}
-
-		// Iterate over text to
-		struct TextInfo<'a> {
-			tuple: &'a (usize, Text),
-			index_array_relative_offset: usize,
-			attribute_array_relative_offset: usize
-		}
+			match mesh.material {
+				Material::VertexColor => assert!(geometry.has_vertex_colors(), "Cannot render mesh with the vertex color material because its geometry doesn't have vertex colors"),
+				_ => assert!(!geometry.has_vertex_colors(), "Cannot render mesh because its geometry has vertex colors which only the vertex color material supports")
+			}
 
-		let mut text_infos: Vec<TextInfo> = Vec::new();
+			let visible_instances: Vec<usize> = instances.iter().copied().filter(|instance| {
+				let mut bounding_box = *geometry.bounding_box();
+				bounding_box.apply_matrix(&transform3d_components.borrow(*instance).global_matrix);
 
-		for tuple in text_components.iter() {
-			let (_, text) = tuple;
+				camera_frustums.iter().any(|f| f.intersects_box(&bounding_box)) || shadow_frustum.as_ref().map_or(false, |f| f.intersects_box(&bounding_box))
+			}).collect();
 
-			if text.string.is_empty() {
+			if visible_instances.is_empty() {
 				continue;
 			}
 
-			let vertex_indices_size = size_of_val(text.indices());
-			let vertex_attributes_size = size_of_val(text.attributes());
+			material_counts[mesh.material.index()] += visible_instances.len();
 
-			text_infos.push(TextInfo {
-				tuple,
+			// 32 bit index arrays can follow an odd number of 16 bit indices so each array is aligned to its index size
+			index_arrays_size = align_offset(index_arrays_size, geometry.indices().index_size());
+
+			instance_group_infos.push(InstanceGroupInfo {
+				mesh,
+				instances: visible_instances,
 				index_array_relative_offset: index_arrays_size,
 				attribute_array_relative_offset: attribute_arrays_size
 			});
 
-			index_arrays_size += vertex_indices_size;
-			attribute_arrays_size += vertex_attributes_size;
+			index_arrays_size += geometry.indices().as_bytes().len();
+			attribute_arrays_size += size_of_val(geometry.attributes());
+		}
+
+		// Merge the text into one batch, the tuples are kept in the same order to copy each text's instance data
+		let mut text_tuples: Vec<&(usize, Text)> = Vec::new();
+		self.text_resources.batch.clear();
+
+		if let Some((_, text_components, _)) = text {
+			for tuple in text_components.iter() {
+				let (_, text) = tuple;
+
+				if text.string.is_empty() {
+					continue;
+				}
+
+				text_tuples.push(tuple);
+				self.text_resources.batch.add(text);
+			}
 		}
 
 		// Calculate offsets
 		let alignment = self.context.physical_device.min_storage_buffer_offset_alignment as usize;
 
+		let [line_count, basic_count, normal_count, lambert_count, phong_count, vertex_color_count] = material_counts;
+
 		let line_instance_data_array_offset = 0;
-		let line_instance_data_array_size = 4 * 16 * material_counts[Material::Line as usize];
+		let line_instance_data_array_size = MESH_INSTANCE_DATA_SIZE * line_count;
 
-		let unaligned_basic_instance_data_array_offset = line_instance_data_array_offset + line_instance_data_array_size;
-		let basic_instance_data_array_padding = (alignment - unaligned_basic_instance_data_array_offset % alignment) % alignment;
-		let basic_instance_data_array_offset = unaligned_basic_instance_data_array_offset + basic_instance_data_array_padding;
-		let basic_instance_data_array_size = 4 * 16 * material_counts[Material::Basic as usize];
+		let basic_instance_data_array_offset = align_offset(line_instance_data_array_offset + line_instance_data_array_size, alignment);
+		let basic_instance_data_array_size = BASIC_INSTANCE_DATA_SIZE * basic_count;
 
-		let unaligned_normal_instance_data_array_offset = basic_instance_data_array_offset + basic_instance_data_array_size;
-		let normal_instance_data_array_padding = (alignment - unaligned_normal_instance_data_array_offset % alignment) % alignment;
-		let normal_instance_data_array_offset = unaligned_normal_instance_data_array_offset + normal_instance_data_array_padding;
-		let normal_instance_data_array_size = 4 * 16 * material_counts[Material::Normal as usize];
+		let normal_instance_data_array_offset = align_offset(basic_instance_data_array_offset + basic_instance_data_array_size, alignment);
+		let normal_instance_data_array_size = MESH_INSTANCE_DATA_SIZE * normal_count;
 		
-		let unaligned_lambert_instance_data_array_offset = normal_instance_data_array_offset + normal_instance_data_array_size;
-		let lambert_instance_data_array_padding = (alignment - unaligned_lambert_instance_data_array_offset % alignment) % alignment;
-		let lambert_instance_data_array_offset = unaligned_lambert_instance_data_array_offset + lambert_instance_data_array_padding;
-		let lambert_instance_data_array_size = 4 * 16 * material_counts[Material::Lambert as usize];
+		let lambert_instance_data_array_offset = align_offset(normal_instance_data_array_offset + normal_instance_data_array_size, alignment);
+		let lambert_instance_data_array_size = LAMBERT_INSTANCE_DATA_SIZE * lambert_count;
+
+		let phong_instance_data_array_offset = align_offset(lambert_instance_data_array_offset + lambert_instance_data_array_size, alignment);
+		let phong_instance_data_array_size = PHONG_INSTANCE_DATA_SIZE * phong_count;
+
+		let vertex_color_instance_data_array_offset = align_offset(phong_instance_data_array_offset + phong_instance_data_array_size, alignment);
+		let vertex_color_instance_data_array_size = MESH_INSTANCE_DATA_SIZE * vertex_color_count;
+
+		let text_instance_data_array_offset = align_offset(vertex_color_instance_data_array_offset + vertex_color_instance_data_array_size, alignment);
+		let text_instance_data_array_size = text_render_system::INSTANCE_DATA_SIZE * text_tuples.len();
+
+		let fresh_index_arrays_offset = text_instance_data_array_offset + text_instance_data_array_size;
+
+		// The debug line vertices follow the attribute arrays and then the text batch's arrays
+		let debug_vertices = self.debug_resources.vertices();
+		let text_batch = &self.text_resources.batch;
+		let trailing_sizes = TrailingArraySizes {
+			index_arrays: index_arrays_size,
+			attribute_arrays: attribute_arrays_size,
+			debug_vertices: size_of_val(debug_vertices),
+			text_indices: size_of_val(text_batch.indices.as_slice()),
+			text_attributes: size_of_val(text_batch.attributes.as_slice()),
+			text_instance_indices: size_of_val(text_batch.instance_indices.as_slice())
+		};
 
-		let unaligned_text_instance_data_array_offset = lambert_instance_data_array_offset + lambert_instance_data_array_size;
-		let text_instance_data_array_padding = (alignment - unaligned_text_instance_data_array_offset % alignment) % alignment;
-		let text_instance_data_array_offset = unaligned_text_instance_data_array_offset + text_instance_data_array_padding;
-		let text_instance_data_array_size = 4 * 16 * text_infos.len();
+		// The descriptor sets are only updated when an instance data array grows, until then the arrays stay where they were last placed
+		let descriptor_sets_outdated =
+			line_instance_data_array_size > in_flight_frame.line_instance_data_resources.array_size ||
+			basic_instance_data_array_size > in_flight_frame.basic_instance_data_resources.array_size ||
+			normal_instance_data_array_size > in_flight_frame.normal_instance_data_resources.array_size ||
+			lambert_instance_data_array_size > in_flight_frame.lambert_instance_data_resources.array_size ||
+			phong_instance_data_array_size > in_flight_frame.phong_instance_data_resources.array_size ||
+			vertex_color_instance_data_array_size > in_flight_frame.vertex_color_instance_data_resources.array_size ||
+			text_instance_data_array_size > in_flight_frame.text_instance_data_resources.array_size;
 
-		let index_arrays_offset = text_instance_data_array_offset + text_instance_data_array_size;
-		
-		let unaligned_attribute_arrays_offset = index_arrays_offset + index_arrays_size;
-		let attribute_arrays_padding = (4 - unaligned_attribute_arrays_offset % 4) % 4;
-		let attribute_arrays_offset = unaligned_attribute_arrays_offset + attribute_arrays_padding;
+		let index_arrays_offset = if descriptor_sets_outdated { fresh_index_arrays_offset } else { in_flight_frame.index_arrays_offset };
+		let mut trailing_layout = TrailingArrayLayout::new(index_arrays_offset, &trailing_sizes);
 
 		// Allocate larger mesh data buffer and update descriptor sets if necessary
-		let buffer_size = (attribute_arrays_offset + attribute_arrays_size) as u64;
+		if trailing_layout.end as u64 > in_flight_frame.instance_data_buffer.capacity {
+			// Everything is packed again from the fresh layout which is never larger than the stored one
+			trailing_layout = TrailingArrayLayout::new(fresh_index_arrays_offset, &trailing_sizes);
+
+			// The old mapping is invalid once the memory is freed so remap after reallocating
+			if !in_flight_frame.instance_data_buffer_ptr.is_null() {
+				unsafe { logical_device.unmap_memory(in_flight_frame.instance_data_buffer.memory) };
+				in_flight_frame.instance_data_buffer_ptr = ptr::null_mut();
+			}
 
-		if buffer_size > in_flight_frame.instance_data_buffer.capacity {
-			in_flight_frame.instance_data_buffer.reallocate(&self.context, buffer_size);
+			in_flight_frame.instance_data_buffer.reserve(&self.context, trailing_layout.end as u64)?;
+			self.context.set_object_name(in_flight_frame.instance_data_buffer.handle, &format!("instance_data_buffer_{}", self.current_in_flight_frame_index));
+			in_flight_frame.instance_data_buffer_ptr = unsafe { logical_device.map_memory(in_flight_frame.instance_data_buffer.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) }?;
 
 			in_flight_frame.update_descriptor_sets(
 				logical_device,
@@ -503,18 +1460,17 @@ impl RenderSystem {
 				normal_instance_data_array_size,
 				lambert_instance_data_array_offset,
 				lambert_instance_data_array_size,
+				phong_instance_data_array_offset,
+				phong_instance_data_array_size,
+				vertex_color_instance_data_array_offset,
+				vertex_color_instance_data_array_size,
 				text_instance_data_array_offset,
 				text_instance_data_array_size,
-				index_arrays_offset);
+				fresh_index_arrays_offset);
 			
 			println!("In flight frame {} instance data buffer reallocated", self.current_in_flight_frame_index);
 		}
-		else if
-			basic_instance_data_array_size > in_flight_frame.basic_instance_data_resources.array_size ||
-			normal_instance_data_array_size > in_flight_frame.normal_instance_data_resources.array_size ||
-			lambert_instance_data_array_size > in_flight_frame.lambert_instance_data_resources.array_size ||
-			text_instance_data_array_size > in_flight_frame.text_instance_data_resources.array_size
-		{
+		else if descriptor_sets_outdated {
 			in_flight_frame.update_descriptor_sets(
 				logical_device,
 				line_instance_data_array_offset,
@@ -525,25 +1481,33 @@ impl RenderSystem {
 				normal_instance_data_array_size,
 				lambert_instance_data_array_offset,
 				lambert_instance_data_array_size,
+				phong_instance_data_array_offset,
+				phong_instance_data_array_size,
+				vertex_color_instance_data_array_offset,
+				vertex_color_instance_data_array_size,
 				text_instance_data_array_offset,
 				text_instance_data_array_size,
-				index_arrays_offset);
+				fresh_index_arrays_offset);
 		}
 
+		debug_assert_eq!(trailing_layout.index_arrays, in_flight_frame.index_arrays_offset);
+
 		let in_flight_frame = &self.in_flight_frames[self.current_in_flight_frame_index];
 		let line_instance_data_resources = &in_flight_frame.line_instance_data_resources;
 		let basic_instance_data_resources = &in_flight_frame.basic_instance_data_resources;
 		let normal_instance_data_resources = &in_flight_frame.normal_instance_data_resources;
 		let lambert_instance_data_resources = &in_flight_frame.lambert_instance_data_resources;
+		let phong_instance_data_resources = &in_flight_frame.phong_instance_data_resources;
+		let vertex_color_instance_data_resources = &in_flight_frame.vertex_color_instance_data_resources;
 		let text_instance_data_resources = &in_flight_frame.text_instance_data_resources;
 
-		let instance_data_buffer_ptr = unsafe { logical_device.map_memory(in_flight_frame.instance_data_buffer.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) }.unwrap();
+		let instance_data_buffer_ptr = in_flight_frame.instance_data_buffer_ptr;
 
 		// Begin mesh command buffers
 		let command_buffer_inheritance_info = vk::CommandBufferInheritanceInfo::builder()
-			.render_pass(self.render_pass)
+			.render_pass(render_pass)
 			.subpass(0)
-			.framebuffer(swapchain_frame.framebuffer);
+			.framebuffer(framebuffer);
 
 		let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
 			.flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE | vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
@@ -551,15 +1515,8 @@ impl RenderSystem {
 
 		unsafe {
 			// Line
-			logical_device.begin_command_buffer(line_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info).unwrap();
-			logical_device.cmd_bind_pipeline(line_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.mesh_resources.line_pipeline);
-			logical_device.cmd_bind_descriptor_sets(
-				line_instance_data_resources.secondary_command_buffer,
-				vk::PipelineBindPoint::GRAPHICS,
-				self.mesh_resources.pipeline_layout,
-				0,
-				&[in_flight_frame.frame_data_descriptor_set],
-				&[]);
+			logical_device.begin_command_buffer(line_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info)?;
+			logical_device.cmd_bind_pipeline(line_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, mesh_pipelines.line);
 			logical_device.cmd_bind_descriptor_sets(
 				line_instance_data_resources.secondary_command_buffer,
 				vk::PipelineBindPoint::GRAPHICS,
@@ -569,15 +1526,8 @@ impl RenderSystem {
 				&[]);
 			
 			// Basic
-			logical_device.begin_command_buffer(basic_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info).unwrap();
-			logical_device.cmd_bind_pipeline(basic_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.mesh_resources.basic_pipeline);
-			logical_device.cmd_bind_descriptor_sets(
-				basic_instance_data_resources.secondary_command_buffer,
-				vk::PipelineBindPoint::GRAPHICS,
-				self.mesh_resources.pipeline_layout,
-				0,
-				&[in_flight_frame.frame_data_descriptor_set],
-				&[]);
+			logical_device.begin_command_buffer(basic_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info)?;
+			logical_device.cmd_bind_pipeline(basic_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, mesh_pipelines.basic);
 			logical_device.cmd_bind_descriptor_sets(
 				basic_instance_data_resources.secondary_command_buffer,
 				vk::PipelineBindPoint::GRAPHICS,
@@ -587,15 +1537,8 @@ impl RenderSystem {
 				&[]);
 			
 			// Normal
-			logical_device.begin_command_buffer(normal_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info).unwrap();
-			logical_device.cmd_bind_pipeline(normal_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.mesh_resources.normal_pipeline);
-			logical_device.cmd_bind_descriptor_sets(
-				normal_instance_data_resources.secondary_command_buffer,
-				vk::PipelineBindPoint::GRAPHICS,
-				self.mesh_resources.pipeline_layout,
-				0,
-				&[in_flight_frame.frame_data_descriptor_set],
-				&[]);
+			logical_device.begin_command_buffer(normal_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info)?;
+			logical_device.cmd_bind_pipeline(normal_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, mesh_pipelines.normal);
 			logical_device.cmd_bind_descriptor_sets(
 				normal_instance_data_resources.secondary_command_buffer,
 				vk::PipelineBindPoint::GRAPHICS,
@@ -605,35 +1548,112 @@ impl RenderSystem {
 				&[]);
 			
 			// Lambert
-			logical_device.begin_command_buffer(lambert_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info).unwrap();
-			logical_device.cmd_bind_pipeline(lambert_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.mesh_resources.lambert_pipeline);
+			logical_device.begin_command_buffer(lambert_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info)?;
+			logical_device.cmd_bind_pipeline(lambert_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, mesh_pipelines.lambert);
 			logical_device.cmd_bind_descriptor_sets(
 				lambert_instance_data_resources.secondary_command_buffer,
 				vk::PipelineBindPoint::GRAPHICS,
 				self.mesh_resources.pipeline_layout,
-				0,
-				&[in_flight_frame.frame_data_descriptor_set],
+				1,
+				&[lambert_instance_data_resources.descriptor_set],
 				&[]);
 			logical_device.cmd_bind_descriptor_sets(
 				lambert_instance_data_resources.secondary_command_buffer,
 				vk::PipelineBindPoint::GRAPHICS,
 				self.mesh_resources.pipeline_layout,
+				2,
+				&[self.shadow_resources.descriptor_set],
+				&[]);
+			
+			// Phong
+			logical_device.begin_command_buffer(phong_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info)?;
+			logical_device.cmd_bind_pipeline(phong_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, mesh_pipelines.phong);
+			logical_device.cmd_bind_descriptor_sets(
+				phong_instance_data_resources.secondary_command_buffer,
+				vk::PipelineBindPoint::GRAPHICS,
+				self.mesh_resources.pipeline_layout,
 				1,
-				&[lambert_instance_data_resources.descriptor_set],
+				&[phong_instance_data_resources.descriptor_set],
+				&[]);
+			logical_device.cmd_bind_descriptor_sets(
+				phong_instance_data_resources.secondary_command_buffer,
+				vk::PipelineBindPoint::GRAPHICS,
+				self.mesh_resources.pipeline_layout,
+				2,
+				&[self.shadow_resources.descriptor_set],
+				&[]);
+			
+			// Vertex color
+			logical_device.begin_command_buffer(vertex_color_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info)?;
+			logical_device.cmd_bind_pipeline(vertex_color_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, mesh_pipelines.vertex_color);
+			logical_device.cmd_bind_descriptor_sets(
+				vertex_color_instance_data_resources.secondary_command_buffer,
+				vk::PipelineBindPoint::GRAPHICS,
+				self.mesh_resources.pipeline_layout,
+				1,
+				&[vertex_color_instance_data_resources.descriptor_set],
 				&[]);
 		}
+
+		// Begin depth pre-pass command buffer, it's in the same render pass as the mesh command buffers and executed first
+		let depth_prepass_enabled = self.mesh_resources.depth_prepass_enabled;
+
+		unsafe {
+			logical_device.begin_command_buffer(in_flight_frame.depth_prepass_secondary_command_buffer, &command_buffer_begin_info)?;
+
+			if depth_prepass_enabled {
+				logical_device.cmd_bind_pipeline(in_flight_frame.depth_prepass_secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, mesh_pipelines.depth_prepass);
+			}
+		}
+
+		// The mesh pipelines' viewport and scissor are dynamic, each mesh is drawn once per viewport
+		let viewport_states: Vec<(vk::Viewport, vk::Rect2D)> = views.iter().map(|(_, viewport)| {
+			let scissor = viewport.rect(extent);
+
+			let viewport = vk::Viewport::builder()
+				.x(scissor.offset.x as f32)
+				.y(scissor.offset.y as f32)
+				.width(scissor.extent.width as f32)
+				.height(scissor.extent.height as f32)
+				.min_depth(0.0)
+				.max_depth(1.0)
+				.build();
+
+			(viewport, scissor)
+		}).collect();
+
+		// Begin shadow command buffer
+		let shadow_command_buffer_inheritance_info = vk::CommandBufferInheritanceInfo::builder()
+			.render_pass(self.shadow_resources.render_pass)
+			.subpass(0)
+			.framebuffer(self.shadow_resources.framebuffer());
+
+		let shadow_command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
+			.flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE | vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
+			.inheritance_info(&shadow_command_buffer_inheritance_info);
+
+		unsafe {
+			logical_device.begin_command_buffer(in_flight_frame.shadow_secondary_command_buffer, &shadow_command_buffer_begin_info)?;
+			logical_device.cmd_bind_pipeline(in_flight_frame.shadow_secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.shadow_resources.pipeline);
+			logical_device.cmd_bind_descriptor_sets(
+				in_flight_frame.shadow_secondary_command_buffer,
+				vk::PipelineBindPoint::GRAPHICS,
+				self.shadow_resources.pipeline_layout,
+				0,
+				&[in_flight_frame.frame_data_descriptor_set],
+				&[0]);
+		}
 		
-		let index_arrays_offset = in_flight_frame.index_arrays_offset;
-		let unaligned_attribute_arrays_offset = index_arrays_offset + index_arrays_size;
-		let attribute_arrays_padding = (4 - unaligned_attribute_arrays_offset % 4) % 4;
-		let attribute_arrays_offset = unaligned_attribute_arrays_offset + attribute_arrays_padding;
+		let index_arrays_offset = trailing_layout.index_arrays;
+		let attribute_arrays_offset = trailing_layout.attribute_arrays;
 
 		let mut instance_group_indices = [0; MATERIALS_COUNT];
 
 		for instance_group in &instance_group_infos {
 			let index_array_offset = index_arrays_offset + instance_group.index_array_relative_offset;
 			let attribute_array_offset = attribute_arrays_offset + instance_group.attribute_array_relative_offset;
-			let (instances, mesh) = instance_group.tuple;
+			let instances = &instance_group.instances;
+			let mesh = instance_group.mesh;
 			let geometry = geometries.borrow(mesh.geometry_handle);
 
 			// Copy geometry data
@@ -641,39 +1661,39 @@ impl RenderSystem {
 			let attributes = geometry.attributes();
 
 			unsafe {
-				let index_array_dst_ptr = instance_data_buffer_ptr.add(index_array_offset) as *mut u16;
-				copy_nonoverlapping(indices.as_ptr(), index_array_dst_ptr, indices.len());
+				let index_array_dst_ptr = instance_data_buffer_ptr.add(index_array_offset) as *mut u8;
+				copy_nonoverlapping(indices.as_bytes().as_ptr(), index_array_dst_ptr, indices.as_bytes().len());
 
 				let attribute_array_dst_ptr = instance_data_buffer_ptr.add(attribute_array_offset) as *mut f32;
 				copy_nonoverlapping(attributes.as_ptr(), attribute_array_dst_ptr, attributes.len());
 			}
 
 			// Copy instance data
-			let instance_group_index = &mut instance_group_indices[mesh.material as usize];
+			let instance_group_index = &mut instance_group_indices[mesh.material.index()];
 			let secondary_command_buffer;
 
 			match mesh.material {
 				Material::Line => {
 					for (instance_index, instance) in instances.iter().enumerate() {
-						let transform_ptr = transform3d_components.borrow(*instance).global_matrix.elements.as_ptr();
-						let offset = line_instance_data_resources.array_offset + 4 * 16 * (*instance_group_index + instance_index);
+						let global_matrix = &transform3d_components.borrow(*instance).global_matrix;
+						let offset = instance_data_offset(line_instance_data_resources.array_offset, MESH_INSTANCE_DATA_SIZE, *instance_group_index, instance_index);
 
 						unsafe {
-							let instance_data_dst_ptr = instance_data_buffer_ptr.add(offset) as *mut [f32; 4];
-							copy_nonoverlapping(transform_ptr, instance_data_dst_ptr, 4);
+							let instance_data_dst = slice::from_raw_parts_mut(instance_data_buffer_ptr.add(offset) as *mut u8, MESH_INSTANCE_DATA_SIZE);
+							copy_mesh_instance_data(instance_data_dst, global_matrix, &mesh.tint, mesh.tint_strength);
 						}
 					}
 
 					secondary_command_buffer = line_instance_data_resources.secondary_command_buffer;
 				},
-				Material::Basic => {
+				Material::Basic { color } => {
 					for (instance_index, instance) in instances.iter().enumerate() {
-						let transform_ptr = transform3d_components.borrow(*instance).global_matrix.elements.as_ptr();
-						let offset = basic_instance_data_resources.array_offset + 4 * 16 * (*instance_group_index + instance_index);
+						let global_matrix = &transform3d_components.borrow(*instance).global_matrix;
+						let offset = instance_data_offset(basic_instance_data_resources.array_offset, BASIC_INSTANCE_DATA_SIZE, *instance_group_index, instance_index);
 
 						unsafe {
-							let instance_data_dst_ptr = instance_data_buffer_ptr.add(offset) as *mut [f32; 4];
-							copy_nonoverlapping(transform_ptr, instance_data_dst_ptr, 4);
+							let instance_data_dst = slice::from_raw_parts_mut(instance_data_buffer_ptr.add(offset) as *mut u8, BASIC_INSTANCE_DATA_SIZE);
+							copy_basic_instance_data(instance_data_dst, global_matrix, &color, &mesh.tint, mesh.tint_strength);
 						}
 					}
 
@@ -681,12 +1701,12 @@ impl RenderSystem {
 				},
 				Material::Normal => {
 					for (instance_index, instance) in instances.iter().enumerate() {
-						let transform_ptr = transform3d_components.borrow(*instance).global_matrix.elements.as_ptr();
-						let instance_data_offset = normal_instance_data_resources.array_offset + 4 * 16 * (*instance_group_index + instance_index);
+						let global_matrix = &transform3d_components.borrow(*instance).global_matrix;
+						let offset = instance_data_offset(normal_instance_data_resources.array_offset, MESH_INSTANCE_DATA_SIZE, *instance_group_index, instance_index);
 
 						unsafe {
-							let instance_data_dst_ptr = instance_data_buffer_ptr.add(instance_data_offset) as *mut [f32; 4];
-							copy_nonoverlapping(transform_ptr, instance_data_dst_ptr, 4);
+							let instance_data_dst = slice::from_raw_parts_mut(instance_data_buffer_ptr.add(offset) as *mut u8, MESH_INSTANCE_DATA_SIZE);
+							copy_mesh_instance_data(instance_data_dst, global_matrix, &mesh.tint, mesh.tint_strength);
 						}
 					}
 
@@ -694,24 +1714,99 @@ impl RenderSystem {
 				},
 				Material::Lambert => {
 					for (instance_index, instance) in instances.iter().enumerate() {
-						let transform_ptr = transform3d_components.borrow(*instance).global_matrix.elements.as_ptr();
-						let instance_data_offset = lambert_instance_data_resources.array_offset + 4 * 16 * (*instance_group_index + instance_index);
+						let global_matrix = &transform3d_components.borrow(*instance).global_matrix;
+						let offset = instance_data_offset(lambert_instance_data_resources.array_offset, LAMBERT_INSTANCE_DATA_SIZE, *instance_group_index, instance_index);
 
 						unsafe {
-							let instance_data_dst_ptr = instance_data_buffer_ptr.add(instance_data_offset) as *mut [f32; 4];
-							copy_nonoverlapping(transform_ptr, instance_data_dst_ptr, 4);
+							let instance_data_dst = slice::from_raw_parts_mut(instance_data_buffer_ptr.add(offset) as *mut u8, LAMBERT_INSTANCE_DATA_SIZE);
+							copy_lambert_instance_data(instance_data_dst, global_matrix, &mesh.tint, mesh.tint_strength);
 						}
 					}
 
 					secondary_command_buffer = lambert_instance_data_resources.secondary_command_buffer;
+				},
+				Material::Phong { shininess, specular } => {
+					for (instance_index, instance) in instances.iter().enumerate() {
+						let global_matrix = &transform3d_components.borrow(*instance).global_matrix;
+						let offset = instance_data_offset(phong_instance_data_resources.array_offset, PHONG_INSTANCE_DATA_SIZE, *instance_group_index, instance_index);
+
+						unsafe {
+							let instance_data_dst = slice::from_raw_parts_mut(instance_data_buffer_ptr.add(offset) as *mut u8, PHONG_INSTANCE_DATA_SIZE);
+							copy_phong_instance_data(instance_data_dst, global_matrix, &specular, shininess, &mesh.tint, mesh.tint_strength);
+						}
+					}
+
+					secondary_command_buffer = phong_instance_data_resources.secondary_command_buffer;
+				},
+				Material::VertexColor => {
+					for (instance_index, instance) in instances.iter().enumerate() {
+						let global_matrix = &transform3d_components.borrow(*instance).global_matrix;
+						let offset = instance_data_offset(vertex_color_instance_data_resources.array_offset, MESH_INSTANCE_DATA_SIZE, *instance_group_index, instance_index);
+
+						unsafe {
+							let instance_data_dst = slice::from_raw_parts_mut(instance_data_buffer_ptr.add(offset) as *mut u8, MESH_INSTANCE_DATA_SIZE);
+							copy_mesh_instance_data(instance_data_dst, global_matrix, &mesh.tint, mesh.tint_strength);
+						}
+					}
+
+					secondary_command_buffer = vertex_color_instance_data_resources.secondary_command_buffer;
 				}
 			}
 
-			// Record draw commands
+			// Record draw commands for each viewport with that viewport's frame data
 			unsafe {
-				logical_device.cmd_bind_index_buffer(secondary_command_buffer, in_flight_frame.instance_data_buffer.handle, index_array_offset as u64, vk::IndexType::UINT16);
+				logical_device.cmd_bind_index_buffer(secondary_command_buffer, in_flight_frame.instance_data_buffer.handle, index_array_offset as u64, geometry.indices().index_type());
 				logical_device.cmd_bind_vertex_buffers(secondary_command_buffer, 0, &[in_flight_frame.instance_data_buffer.handle], &[attribute_array_offset as u64]);
-				logical_device.cmd_draw_indexed(secondary_command_buffer, geometry.indices().len() as u32, instances.len() as u32, 0, 0, *instance_group_index as u32);
+
+				for (view_index, (viewport, scissor)) in viewport_states.iter().enumerate() {
+					let frame_data_offset = (frame_data_stride * view_index) as u32;
+					logical_device.cmd_set_viewport(secondary_command_buffer, 0, &[*viewport]);
+					logical_device.cmd_set_scissor(secondary_command_buffer, 0, &[*scissor]);
+					logical_device.cmd_bind_descriptor_sets(secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.mesh_resources.pipeline_layout, 0, &[in_flight_frame.frame_data_descriptor_set], &[frame_data_offset]);
+					logical_device.cmd_draw_indexed(secondary_command_buffer, geometry.indices().len() as u32, instances.len() as u32, 0, 0, *instance_group_index as u32);
+				}
+			}
+
+			// Record shadow and depth pre-pass draw commands, lines and vertex colored meshes don't cast shadows and aren't in the pre-pass
+			let depth_only_instance_data = match mesh.material {
+				Material::Line | Material::VertexColor => None,
+				Material::Basic { .. } => Some((basic_instance_data_resources.descriptor_set, BASIC_INSTANCE_DATA_SIZE)),
+				Material::Normal => Some((normal_instance_data_resources.descriptor_set, MESH_INSTANCE_DATA_SIZE)),
+				Material::Lambert => Some((lambert_instance_data_resources.descriptor_set, LAMBERT_INSTANCE_DATA_SIZE)),
+				Material::Phong { .. } => Some((phong_instance_data_resources.descriptor_set, PHONG_INSTANCE_DATA_SIZE))
+			};
+
+			match depth_only_instance_data {
+				Some((descriptor_set, instance_data_size)) if shadows_enabled => unsafe {
+					let shadow_command_buffer = in_flight_frame.shadow_secondary_command_buffer;
+					let instance_stride = (instance_data_size / 16) as u32;
+					logical_device.cmd_bind_descriptor_sets(shadow_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.shadow_resources.pipeline_layout, 1, &[descriptor_set], &[]);
+					logical_device.cmd_push_constants(shadow_command_buffer, self.shadow_resources.pipeline_layout, vk::ShaderStageFlags::VERTEX, 0, &instance_stride.to_ne_bytes());
+					logical_device.cmd_bind_index_buffer(shadow_command_buffer, in_flight_frame.instance_data_buffer.handle, index_array_offset as u64, geometry.indices().index_type());
+					logical_device.cmd_bind_vertex_buffers(shadow_command_buffer, 0, &[in_flight_frame.instance_data_buffer.handle], &[attribute_array_offset as u64]);
+					logical_device.cmd_draw_indexed(shadow_command_buffer, geometry.indices().len() as u32, instances.len() as u32, 0, 0, *instance_group_index as u32);
+				},
+				_ => ()
+			}
+
+			match depth_only_instance_data {
+				Some((descriptor_set, instance_data_size)) if depth_prepass_enabled => unsafe {
+					let depth_prepass_command_buffer = in_flight_frame.depth_prepass_secondary_command_buffer;
+					let instance_stride = (instance_data_size / 16) as u32;
+					logical_device.cmd_bind_descriptor_sets(depth_prepass_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.mesh_resources.depth_prepass_pipeline_layout, 1, &[descriptor_set], &[]);
+					logical_device.cmd_push_constants(depth_prepass_command_buffer, self.mesh_resources.depth_prepass_pipeline_layout, vk::ShaderStageFlags::VERTEX, 0, &instance_stride.to_ne_bytes());
+					logical_device.cmd_bind_index_buffer(depth_prepass_command_buffer, in_flight_frame.instance_data_buffer.handle, index_array_offset as u64, geometry.indices().index_type());
+					logical_device.cmd_bind_vertex_buffers(depth_prepass_command_buffer, 0, &[in_flight_frame.instance_data_buffer.handle], &[attribute_array_offset as u64]);
+
+					for (view_index, (viewport, scissor)) in viewport_states.iter().enumerate() {
+						let frame_data_offset = (frame_data_stride * view_index) as u32;
+						logical_device.cmd_set_viewport(depth_prepass_command_buffer, 0, &[*viewport]);
+						logical_device.cmd_set_scissor(depth_prepass_command_buffer, 0, &[*scissor]);
+						logical_device.cmd_bind_descriptor_sets(depth_prepass_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.mesh_resources.depth_prepass_pipeline_layout, 0, &[in_flight_frame.frame_data_descriptor_set], &[frame_data_offset]);
+						logical_device.cmd_draw_indexed(depth_prepass_command_buffer, geometry.indices().len() as u32, instances.len() as u32, 0, 0, *instance_group_index as u32);
+					}
+				},
+				_ => ()
 			}
 
 			*instance_group_index += instances.len();
@@ -719,34 +1814,67 @@ impl RenderSystem {
 
 		// End command buffers and add to submission list if there are meshes to draw
 		unsafe {
-			logical_device.end_command_buffer(line_instance_data_resources.secondary_command_buffer).unwrap();
-			logical_device.end_command_buffer(basic_instance_data_resources.secondary_command_buffer).unwrap();
-			logical_device.end_command_buffer(normal_instance_data_resources.secondary_command_buffer).unwrap();
-			logical_device.end_command_buffer(lambert_instance_data_resources.secondary_command_buffer).unwrap();
+			logical_device.end_command_buffer(line_instance_data_resources.secondary_command_buffer)?;
+			logical_device.end_command_buffer(basic_instance_data_resources.secondary_command_buffer)?;
+			logical_device.end_command_buffer(normal_instance_data_resources.secondary_command_buffer)?;
+			logical_device.end_command_buffer(lambert_instance_data_resources.secondary_command_buffer)?;
+			logical_device.end_command_buffer(phong_instance_data_resources.secondary_command_buffer)?;
+			logical_device.end_command_buffer(vertex_color_instance_data_resources.secondary_command_buffer)?;
+			logical_device.end_command_buffer(in_flight_frame.shadow_secondary_command_buffer)?;
+			logical_device.end_command_buffer(in_flight_frame.depth_prepass_secondary_command_buffer)?;
 		}
 
 		let mut secondary_command_buffers = vec![];
 
-		if material_counts[Material::Line as usize] != 0 || self.mesh_resources.static_material_counts[Material::Line as usize] != 0 {
+		if depth_prepass_enabled {
+			secondary_command_buffers.push(in_flight_frame.depth_prepass_secondary_command_buffer);
+		}
+
+		let [static_line_count, static_basic_count, static_normal_count, static_lambert_count, static_phong_count, static_vertex_color_count] = self.mesh_resources.static_material_counts;
+
+		if line_count != 0 || static_line_count != 0 {
 			secondary_command_buffers.push(line_instance_data_resources.secondary_command_buffer);
 		}
 
-		if material_counts[Material::Basic as usize] != 0 || self.mesh_resources.static_material_counts[Material::Basic as usize] != 0 {
+		if basic_count != 0 || static_basic_count != 0 {
 			secondary_command_buffers.push(basic_instance_data_resources.secondary_command_buffer);
 		}
 
-		if material_counts[Material::Normal as usize] != 0 || self.mesh_resources.static_material_counts[Material::Normal as usize] != 0 {
+		if normal_count != 0 || static_normal_count != 0 {
 			secondary_command_buffers.push(normal_instance_data_resources.secondary_command_buffer);
 		}
 
-		if material_counts[Material::Lambert as usize] != 0 || self.mesh_resources.static_material_counts[Material::Lambert as usize] != 0 {
+		if lambert_count != 0 || static_lambert_count != 0 {
 			secondary_command_buffers.push(lambert_instance_data_resources.secondary_command_buffer);
 		}
 
+		if phong_count != 0 || static_phong_count != 0 {
+			secondary_command_buffers.push(phong_instance_data_resources.secondary_command_buffer);
+		}
+
+		if vertex_color_count != 0 || static_vertex_color_count != 0 {
+			secondary_command_buffers.push(vertex_color_instance_data_resources.secondary_command_buffer);
+		}
+
 		// Begin text command buffer
 		unsafe {
-			logical_device.begin_command_buffer(text_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info).unwrap();
+			logical_device.begin_command_buffer(text_instance_data_resources.secondary_command_buffer, &command_buffer_begin_info)?;
 			logical_device.cmd_bind_pipeline(text_instance_data_resources.secondary_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.text_resources.pipeline);
+
+			let viewport = vk::Viewport::builder()
+				.x(0.0)
+				.y(0.0)
+				.width(extent.width as f32)
+				.height(extent.height as f32)
+				.min_depth(0.0)
+				.max_depth(1.0);
+			
+			let scissor = vk::Rect2D::builder()
+				.offset(vk::Offset2D::builder().x(0).y(0).build())
+				.extent(extent);
+
+			logical_device.cmd_set_viewport(text_instance_data_resources.secondary_command_buffer, 0, &[viewport.build()]);
+			logical_device.cmd_set_scissor(text_instance_data_resources.secondary_command_buffer, 0, &[scissor.build()]);
 			logical_device.cmd_bind_descriptor_sets(
 				text_instance_data_resources.secondary_command_buffer,
 				vk::PipelineBindPoint::GRAPHICS,
@@ -770,62 +1898,98 @@ impl RenderSystem {
 				&[]);
 		}
 
-		// Copy text data into buffer and record draw commands
-		for (index, text_info) in text_infos.iter().enumerate() {
-			let (entity, text) = text_info.tuple;
-			let font = fonts.borrow(text.font);
-			let submission_info = font.submission_info.as_ref().unwrap(); // error message
-			assert!(submission_info.generation == self.text_resources.submission_generation);
+		// Copy text data into buffer and record a single draw for the whole batch
+		if let Some((fonts, _, transform2d_components)) = text {
+			for (index, (entity, text)) in text_tuples.iter().enumerate() {
+				// Each font the text may draw glyphs from must have been submitted
+				let mut atlas_indices = [0; MAX_FALLBACK_FONTS + 1];
 
-			let instance_data_offset = text_instance_data_resources.array_offset + 4 * 16 * index;
-			let index_array_offset = index_arrays_offset + text_info.index_array_relative_offset;
-			let attribute_array_offset = attribute_arrays_offset + text_info.attribute_array_relative_offset;
-
-			let indices = text.indices();
-			let attributes = text.attributes();
-
-			let projection_matrix = &self.text_resources.projection_matrix;
-			let transform_matrix = &transform2d_components.borrow(*entity).matrix;
-			let final_matrix = projection_matrix * transform_matrix;
-
-			unsafe {
-				// Copy data
-				let final_matrix_dst_ptr = instance_data_buffer_ptr.add(instance_data_offset) as *mut [f32; 4];
-				copy_nonoverlapping(final_matrix.to_padded_array().as_ptr(), final_matrix_dst_ptr, 3);
+				for (atlas_index, font_handle) in atlas_indices.iter_mut().zip(std::iter::once(&text.font).chain(&text.fallback_fonts)) {
+					let submission_info = fonts.borrow(*font_handle).submission_info.as_ref().expect("Cannot render text because its font has not been submitted");
+					assert!(submission_info.generation == self.text_resources.submission_generation, "Cannot render text because its font was not part of the latest submission");
+					*atlas_index = submission_info.index as u32;
+				}
 
-				let atlas_index_dst_ptr = instance_data_buffer_ptr.add(instance_data_offset + 12 * 4) as *mut i32;
-				copy_nonoverlapping(&(submission_info.index as i32), atlas_index_dst_ptr, 1);
+				let instance_data_offset = text_instance_data_resources.array_offset + text_render_system::INSTANCE_DATA_SIZE * index;
 
-				let index_array_dst_ptr = instance_data_buffer_ptr.add(index_array_offset) as *mut u16;
-				copy_nonoverlapping(indices.as_ptr(), index_array_dst_ptr, indices.len());
+				let projection_matrix = &self.text_resources.projection_matrix;
+				let transform_matrix = &transform2d_components.borrow(*entity).matrix;
+				let final_matrix = projection_matrix * transform_matrix;
 
-				let attribute_array_dst_ptr = instance_data_buffer_ptr.add(attribute_array_offset) as *mut f32;
-				copy_nonoverlapping(attributes.as_ptr(), attribute_array_dst_ptr, attributes.len());
+				unsafe {
+					let instance_data_dst = slice::from_raw_parts_mut(instance_data_buffer_ptr.add(instance_data_offset) as *mut u8, text_render_system::INSTANCE_DATA_SIZE);
+					text_render_system::copy_instance_data(instance_data_dst, &final_matrix, text, &atlas_indices);
+				}
+			}
 
-				// Record draw commands
-				logical_device.cmd_bind_index_buffer(text_instance_data_resources.secondary_command_buffer, in_flight_frame.instance_data_buffer.handle, index_array_offset as u64, vk::IndexType::UINT16);
-				logical_device.cmd_bind_vertex_buffers(text_instance_data_resources.secondary_command_buffer, 0, &[in_flight_frame.instance_data_buffer.handle], &[attribute_array_offset as u64]);
-				logical_device.cmd_draw_indexed(text_instance_data_resources.secondary_command_buffer, indices.len() as u32, 1, 0, 0, index as u32);
+			let text_batch = &self.text_resources.batch;
+
+			if !text_batch.is_empty() {
+				unsafe {
+					// Copy data
+					let index_array_dst_ptr = instance_data_buffer_ptr.add(trailing_layout.text_indices) as *mut u32;
+					copy_nonoverlapping(text_batch.indices.as_ptr(), index_array_dst_ptr, text_batch.indices.len());
+
+					let attribute_array_dst_ptr = instance_data_buffer_ptr.add(trailing_layout.text_attributes) as *mut f32;
+					copy_nonoverlapping(text_batch.attributes.as_ptr(), attribute_array_dst_ptr, text_batch.attributes.len());
+
+					let instance_index_array_dst_ptr = instance_data_buffer_ptr.add(trailing_layout.text_instance_indices) as *mut u32;
+					copy_nonoverlapping(text_batch.instance_indices.as_ptr(), instance_index_array_dst_ptr, text_batch.instance_indices.len());
+
+					// Record draw command
+					let instance_data_buffer_handle = in_flight_frame.instance_data_buffer.handle;
+					logical_device.cmd_bind_index_buffer(text_instance_data_resources.secondary_command_buffer, instance_data_buffer_handle, trailing_layout.text_indices as u64, vk::IndexType::UINT32);
+					logical_device.cmd_bind_vertex_buffers(
+						text_instance_data_resources.secondary_command_buffer,
+						0,
+						&[instance_data_buffer_handle, instance_data_buffer_handle],
+						&[trailing_layout.text_attributes as u64, trailing_layout.text_instance_indices as u64]);
+					logical_device.cmd_draw_indexed(text_instance_data_resources.secondary_command_buffer, text_batch.indices.len() as u32, 1, 0, 0, 0);
+				}
 			}
 		}
 
 		// End command buffer and add to submission list if there are texts to draw
-		unsafe { logical_device.end_command_buffer(text_instance_data_resources.secondary_command_buffer) }.unwrap();
+		unsafe { logical_device.end_command_buffer(text_instance_data_resources.secondary_command_buffer) }?;
 
-		if !text_infos.is_empty() {
+		if !text_tuples.is_empty() {
 			secondary_command_buffers.push(text_instance_data_resources.secondary_command_buffer);
 		}
 
-		// Flush and unmap mesh buffer
+		// Copy debug lines into buffer and record draw commands for each viewport
+		if !debug_vertices.is_empty() {
+			let debug_command_buffer = in_flight_frame.debug_secondary_command_buffer;
+			let debug_vertices_offset = trailing_layout.debug_vertices;
+
+			unsafe {
+				let debug_vertices_dst_ptr = instance_data_buffer_ptr.add(debug_vertices_offset) as *mut f32;
+				copy_nonoverlapping(debug_vertices.as_ptr(), debug_vertices_dst_ptr, debug_vertices.len());
+
+				logical_device.begin_command_buffer(debug_command_buffer, &command_buffer_begin_info)?;
+				logical_device.cmd_bind_pipeline(debug_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.debug_resources.pipelines[pipeline_index]);
+				logical_device.cmd_bind_vertex_buffers(debug_command_buffer, 0, &[in_flight_frame.instance_data_buffer.handle], &[debug_vertices_offset as u64]);
+
+				for (view_index, (viewport, scissor)) in viewport_states.iter().enumerate() {
+					let frame_data_offset = (frame_data_stride * view_index) as u32;
+					logical_device.cmd_set_viewport(debug_command_buffer, 0, &[*viewport]);
+					logical_device.cmd_set_scissor(debug_command_buffer, 0, &[*scissor]);
+					logical_device.cmd_bind_descriptor_sets(debug_command_buffer, vk::PipelineBindPoint::GRAPHICS, self.debug_resources.pipeline_layout, 0, &[in_flight_frame.frame_data_descriptor_set], &[frame_data_offset]);
+					logical_device.cmd_draw(debug_command_buffer, self.debug_resources.vertex_count() as u32, 1, 0, 0);
+				}
+
+				logical_device.end_command_buffer(debug_command_buffer)?;
+			}
+
+			secondary_command_buffers.push(debug_command_buffer);
+		}
+
+		// Flush instance data buffer
 		let range = vk::MappedMemoryRange::builder()
 			.memory(in_flight_frame.instance_data_buffer.memory)
 			.offset(0)
 			.size(vk::WHOLE_SIZE);
 		
-		unsafe {
-			logical_device.flush_mapped_memory_ranges(&[range.build()]).unwrap();
-			logical_device.unmap_memory(in_flight_frame.instance_data_buffer.memory);
-		}
+		unsafe { logical_device.flush_mapped_memory_ranges(&[range.build()]) }?;
 
 		// Record primary command buffer
 		let color_attachment_clear_value = vk::ClearValue {
@@ -840,62 +2004,175 @@ impl RenderSystem {
 			}
 		};
 		let clear_colors = [color_attachment_clear_value, depth_attachment_clear_value];
+		let shadow_clear_colors = [depth_attachment_clear_value];
 
 		let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
 			.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
 
 		let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
-			.render_pass(self.render_pass)
-			.framebuffer(self.swapchain.frames[image_index as usize].framebuffer)
+			.render_pass(render_pass)
+			.framebuffer(framebuffer)
 			.render_area(vk::Rect2D::builder()
 				.offset(vk::Offset2D::builder().x(0).y(0).build())
-				.extent(self.swapchain.extent)
+				.extent(extent)
 				.build())
 			.clear_values(&clear_colors);
+
+		// The shadow render pass always runs so the shadow map is cleared and transitioned to be read by the lambert pipeline
+		let shadow_render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+			.render_pass(self.shadow_resources.render_pass)
+			.framebuffer(self.shadow_resources.framebuffer())
+			.render_area(vk::Rect2D::builder()
+				.offset(vk::Offset2D::builder().x(0).y(0).build())
+				.extent(self.shadow_resources.extent())
+				.build())
+			.clear_values(&shadow_clear_colors);
 		
+		let timestamps_enabled = self.context.physical_device.timestamp_period.is_some();
+		let first_query = 2 * self.current_in_flight_frame_index as u32;
+
 		unsafe {
-			logical_device.begin_command_buffer(in_flight_frame.primary_command_buffer, &command_buffer_begin_info).unwrap();
+			logical_device.begin_command_buffer(in_flight_frame.primary_command_buffer, &command_buffer_begin_info)?;
+
+			if timestamps_enabled {
+				logical_device.cmd_reset_query_pool(in_flight_frame.primary_command_buffer, self.timestamp_query_pool, first_query, 2);
+				logical_device.cmd_write_timestamp(in_flight_frame.primary_command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, self.timestamp_query_pool, first_query);
+			}
+
+			logical_device.cmd_begin_render_pass(in_flight_frame.primary_command_buffer, &shadow_render_pass_begin_info, vk::SubpassContents::SECONDARY_COMMAND_BUFFERS);
+
+			if shadows_enabled {
+				logical_device.cmd_execute_commands(in_flight_frame.primary_command_buffer, &[in_flight_frame.shadow_secondary_command_buffer]);
+			}
+
+			logical_device.cmd_end_render_pass(in_flight_frame.primary_command_buffer);
 			logical_device.cmd_begin_render_pass(in_flight_frame.primary_command_buffer, &render_pass_begin_info, vk::SubpassContents::SECONDARY_COMMAND_BUFFERS);
 			logical_device.cmd_execute_commands(in_flight_frame.primary_command_buffer, &secondary_command_buffers);
 			logical_device.cmd_end_render_pass(in_flight_frame.primary_command_buffer);
-			logical_device.end_command_buffer(in_flight_frame.primary_command_buffer).unwrap();
+
+			if let (Some(image_index), Some(scaled_render_target), Some(swapchain)) = (image_index, &self.scaled_render_target, &self.swapchain) {
+				let swapchain_image = swapchain.frames[image_index as usize].image;
+				scaled_render_target.cmd_blit_to_swapchain_image(logical_device, in_flight_frame.primary_command_buffer, swapchain_image, swapchain.extent);
+			}
+
+			if timestamps_enabled {
+				logical_device.cmd_write_timestamp(in_flight_frame.primary_command_buffer, vk::PipelineStageFlags::BOTTOM_OF_PIPE, self.timestamp_query_pool, first_query + 1);
+			}
+
+			logical_device.end_command_buffer(in_flight_frame.primary_command_buffer)?;
 		}
 
-		// Wait for image to be available then submit primary command buffer
+		// Wait for image to be available then submit primary command buffer, render targets have no swapchain image to wait for
 		let image_available_semaphores = [in_flight_frame.image_available];
-		let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+		let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::TRANSFER];
 		let command_buffers = [in_flight_frame.primary_command_buffer];
 		let render_finished_semaphores = [in_flight_frame.render_finished];
-		let submit_info = vk::SubmitInfo::builder()
-			.wait_semaphores(&image_available_semaphores)
-			.wait_dst_stage_mask(&wait_stages)
-			.command_buffers(&command_buffers)
-			.signal_semaphores(&render_finished_semaphores);
+		let mut submit_info = vk::SubmitInfo::builder()
+			.command_buffers(&command_buffers);
+
+		if image_index.is_some() {
+			submit_info = submit_info
+				.wait_semaphores(&image_available_semaphores)
+				.wait_dst_stage_mask(&wait_stages)
+				.signal_semaphores(&render_finished_semaphores);
+		}
+
+		// A static geometry upload on the transfer queue has to finish before this frame reads it
+		self.staging_buffer.wait_for_transfer(logical_device)?;
 
 		unsafe {
-			logical_device.reset_fences(&[in_flight_frame.fence]).unwrap();
-			logical_device.queue_submit(self.context.graphics_queue, &[submit_info.build()], in_flight_frame.fence).unwrap();
+			logical_device.reset_fences(&[in_flight_frame.fence])?;
+			logical_device.queue_submit(self.context.graphics_queue, &[submit_info.build()], in_flight_frame.fence)?;
 		}
 
 		// Wait for render to finish then present swapchain image
-		let swapchains = [self.swapchain.handle];
-		let image_indices = [image_index];
-		let present_info = vk::PresentInfoKHR::builder()
-			.wait_semaphores(&render_finished_semaphores)
-			.swapchains(&swapchains)
-			.image_indices(&image_indices);
-		
-		let result = unsafe { self.swapchain.extension.queue_present(self.context.graphics_queue, &present_info) };
-
-		let surface_changed = match result {
-			Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => true,
-			Err(e) => panic!("Could not present swapchain image: {}", e),
-			_ => false
+		let status = match image_index {
+			Some(image_index) => {
+				let swapchain = self.swapchain.as_ref().unwrap();
+				let swapchains = [swapchain.handle];
+				let image_indices = [image_index];
+				let present_info = vk::PresentInfoKHR::builder()
+					.wait_semaphores(&render_finished_semaphores)
+					.swapchains(&swapchains)
+					.image_indices(&image_indices);
+				
+				let result = unsafe { swapchain.extension.queue_present(self.context.graphics_queue, &present_info) };
+				self.last_presented_image_index = Some(image_index);
+
+				match SwapchainStatus::from_result(result)? {
+					SwapchainStatus::Optimal if acquire_suboptimal => SwapchainStatus::Suboptimal,
+					status => status
+				}
+			},
+			None => SwapchainStatus::Optimal
 		};
 
-		self.current_in_flight_frame_index = (self.current_in_flight_frame_index + 1) % IN_FLIGHT_FRAMES_COUNT;
+		// Render targets drawn earlier in the frame also show the lines, they're consumed by the swapchain render
+		if render_target.is_none() {
+			self.debug_resources.clear();
+		}
+
+		self.in_flight_frames[self.current_in_flight_frame_index].timestamps_written = timestamps_enabled;
+		self.current_in_flight_frame_index = (self.current_in_flight_frame_index + 1) % self.in_flight_frames.len();
+
+		// Render targets are drawn without the swapchain so there's nothing to recreate
+		if let (Some(_), Some(swapchain)) = (image_index, &self.swapchain) {
+			if should_recreate(status, swapchain.extent, self.surface_extent(swapchain)?) {
+				let (framebuffer_width, framebuffer_height) = self.framebuffer_size;
+				self.recreate_swapchain(framebuffer_width, framebuffer_height)?;
+				swapchain_recreated = true;
+			}
+		}
+
+		Ok(swapchain_recreated)
+	}
+}
+
+struct AcquiredImage {
+	index: u32,
+	suboptimal: bool,
+	recreated: bool
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SwapchainStatus {
+	Optimal,
+	Suboptimal,
+	OutOfDate
+}
+
+impl SwapchainStatus {
+	fn from_result(result: Result<bool, vk::Result>) -> Result<Self, vk::Result> {
+		match result {
+			Ok(false) => Ok(Self::Optimal),
+			Ok(true) => Ok(Self::Suboptimal),
+			Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => Ok(Self::OutOfDate),
+			Err(e) => Err(e)
+		}
+	}
+}
+
+// Some drivers keep reporting suboptimal every frame, e.g. on HiDPI displays, so it only causes a recreation when the surface size actually changed
+fn should_recreate(status: SwapchainStatus, swapchain_extent: vk::Extent2D, surface_extent: vk::Extent2D) -> bool {
+	match status {
+		SwapchainStatus::Optimal => false,
+		SwapchainStatus::Suboptimal => swapchain_extent.width != surface_extent.width || swapchain_extent.height != surface_extent.height,
+		SwapchainStatus::OutOfDate => true
+	}
+}
 
-		surface_changed
+// An out of date swapchain is recreated and the acquire is retried once, only an error from the retry is returned
+fn acquire_with_recreation<T, A, R>(target: &mut T, acquire: A, recreate: R) -> Result<AcquiredImage, RenderSystemError>
+	where A: Fn(&mut T) -> Result<(u32, bool), vk::Result>, R: Fn(&mut T) -> Result<(), RenderSystemError>
+{
+	match acquire(target) {
+		Ok((index, suboptimal)) => Ok(AcquiredImage { index, suboptimal, recreated: false }),
+		Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+			recreate(target)?;
+			let (index, suboptimal) = acquire(target)?;
+			Ok(AcquiredImage { index, suboptimal, recreated: true })
+		},
+		Err(e) => Err(e.into())
 	}
 }
 
@@ -905,34 +2182,560 @@ impl Drop for RenderSystem {
 
 		unsafe { logical_device.device_wait_idle() }.unwrap();
 
+		for (_, render_target) in self.render_targets.iter() {
+			render_target.drop(logical_device);
+		}
+
+		if let Some(scaled_render_target) = &self.scaled_render_target {
+			scaled_render_target.drop(logical_device);
+		}
+
 		self.text_resources.drop(logical_device);
+		self.debug_resources.drop(logical_device);
 		self.mesh_resources.drop(logical_device);
+		self.shadow_resources.drop(logical_device);
+		self.staging_buffer.drop(logical_device);
 
 		unsafe {
-			for frame in &mut self.in_flight_frames {
-				logical_device.destroy_semaphore(frame.image_available, None);
-				logical_device.destroy_semaphore(frame.render_finished, None);
-				logical_device.destroy_fence(frame.fence, None);
-				frame.frame_data_buffer.drop(&self.context.logical_device);
-				frame.instance_data_buffer.drop(&self.context.logical_device);
+			for frame in &self.in_flight_frames {
+				frame.drop(logical_device);
 			}
 			
 			logical_device.destroy_descriptor_set_layout(self.instance_data_descriptor_set_layout, None);
 			logical_device.destroy_descriptor_set_layout(self.frame_data_descriptor_set_layout, None);
+			logical_device.destroy_query_pool(self.timestamp_query_pool, None);
 			logical_device.destroy_command_pool(self.command_pool, None);
 			logical_device.destroy_descriptor_pool(self.descriptor_pool, None);
 
-			self.swapchain.extension.destroy_swapchain(self.swapchain.handle, None);
-			logical_device.destroy_image(self.swapchain.depth_image_resources.image, None);
-			logical_device.destroy_image_view(self.swapchain.depth_image_resources.image_view, None);
-			logical_device.free_memory(self.swapchain.depth_image_resources.memory, None);
-
-			for frame in &self.swapchain.frames {
-				logical_device.destroy_image_view(frame.image_view, None);
-				logical_device.destroy_framebuffer(frame.framebuffer, None);
+			if let Some(swapchain) = &self.swapchain {
+				swapchain.drop(logical_device);
 			}
 
+			logical_device.destroy_render_pass(self.scaled_render_pass, None);
+			logical_device.destroy_render_pass(self.offscreen_render_pass, None);
 			logical_device.destroy_render_pass(self.render_pass, None);
 		}
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn read_missing_shader_file() {
+		use std::io;
+		use super::RenderSystemError;
+
+		match super::read_shader_file("missing.spv") {
+			Err(RenderSystemError::ShaderFile(path, error)) => {
+				assert!(path.ends_with("missing.spv"));
+				assert_eq!(error.kind(), io::ErrorKind::NotFound);
+			},
+			_ => panic!("Expected a shader file error")
+		}
+	}
+
+	#[test]
+	fn align_offset() {
+		assert_eq!(super::align_offset(0, 256), 0);
+		assert_eq!(super::align_offset(1, 256), 256);
+		assert_eq!(super::align_offset(256, 256), 256);
+		assert_eq!(super::align_offset(257, 256), 512);
+		assert_eq!(super::align_offset(6, 4), 8);
+	}
+
+	#[test]
+	fn is_srgb_format() {
+		use ash::vk;
+		use super::is_srgb_format;
+
+		assert!(is_srgb_format(vk::Format::B8G8R8A8_SRGB));
+		assert!(is_srgb_format(vk::Format::R8G8B8A8_SRGB));
+		assert!(!is_srgb_format(vk::Format::B8G8R8A8_UNORM));
+		assert!(!is_srgb_format(vk::Format::R8G8B8A8_UNORM));
+		assert!(!is_srgb_format(vk::Format::A2B10G10R10_UNORM_PACK32));
+		assert!(!is_srgb_format(vk::Format::R16G16B16A16_SFLOAT));
+	}
+
+	#[test]
+	fn frame_data_layout() {
+		use super::*;
+
+		// Each member is aligned as std140 requires and follows the previous one
+		assert_eq!(FRAME_DATA_INVERSE_VIEW_MATRIX_OFFSET, 64);
+		assert_eq!(FRAME_DATA_AMBIENT_LIGHT_OFFSET, FRAME_DATA_INVERSE_VIEW_MATRIX_OFFSET + 64);
+		assert_eq!(FRAME_DATA_POINT_LIGHT_COUNT_OFFSET, FRAME_DATA_AMBIENT_LIGHT_OFFSET + 12);
+		assert_eq!(FRAME_DATA_POINT_LIGHTS_OFFSET, FRAME_DATA_POINT_LIGHT_COUNT_OFFSET + 4);
+		assert_eq!(FRAME_DATA_DIRECTIONAL_LIGHT_DIRECTION_OFFSET, FRAME_DATA_POINT_LIGHTS_OFFSET + FRAME_DATA_POINT_LIGHT_STRIDE * MAX_POINT_LIGHTS);
+		assert_eq!(FRAME_DATA_SHADOWS_ENABLED_OFFSET, FRAME_DATA_DIRECTIONAL_LIGHT_DIRECTION_OFFSET + 12);
+		assert_eq!(FRAME_DATA_DIRECTIONAL_LIGHT_COLOR_OFFSET, FRAME_DATA_SHADOWS_ENABLED_OFFSET + 4);
+		assert_eq!(FRAME_DATA_LIGHT_SPACE_MATRIX_OFFSET, FRAME_DATA_DIRECTIONAL_LIGHT_COLOR_OFFSET + 16);
+		assert_eq!(FRAME_DATA_CAMERA_POSITION_OFFSET, FRAME_DATA_LIGHT_SPACE_MATRIX_OFFSET + 64);
+		assert_eq!(FRAME_DATA_CAMERA_POSITION_OFFSET % 16, 0);
+		assert_eq!(FRAME_DATA_FOG_COLOR_OFFSET, FRAME_DATA_CAMERA_POSITION_OFFSET + 16);
+		assert_eq!(FRAME_DATA_FOG_DENSITY_OFFSET, FRAME_DATA_FOG_COLOR_OFFSET + 12);
+		assert_eq!(FRAME_DATA_FOG_ENABLED_OFFSET, FRAME_DATA_FOG_DENSITY_OFFSET + 4);
+		assert_eq!(FRAME_DATA_MEMORY_SIZE, FRAME_DATA_FOG_ENABLED_OFFSET + 16);
+		assert_eq!(FRAME_DATA_MEMORY_SIZE % 16, 0);
+	}
+
+	#[test]
+	fn camera_frame_data() {
+		use std::convert::TryInto;
+		use super::*;
+
+		let read_f32 = |src: &[u8], offset: usize| f32::from_ne_bytes(src[offset..offset + 4].try_into().unwrap());
+
+		let mut camera = Camera::new(1.0, 1.0, 0.1, 10.0);
+		camera.transform.position.set(1.0, 2.0, 3.0);
+		camera.update();
+
+		let mut inverse_view_matrix = camera.transform.global_matrix;
+		inverse_view_matrix.invert();
+
+		let mut dst = [0u8; FRAME_DATA_MEMORY_SIZE];
+		copy_camera_frame_data(&mut dst, &camera, &inverse_view_matrix);
+
+		assert_eq!(read_f32(&dst, 0), camera.projection_matrix.elements[0][0]);
+		assert_eq!(read_f32(&dst, FRAME_DATA_INVERSE_VIEW_MATRIX_OFFSET + 12), -1.0);
+		assert_eq!(read_f32(&dst, 400), 1.0);
+		assert_eq!(read_f32(&dst, 404), 2.0);
+		assert_eq!(read_f32(&dst, 408), 3.0);
+	}
+
+	// Needs a display and a Vulkan device so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn cull_mode_persists_across_resize() {
+		use super::*;
+		use crate::Window;
+
+		let window = Window::new("Test");
+		let mut render_system = RenderSystem::new(&window.glfw, &window.glfw_window, false, SampleCount::X1, 2).unwrap();
+		assert_eq!(render_system.cull_mode(), CullMode::Back);
+
+		render_system.set_cull_mode(CullMode::None).unwrap();
+		render_system.recreate_swapchain(640, 480).unwrap();
+		assert_eq!(render_system.cull_mode(), CullMode::None);
+
+		render_system.set_cull_mode(CullMode::Front).unwrap();
+		render_system.recreate_swapchain(1280, 720).unwrap();
+		assert_eq!(render_system.cull_mode(), CullMode::Front);
+	}
+
+	// Needs a display and a Vulkan device so it's only run with --ignored, validation reports any pipeline left alive when the device is destroyed
+	#[test]
+	#[ignore]
+	fn reload_shaders_replaces_pipelines() {
+		use super::*;
+		use crate::Window;
+
+		let window = Window::new("Test");
+		let mut render_system = RenderSystem::new(&window.glfw, &window.glfw_window, true, SampleCount::X1, 2).unwrap();
+		let line_pipeline = render_system.mesh_resources.pipelines[SWAPCHAIN_PIPELINES].line;
+		let text_pipeline = render_system.text_resources.pipeline;
+		let debug_pipeline = render_system.debug_resources.pipelines[SWAPCHAIN_PIPELINES];
+		let shadow_pipeline = render_system.shadow_resources.pipeline;
+
+		render_system.reload_shaders().unwrap();
+		render_system.reload_shaders().unwrap();
+
+		assert_ne!(render_system.mesh_resources.pipelines[SWAPCHAIN_PIPELINES].line, line_pipeline);
+		assert_ne!(render_system.text_resources.pipeline, text_pipeline);
+		assert_ne!(render_system.debug_resources.pipelines[SWAPCHAIN_PIPELINES], debug_pipeline);
+		assert_ne!(render_system.shadow_resources.pipeline, shadow_pipeline);
+	}
+
+	// Needs a display and a Vulkan device so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn staging_buffer_reused_across_submissions() {
+		use super::*;
+		use crate::Window;
+
+		let window = Window::new("Test");
+		let mut render_system = RenderSystem::new(&window.glfw, &window.glfw_window, false, SampleCount::X1, 2).unwrap();
+		let mut geometries = Pool::<Geometry3D>::new();
+		let handles = [geometries.add(Geometry3D::create_box()), geometries.add(Geometry3D::create_plane())];
+
+		render_system.submit_static_geometries(&mut geometries, &handles).unwrap();
+		let memory = render_system.staging_buffer.memory();
+
+		render_system.submit_static_geometries(&mut geometries, &handles).unwrap();
+		assert_eq!(render_system.staging_buffer.memory(), memory);
+
+		render_system.submit_static_geometries(&mut geometries, &handles[..1]).unwrap();
+		assert_eq!(render_system.staging_buffer.memory(), memory);
+	}
+
+	// Needs a display and a Vulkan device so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn clear_static_geometries() {
+		use super::*;
+		use crate::Window;
+
+		let window = Window::new("Test");
+		let mut render_system = RenderSystem::new(&window.glfw, &window.glfw_window, false, SampleCount::X1, 2).unwrap();
+		let mut geometries = Pool::<Geometry3D>::new();
+		let level_1 = [geometries.add(Geometry3D::create_box()), geometries.add(Geometry3D::create_plane())];
+		let level_2 = [geometries.add(Geometry3D::create_plane())];
+
+		render_system.submit_static_geometries(&mut geometries, &level_1).unwrap();
+		assert!(render_system.mesh_resources.static_geometry_buffer.capacity > 0);
+
+		render_system.clear_static_geometries().unwrap();
+		assert_eq!(render_system.mesh_resources.static_geometry_buffer.handle, vk::Buffer::null());
+		assert_eq!(render_system.mesh_resources.static_geometry_buffer.capacity, 0);
+
+		// An empty submission doesn't allocate or copy anything
+		render_system.submit_static_geometries(&mut geometries, &[]).unwrap();
+		assert_eq!(render_system.mesh_resources.static_geometry_buffer.capacity, 0);
+
+		render_system.submit_static_geometries(&mut geometries, &level_2).unwrap();
+		let geometry = geometries.borrow(level_2[0]);
+		let submission_info = geometry.submission_info.as_ref().unwrap();
+		let size = (submission_info.attributes_array_offset + size_of_val(geometry.attributes())) as u64;
+		assert_ne!(render_system.mesh_resources.static_geometry_buffer.handle, vk::Buffer::null());
+		assert!(render_system.mesh_resources.static_geometry_buffer.capacity >= size);
+		assert!(geometries.borrow(level_1[0]).submission_info.as_ref().unwrap().generation < submission_info.generation);
+
+		// Rendering waits for the upload so the new geometry is in the buffer
+		let camera = Camera::new(1.0, 75.0, 0.1, 50.0);
+		let light_components = ComponentList::<Light>::new();
+		let mesh_components = MultiComponentList::<Mesh>::new();
+		let transform3d_components = Transform3DComponentList::new();
+		render_system.render(&[(&camera, Viewport::FULL)], &light_components, &geometries, &mesh_components, &transform3d_components, &Pool::new(), &TextComponentList::new(), &Transform2DComponentList::new()).unwrap();
+	}
+
+	// Needs a display and a Vulkan device so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn resize_keeps_pipeline_layouts_and_descriptor_pool() {
+		use super::*;
+		use crate::Window;
+
+		let window = Window::new("Test");
+		let mut render_system = RenderSystem::new(&window.glfw, &window.glfw_window, false, SampleCount::X1, 2).unwrap();
+		let descriptor_pool = render_system.descriptor_pool;
+		let mesh_pipeline_layout = render_system.mesh_resources.pipeline_layout;
+		let text_pipeline_layout = render_system.text_resources.pipeline_layout;
+		let lambert_pipeline = render_system.mesh_resources.pipelines[SWAPCHAIN_PIPELINES].lambert;
+
+		render_system.recreate_swapchain(640, 480).unwrap();
+		render_system.recreate_swapchain(1280, 720).unwrap();
+
+		assert_eq!(render_system.descriptor_pool, descriptor_pool);
+		assert_eq!(render_system.mesh_resources.pipeline_layout, mesh_pipeline_layout);
+		assert_eq!(render_system.text_resources.pipeline_layout, text_pipeline_layout);
+		assert_eq!(render_system.mesh_resources.pipelines[SWAPCHAIN_PIPELINES].lambert, lambert_pipeline);
+	}
+
+	#[test]
+	fn acquire_out_of_date_recreates_once() {
+		use ash::vk;
+		use super::{acquire_with_recreation, RenderSystemError};
+
+		struct MockSwapchain {
+			results: Vec<Result<(u32, bool), vk::Result>>,
+			recreations: u32
+		}
+
+		let mut swapchain = MockSwapchain { results: vec![Err(vk::Result::ERROR_OUT_OF_DATE_KHR), Ok((2, false))], recreations: 0 };
+		let acquire = |swapchain: &mut MockSwapchain| swapchain.results.remove(0);
+		let recreate = |swapchain: &mut MockSwapchain| {
+			swapchain.recreations += 1;
+			Ok(())
+		};
+
+		let acquired_image = acquire_with_recreation(&mut swapchain, acquire, recreate).unwrap();
+		assert_eq!(acquired_image.index, 2);
+		assert!(acquired_image.recreated);
+		assert_eq!(swapchain.recreations, 1);
+
+		let mut swapchain = MockSwapchain { results: vec![Ok((0, false))], recreations: 0 };
+		let acquired_image = acquire_with_recreation(&mut swapchain, acquire, recreate).unwrap();
+		assert!(!acquired_image.recreated);
+		assert_eq!(swapchain.recreations, 0);
+
+		let mut swapchain = MockSwapchain { results: vec![Err(vk::Result::ERROR_OUT_OF_DATE_KHR), Err(vk::Result::ERROR_OUT_OF_DATE_KHR)], recreations: 0 };
+		assert!(matches!(acquire_with_recreation(&mut swapchain, acquire, recreate), Err(RenderSystemError::Vulkan(vk::Result::ERROR_OUT_OF_DATE_KHR))));
+		assert_eq!(swapchain.recreations, 1);
+	}
+
+	#[test]
+	fn suboptimal_only_recreates_on_size_change() {
+		use ash::vk;
+		use super::{SwapchainStatus, should_recreate};
+
+		assert_eq!(SwapchainStatus::from_result(Ok(false)), Ok(SwapchainStatus::Optimal));
+		assert_eq!(SwapchainStatus::from_result(Ok(true)), Ok(SwapchainStatus::Suboptimal));
+		assert_eq!(SwapchainStatus::from_result(Err(vk::Result::ERROR_OUT_OF_DATE_KHR)), Ok(SwapchainStatus::OutOfDate));
+		assert_eq!(SwapchainStatus::from_result(Err(vk::Result::ERROR_DEVICE_LOST)), Err(vk::Result::ERROR_DEVICE_LOST));
+
+		let mut swapchain_extent = vk::Extent2D { width: 1280, height: 720 };
+		let surface_extent = vk::Extent2D { width: 1280, height: 720 };
+		let mut recreations = 0;
+
+		// Suboptimal persisting across frames at the same size never recreates
+		for _ in 0..10 {
+			assert!(!should_recreate(SwapchainStatus::Suboptimal, swapchain_extent, surface_extent));
+		}
+
+		// A size change recreates once then the suboptimal frames after it don't
+		let surface_extent = vk::Extent2D { width: 2560, height: 1440 };
+
+		for _ in 0..10 {
+			if should_recreate(SwapchainStatus::Suboptimal, swapchain_extent, surface_extent) {
+				swapchain_extent = surface_extent;
+				recreations += 1;
+			}
+		}
+
+		assert_eq!(recreations, 1);
+
+		// Out of date always recreates, optimal never does
+		assert!(should_recreate(SwapchainStatus::OutOfDate, swapchain_extent, surface_extent));
+		assert!(!should_recreate(SwapchainStatus::Optimal, swapchain_extent, vk::Extent2D { width: 640, height: 480 }));
+	}
+
+	#[test]
+	#[ignore]
+	// Needs a display and a Vulkan device so it's only run with --ignored
+	fn three_in_flight_frames() {
+		use std::collections::HashSet;
+		use super::*;
+		use crate::Window;
+
+		let window = Window::new("Test");
+		let render_system = RenderSystem::new(&window.glfw, &window.glfw_window, false, SampleCount::X1, 3).unwrap();
+		assert_eq!(render_system.in_flight_frames.len(), 3);
+
+		let mut command_buffers = HashSet::new();
+		let mut descriptor_sets = HashSet::new();
+
+		for frame in &render_system.in_flight_frames {
+			command_buffers.insert(frame.primary_command_buffer);
+			command_buffers.insert(frame.shadow_secondary_command_buffer);
+			command_buffers.insert(frame.depth_prepass_secondary_command_buffer);
+			command_buffers.insert(frame.debug_secondary_command_buffer);
+			descriptor_sets.insert(frame.frame_data_descriptor_set);
+
+			for resources in &[
+				&frame.line_instance_data_resources,
+				&frame.basic_instance_data_resources,
+				&frame.normal_instance_data_resources,
+				&frame.lambert_instance_data_resources,
+				&frame.phong_instance_data_resources,
+				&frame.vertex_color_instance_data_resources,
+				&frame.text_instance_data_resources]
+			{
+				command_buffers.insert(resources.secondary_command_buffer);
+				descriptor_sets.insert(resources.descriptor_set);
+			}
+		}
+
+		assert_eq!(command_buffers.len(), 3 * 11);
+		assert_eq!(descriptor_sets.len(), 3 * 8);
+	}
+
+	#[test]
+	fn cull_mode_flags() {
+		use ash::vk;
+		use super::CullMode;
+
+		assert_eq!(CullMode::None.as_flags(), vk::CullModeFlags::NONE);
+		assert_eq!(CullMode::Back.as_flags(), vk::CullModeFlags::BACK);
+		assert_eq!(CullMode::Front.as_flags(), vk::CullModeFlags::FRONT);
+	}
+
+	// Needs a display and a Vulkan device so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn render_same_components_twice() {
+		use super::*;
+		use crate::{Window, component::Transform3D};
+
+		let window = Window::new("Test");
+		let mut render_system = RenderSystem::new(&window.glfw, &window.glfw_window, false, SampleCount::X1, 2).unwrap();
+		let mut geometries = Pool::<Geometry3D>::new();
+		let geometry_handle = geometries.add(Geometry3D::create_box());
+		render_system.submit_static_geometries(&mut geometries, &[geometry_handle]).unwrap();
+
+		let mut mesh_components = MultiComponentList::<Mesh>::new();
+		let mut transform3d_components = Transform3DComponentList::new();
+		let index = mesh_components.add(Mesh::new(geometry_handle, Material::Normal));
+		mesh_components.assign(0, index);
+		transform3d_components.add(0, Transform3D::new());
+
+		let mut left_camera = Camera::new(0.5, 75.0, 0.1, 50.0);
+		left_camera.transform.position.set(0.0, 0.0, -5.0);
+		left_camera.update();
+		let mut right_camera = Camera::new(0.5, 75.0, 0.1, 50.0);
+		right_camera.transform.position.set(2.0, 0.0, -5.0);
+		right_camera.update();
+
+		// The components are only borrowed so the same scene can be drawn into a texture and then from two cameras
+		let light_components = ComponentList::<Light>::new();
+		let render_target = render_system.create_render_target(64, 64);
+		render_system.render_to_texture(render_target, &left_camera, &light_components, &geometries, &mesh_components, &transform3d_components).unwrap();
+		render_system.render(
+			&[(&left_camera, Viewport::new(0.0, 0.0, 0.5, 1.0)), (&right_camera, Viewport::new(0.5, 0.0, 0.5, 1.0))],
+			&light_components,
+			&geometries,
+			&mesh_components,
+			&transform3d_components,
+			&Pool::new(),
+			&TextComponentList::new(),
+			&Transform2DComponentList::new()).unwrap();
+
+		// Drawing into the texture again after the swapchain render still finds the box
+		render_system.render_to_texture(render_target, &left_camera, &light_components, &geometries, &mesh_components, &transform3d_components).unwrap();
+		let pixels = render_system.read_pixels(render_target).unwrap();
+		assert!(pixels.chunks_exact(4).any(|pixel| pixel[..3] != [0, 0, 0]));
+
+		assert_eq!(mesh_components.iter().count(), 1);
+		render_system.destroy_render_target(render_target);
+	}
+
+	// Needs a Vulkan device so it's only run with --ignored, there's no window so it doesn't need a display
+	#[test]
+	#[ignore]
+	fn headless_render_to_texture() {
+		use super::*;
+		use crate::component::Transform3D;
+
+		let mut render_system = RenderSystem::new_headless(false, SampleCount::X1, 2).unwrap();
+		assert!(render_system.is_headless());
+		assert_eq!(render_system.get_swapchain_extent(), (0, 0));
+
+		let mut geometries = Pool::<Geometry3D>::new();
+		let geometry_handle = geometries.add(Geometry3D::create_box());
+		render_system.submit_static_geometries(&mut geometries, &[geometry_handle]).unwrap();
+
+		let mut mesh_components = MultiComponentList::<Mesh>::new();
+		let mut transform3d_components = Transform3DComponentList::new();
+		let index = mesh_components.add(Mesh::new(geometry_handle, Material::Normal));
+		mesh_components.assign(0, index);
+		transform3d_components.add(0, Transform3D::new());
+
+		let mut camera = Camera::new(1.0, 75.0, 0.1, 50.0);
+		camera.transform.position.set(0.0, 0.0, -5.0);
+		camera.update();
+
+		let light_components = ComponentList::<Light>::new();
+		let render_target = render_system.create_render_target(64, 64);
+		render_system.render_to_texture(render_target, &camera, &light_components, &geometries, &mesh_components, &transform3d_components).unwrap();
+
+		let pixels = render_system.read_pixels(render_target).unwrap();
+		assert_eq!(pixels.len(), 64 * 64 * 4);
+		assert!(pixels.chunks_exact(4).any(|pixel| pixel[..3] != [0, 0, 0]));
+
+		// There's no swapchain to render to, recreate or capture
+		let result = render_system.render(&[(&camera, Viewport::FULL)], &light_components, &geometries, &mesh_components, &transform3d_components, &Pool::new(), &TextComponentList::new(), &Transform2DComponentList::new());
+		assert!(matches!(result, Err(RenderSystemError::Headless)));
+		assert!(matches!(render_system.recreate_swapchain(640, 480), Err(RenderSystemError::Headless)));
+		assert!(matches!(render_system.capture_screenshot("headless.png"), Err(ScreenshotError::Unsupported)));
+
+		render_system.destroy_render_target(render_target);
+	}
+
+	#[test]
+	fn trailing_array_layout() {
+		use super::{TrailingArrayLayout, TrailingArraySizes};
+
+		let sizes = TrailingArraySizes {
+			index_arrays: 6,
+			attribute_arrays: 48,
+			debug_vertices: 24,
+			text_indices: 24,
+			text_attributes: 144,
+			text_instance_indices: 16
+		};
+
+		// Each section starts where the previous ends, index arrays are u16 so the attributes are realigned to 4 bytes
+		let layout = TrailingArrayLayout::new(256, &sizes);
+		assert_eq!(layout.index_arrays, 256);
+		assert_eq!(layout.attribute_arrays, 264);
+		assert_eq!(layout.debug_vertices, 312);
+		assert_eq!(layout.text_indices, 336);
+		assert_eq!(layout.text_attributes, 360);
+		assert_eq!(layout.text_instance_indices, 504);
+		assert_eq!(layout.end, 520);
+
+		// A larger stored base moves every section by the same amount so nothing overlaps
+		let stored_layout = TrailingArrayLayout::new(512, &sizes);
+		assert_eq!(stored_layout.debug_vertices - stored_layout.index_arrays, layout.debug_vertices - layout.index_arrays);
+		assert_eq!(stored_layout.end - layout.end, 256);
+	}
+
+	// Needs a display and a Vulkan device so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn shrinking_counts_keep_geometry_intact() {
+		use super::*;
+		use crate::{Window, component::{Text, Transform2D, Transform3D}};
+
+		let window = Window::new("Test");
+		let mut render_system = RenderSystem::new(&window.glfw, &window.glfw_window, false, SampleCount::X1, 2).unwrap();
+		let mut geometries = Pool::<Geometry3D>::new();
+		let geometry_handle = geometries.add(Geometry3D::create_box());
+		render_system.submit_static_geometries(&mut geometries, &[geometry_handle]).unwrap();
+
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(Font::new("../game/res/roboto.ttf", 14).unwrap());
+		render_system.submit_fonts(&mut fonts).unwrap();
+
+		let mut camera = Camera::new(1.0, 75.0, 0.1, 50.0);
+		camera.transform.position.set(0.0, 0.0, -5.0);
+		camera.update();
+
+		let light_components = ComponentList::<Light>::new();
+		let render_target = render_system.create_render_target(64, 64);
+
+		// Boxes spread out in front of the camera and the same number of labels
+		let create_scene = |box_count: usize, text_count: usize| {
+			let mut mesh_components = MultiComponentList::<Mesh>::new();
+			let mut transform3d_components = Transform3DComponentList::new();
+			let mut text_components = TextComponentList::new();
+			let mut transform2d_components = Transform2DComponentList::new();
+
+			for entity in 0..box_count {
+				let index = mesh_components.add(Mesh::new(geometry_handle, Material::Normal));
+				mesh_components.assign(entity, index);
+				let mut transform = Transform3D::new();
+				transform.position.set(entity as f32 * 0.1, 0.0, entity as f32 * 0.5);
+				transform3d_components.add(entity, transform);
+			}
+
+			for entity in 0..text_count {
+				text_components.add(entity, Text::new(font_handle, format!("Label {}", entity)));
+				transform2d_components.add(entity, Transform2D::new());
+			}
+
+			text_components.generate_dirties(&fonts);
+			(mesh_components, transform3d_components, text_components, transform2d_components)
+		};
+
+		let (mesh_components, transform3d_components, text_components, transform2d_components) = create_scene(1, 1);
+		render_system.render_to_texture(render_target, &camera, &light_components, &geometries, &mesh_components, &transform3d_components).unwrap();
+		let expected = render_system.read_pixels(render_target).unwrap();
+		assert!(expected.chunks_exact(4).any(|pixel| pixel[..3] != [0, 0, 0]));
+
+		// Grow every in flight frame's arrays then shrink back, the arrays stay placed for the larger counts
+		let (large_mesh_components, large_transform3d_components, large_text_components, large_transform2d_components) = create_scene(50, 20);
+
+		for _ in 0..4 {
+			render_system.debug_line(&vector3::ZERO, &vector3::UNIT_X, &vector3::ONE);
+			render_system.render(&[(&camera, Viewport::FULL)], &light_components, &geometries, &large_mesh_components, &large_transform3d_components, &fonts, &large_text_components, &large_transform2d_components).unwrap();
+		}
+
+		for _ in 0..4 {
+			render_system.debug_line(&vector3::ZERO, &vector3::UNIT_X, &vector3::ONE);
+			render_system.render(&[(&camera, Viewport::FULL)], &light_components, &geometries, &mesh_components, &transform3d_components, &fonts, &text_components, &transform2d_components).unwrap();
+		}
+
+		render_system.render_to_texture(render_target, &camera, &light_components, &geometries, &mesh_components, &transform3d_components).unwrap();
+		assert_eq!(render_system.read_pixels(render_target).unwrap(), expected);
+		render_system.destroy_render_target(render_target);
+	}
 }
\ No newline at end of file
@@ -0,0 +1,188 @@
+use std::{fs, io, ptr::copy_nonoverlapping};
+use ash::{vk, version::DeviceV1_0};
+use crate::vulkan::{Context, Buffer};
+use super::ScreenshotError;
+
+// Copies a color image into a host visible buffer and returns the pixels as tightly packed R8G8B8A8 rows, top row first
+// The image is transitioned from the layout it's in to TRANSFER_SRC_OPTIMAL and back again, the caller ensures it's no longer being rendered to
+pub fn read_image_pixels(context: &Context, command_pool: vk::CommandPool, image: vk::Image, layout: vk::ImageLayout, format: vk::Format, extent: vk::Extent2D) -> Result<Vec<u8>, vk::Result> {
+	assert!(is_rgba8_compatible_format(format), "Cannot read pixels because the format {:?} is not an 8 bit RGBA or BGRA format", format);
+
+	let logical_device = &context.logical_device;
+	let size = (extent.width * extent.height * 4) as usize;
+
+	let buffer = Buffer::new(
+		context,
+		size as vk::DeviceSize,
+		vk::BufferUsageFlags::TRANSFER_DST,
+		vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT)?;
+
+	let subresource_range = vk::ImageSubresourceRange::builder()
+		.aspect_mask(vk::ImageAspectFlags::COLOR)
+		.base_mip_level(0)
+		.level_count(1)
+		.base_array_layer(0)
+		.layer_count(1)
+		.build();
+
+	let transfer_barrier = vk::ImageMemoryBarrier::builder()
+		.src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+		.dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+		.old_layout(layout)
+		.new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+		.src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+		.dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+		.image(image)
+		.subresource_range(subresource_range);
+
+	let restore_barrier = vk::ImageMemoryBarrier::builder()
+		.src_access_mask(vk::AccessFlags::TRANSFER_READ)
+		.dst_access_mask(vk::AccessFlags::empty())
+		.old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+		.new_layout(layout)
+		.src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+		.dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+		.image(image)
+		.subresource_range(subresource_range);
+
+	// A buffer row length of 0 means the rows are tightly packed
+	let region = vk::BufferImageCopy::builder()
+		.buffer_offset(0)
+		.buffer_row_length(0)
+		.buffer_image_height(0)
+		.image_subresource(vk::ImageSubresourceLayers::builder()
+			.aspect_mask(vk::ImageAspectFlags::COLOR)
+			.mip_level(0)
+			.base_array_layer(0)
+			.layer_count(1)
+			.build())
+		.image_offset(vk::Offset3D::builder().x(0).y(0).z(0).build())
+		.image_extent(vk::Extent3D::builder().width(extent.width).height(extent.height).depth(1).build());
+
+	let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
+		.level(vk::CommandBufferLevel::PRIMARY)
+		.command_pool(command_pool)
+		.command_buffer_count(1);
+
+	let command_buffer = unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }?[0];
+	let command_buffers = [command_buffer];
+
+	let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
+		.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+	let submit_info = vk::SubmitInfo::builder()
+		.command_buffers(&command_buffers);
+
+	// The command buffer is freed whether or not the copy succeeded
+	let copied = unsafe {
+		logical_device.begin_command_buffer(command_buffer, &command_buffer_begin_info)
+			.and_then(|_| {
+				logical_device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[], &[], &[transfer_barrier.build()]);
+				logical_device.cmd_copy_image_to_buffer(command_buffer, image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, buffer.handle, &[region.build()]);
+				logical_device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::BOTTOM_OF_PIPE, vk::DependencyFlags::empty(), &[], &[], &[restore_barrier.build()]);
+				logical_device.end_command_buffer(command_buffer)
+			})
+			.and_then(|_| logical_device.queue_submit(context.graphics_queue, &[submit_info.build()], vk::Fence::null()))
+			.and_then(|_| logical_device.queue_wait_idle(context.graphics_queue))
+	};
+
+	unsafe { logical_device.free_command_buffers(command_pool, &command_buffers) };
+	copied?;
+
+	// Copy the pixels out of the buffer
+	let mut pixels = vec![0; size];
+
+	unsafe {
+		let buffer_ptr = logical_device.map_memory(buffer.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())?;
+		copy_nonoverlapping(buffer_ptr as *const u8, pixels.as_mut_ptr(), size);
+		logical_device.unmap_memory(buffer.memory);
+	}
+
+	drop(buffer);
+
+	if is_bgra_format(format) {
+		swizzle_bgra_to_rgba(&mut pixels);
+	}
+
+	Ok(pixels)
+}
+
+pub fn save_png(path: &str, width: u32, height: u32, pixels: &[u8]) -> Result<(), ScreenshotError> {
+	assert_eq!(pixels.len(), (width * height * 4) as usize, "Cannot save PNG because there are {} bytes for a {}x{} image", pixels.len(), width, height);
+
+	let file = fs::File::create(path)?;
+	let mut encoder = png::Encoder::new(io::BufWriter::new(file), width, height);
+	encoder.set_color(png::ColorType::RGBA);
+	encoder.set_depth(png::BitDepth::Eight);
+
+	let mut writer = encoder.write_header()?;
+	writer.write_image_data(pixels)?;
+	Ok(())
+}
+
+fn is_rgba8_compatible_format(format: vk::Format) -> bool {
+	matches!(format,
+		vk::Format::R8G8B8A8_UNORM
+		| vk::Format::R8G8B8A8_SRGB
+		| vk::Format::B8G8R8A8_UNORM
+		| vk::Format::B8G8R8A8_SRGB)
+}
+
+fn is_bgra_format(format: vk::Format) -> bool {
+	matches!(format, vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB)
+}
+
+fn swizzle_bgra_to_rgba(pixels: &mut [u8]) {
+	assert_eq!(pixels.len() % 4, 0, "Cannot swizzle pixels because the length {} is not a multiple of 4", pixels.len());
+
+	for pixel in pixels.chunks_exact_mut(4) {
+		pixel.swap(0, 2);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn swizzle_bgra_to_rgba() {
+		let mut pixels = [10, 20, 30, 40, 50, 60, 70, 80];
+		super::swizzle_bgra_to_rgba(&mut pixels);
+		assert_eq!(pixels, [30, 20, 10, 40, 70, 60, 50, 80]);
+	}
+
+	#[test]
+	fn bgra_formats() {
+		assert!(is_bgra_format(vk::Format::B8G8R8A8_SRGB));
+		assert!(is_bgra_format(vk::Format::B8G8R8A8_UNORM));
+		assert!(!is_bgra_format(vk::Format::R8G8B8A8_UNORM));
+		assert!(is_rgba8_compatible_format(vk::Format::R8G8B8A8_UNORM));
+		assert!(!is_rgba8_compatible_format(vk::Format::A2B10G10R10_UNORM_PACK32));
+	}
+
+	#[test]
+	fn save_png() {
+		let pixels = [255, 0, 0, 255, 0, 255, 0, 128];
+		let path = std::env::temp_dir().join("readback_save_png.png");
+		super::save_png(path.to_str().unwrap(), 2, 1, &pixels).unwrap();
+
+		let decoder = png::Decoder::new(fs::File::open(&path).unwrap());
+		let (info, mut reader) = decoder.read_info().unwrap();
+		assert_eq!(info.width, 2);
+		assert_eq!(info.height, 1);
+		assert_eq!(info.color_type, png::ColorType::RGBA);
+
+		let mut decoded_pixels = vec![0; info.buffer_size()];
+		reader.next_frame(&mut decoded_pixels).unwrap();
+		assert_eq!(decoded_pixels, pixels);
+
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn save_png_missing_directory() {
+		let path = std::env::temp_dir().join("readback_missing_directory").join("screenshot.png");
+		let result = super::save_png(path.to_str().unwrap(), 1, 1, &[0, 0, 0, 255]);
+		assert!(matches!(result, Err(ScreenshotError::Io(_))));
+	}
+}
\ No newline at end of file
@@ -0,0 +1,288 @@
+use ash::{vk, version::DeviceV1_0};
+use crate::vulkan::Context;
+use super::{ImageResources, readback::read_image_pixels};
+
+// Render targets don't depend on the surface's format so their pixels are always read back as RGBA8
+// Since the format is UNORM the pipelines drawing into render targets gamma correct in the shaders
+pub const RENDER_TARGET_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+// An offscreen color and depth target the scene can be rendered into instead of the swapchain
+// - The color image has the format of the render pass it's created for, RENDER_TARGET_FORMAT or the surface's format for the scaled render target
+// - The color image ends the render pass in SHADER_READ_ONLY_OPTIMAL so it can be sampled or copied from
+// - When multisampling, a multisampled color image is resolved into the color image like the swapchain images
+pub struct RenderTarget {
+	color_image_resources: ImageResources,
+	multisampled_color_image_resources: Option<ImageResources>,
+	depth_image_resources: ImageResources,
+	pub framebuffer: vk::Framebuffer,
+	pub extent: vk::Extent2D,
+	format: vk::Format
+}
+
+impl RenderTarget {
+	pub fn new(context: &Context, width: u32, height: u32, format: vk::Format, render_pass: vk::RenderPass, sample_count: vk::SampleCountFlags) -> Self {
+		assert!(width > 0 && height > 0, "Cannot create render target because the width {} and height {} must both be greater than 0", width, height);
+
+		let extent = vk::Extent2D::builder()
+			.width(width)
+			.height(height)
+			.build();
+
+		let color_image_resources = create_image_resources(
+			context,
+			extent,
+			format,
+			vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_SRC,
+			vk::SampleCountFlags::TYPE_1,
+			vk::ImageAspectFlags::COLOR);
+		context.set_object_name(color_image_resources.image, "render_target_color_image");
+
+		let multisampled_color_image_resources = if sample_count != vk::SampleCountFlags::TYPE_1 {
+			let image_resources = create_image_resources(
+				context,
+				extent,
+				format,
+				vk::ImageUsageFlags::TRANSIENT_ATTACHMENT | vk::ImageUsageFlags::COLOR_ATTACHMENT,
+				sample_count,
+				vk::ImageAspectFlags::COLOR);
+			context.set_object_name(image_resources.image, "render_target_multisampled_color_image");
+
+			Some(image_resources)
+		}
+		else {
+			None
+		};
+
+		let depth_image_resources = create_image_resources(
+			context,
+			extent,
+			vk::Format::D32_SFLOAT,
+			vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+			sample_count,
+			vk::ImageAspectFlags::DEPTH);
+		context.set_object_name(depth_image_resources.image, "render_target_depth_image");
+
+		// Create framebuffer, the attachments are in the same order as the swapchain framebuffers
+		let attachments = match &multisampled_color_image_resources {
+			Some(multisampled_color_image_resources) => vec![multisampled_color_image_resources.image_view, depth_image_resources.image_view, color_image_resources.image_view],
+			None => vec![color_image_resources.image_view, depth_image_resources.image_view]
+		};
+
+		let framebuffer_create_info = vk::FramebufferCreateInfo::builder()
+			.render_pass(render_pass)
+			.attachments(&attachments)
+			.width(width)
+			.height(height)
+			.layers(1);
+
+		let framebuffer = unsafe { context.logical_device.create_framebuffer(&framebuffer_create_info, None) }.unwrap();
+
+		Self {
+			color_image_resources,
+			multisampled_color_image_resources,
+			depth_image_resources,
+			framebuffer,
+			extent,
+			format
+		}
+	}
+
+	// The caller ensures the render target is no longer being rendered to
+	pub fn read_pixels(&self, context: &Context, command_pool: vk::CommandPool) -> Result<Vec<u8>, vk::Result> {
+		read_image_pixels(context, command_pool, self.color_image_resources.image, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL, self.format, self.extent)
+	}
+
+	// Copies the color image into a swapchain image, scaling it to the swapchain's extent
+	// The color image is left in TRANSFER_SRC_OPTIMAL and the swapchain image in PRESENT_SRC_KHR
+	pub fn cmd_blit_to_swapchain_image(&self, logical_device: &ash::Device, command_buffer: vk::CommandBuffer, swapchain_image: vk::Image, swapchain_extent: vk::Extent2D) {
+		let subresource_range = vk::ImageSubresourceRange::builder()
+			.aspect_mask(vk::ImageAspectFlags::COLOR)
+			.base_mip_level(0)
+			.level_count(1)
+			.base_array_layer(0)
+			.layer_count(1)
+			.build();
+
+		let color_image_barrier = vk::ImageMemoryBarrier::builder()
+			.src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+			.dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+			.old_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+			.new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+			.src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+			.dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+			.image(self.color_image_resources.image)
+			.subresource_range(subresource_range);
+
+		let swapchain_image_barrier = vk::ImageMemoryBarrier::builder()
+			.src_access_mask(vk::AccessFlags::empty())
+			.dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+			.old_layout(vk::ImageLayout::UNDEFINED)
+			.new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+			.src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+			.dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+			.image(swapchain_image)
+			.subresource_range(subresource_range);
+
+		let subresource_layers = vk::ImageSubresourceLayers::builder()
+			.aspect_mask(vk::ImageAspectFlags::COLOR)
+			.mip_level(0)
+			.base_array_layer(0)
+			.layer_count(1)
+			.build();
+
+		let region = vk::ImageBlit::builder()
+			.src_subresource(subresource_layers)
+			.src_offsets([vk::Offset3D::default(), vk::Offset3D { x: self.extent.width as i32, y: self.extent.height as i32, z: 1 }])
+			.dst_subresource(subresource_layers)
+			.dst_offsets([vk::Offset3D::default(), vk::Offset3D { x: swapchain_extent.width as i32, y: swapchain_extent.height as i32, z: 1 }]);
+
+		let present_barrier = vk::ImageMemoryBarrier::builder()
+			.src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+			.dst_access_mask(vk::AccessFlags::empty())
+			.old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+			.new_layout(vk::ImageLayout::PRESENT_SRC_KHR)
+			.src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+			.dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+			.image(swapchain_image)
+			.subresource_range(subresource_range);
+
+		unsafe {
+			logical_device.cmd_pipeline_barrier(
+				command_buffer,
+				vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+				vk::PipelineStageFlags::TRANSFER,
+				vk::DependencyFlags::empty(),
+				&[],
+				&[],
+				&[color_image_barrier.build(), swapchain_image_barrier.build()]);
+
+			logical_device.cmd_blit_image(
+				command_buffer,
+				self.color_image_resources.image,
+				vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+				swapchain_image,
+				vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+				&[region.build()],
+				vk::Filter::LINEAR);
+
+			logical_device.cmd_pipeline_barrier(
+				command_buffer,
+				vk::PipelineStageFlags::TRANSFER,
+				vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+				vk::DependencyFlags::empty(),
+				&[],
+				&[],
+				&[present_barrier.build()]);
+		}
+	}
+
+	pub fn drop(&self, logical_device: &ash::Device) {
+		unsafe {
+			logical_device.destroy_framebuffer(self.framebuffer, None);
+
+			let mut image_resources = vec![&self.color_image_resources, &self.depth_image_resources];
+			image_resources.extend(&self.multisampled_color_image_resources);
+
+			for image_resources in image_resources {
+				logical_device.destroy_image_view(image_resources.image_view, None);
+				logical_device.destroy_image(image_resources.image, None);
+				logical_device.free_memory(image_resources.memory, None);
+			}
+		}
+	}
+}
+
+// The extent of the scene when it's rendered at a fraction of the swapchain's resolution, never smaller than 1x1
+pub fn scaled_extent(extent: vk::Extent2D, scale: f32) -> vk::Extent2D {
+	vk::Extent2D::builder()
+		.width(((extent.width as f32 * scale).round() as u32).max(1))
+		.height(((extent.height as f32 * scale).round() as u32).max(1))
+		.build()
+}
+
+fn create_image_resources(
+	context: &Context,
+	extent: vk::Extent2D,
+	format: vk::Format,
+	usage: vk::ImageUsageFlags,
+	sample_count: vk::SampleCountFlags,
+	aspect_mask: vk::ImageAspectFlags)
+	-> ImageResources
+{
+	let logical_device = &context.logical_device;
+
+	// Create image
+	let image_create_info = vk::ImageCreateInfo::builder()
+		.image_type(vk::ImageType::TYPE_2D)
+		.extent(vk::Extent3D::builder()
+			.width(extent.width)
+			.height(extent.height)
+			.depth(1)
+			.build())
+		.mip_levels(1)
+		.array_layers(1)
+		.format(format)
+		.tiling(vk::ImageTiling::OPTIMAL)
+		.initial_layout(vk::ImageLayout::UNDEFINED)
+		.usage(usage)
+		.samples(sample_count)
+		.sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+	let image = unsafe { logical_device.create_image(&image_create_info, None) }.unwrap();
+
+	// Allocate image memory and bind it to the image
+	let memory_requirements = unsafe { logical_device.get_image_memory_requirements(image) };
+	let memory_type_index = context.physical_device.find_memory_type_index(memory_requirements.memory_type_bits, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+	let allocate_info = vk::MemoryAllocateInfo::builder()
+		.allocation_size(memory_requirements.size)
+		.memory_type_index(memory_type_index as u32);
+
+	let memory = unsafe { logical_device.allocate_memory(&allocate_info, None) }.unwrap();
+	unsafe { logical_device.bind_image_memory(image, memory, 0) }.unwrap();
+
+	// Create image view
+	let image_view_create_info = vk::ImageViewCreateInfo::builder()
+		.image(image)
+		.view_type(vk::ImageViewType::TYPE_2D)
+		.format(format)
+		.subresource_range(vk::ImageSubresourceRange::builder()
+			.aspect_mask(aspect_mask)
+			.base_mip_level(0)
+			.level_count(1)
+			.base_array_layer(0)
+			.layer_count(1)
+			.build());
+
+	let image_view = unsafe { logical_device.create_image_view(&image_view_create_info, None) }.unwrap();
+
+	ImageResources {
+		image,
+		image_view,
+		memory
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scaled_extent() {
+		let extent = vk::Extent2D::builder().width(1280).height(720).build();
+
+		let scaled = super::scaled_extent(extent, 1.0);
+		assert_eq!((scaled.width, scaled.height), (1280, 720));
+
+		let scaled = super::scaled_extent(extent, 0.7);
+		assert_eq!((scaled.width, scaled.height), (896, 504));
+
+		let scaled = super::scaled_extent(extent, 0.25);
+		assert_eq!((scaled.width, scaled.height), (320, 180));
+
+		// A tiny window still has a valid extent
+		let extent = vk::Extent2D::builder().width(1).height(1).build();
+		let scaled = super::scaled_extent(extent, 0.25);
+		assert_eq!((scaled.width, scaled.height), (1, 1));
+	}
+}
\ No newline at end of file
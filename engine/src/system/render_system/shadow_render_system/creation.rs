@@ -0,0 +1,316 @@
+use std::ffi::CString;
+use ash::{vk, version::DeviceV1_0};
+use crate::vulkan::Context;
+use super::{ShadowMap, super::{create_shader_module, RenderSystemError}};
+
+const SHADOW_MAP_FORMAT: vk::Format = vk::Format::D32_SFLOAT;
+
+pub fn create_render_pass(logical_device: &ash::Device) -> vk::RenderPass {
+	// The depth attachment is left in a read only layout so the main render pass can sample it
+	let depth_attachment_description = vk::AttachmentDescription::builder()
+		.format(SHADOW_MAP_FORMAT)
+		.samples(vk::SampleCountFlags::TYPE_1)
+		.load_op(vk::AttachmentLoadOp::CLEAR)
+		.store_op(vk::AttachmentStoreOp::STORE)
+		.stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+		.stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+		.initial_layout(vk::ImageLayout::UNDEFINED)
+		.final_layout(vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL);
+	let attachment_descriptions = [depth_attachment_description.build()];
+
+	let depth_attachment_ref = vk::AttachmentReference::builder()
+		.attachment(0)
+		.layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+	let subpass_description = vk::SubpassDescription::builder()
+		.pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+		.depth_stencil_attachment(&depth_attachment_ref);
+	let subpass_descriptions = [subpass_description.build()];
+
+	// Don't write to the shadow map until the previous frame is done reading it and don't read it until it's written
+	let write_subpass_dependency = vk::SubpassDependency::builder()
+		.src_subpass(vk::SUBPASS_EXTERNAL)
+		.dst_subpass(0)
+		.src_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+		.src_access_mask(vk::AccessFlags::SHADER_READ)
+		.dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+		.dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
+
+	let read_subpass_dependency = vk::SubpassDependency::builder()
+		.src_subpass(0)
+		.dst_subpass(vk::SUBPASS_EXTERNAL)
+		.src_stage_mask(vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+		.src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+		.dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+		.dst_access_mask(vk::AccessFlags::SHADER_READ);
+
+	let subpass_dependencies = [write_subpass_dependency.build(), read_subpass_dependency.build()];
+
+	let render_pass_create_info = vk::RenderPassCreateInfo::builder()
+		.attachments(&attachment_descriptions)
+		.subpasses(&subpass_descriptions)
+		.dependencies(&subpass_dependencies);
+
+	unsafe { logical_device.create_render_pass(&render_pass_create_info, None) }.unwrap()
+}
+
+pub fn create_descriptor_set_layout(logical_device: &ash::Device) -> vk::DescriptorSetLayout {
+	let layout_binding = vk::DescriptorSetLayoutBinding::builder()
+		.binding(0)
+		.descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+		.descriptor_count(1)
+		.stage_flags(vk::ShaderStageFlags::FRAGMENT);
+	let layout_bindings = [layout_binding.build()];
+
+	let create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+		.bindings(&layout_bindings);
+
+	unsafe { logical_device.create_descriptor_set_layout(&create_info, None) }.unwrap()
+}
+
+pub fn create_pipeline_layout(
+	logical_device: &ash::Device,
+	frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
+	instance_data_descriptor_set_layout: vk::DescriptorSetLayout)
+	-> vk::PipelineLayout
+{
+	let descriptor_set_layouts = [frame_data_descriptor_set_layout, instance_data_descriptor_set_layout];
+
+	// The instance stride
+	let push_constant_range = vk::PushConstantRange::builder()
+		.stage_flags(vk::ShaderStageFlags::VERTEX)
+		.offset(0)
+		.size(4);
+	let push_constant_ranges = [push_constant_range.build()];
+
+	let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::builder()
+		.set_layouts(&descriptor_set_layouts)
+		.push_constant_ranges(&push_constant_ranges);
+
+	unsafe { logical_device.create_pipeline_layout(&pipeline_layout_create_info, None) }.unwrap()
+}
+
+pub fn create_pipeline(logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, resolution: u32, pipeline_layout: vk::PipelineLayout, render_pass: vk::RenderPass) -> Result<vk::Pipeline, RenderSystemError> {
+	// Create entry point string
+	let entry_point = CString::new("main").unwrap();
+	let entry_point_cstr = entry_point.as_c_str();
+
+	// Create shader stage create infos, there's no fragment stage since only depth is written
+	let vert_module = create_shader_module(logical_device, "shadow.vert.spv")?;
+	let vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::VERTEX)
+		.module(vert_module)
+		.name(entry_point_cstr);
+
+	let stage_create_infos = [vert_stage_create_info.build()];
+
+	// Create vertex input state create info, the normals are skipped over
+	let input_binding_description = vk::VertexInputBindingDescription::builder()
+		.binding(0)
+		.stride(24)
+		.input_rate(vk::VertexInputRate::VERTEX);
+	let input_binding_descriptions = [input_binding_description.build()];
+
+	let input_attribute_description_position = vk::VertexInputAttributeDescription::builder()
+		.binding(0)
+		.location(0)
+		.format(vk::Format::R32G32B32_SFLOAT)
+		.offset(0);
+	let input_attribute_descriptions = [input_attribute_description_position.build()];
+
+	let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::builder()
+		.vertex_binding_descriptions(&input_binding_descriptions)
+		.vertex_attribute_descriptions(&input_attribute_descriptions);
+
+	// Create input assembly state create info
+	let input_assembly_state_create_info = vk::PipelineInputAssemblyStateCreateInfo::builder()
+		.topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+		.primitive_restart_enable(false);
+
+	// Create viewport state create info
+	let viewport = vk::Viewport::builder()
+		.x(0.0)
+		.y(0.0)
+		.width(resolution as f32)
+		.height(resolution as f32)
+		.min_depth(0.0)
+		.max_depth(1.0);
+	let viewports = [viewport.build()];
+
+	let scissor = vk::Rect2D::builder()
+		.offset(vk::Offset2D::builder().x(0).y(0).build())
+		.extent(vk::Extent2D::builder().width(resolution).height(resolution).build());
+	let scissors = [scissor.build()];
+
+	let viewport_state_create_info = vk::PipelineViewportStateCreateInfo::builder()
+		.viewports(&viewports)
+		.scissors(&scissors);
+
+	// Create rasterization state create info, the depth bias pushes the stored depth away from the light to avoid acne
+	let rasterization_state_create_info = vk::PipelineRasterizationStateCreateInfo::builder()
+		.depth_clamp_enable(false)
+		.rasterizer_discard_enable(false)
+		.polygon_mode(vk::PolygonMode::FILL)
+		.line_width(1.0)
+		.cull_mode(vk::CullModeFlags::BACK)
+		.front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+		.depth_bias_enable(true)
+		.depth_bias_constant_factor(1.25)
+		.depth_bias_clamp(0.0)
+		.depth_bias_slope_factor(1.75);
+
+	// Create multisample state create info
+	let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo::builder()
+		.sample_shading_enable(false)
+		.rasterization_samples(vk::SampleCountFlags::TYPE_1);
+
+	// Create depth stencil state create info
+	let depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo::builder()
+		.depth_test_enable(true)
+		.depth_write_enable(true)
+		.depth_compare_op(vk::CompareOp::LESS)
+		.depth_bounds_test_enable(false)
+		.stencil_test_enable(false);
+
+	// Create color blend state create info
+	let color_blend_state_create_info = vk::PipelineColorBlendStateCreateInfo::builder()
+		.logic_op_enable(false);
+
+	// Create pipeline
+	let pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
+		.stages(&stage_create_infos)
+		.vertex_input_state(&vertex_input_state_create_info)
+		.input_assembly_state(&input_assembly_state_create_info)
+		.viewport_state(&viewport_state_create_info)
+		.rasterization_state(&rasterization_state_create_info)
+		.multisample_state(&multisample_state_create_info)
+		.depth_stencil_state(&depth_stencil_state_create_info)
+		.color_blend_state(&color_blend_state_create_info)
+		.layout(pipeline_layout)
+		.render_pass(render_pass)
+		.subpass(0);
+
+	let pipeline = unsafe { logical_device.create_graphics_pipelines(pipeline_cache, &[pipeline_create_info.build()], None) }.map_err(|(_, result)| result)?[0];
+
+	// Destroy shader module
+	unsafe { logical_device.destroy_shader_module(vert_module, None) };
+
+	Ok(pipeline)
+}
+
+pub fn create_descriptor_set(logical_device: &ash::Device, descriptor_set_layout: vk::DescriptorSetLayout, descriptor_pool: vk::DescriptorPool) -> vk::DescriptorSet {
+	let descriptor_set_layouts = [descriptor_set_layout];
+	let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo::builder()
+		.descriptor_pool(descriptor_pool)
+		.set_layouts(&descriptor_set_layouts);
+
+	unsafe { logical_device.allocate_descriptor_sets(&descriptor_set_allocate_info) }.unwrap()[0]
+}
+
+pub fn create_sampler(logical_device: &ash::Device) -> vk::Sampler {
+	// Samples outside of the shadow map compare against a depth of 1 so they're lit
+	let sampler_create_info = vk::SamplerCreateInfo::builder()
+		.mag_filter(vk::Filter::NEAREST)
+		.min_filter(vk::Filter::NEAREST)
+		.address_mode_u(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+		.address_mode_v(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+		.address_mode_w(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+		.anisotropy_enable(false)
+		.border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE)
+		.unnormalized_coordinates(false)
+		.compare_enable(true)
+		.compare_op(vk::CompareOp::LESS_OR_EQUAL)
+		.mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+		.mip_lod_bias(0.0)
+		.min_lod(0.0)
+		.max_lod(0.0);
+
+	unsafe { logical_device.create_sampler(&sampler_create_info, None) }.unwrap()
+}
+
+pub(super) fn create_shadow_map(context: &Context, resolution: u32, render_pass: vk::RenderPass) -> ShadowMap {
+	let logical_device = &context.logical_device;
+
+	// Create image
+	let image_create_info = vk::ImageCreateInfo::builder()
+		.image_type(vk::ImageType::TYPE_2D)
+		.extent(vk::Extent3D::builder()
+			.width(resolution)
+			.height(resolution)
+			.depth(1)
+			.build())
+		.mip_levels(1)
+		.array_layers(1)
+		.format(SHADOW_MAP_FORMAT)
+		.tiling(vk::ImageTiling::OPTIMAL)
+		.initial_layout(vk::ImageLayout::UNDEFINED)
+		.usage(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED)
+		.samples(vk::SampleCountFlags::TYPE_1)
+		.sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+	let image = unsafe { logical_device.create_image(&image_create_info, None) }.unwrap();
+	context.set_object_name(image, "shadow_map_image");
+
+	// Allocate image memory and bind it to the image
+	let memory_requirements = unsafe { logical_device.get_image_memory_requirements(image) };
+	let memory_type_index = context.physical_device.find_memory_type_index(memory_requirements.memory_type_bits, vk::MemoryPropertyFlags::DEVICE_LOCAL);
+
+	let allocate_info = vk::MemoryAllocateInfo::builder()
+		.allocation_size(memory_requirements.size)
+		.memory_type_index(memory_type_index as u32);
+
+	let memory = unsafe { logical_device.allocate_memory(&allocate_info, None) }.unwrap();
+	unsafe { logical_device.bind_image_memory(image, memory, 0) }.unwrap();
+
+	// Create image view
+	let image_view_create_info = vk::ImageViewCreateInfo::builder()
+		.image(image)
+		.view_type(vk::ImageViewType::TYPE_2D)
+		.format(SHADOW_MAP_FORMAT)
+		.subresource_range(vk::ImageSubresourceRange::builder()
+			.aspect_mask(vk::ImageAspectFlags::DEPTH)
+			.base_mip_level(0)
+			.level_count(1)
+			.base_array_layer(0)
+			.layer_count(1)
+			.build());
+
+	let image_view = unsafe { logical_device.create_image_view(&image_view_create_info, None) }.unwrap();
+
+	// Create framebuffer
+	let attachments = [image_view];
+	let framebuffer_create_info = vk::FramebufferCreateInfo::builder()
+		.render_pass(render_pass)
+		.attachments(&attachments)
+		.width(resolution)
+		.height(resolution)
+		.layers(1);
+
+	let framebuffer = unsafe { logical_device.create_framebuffer(&framebuffer_create_info, None) }.unwrap();
+
+	ShadowMap {
+		image,
+		image_view,
+		memory,
+		framebuffer,
+		resolution
+	}
+}
+
+pub fn update_descriptor_set(logical_device: &ash::Device, sampler: vk::Sampler, image_view: vk::ImageView, descriptor_set: vk::DescriptorSet) {
+	let descriptor_image_info = vk::DescriptorImageInfo::builder()
+		.sampler(sampler)
+		.image_view(image_view)
+		.image_layout(vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL);
+	let descriptor_image_infos = [descriptor_image_info.build()];
+
+	let write_descriptor_set = vk::WriteDescriptorSet::builder()
+		.dst_set(descriptor_set)
+		.dst_binding(0)
+		.dst_array_element(0)
+		.descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+		.image_info(&descriptor_image_infos)
+		.build();
+
+	unsafe { logical_device.update_descriptor_sets(&[write_descriptor_set], &[]) };
+}
\ No newline at end of file
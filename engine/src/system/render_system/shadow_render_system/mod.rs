@@ -0,0 +1,158 @@
+use ash::{vk, version::DeviceV1_0};
+use crate::{math::Matrix4, vulkan::Context};
+use super::RenderSystemError;
+
+mod creation;
+use creation::*;
+
+// The volume around the directional light which casts shadows
+const SHADOW_VOLUME_HALF_EXTENT: f32 = 20.0;
+const SHADOW_VOLUME_NEAR: f32 = 0.1;
+const SHADOW_VOLUME_FAR: f32 = 100.0;
+
+// Descriptor bindings
+// - The depth only pipeline uses set 0 (frame data) and set 1 (instance data) like the mesh pipelines
+// - A vertex stage push constant holds the number of vec4s per instance since lambert instances are larger
+// - The mesh pipeline layout adds set 2 binding 0, the shadow map as a combined image sampler read by the lambert fragment shader
+// The shadow map doesn't depend on the swapchain so nothing here is recreated with it
+pub struct ShadowRenderSystem {
+	pub render_pass: vk::RenderPass,
+	pub descriptor_set_layout: vk::DescriptorSetLayout,
+	pub pipeline_layout: vk::PipelineLayout,
+	pub pipeline: vk::Pipeline,
+	pub descriptor_set: vk::DescriptorSet,
+	sampler: vk::Sampler,
+	shadow_map: ShadowMap,
+	pub enabled: bool
+}
+
+struct ShadowMap {
+	image: vk::Image,
+	image_view: vk::ImageView,
+	memory: vk::DeviceMemory,
+	framebuffer: vk::Framebuffer,
+	resolution: u32
+}
+
+impl ShadowRenderSystem {
+	pub fn new(
+		context: &Context,
+		frame_data_descriptor_set_layout: vk::DescriptorSetLayout,
+		instance_data_descriptor_set_layout: vk::DescriptorSetLayout,
+		descriptor_pool: vk::DescriptorPool)
+		-> Result<Self, RenderSystemError>
+	{
+		let logical_device = &context.logical_device;
+
+		// Until shadows are enabled a 1x1 shadow map is bound so the lambert descriptor set is always valid
+		let resolution = 1;
+		let render_pass = create_render_pass(logical_device);
+		let descriptor_set_layout = create_descriptor_set_layout(logical_device);
+		let pipeline_layout = create_pipeline_layout(logical_device, frame_data_descriptor_set_layout, instance_data_descriptor_set_layout);
+		let pipeline = match create_pipeline(logical_device, context.pipeline_cache, resolution, pipeline_layout, render_pass) {
+			Ok(pipeline) => pipeline,
+			Err(error) => {
+				unsafe {
+					logical_device.destroy_pipeline_layout(pipeline_layout, None);
+					logical_device.destroy_descriptor_set_layout(descriptor_set_layout, None);
+					logical_device.destroy_render_pass(render_pass, None);
+				}
+
+				return Err(error);
+			}
+		};
+
+		let descriptor_set = create_descriptor_set(logical_device, descriptor_set_layout, descriptor_pool);
+		let sampler = create_sampler(logical_device);
+		let shadow_map = create_shadow_map(context, resolution, render_pass);
+		update_descriptor_set(logical_device, sampler, shadow_map.image_view, descriptor_set);
+
+		Ok(Self {
+			render_pass,
+			descriptor_set_layout,
+			pipeline_layout,
+			pipeline,
+			descriptor_set,
+			sampler,
+			shadow_map,
+			enabled: false
+		})
+	}
+
+	pub fn enable(&mut self, context: &Context, resolution: u32) -> Result<(), RenderSystemError> {
+		assert!(resolution > 0, "Cannot enable shadows because the resolution must be greater than 0");
+
+		let logical_device = &context.logical_device;
+		let pipeline = create_pipeline(logical_device, context.pipeline_cache, resolution, self.pipeline_layout, self.render_pass)?;
+
+		unsafe {
+			logical_device.device_wait_idle()?;
+			logical_device.destroy_pipeline(self.pipeline, None);
+		}
+
+		self.destroy_shadow_map(logical_device);
+
+		self.pipeline = pipeline;
+		self.shadow_map = create_shadow_map(context, resolution, self.render_pass);
+		update_descriptor_set(logical_device, self.sampler, self.shadow_map.image_view, self.descriptor_set);
+
+		self.enabled = true;
+		Ok(())
+	}
+
+	// The caller ensures the device is idle, the old pipeline is kept if the new one can't be created
+	pub fn recreate_pipeline(&mut self, logical_device: &ash::Device, pipeline_cache: vk::PipelineCache) -> Result<(), RenderSystemError> {
+		let pipeline = create_pipeline(logical_device, pipeline_cache, self.shadow_map.resolution, self.pipeline_layout, self.render_pass)?;
+		unsafe { logical_device.destroy_pipeline(self.pipeline, None) };
+		self.pipeline = pipeline;
+		Ok(())
+	}
+
+	pub fn framebuffer(&self) -> vk::Framebuffer {
+		self.shadow_map.framebuffer
+	}
+
+	pub fn extent(&self) -> vk::Extent2D {
+		vk::Extent2D::builder()
+			.width(self.shadow_map.resolution)
+			.height(self.shadow_map.resolution)
+			.build()
+	}
+
+	pub fn light_space_matrix(light_matrix: &Matrix4) -> Matrix4 {
+		let mut view_matrix = *light_matrix;
+		view_matrix.invert();
+
+		let mut projection_matrix = Matrix4::default();
+		projection_matrix.make_orthographic(
+			-SHADOW_VOLUME_HALF_EXTENT,
+			SHADOW_VOLUME_HALF_EXTENT,
+			-SHADOW_VOLUME_HALF_EXTENT,
+			SHADOW_VOLUME_HALF_EXTENT,
+			SHADOW_VOLUME_NEAR,
+			SHADOW_VOLUME_FAR);
+
+		projection_matrix * view_matrix
+	}
+
+	fn destroy_shadow_map(&self, logical_device: &ash::Device) {
+		unsafe {
+			logical_device.destroy_framebuffer(self.shadow_map.framebuffer, None);
+			logical_device.destroy_image_view(self.shadow_map.image_view, None);
+			logical_device.destroy_image(self.shadow_map.image, None);
+			logical_device.free_memory(self.shadow_map.memory, None);
+		}
+	}
+
+	pub fn drop(&self, logical_device: &ash::Device) {
+		self.destroy_shadow_map(logical_device);
+
+		unsafe {
+			logical_device.destroy_sampler(self.sampler, None);
+			logical_device.destroy_pipeline(self.pipeline, None);
+			logical_device.destroy_pipeline_layout(self.pipeline_layout, None);
+			logical_device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+			logical_device.destroy_render_pass(self.render_pass, None);
+		}
+	}
+}
\ No newline at end of file
@@ -1,6 +1,7 @@
 use std::ffi::CString;
 use ash::{vk, version::DeviceV1_0};
-use super::{super::create_shader_module, MAX_FONTS};
+use crate::component::text::ATTRIBUTES_PER_VERTEX;
+use super::{super::{create_shader_module, RenderSystemError}, MAX_FONTS};
 
 pub fn create_sampler_descriptor_set_layout(logical_device: &ash::Device) -> vk::DescriptorSetLayout {
 	let layout_binding = vk::DescriptorSetLayoutBinding::builder()
@@ -50,32 +51,51 @@ pub fn create_pipeline_layout(
 }
 
 
-pub fn create_pipeline(logical_device: &ash::Device, extent: vk::Extent2D, pipeline_layout: vk::PipelineLayout, render_pass: vk::RenderPass) -> vk::Pipeline {
+pub fn create_pipeline(logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, pipeline_layout: vk::PipelineLayout, render_pass: vk::RenderPass, sample_count: vk::SampleCountFlags, gamma_correction: bool) -> Result<vk::Pipeline, RenderSystemError> {
 	// Create entry point string
 	let entry_point = CString::new("main").unwrap();
 	let entry_point_cstr = entry_point.as_c_str();
 
+	// Create the gamma correction specialization info, a bool specialization constant is 4 bytes
+	let gamma_correction_data = (gamma_correction as u32).to_ne_bytes();
+	let specialization_map_entry = vk::SpecializationMapEntry::builder()
+		.constant_id(0)
+		.offset(0)
+		.size(4);
+	let specialization_map_entries = [specialization_map_entry.build()];
+
+	let specialization_info = vk::SpecializationInfo::builder()
+		.map_entries(&specialization_map_entries)
+		.data(&gamma_correction_data);
+
 	// Create shader stage create infos
-	let vert_module = create_shader_module(logical_device, "text.vert.spv");
+	let vert_module = create_shader_module(logical_device, "text.vert.spv")?;
 	let vert_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::VERTEX)
 		.module(vert_module)
 		.name(entry_point_cstr);
 	
-	let frag_module = create_shader_module(logical_device, "text.frag.spv");
+	let frag_module = create_shader_module(logical_device, "text.frag.spv")?;
 	let frag_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
 		.stage(vk::ShaderStageFlags::FRAGMENT)
 		.module(frag_module)
-		.name(entry_point_cstr);
+		.name(entry_point_cstr)
+		.specialization_info(&specialization_info);
 	
 	let stage_create_infos = [vert_stage_create_info.build(), frag_stage_create_info.build()];
 
 	// Create vertex input state create info
 	let input_binding_description = vk::VertexInputBindingDescription::builder()
 		.binding(0)
-		.stride(16)
+		.stride((ATTRIBUTES_PER_VERTEX * 4) as u32)
 		.input_rate(vk::VertexInputRate::VERTEX);
-	let input_binding_descriptions = [input_binding_description.build()];
+
+	// The merged batch's instance indices are a separate array after the attributes
+	let instance_index_binding_description = vk::VertexInputBindingDescription::builder()
+		.binding(1)
+		.stride(4)
+		.input_rate(vk::VertexInputRate::VERTEX);
+	let input_binding_descriptions = [input_binding_description.build(), instance_index_binding_description.build()];
 
 	let input_attribute_description_position = vk::VertexInputAttributeDescription::builder()	
 		.binding(0)
@@ -90,8 +110,34 @@ pub fn create_pipeline(logical_device: &ash::Device, extent: vk::Extent2D, pipel
 		.format(vk::Format::R32G32_SFLOAT)
 		.offset(8)
 		.build();
+	
+	let input_attribute_description_glyph_bounds = vk::VertexInputAttributeDescription::builder()	
+		.binding(0)
+		.location(2)
+		.format(vk::Format::R32G32B32A32_SFLOAT)
+		.offset(16)
+		.build();
 
-	let input_attribute_descriptions = [input_attribute_description_position, input_attribute_description_texture_position];
+	let input_attribute_description_font_slot = vk::VertexInputAttributeDescription::builder()
+		.binding(0)
+		.location(3)
+		.format(vk::Format::R32_SFLOAT)
+		.offset(32)
+		.build();
+
+	let input_attribute_description_instance_index = vk::VertexInputAttributeDescription::builder()
+		.binding(1)
+		.location(4)
+		.format(vk::Format::R32_UINT)
+		.offset(0)
+		.build();
+
+	let input_attribute_descriptions = [
+		input_attribute_description_position,
+		input_attribute_description_texture_position,
+		input_attribute_description_glyph_bounds,
+		input_attribute_description_font_slot,
+		input_attribute_description_instance_index];
 
 	let vert_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::builder()
 		.vertex_binding_descriptions(&input_binding_descriptions)
@@ -102,24 +148,14 @@ pub fn create_pipeline(logical_device: &ash::Device, extent: vk::Extent2D, pipel
 		.topology(vk::PrimitiveTopology::TRIANGLE_LIST)
 		.primitive_restart_enable(false);
 	
-	// Create viewport state create info
-	let viewport = vk::Viewport::builder()
-		.x(0.0)
-		.y(0.0)
-		.width(extent.width as f32)
-		.height(extent.height as f32)
-		.min_depth(0.0)
-		.max_depth(1.0);
-	let viewports = [viewport.build()];
-
-	let scissor = vk::Rect2D::builder()
-		.offset(vk::Offset2D::builder().x(0).y(0).build())
-		.extent(extent);
-	let scissors = [scissor.build()];
-
+	// Create viewport state create info, the viewport and scissor are dynamic so the pipeline doesn't depend on the framebuffer size
 	let viewport_state_create_info = vk::PipelineViewportStateCreateInfo::builder()
-		.viewports(&viewports)
-		.scissors(&scissors);
+		.viewport_count(1)
+		.scissor_count(1);
+
+	let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+	let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo::builder()
+		.dynamic_states(&dynamic_states);
 	
 	// Create rasterization state create info
 	let rasterization_state_create_info = vk::PipelineRasterizationStateCreateInfo::builder()
@@ -134,7 +170,7 @@ pub fn create_pipeline(logical_device: &ash::Device, extent: vk::Extent2D, pipel
 	// Create multisample state create info
 	let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo::builder()
 		.sample_shading_enable(false)
-		.rasterization_samples(vk::SampleCountFlags::TYPE_1);
+		.rasterization_samples(sample_count);
 	
 	// Create depth stencil state create info
 	let depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo::builder()
@@ -164,6 +200,7 @@ pub fn create_pipeline(logical_device: &ash::Device, extent: vk::Extent2D, pipel
 		.vertex_input_state(&vert_input_state_create_info)
 		.input_assembly_state(&input_assembly_state_create_info)
 		.viewport_state(&viewport_state_create_info)
+		.dynamic_state(&dynamic_state_create_info)
 		.rasterization_state(&rasterization_state_create_info)
 		.multisample_state(&multisample_state_create_info)
 		.depth_stencil_state(&depth_stencil_state_create_info)
@@ -172,7 +209,7 @@ pub fn create_pipeline(logical_device: &ash::Device, extent: vk::Extent2D, pipel
 		.render_pass(render_pass)
 		.subpass(0);
 	
-	let pipeline = unsafe { logical_device.create_graphics_pipelines(vk::PipelineCache::null(), &[pipeline_create_info.build()], None) }.unwrap()[0];
+	let pipeline = unsafe { logical_device.create_graphics_pipelines(pipeline_cache, &[pipeline_create_info.build()], None) }.map_err(|(_, result)| result)?[0];
 
 	// Destroy shader modules
 	unsafe {
@@ -180,7 +217,7 @@ pub fn create_pipeline(logical_device: &ash::Device, extent: vk::Extent2D, pipel
 		logical_device.destroy_shader_module(frag_module, None);
 	}
 
-	pipeline
+	Ok(pipeline)
 }
 
 pub fn create_descriptor_sets(
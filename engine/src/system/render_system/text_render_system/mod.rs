@@ -1,11 +1,25 @@
-use std::{fs::File, io::{Read, Seek, SeekFrom}, ptr::copy_nonoverlapping, mem::size_of};
+use std::{fs::File, io::{Read, Seek, SeekFrom}, iter::repeat, ptr::copy_nonoverlapping};
 use ash::{vk, version::DeviceV1_0};
-use crate::{pool::Pool, font::{Font, SubmissionInfo}, vulkan::{Context, Buffer}, math::Matrix3};
-use super::MAX_FONTS;
+use crate::{pool::Pool, font::{Font, SubmissionInfo, FNT_ATLAS_OFFSET}, vulkan::{Context, StagingBuffer}, math::Matrix3, component::{Text, text::{ATTRIBUTES_PER_VERTEX, MAX_FALLBACK_FONTS}}};
+use super::{RenderSystemError, MAX_FONTS};
 
 mod creation;
 use creation::*;
 
+// Instance data layout (std140)
+// - mat3 matrix at 0, each row is padded to a vec4
+// - vec3 color at 48
+// - uint atlasIndex at 60
+// - vec3 outlineColor at 64
+// - float outlineWidth at 76
+// - uvec3 fallbackAtlasIndices at 80, padded to a vec4
+pub const INSTANCE_DATA_SIZE: usize = 24 * 4;
+const INSTANCE_DATA_COLOR_OFFSET: usize = 12 * 4;
+const INSTANCE_DATA_ATLAS_INDEX_OFFSET: usize = 15 * 4;
+const INSTANCE_DATA_OUTLINE_COLOR_OFFSET: usize = 16 * 4;
+const INSTANCE_DATA_OUTLINE_WIDTH_OFFSET: usize = 19 * 4;
+const INSTANCE_DATA_FALLBACK_ATLAS_INDICES_OFFSET: usize = 20 * 4;
+
 pub struct TextRenderSystem {
 	sampler_descriptor_set_layout: vk::DescriptorSetLayout,
 	atlases_descriptor_set_layout: vk::DescriptorSetLayout,
@@ -19,7 +33,8 @@ pub struct TextRenderSystem {
 	empty_image: vk::Image,
 	empty_image_view: vk::ImageView,
 	pub submission_generation: usize,
-	pub projection_matrix: Matrix3
+	pub projection_matrix: Matrix3,
+	pub batch: TextBatch
 }
 
 struct Atlas {
@@ -28,11 +43,24 @@ struct Atlas {
 }
 
 impl TextRenderSystem {
-	pub fn new(logical_device: &ash::Device, instance_data_descriptor_set_layout: vk::DescriptorSetLayout, extent: vk::Extent2D, render_pass: vk::RenderPass, descriptor_pool: vk::DescriptorPool) -> Self {
+	#[allow(clippy::clippy::too_many_arguments)]
+	pub fn new(logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, instance_data_descriptor_set_layout: vk::DescriptorSetLayout, extent: vk::Extent2D, render_pass: vk::RenderPass, sample_count: vk::SampleCountFlags, gamma_correction: bool, descriptor_pool: vk::DescriptorPool) -> Result<Self, RenderSystemError> {
 		let sampler_descriptor_set_layout = create_sampler_descriptor_set_layout(logical_device);
 		let atlases_descriptor_set_layout = create_atlases_descriptor_set_layout(logical_device);
 		let pipeline_layout = create_pipeline_layout(logical_device, instance_data_descriptor_set_layout, sampler_descriptor_set_layout, atlases_descriptor_set_layout);
-		let pipeline = create_pipeline(logical_device, extent, pipeline_layout, render_pass);
+		let pipeline = match create_pipeline(logical_device, pipeline_cache, pipeline_layout, render_pass, sample_count, gamma_correction) {
+			Ok(pipeline) => pipeline,
+			Err(error) => {
+				unsafe {
+					logical_device.destroy_pipeline_layout(pipeline_layout, None);
+					logical_device.destroy_descriptor_set_layout(atlases_descriptor_set_layout, None);
+					logical_device.destroy_descriptor_set_layout(sampler_descriptor_set_layout, None);
+				}
+
+				return Err(error);
+			}
+		};
+
 		let descriptor_sets = create_descriptor_sets(logical_device, sampler_descriptor_set_layout, atlases_descriptor_set_layout, descriptor_pool);
 		let sampler = create_sampler(logical_device);
 		update_sampler(logical_device, sampler, descriptor_sets[0]);
@@ -42,7 +70,7 @@ impl TextRenderSystem {
 			[0.0, 2.0 / extent.height as f32, -1.0],
 			[0.0, 0.0, 1.0]]);
 
-		Self {
+		Ok(Self {
 			sampler_descriptor_set_layout,
 			atlases_descriptor_set_layout,
 			pipeline_layout,
@@ -55,25 +83,26 @@ impl TextRenderSystem {
 			empty_image: vk::Image::null(),
 			empty_image_view: vk::ImageView::null(),
 			submission_generation: 0,
-			projection_matrix
-		}
+			projection_matrix,
+			batch: TextBatch::new()
+		})
 	}
 
-	pub fn handle_swapchain_recreation(&mut self, logical_device: &ash::Device, extent: vk::Extent2D, render_pass: vk::RenderPass) {
-		unsafe { logical_device.destroy_pipeline(self.pipeline, None) };
-
-		self.pipeline = create_pipeline(logical_device, extent, self.pipeline_layout, render_pass);
-		
+	// Text is positioned in swapchain pixels even when the scene is rendered at a lower resolution
+	pub fn handle_swapchain_recreation(&mut self, extent: vk::Extent2D) {
 		self.projection_matrix.elements[0][0] = 2.0 / extent.width as f32;
 		self.projection_matrix.elements[1][1] = 2.0 / extent.height as f32;
 	}
 
-	pub fn submit_fonts(&mut self, context: &Context, command_pool: vk::CommandPool, fonts: &mut Pool<Font>) {
+	pub fn submit_fonts(&mut self, context: &Context, command_pool: vk::CommandPool, staging_buffer: &mut StagingBuffer, fonts: &mut Pool<Font>) -> Result<(), RenderSystemError> {
 		let logical_device = &context.logical_device;
 
+		// Read the atlases first so a missing font file leaves the submitted fonts untouched
+		let atlases = fonts.iter().map(|(_, font)| read_atlas(font)).collect::<Result<Vec<Vec<u8>>, RenderSystemError>>()?;
+
 		// Free memory and destroy resources
 		unsafe {
-			logical_device.queue_wait_idle(context.graphics_queue).unwrap();
+			logical_device.queue_wait_idle(context.graphics_queue)?;
 			logical_device.free_memory(self.memory, None);
 			logical_device.destroy_image_view(self.empty_image_view, None);
 			logical_device.destroy_image(self.empty_image, None);
@@ -89,11 +118,11 @@ impl TextRenderSystem {
 
 		// Don't do anything if there are no fonts
 		if fonts.is_empty() {
-			return;
+			return Ok(());
 		}
 
 		// Ensure there are not more fonts than what's allowed
-		assert!(fonts.occupied_record_count() <= MAX_FONTS, "Cannot submit fonts, {} is more than the allowed {}", fonts.occupied_record_count(), MAX_FONTS);
+		assert!(fonts.len() <= MAX_FONTS, "Cannot submit fonts, {} is more than the allowed {}", fonts.len(), MAX_FONTS);
 
 		// Create images and calculate buffer size
 		struct TempFontInfo<'a> {
@@ -106,7 +135,7 @@ impl TextRenderSystem {
 		let mut font_infos: Vec<TempFontInfo> = vec![];
 		let mut offset = 0;
 
-		for font in fonts.iter_mut() {
+		for (_, font) in fonts.iter_mut() {
 			let image_create_info = vk::ImageCreateInfo::builder()
 				.image_type(vk::ImageType::TYPE_2D)
 				.extent(vk::Extent3D::builder().width(font.atlas_width as u32).height(font.atlas_height as u32).depth(1).build())
@@ -119,7 +148,7 @@ impl TextRenderSystem {
 				.sharing_mode(vk::SharingMode::EXCLUSIVE)
 				.samples(vk::SampleCountFlags::TYPE_1);
 		
-			let image = unsafe { logical_device.create_image(&image_create_info, None) }.unwrap();
+			let image = unsafe { logical_device.create_image(&image_create_info, None) }?;
 
 			let image_memory_requirements = unsafe { logical_device.get_image_memory_requirements(image) };
 			let alignment = image_memory_requirements.alignment;
@@ -148,37 +177,17 @@ impl TextRenderSystem {
 			.sharing_mode(vk::SharingMode::EXCLUSIVE)
 			.samples(vk::SampleCountFlags::TYPE_1);
 
-		self.empty_image = unsafe { logical_device.create_image(&empty_image_create_info, None) }.unwrap();
+		self.empty_image = unsafe { logical_device.create_image(&empty_image_create_info, None) }?;
 
-		// Create staging buffer
-		let staging_buffer = Buffer::new(context, offset, vk::BufferUsageFlags::TRANSFER_SRC, vk::MemoryPropertyFlags::HOST_VISIBLE);
-
-		// Copy atlases into staging buffer
-		let staging_buffer_ptr = unsafe { logical_device.map_memory(staging_buffer.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) }.unwrap();
-
-		for font_info in &font_infos {
-			let font = &font_info.font;
-
-			let mut file = File::open(&font.fnt_path).unwrap();
-			file.seek(SeekFrom::Start(2 * size_of::<u32>() as u64)).unwrap();
-			let mut atlas = vec![0u8; font.atlas_width * font.atlas_height];
-			file.read_exact(&mut atlas).unwrap();
-
-			unsafe {
-				let dst_ptr = staging_buffer_ptr.add(font_info.offset as usize) as *mut u8;
-				copy_nonoverlapping(atlas.as_ptr(), dst_ptr, font.atlas_width * font.atlas_height);
+		// Copy atlases into the staging buffer
+		staging_buffer.write(context, offset, |staging_buffer_ptr| {
+			for (font_info, atlas) in font_infos.iter().zip(&atlases) {
+				unsafe {
+					let dst_ptr = staging_buffer_ptr.add(font_info.offset as usize);
+					copy_nonoverlapping(atlas.as_ptr(), dst_ptr, atlas.len());
+				}
 			}
-		}
-
-		let range = vk::MappedMemoryRange::builder()
-			.memory(staging_buffer.memory)
-			.offset(0)
-			.size(vk::WHOLE_SIZE);
-		
-		unsafe {
-			logical_device.flush_mapped_memory_ranges(&[range.build()]).unwrap();
-			logical_device.unmap_memory(staging_buffer.memory);
-		}
+		})?;
 
 		// Create device local buffer
 		let first_image = font_infos[0].image;
@@ -189,11 +198,11 @@ impl TextRenderSystem {
 			.allocation_size(offset as u64)
 			.memory_type_index(memory_type_index as u32);
 	
-		self.memory = unsafe { logical_device.allocate_memory(&memory_allocate_info, None) }.unwrap();
+		self.memory = unsafe { logical_device.allocate_memory(&memory_allocate_info, None) }?;
 
 		// Bind images to device local buffer and create image view
 		for font_info in &mut font_infos {
-			unsafe { logical_device.bind_image_memory(font_info.image, self.memory, font_info.offset) }.unwrap();
+			unsafe { logical_device.bind_image_memory(font_info.image, self.memory, font_info.offset) }?;
 
 			let image_view_create_info = vk::ImageViewCreateInfo::builder()
 				.image(font_info.image)
@@ -207,10 +216,10 @@ impl TextRenderSystem {
 					.layer_count(1)
 					.build());
 			
-			font_info.image_view = unsafe { logical_device.create_image_view(&image_view_create_info, None) }.unwrap();
+			font_info.image_view = unsafe { logical_device.create_image_view(&image_view_create_info, None) }?;
 		}
 
-		unsafe { logical_device.bind_image_memory(self.empty_image, self.memory, 0) }.unwrap();
+		unsafe { logical_device.bind_image_memory(self.empty_image, self.memory, 0) }?;
 
 		let empty_image_view_create_info = vk::ImageViewCreateInfo::builder()
 			.image(self.empty_image)
@@ -224,7 +233,7 @@ impl TextRenderSystem {
 				.layer_count(1)
 				.build());
 		
-		self.empty_image_view = unsafe { logical_device.create_image_view(&empty_image_view_create_info, None) }.unwrap();
+		self.empty_image_view = unsafe { logical_device.create_image_view(&empty_image_view_create_info, None) }?;
 
 		// Record command buffer to copy staging buffer to device local buffer
 		let mut transfer_image_memory_barriers: Vec<vk::ImageMemoryBarrier> = Vec::with_capacity(font_infos.len());
@@ -285,56 +294,28 @@ impl TextRenderSystem {
 		
 		shader_read_image_memory_barriers.push(empty_image_memory_barrier.build());
 
-		let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
-			.level(vk::CommandBufferLevel::PRIMARY)
-			.command_pool(command_pool)
-			.command_buffer_count(1);
-
-		let command_buffer = unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }.unwrap()[0];
-
-		let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
-			.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
-
-		unsafe {
-			logical_device.begin_command_buffer(command_buffer, &command_buffer_begin_info).unwrap();
-			logical_device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[], &[], &transfer_image_memory_barriers);
-		}
-
-		for font_info in &font_infos {
-			let region = vk::BufferImageCopy::builder()
-				.buffer_offset(font_info.offset)
-				.buffer_row_length(0)
-				.buffer_image_height(0)
-				.image_subresource(vk::ImageSubresourceLayers::builder()
-					.aspect_mask(vk::ImageAspectFlags::COLOR)
-					.mip_level(0)
-					.base_array_layer(0)
-					.layer_count(1)
-					.build())
-				.image_offset(vk::Offset3D::builder().x(0).y(0).z(0).build())
-				.image_extent(vk::Extent3D::builder().width(font_info.font.atlas_width as u32).height(font_info.font.atlas_height as u32).depth(1).build());
-
-			unsafe { logical_device.cmd_copy_buffer_to_image(command_buffer, staging_buffer.handle, font_info.image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[region.build()]) };
-		}
-
-		unsafe {
-			logical_device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::FRAGMENT_SHADER, vk::DependencyFlags::empty(), &[], &[], &shader_read_image_memory_barriers);
-			logical_device.end_command_buffer(command_buffer).unwrap();
-		}
-
-		// Submit command buffer
-		let command_buffers = [command_buffer];
-		let submit_info = vk::SubmitInfo::builder()
-			.command_buffers(&command_buffers);
-		
-		unsafe {
-			logical_device.queue_submit(context.graphics_queue, &[submit_info.build()], vk::Fence::null()).unwrap();
-			logical_device.queue_wait_idle(context.graphics_queue).unwrap();
-			logical_device.free_command_buffers(command_pool, &command_buffers);
-		}
+		staging_buffer.submit(context, command_pool, |command_buffer, staging_buffer_handle| {
+			unsafe { logical_device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, vk::PipelineStageFlags::TRANSFER, vk::DependencyFlags::empty(), &[], &[], &transfer_image_memory_barriers) };
+
+			for font_info in &font_infos {
+				let region = vk::BufferImageCopy::builder()
+					.buffer_offset(font_info.offset)
+					.buffer_row_length(0)
+					.buffer_image_height(0)
+					.image_subresource(vk::ImageSubresourceLayers::builder()
+						.aspect_mask(vk::ImageAspectFlags::COLOR)
+						.mip_level(0)
+						.base_array_layer(0)
+						.layer_count(1)
+						.build())
+					.image_offset(vk::Offset3D::builder().x(0).y(0).z(0).build())
+					.image_extent(vk::Extent3D::builder().width(font_info.font.atlas_width as u32).height(font_info.font.atlas_height as u32).depth(1).build());
+
+				unsafe { logical_device.cmd_copy_buffer_to_image(command_buffer, staging_buffer_handle, font_info.image, vk::ImageLayout::TRANSFER_DST_OPTIMAL, &[region.build()]) };
+			}
 
-		// Destroy staging buffer
-		staging_buffer.drop(logical_device);
+			unsafe { logical_device.cmd_pipeline_barrier(command_buffer, vk::PipelineStageFlags::TRANSFER, vk::PipelineStageFlags::FRAGMENT_SHADER, vk::DependencyFlags::empty(), &[], &[], &shader_read_image_memory_barriers) };
+		})?;
 
 		// Update descriptor sets
 		let mut descriptor_image_infos: Vec<vk::DescriptorImageInfo> = Vec::with_capacity(MAX_FONTS);
@@ -378,6 +359,16 @@ impl TextRenderSystem {
 				image_view: font_info.image_view
 			});
 		}
+
+		Ok(())
+	}
+
+	// The caller ensures the device is idle, the old pipeline is kept if the new one can't be created
+	pub fn recreate_pipeline(&mut self, logical_device: &ash::Device, pipeline_cache: vk::PipelineCache, render_pass: vk::RenderPass, sample_count: vk::SampleCountFlags, gamma_correction: bool) -> Result<(), RenderSystemError> {
+		let pipeline = create_pipeline(logical_device, pipeline_cache, self.pipeline_layout, render_pass, sample_count, gamma_correction)?;
+		unsafe { logical_device.destroy_pipeline(self.pipeline, None) };
+		self.pipeline = pipeline;
+		Ok(())
 	}
 
 	pub fn drop(&self, logical_device: &ash::Device) {
@@ -400,4 +391,216 @@ impl TextRenderSystem {
 			logical_device.destroy_descriptor_set_layout(self.sampler_descriptor_set_layout, None);
 		}
 	}
+}
+
+// The atlas follows the header at the start of the font file
+fn read_atlas(font: &Font) -> Result<Vec<u8>, RenderSystemError> {
+	let mut atlas = vec![0u8; font.atlas_width * font.atlas_height];
+
+	File::open(&font.fnt_path)
+		.and_then(|mut file| {
+			file.seek(SeekFrom::Start(FNT_ATLAS_OFFSET as u64))?;
+			file.read_exact(&mut atlas)
+		})
+		.map_err(|error| RenderSystemError::FontFile(font.fnt_path.clone(), error))?;
+
+	Ok(atlas)
+}
+
+// Every text's glyphs merged into one index and attribute array so they're all drawn with a single call
+// All the atlases are bound at once so texts with different fonts can still be merged
+// Each vertex also gets the index of its text's instance data since the instance index is the same for the whole draw
+pub struct TextBatch {
+	pub indices: Vec<u32>,
+	pub attributes: Vec<f32>,
+	pub instance_indices: Vec<u32>,
+	text_count: usize
+}
+
+impl TextBatch {
+	pub fn new() -> Self {
+		Self {
+			indices: Vec::new(),
+			attributes: Vec::new(),
+			instance_indices: Vec::new(),
+			text_count: 0
+		}
+	}
+
+	// The vectors keep their capacity so rebuilding the batch every frame doesn't allocate
+	pub fn clear(&mut self) {
+		self.indices.clear();
+		self.attributes.clear();
+		self.instance_indices.clear();
+		self.text_count = 0;
+	}
+
+	// The text's instance data is expected at the index of the order it was added in
+	pub fn add(&mut self, text: &Text) {
+		let vertex_offset = (self.attributes.len() / ATTRIBUTES_PER_VERTEX) as u32;
+		let vertex_count = text.attributes().len() / ATTRIBUTES_PER_VERTEX;
+
+		self.indices.extend(text.indices().iter().map(|index| *index as u32 + vertex_offset));
+		self.attributes.extend_from_slice(text.attributes());
+		self.instance_indices.extend(repeat(self.text_count as u32).take(vertex_count));
+		self.text_count += 1;
+	}
+
+	pub fn text_count(&self) -> usize {
+		self.text_count
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.indices.is_empty()
+	}
+}
+
+// The atlas indices are the primary font's followed by each fallback font's, unused fallback slots are 0
+pub fn copy_instance_data(dst: &mut [u8], matrix: &Matrix3, text: &Text, atlas_indices: &[u32; MAX_FALLBACK_FONTS + 1]) {
+	assert_eq!(dst.len(), INSTANCE_DATA_SIZE, "Cannot copy text instance data because the destination is {} bytes instead of {}", dst.len(), INSTANCE_DATA_SIZE);
+
+	for (row_index, row) in matrix.to_padded_array().iter().enumerate() {
+		for (col_index, element) in row.iter().enumerate() {
+			copy_f32(dst, 16 * row_index + 4 * col_index, *element);
+		}
+	}
+
+	copy_f32(dst, INSTANCE_DATA_COLOR_OFFSET, text.color.x);
+	copy_f32(dst, INSTANCE_DATA_COLOR_OFFSET + 4, text.color.y);
+	copy_f32(dst, INSTANCE_DATA_COLOR_OFFSET + 8, text.color.z);
+	dst[INSTANCE_DATA_ATLAS_INDEX_OFFSET..INSTANCE_DATA_ATLAS_INDEX_OFFSET + 4].copy_from_slice(&atlas_indices[0].to_ne_bytes());
+	copy_f32(dst, INSTANCE_DATA_OUTLINE_COLOR_OFFSET, text.outline_color.x);
+	copy_f32(dst, INSTANCE_DATA_OUTLINE_COLOR_OFFSET + 4, text.outline_color.y);
+	copy_f32(dst, INSTANCE_DATA_OUTLINE_COLOR_OFFSET + 8, text.outline_color.z);
+	copy_f32(dst, INSTANCE_DATA_OUTLINE_WIDTH_OFFSET, text.outline_width);
+
+	for (index, atlas_index) in atlas_indices[1..].iter().enumerate() {
+		let offset = INSTANCE_DATA_FALLBACK_ATLAS_INDICES_OFFSET + 4 * index;
+		dst[offset..offset + 4].copy_from_slice(&atlas_index.to_ne_bytes());
+	}
+}
+
+fn copy_f32(dst: &mut [u8], offset: usize, value: f32) {
+	dst[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::convert::TryInto;
+	use crate::{math::Vector3, pool::Handle};
+
+	fn read_f32(src: &[u8], offset: usize) -> f32 {
+		f32::from_ne_bytes(src[offset..offset + 4].try_into().unwrap())
+	}
+
+	#[test]
+	fn instance_data_layout() {
+		// std140 aligns a vec3 to 16 bytes and lets a scalar fill the rest of its vec4
+		assert_eq!(INSTANCE_DATA_COLOR_OFFSET % 16, 0);
+		assert_eq!(INSTANCE_DATA_ATLAS_INDEX_OFFSET, INSTANCE_DATA_COLOR_OFFSET + 12);
+		assert_eq!(INSTANCE_DATA_OUTLINE_COLOR_OFFSET % 16, 0);
+		assert_eq!(INSTANCE_DATA_OUTLINE_WIDTH_OFFSET, INSTANCE_DATA_OUTLINE_COLOR_OFFSET + 12);
+		assert_eq!(INSTANCE_DATA_SIZE % 16, 0);
+
+		let matrix = Matrix3::new([
+			[1.0, 2.0, 3.0],
+			[4.0, 5.0, 6.0],
+			[7.0, 8.0, 9.0]]);
+
+		let mut text = Text::new(Handle::null(), String::new());
+		text.color = Vector3::new(0.1, 0.2, 0.3);
+		text.outline_color = Vector3::new(0.4, 0.5, 0.6);
+		text.outline_width = 2.0;
+
+		let mut dst = [0u8; INSTANCE_DATA_SIZE];
+		copy_instance_data(&mut dst, &matrix, &text, &[7, 8, 9, 0]);
+
+		assert_eq!(read_f32(&dst, 0), 1.0);
+		assert_eq!(read_f32(&dst, 8), 3.0);
+		assert_eq!(read_f32(&dst, 16), 4.0);
+		assert_eq!(read_f32(&dst, 40), 9.0);
+		assert_eq!(read_f32(&dst, 48), 0.1);
+		assert_eq!(read_f32(&dst, 52), 0.2);
+		assert_eq!(read_f32(&dst, 56), 0.3);
+		assert_eq!(u32::from_ne_bytes(dst[60..64].try_into().unwrap()), 7);
+		assert_eq!(read_f32(&dst, 64), 0.4);
+		assert_eq!(read_f32(&dst, 68), 0.5);
+		assert_eq!(read_f32(&dst, 72), 0.6);
+		assert_eq!(read_f32(&dst, 76), 2.0);
+		assert_eq!(u32::from_ne_bytes(dst[80..84].try_into().unwrap()), 8);
+		assert_eq!(u32::from_ne_bytes(dst[84..88].try_into().unwrap()), 9);
+		assert_eq!(u32::from_ne_bytes(dst[88..92].try_into().unwrap()), 0);
+	}
+
+	#[test]
+	fn instance_data_default_outline() {
+		let text = Text::new(Handle::null(), String::new());
+		let mut dst = [0u8; INSTANCE_DATA_SIZE];
+		copy_instance_data(&mut dst, &Matrix3::new([[0.0; 3]; 3]), &text, &[0; MAX_FALLBACK_FONTS + 1]);
+
+		// A zero width outline leaves the fill untouched in the shader
+		assert_eq!(read_f32(&dst, 76), 0.0);
+		assert_eq!(read_f32(&dst, 48), 1.0);
+	}
+
+	#[test]
+	fn batch_merges_texts() {
+		use crate::{Font, font::Glyph, pool::Pool};
+
+		let glyphs = "0123456789".chars().map(|c| Glyph {
+			char_code: c as u32,
+			position_x: 0.0,
+			position_y: 0.0,
+			width: 8.0,
+			height: 10.0,
+			bearing_x: 1.0,
+			bearing_y: -10.0,
+			advance: 10.0
+		}).collect();
+
+		let mut fonts = Pool::<Font>::new();
+		let font_handle = fonts.add(Font {
+			fnt_path: String::new(),
+			atlas_width: 0,
+			atlas_height: 0,
+			space_advance: 5.0,
+			line_height: 12.0,
+			glyphs,
+			kerning_pairs: vec![],
+			submission_info: None
+		});
+		let strings = ["12", "345", "6789"];
+		let mut texts = vec![];
+
+		for string in &strings {
+			let mut text = Text::new(font_handle, String::from(*string));
+			text.generate(&fonts);
+			texts.push(text);
+		}
+
+		let mut batch = TextBatch::new();
+
+		for text in &texts {
+			batch.add(text);
+		}
+
+		let glyph_count = 2 + 3 + 4;
+		assert_eq!(batch.text_count(), 3);
+		assert_eq!(batch.indices.len(), 6 * glyph_count);
+		assert_eq!(batch.attributes.len(), 4 * ATTRIBUTES_PER_VERTEX * glyph_count);
+		assert_eq!(batch.instance_indices.len(), 4 * glyph_count);
+
+		// Each text's indices are moved past the vertices of the texts before it
+		assert_eq!(&batch.indices[..6], &[0, 1, 2, 0, 2, 3]);
+		assert_eq!(&batch.indices[12..18], &[8, 9, 10, 8, 10, 11]);
+		assert_eq!(*batch.indices.iter().max().unwrap() as usize, 4 * glyph_count - 1);
+		assert_eq!(&batch.instance_indices[..8], &[0; 8]);
+		assert_eq!(&batch.instance_indices[8..20], &[1; 12]);
+		assert_eq!(&batch.instance_indices[20..], &[2; 16]);
+
+		batch.clear();
+		assert!(batch.is_empty());
+		assert_eq!(batch.text_count(), 0);
+	}
 }
\ No newline at end of file
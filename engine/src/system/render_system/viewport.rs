@@ -0,0 +1,57 @@
+use ash::vk;
+
+// A region of the framebuffer as fractions of its width and height with the origin at the top left
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+	pub x: f32,
+	pub y: f32,
+	pub width: f32,
+	pub height: f32
+}
+
+impl Viewport {
+	pub const FULL: Self = Self { x: 0.0, y: 0.0, width: 1.0, height: 1.0 };
+
+	pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+		Self { x, y, width, height }
+	}
+
+	// The edges are rounded rather than the size so viewports which share an edge don't overlap or leave a gap
+	pub(super) fn rect(&self, extent: vk::Extent2D) -> vk::Rect2D {
+		let left = (self.x * extent.width as f32).round() as i32;
+		let top = (self.y * extent.height as f32).round() as i32;
+		let right = ((self.x + self.width) * extent.width as f32).round() as i32;
+		let bottom = ((self.y + self.height) * extent.height as f32).round() as i32;
+
+		vk::Rect2D::builder()
+			.offset(vk::Offset2D::builder().x(left).y(top).build())
+			.extent(vk::Extent2D::builder().width((right - left).max(0) as u32).height((bottom - top).max(0) as u32).build())
+			.build()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rect() {
+		let extent = vk::Extent2D::builder().width(1280).height(720).build();
+		let rect = Viewport::FULL.rect(extent);
+		assert_eq!((rect.offset.x, rect.offset.y, rect.extent.width, rect.extent.height), (0, 0, 1280, 720));
+	}
+
+	#[test]
+	fn rect_vertical_split() {
+		// An odd width can't be split evenly but the halves still tile the framebuffer
+		let extent = vk::Extent2D::builder().width(1281).height(721).build();
+		let left = Viewport::new(0.0, 0.0, 0.5, 1.0).rect(extent);
+		let right = Viewport::new(0.5, 0.0, 0.5, 1.0).rect(extent);
+
+		assert_eq!(left.offset.x, 0);
+		assert_eq!(left.offset.x + left.extent.width as i32, right.offset.x);
+		assert_eq!(right.offset.x + right.extent.width as i32, 1281);
+		assert_eq!(left.extent.height, 721);
+		assert_eq!(right.extent.height, 721);
+	}
+}
\ No newline at end of file
@@ -1,79 +1,256 @@
+use std::cmp::max;
+#[cfg(test)]
+use std::cell::Cell;
 use ash::{vk, version::DeviceV1_0};
 use crate::vulkan::Context;
 
+// The number of buffer allocations this thread hasn't freed yet, tests check it returns to zero
+#[cfg(test)]
+thread_local!(static LIVE_ALLOCATIONS: Cell<isize> = Cell::new(0));
+
+#[cfg(test)]
+fn track_allocations(delta: isize) {
+	LIVE_ALLOCATIONS.with(|count| count.set(count.get() + delta));
+}
+
+#[cfg(not(test))]
+fn track_allocations(_delta: isize) {}
+
+// Keeps a clone of the logical device so the memory is freed when the buffer is dropped, it must be dropped before the device is destroyed
 pub struct Buffer {
+	logical_device: Option<ash::Device>,
 	pub handle: vk::Buffer,
 	pub memory: vk::DeviceMemory,
 	usage: vk::BufferUsageFlags,
 	properties: vk::MemoryPropertyFlags,
+	concurrent: bool,
 	pub capacity: vk::DeviceSize
 }
 
 impl Buffer {
-	pub fn new(context: &Context, capacity: vk::DeviceSize, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> Self {
-		let (handle, memory) = Self::allocate(context, capacity, usage, properties);
+	pub fn new(context: &Context, capacity: vk::DeviceSize, usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> Result<Self, vk::Result> {
+		let (handle, memory) = Self::allocate(context, capacity, usage, properties, false)?;
 
-		Self {
+		Ok(Self {
+			logical_device: Some(context.logical_device.clone()),
 			handle,
 			memory,
 			usage,
 			properties,
+			concurrent: false,
 			capacity
-		}
+		})
 	}
 
 	pub fn null(usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> Self {
 		Self {
+			logical_device: None,
 			handle: vk::Buffer::null(),
 			memory: vk::DeviceMemory::null(),
 			usage,
 			properties,
+			concurrent: false,
 			capacity: 0
 		}
 	}
 
-	pub fn reallocate(&mut self, context: &Context, capacity: vk::DeviceSize) {
-		unsafe {
-			context.logical_device.free_memory(self.memory, None);
-			context.logical_device.destroy_buffer(self.handle, None);
+	// Buffers written by the transfer queue and read by the graphics queue are shared between the families so no ownership transfer is needed
+	pub fn null_concurrent(usage: vk::BufferUsageFlags, properties: vk::MemoryPropertyFlags) -> Self {
+		Self {
+			concurrent: true,
+			..Self::null(usage, properties)
 		}
+	}
+
+	// The buffer is left in the null state if the new allocation fails
+	pub fn reallocate(&mut self, context: &Context, capacity: vk::DeviceSize) -> Result<(), vk::Result> {
+		self.release(&context.logical_device);
 
-		let (handle, memory) = Self::allocate(context, capacity, self.usage, self.properties);
+		let (handle, memory) = match Self::allocate(context, capacity, self.usage, self.properties, self.concurrent) {
+			Ok(allocation) => allocation,
+			Err(error) => {
+				self.handle = vk::Buffer::null();
+				self.memory = vk::DeviceMemory::null();
+				self.capacity = 0;
+				return Err(error);
+			}
+		};
 
+		self.logical_device.get_or_insert_with(|| context.logical_device.clone());
 		self.handle = handle;
 		self.memory = memory;
 		self.capacity = capacity;
+		Ok(())
+	}
+
+	fn release(&self, logical_device: &ash::Device) {
+		if self.memory != vk::DeviceMemory::null() {
+			track_allocations(-1);
+		}
+
+		unsafe {
+			logical_device.free_memory(self.memory, None);
+			logical_device.destroy_buffer(self.handle, None);
+		}
+	}
+
+	// Returns to the null state, the memory is freed when the old buffer is dropped and the next reserve allocates again
+	pub fn free(&mut self) {
+		*self = Self {
+			concurrent: self.concurrent,
+			..Self::null(self.usage, self.properties)
+		};
+	}
+
+	// Returns whether the buffer was reallocated
+	pub fn reserve(&mut self, context: &Context, capacity: vk::DeviceSize) -> Result<bool, vk::Result> {
+		if let Some(grown_capacity) = Self::grown_capacity(self.capacity, capacity) {
+			self.reallocate(context, grown_capacity)?;
+			Ok(true)
+		}
+		else {
+			Ok(false)
+		}
+	}
+
+	fn grown_capacity(current_capacity: vk::DeviceSize, required_capacity: vk::DeviceSize) -> Option<vk::DeviceSize> {
+		if required_capacity <= current_capacity {
+			return None;
+		}
+
+		// Grow by at least 1.5x so a steadily growing size only reallocates a logarithmic number of times
+		Some(max(required_capacity + required_capacity / 2, current_capacity + current_capacity / 2))
 	}
 
 	fn allocate(
 		context: &Context,
 		capacity: vk::DeviceSize,
 		usage: vk::BufferUsageFlags,
-		properties: vk::MemoryPropertyFlags) -> (vk::Buffer, vk::DeviceMemory)
+		properties: vk::MemoryPropertyFlags,
+		concurrent: bool) -> Result<(vk::Buffer, vk::DeviceMemory), vk::Result>
 	{
+		let graphics_queue_family = context.physical_device.graphics_queue_family;
+		let transfer_queue_family = context.physical_device.transfer_queue_family;
+		let queue_family_indices = [graphics_queue_family, transfer_queue_family.unwrap_or(graphics_queue_family)];
+
 		let create_info = vk::BufferCreateInfo::builder()
 			.size(capacity)
-			.usage(usage)
-			.sharing_mode(vk::SharingMode::EXCLUSIVE);
+			.usage(usage);
+
+		let create_info = if concurrent && transfer_queue_family.is_some() {
+			create_info
+				.sharing_mode(vk::SharingMode::CONCURRENT)
+				.queue_family_indices(&queue_family_indices)
+		}
+		else {
+			create_info.sharing_mode(vk::SharingMode::EXCLUSIVE)
+		};
 		
-		let handle = unsafe { context.logical_device.create_buffer(&create_info, None).unwrap() };
-		let memory_requirements = unsafe { context.logical_device.get_buffer_memory_requirements(handle) };
+		let logical_device = &context.logical_device;
+		let handle = unsafe { logical_device.create_buffer(&create_info, None) }?;
+		let memory_requirements = unsafe { logical_device.get_buffer_memory_requirements(handle) };
 		let memory_type_index = context.physical_device.find_memory_type_index(memory_requirements.memory_type_bits, properties);
 
 		let allocate_info = vk::MemoryAllocateInfo::builder()
 			.allocation_size(memory_requirements.size)
 			.memory_type_index(memory_type_index as u32);
-	
-		let memory = unsafe { context.logical_device.allocate_memory(&allocate_info, None).unwrap() };
-		unsafe { context.logical_device.bind_buffer_memory(handle, memory, 0).unwrap() };
 
-		(handle, memory)
+		// Nothing else owns the handles yet so whatever was created is destroyed here on failure
+		let memory = match unsafe { logical_device.allocate_memory(&allocate_info, None) } {
+			Ok(memory) => memory,
+			Err(error) => {
+				unsafe { logical_device.destroy_buffer(handle, None) };
+				return Err(error);
+			}
+		};
+
+		if let Err(error) = unsafe { logical_device.bind_buffer_memory(handle, memory, 0) } {
+			unsafe {
+				logical_device.free_memory(memory, None);
+				logical_device.destroy_buffer(handle, None);
+			}
+
+			return Err(error);
+		}
+
+		track_allocations(1);
+		Ok((handle, memory))
 	}
+}
 
-	pub fn drop(&self, logical_device: &ash::Device) {
-		unsafe {
-			logical_device.free_memory(self.memory, None);
-			logical_device.destroy_buffer(self.handle, None);
+impl Drop for Buffer {
+	fn drop(&mut self) {
+		// Null buffers that were never allocated have nothing to free
+		if let Some(logical_device) = &self.logical_device {
+			self.release(logical_device);
 		}
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn grown_capacity() {
+		assert_eq!(Buffer::grown_capacity(100, 50), None);
+		assert_eq!(Buffer::grown_capacity(100, 100), None);
+		assert_eq!(Buffer::grown_capacity(100, 101), Some(151));
+		assert_eq!(Buffer::grown_capacity(0, 10), Some(15));
+	}
+
+	#[test]
+	fn grown_capacity_amortized() {
+		let mut capacity = 0;
+		let mut allocation_count = 0;
+
+		for required_capacity in 1..=10000 {
+			if let Some(grown_capacity) = Buffer::grown_capacity(capacity, required_capacity) {
+				capacity = grown_capacity;
+				allocation_count += 1;
+			}
+
+			assert!(capacity >= required_capacity);
+		}
+
+		assert!(allocation_count < 25, "{} allocations", allocation_count);
+	}
+
+	fn live_allocations() -> isize {
+		LIVE_ALLOCATIONS.with(|count| count.get())
+	}
+
+	// Needs a display and a Vulkan device so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn drop_frees_memory() {
+		let window = crate::Window::new("Test");
+		let context = Context::new(&window.glfw, &window.glfw_window, true).unwrap();
+		assert_eq!(live_allocations(), 0);
+
+		let buffer = Buffer::new(&context, 64, vk::BufferUsageFlags::UNIFORM_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE).unwrap();
+		assert_eq!(live_allocations(), 1);
+		drop(buffer);
+		assert_eq!(live_allocations(), 0);
+
+		// Growing frees the old allocation before making the new one
+		let mut buffer = Buffer::null(vk::BufferUsageFlags::VERTEX_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE);
+		buffer.reserve(&context, 64).unwrap();
+		buffer.reserve(&context, 1024).unwrap();
+		assert_eq!(live_allocations(), 1);
+
+		// The old allocation is freed when the buffer returned to the null state is dropped
+		let mut freed_buffer = Buffer::null(vk::BufferUsageFlags::VERTEX_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE);
+		freed_buffer.reserve(&context, 64).unwrap();
+		freed_buffer.free();
+		assert_eq!(live_allocations(), 1);
+
+		drop(buffer);
+		assert_eq!(live_allocations(), 0);
+
+		// Never allocated so dropping it doesn't touch the device
+		let buffer = Buffer::null(vk::BufferUsageFlags::VERTEX_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE);
+		drop(buffer);
+		assert_eq!(live_allocations(), 0);
+	}
 }
\ No newline at end of file
@@ -1,15 +1,19 @@
-use std::{ffi::{CString, CStr}, os::raw::{c_void, c_char}};
+use std::{ffi::{CString, CStr}, os::raw::{c_void, c_char}, path::Path};
 use ash::{vk, version::EntryV1_0, version::InstanceV1_0, version::DeviceV1_0, extensions::ext, extensions::khr, vk::Handle};
-use super::PhysicalDevice;
+use crate::system::RenderSystemError;
+use super::{PhysicalDevice, pipeline_cache::{self, PIPELINE_CACHE_PATH}};
 
 pub struct Context {
 	pub instance: ash::Instance,
-	pub debug_utils: DebugUtils,
+	pub debug_utils: Option<DebugUtils>,
 	pub physical_device: PhysicalDevice,
-	pub surface: Surface,
+	// None when headless
+	pub surface: Option<Surface>,
 	pub logical_device: ash::Device,
 	pub graphics_queue: vk::Queue,
-	pub present_queue: vk::Queue
+	pub present_queue: vk::Queue,
+	pub transfer_queue: Option<vk::Queue>,
+	pub pipeline_cache: vk::PipelineCache
 }
 
 pub struct DebugUtils {
@@ -24,33 +28,66 @@ pub struct Surface {
 }
 
 impl Context {
-	pub fn new(glfw: &glfw::Glfw, window: &glfw::Window) -> Self {
+	pub fn new(glfw: &glfw::Glfw, window: &glfw::Window, enable_validation: bool) -> Result<Self, RenderSystemError> {
+		Self::create(Some((glfw, window)), enable_validation)
+	}
+
+	// Without a surface there's nothing to present to so the present queue is the graphics queue and only render targets can be drawn
+	pub fn new_headless(enable_validation: bool) -> Result<Self, RenderSystemError> {
+		Self::create(None, enable_validation)
+	}
+
+	fn create(window: Option<(&glfw::Glfw, &glfw::Window)>, enable_validation: bool) -> Result<Self, RenderSystemError> {
 		// Create entry
-		let entry = unsafe { ash::Entry::new() }.unwrap();
+		let entry = unsafe { ash::Entry::new() }.map_err(|error| RenderSystemError::Loading(format!("{:?}", error)))?;
 
-		// Create layer and extension lists
+		// Only enable validation if the layer and debug utils extension are actually available
 		let validation_layer = CString::new("VK_LAYER_KHRONOS_validation").unwrap();
-		let required_layers = [validation_layer.as_c_str()];
-		let required_device_extensions = [khr::Swapchain::name()];
-		
-		let mut required_instance_extensions = vec![ext::DebugUtils::name()];
-		let required_glfw_instance_extensions_cstring: Vec<CString> = glfw.get_required_instance_extensions().unwrap().iter().map(|s| CString::new(s.as_str()).unwrap()).collect();
-		let required_glfw_instance_extensions_cstr: Vec<&CStr> = required_glfw_instance_extensions_cstring.iter().map(|s| s.as_c_str()).collect();
-		required_instance_extensions.extend_from_slice(&required_glfw_instance_extensions_cstr);
+		let available_layers = entry.enumerate_instance_layer_properties()?;
+		let available_instance_extensions = entry.enumerate_instance_extension_properties()?;
+
+		let validation_supported = available_layers.iter()
+			.any(|available_layer| unsafe { CStr::from_ptr(available_layer.layer_name.as_ptr()) } == validation_layer.as_c_str())
+			&& available_instance_extensions.iter()
+			.any(|available_instance_extension| unsafe { CStr::from_ptr(available_instance_extension.extension_name.as_ptr()) } == ext::DebugUtils::name());
+
+		if enable_validation && !validation_supported {
+			println!("Validation requested but not supported, continuing without it");
+		}
+
+		let validation_enabled = enable_validation && validation_supported;
+
+		// Create layer and extension lists
+		let mut required_layers = vec![];
+		let mut required_instance_extensions = vec![];
 
-		// Check layer and extension support
-		let available_layers = entry.enumerate_instance_layer_properties().unwrap();
-		for required_layer in &required_layers {
-			available_layers.iter()
-				.find(|available_layer| unsafe { CStr::from_ptr(available_layer.layer_name.as_ptr()) } == *required_layer)
-				.unwrap_or_else(|| panic!("Required layer {} not supported", required_layer.to_str().unwrap()));
+		if validation_enabled {
+			required_layers.push(validation_layer.as_c_str());
+			required_instance_extensions.push(ext::DebugUtils::name());
 		}
 
-		let available_instance_extensions = entry.enumerate_instance_extension_properties().unwrap();
+		let mut required_device_extensions = vec![];
+		let mut required_glfw_instance_extensions_cstring: Vec<CString> = vec![];
+
+		if let Some((glfw, _)) = window {
+			required_device_extensions.push(khr::Swapchain::name());
+
+			let required_glfw_instance_extensions = glfw.get_required_instance_extensions()
+				.ok_or_else(|| RenderSystemError::Loading(String::from("GLFW could not find a Vulkan loader")))?;
+			required_glfw_instance_extensions_cstring = required_glfw_instance_extensions.iter().map(|s| CString::new(s.as_str()).unwrap()).collect();
+		}
+
+		let required_glfw_instance_extensions_cstr: Vec<&CStr> = required_glfw_instance_extensions_cstring.iter().map(|s| s.as_c_str()).collect();
+		required_instance_extensions.extend_from_slice(&required_glfw_instance_extensions_cstr);
+
+		// Check extension support
 		for required_instance_extension in &required_instance_extensions {
-			available_instance_extensions.iter()
-				.find(|available_instance_extension| unsafe { CStr::from_ptr(available_instance_extension.extension_name.as_ptr()) } == *required_instance_extension)
-				.unwrap_or_else(|| panic!("Required instance extension {} not supported", required_instance_extension.to_str().unwrap()));
+			let supported = available_instance_extensions.iter()
+				.any(|available_instance_extension| unsafe { CStr::from_ptr(available_instance_extension.extension_name.as_ptr()) } == *required_instance_extension);
+
+			if !supported {
+				return Err(RenderSystemError::MissingInstanceExtension(required_instance_extension.to_string_lossy().into_owned()));
+			}
 		}
 
 		// Create instance
@@ -72,53 +109,84 @@ impl Context {
 			.pfn_user_callback(Some(Self::debug_message_callback))
 			.build();
 	
-		let instance_create_info = vk::InstanceCreateInfo::builder()
+		let mut instance_create_info = vk::InstanceCreateInfo::builder()
 			.application_info(&app_info)
 			.enabled_layer_names(&layers)
-			.enabled_extension_names(&instance_extensions)
-			.push_next(&mut debug_messenger_create_info);
+			.enabled_extension_names(&instance_extensions);
+
+		// Chaining the messenger create info also reports messages from instance creation and destruction
+		if validation_enabled {
+			instance_create_info = instance_create_info.push_next(&mut debug_messenger_create_info);
+		}
 		
-		let instance = unsafe { entry.create_instance(&instance_create_info, None).unwrap() };
+		let instance = unsafe { entry.create_instance(&instance_create_info, None) }.map_err(|error| match error {
+			ash::InstanceError::VkError(result) => RenderSystemError::Vulkan(result),
+			ash::InstanceError::LoadError(names) => RenderSystemError::Loading(names.join(", "))
+		})?;
 
 		// Create debug utils
-		let debug_utils_extension = ext::DebugUtils::new(&entry, &instance);
-		let debug_utils_messenger_handle = unsafe { debug_utils_extension.create_debug_utils_messenger(&debug_messenger_create_info, None).unwrap() };
-		let debug_utils = DebugUtils {
-			extension: debug_utils_extension,
-			messenger_handle: debug_utils_messenger_handle
+		let debug_utils = if validation_enabled {
+			let debug_utils_extension = ext::DebugUtils::new(&entry, &instance);
+			let debug_utils_messenger_handle = unsafe { debug_utils_extension.create_debug_utils_messenger(&debug_messenger_create_info, None) }?;
+
+			Some(DebugUtils {
+				extension: debug_utils_extension,
+				messenger_handle: debug_utils_messenger_handle
+			})
+		}
+		else {
+			None
 		};
 
 		// Create surface extension and handle
-		let surface_extension = khr::Surface::new(&entry, &instance);
-		let mut surface_handle_raw: u64 = 0;
-		let result = window.create_window_surface(instance.handle().as_raw() as usize, std::ptr::null(), &mut surface_handle_raw as *mut u64);
-		assert_eq!(result, 0, "Could not create window surface");
-		let surface_handle = vk::SurfaceKHR::from_raw(surface_handle_raw);
+		let surface = match window {
+			Some((_, window)) => {
+				let surface_extension = khr::Surface::new(&entry, &instance);
+				let mut surface_handle_raw: u64 = 0;
+				let result = window.create_window_surface(instance.handle().as_raw() as usize, std::ptr::null(), &mut surface_handle_raw as *mut u64);
+				if result != 0 {
+					return Err(RenderSystemError::SurfaceCreation(vk::Result::from_raw(result as i32)));
+				}
+
+				Some((surface_extension, vk::SurfaceKHR::from_raw(surface_handle_raw)))
+			},
+			None => None
+		};
 
 		// Create the physical device
 		let device_extensions: Vec<CString> = required_device_extensions.iter().map(|extension| CString::new(extension.to_str().unwrap()).unwrap()).collect();
-		let physical_device = PhysicalDevice::new(&instance, surface_handle, &surface_extension, &device_extensions);
+		let physical_device = PhysicalDevice::new(&instance, surface.as_ref().map(|(extension, handle)| (extension, *handle)), &device_extensions)?;
 
 		// Create surface format
-		let surface_formats = unsafe { surface_extension.get_physical_device_surface_formats(physical_device.handle, surface_handle).unwrap() };
-		let surface_format_option = surface_formats.iter().find(|f| f.format == vk::Format::B8G8R8A8_SRGB && f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR);
-		let surface_format = *surface_format_option.unwrap_or_else(|| &surface_formats[0]);
+		let surface = match surface {
+			Some((surface_extension, surface_handle)) => {
+				let surface_formats = unsafe { surface_extension.get_physical_device_surface_formats(physical_device.handle, surface_handle) }?;
+				let surface_format_option = surface_formats.iter().find(|f| f.format == vk::Format::B8G8R8A8_SRGB && f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR);
+				let surface_format = *surface_format_option.unwrap_or_else(|| &surface_formats[0]);
+
+				Some(Surface {
+					extension: surface_extension,
+					handle: surface_handle,
+					format: surface_format
+				})
+			},
+			None => None
+		};
 
-		// Create logical device and queues
+		// Create logical device and queues, each family only gets one create info
 		let graphics_queue_family = physical_device.graphics_queue_family;
 		let present_queue_family = physical_device.present_queue_family;
+		let transfer_queue_family = physical_device.transfer_queue_family;
 
-		let mut device_queue_create_infos = vec![vk::DeviceQueueCreateInfo::builder()
-			.queue_family_index(graphics_queue_family)
-			.queue_priorities(&[1.0])
-			.build()];
+		let mut queue_families = vec![graphics_queue_family, present_queue_family];
+		queue_families.extend(transfer_queue_family);
+		queue_families.sort_unstable();
+		queue_families.dedup();
 
-		if graphics_queue_family != present_queue_family {
-			device_queue_create_infos.push(vk::DeviceQueueCreateInfo::builder()
-				.queue_family_index(present_queue_family)
-				.queue_priorities(&[1.0])
-				.build());
-		}
+		let device_queue_create_infos: Vec<vk::DeviceQueueCreateInfo> = queue_families.iter().map(|&queue_family| vk::DeviceQueueCreateInfo::builder()
+			.queue_family_index(queue_family)
+			.queue_priorities(&[1.0])
+			.build()).collect();
 
 		let features = vk::PhysicalDeviceFeatures::builder();
 		let device_extensions: Vec<*const c_char> = required_device_extensions.iter().map(|extension| extension.as_ptr()).collect();
@@ -129,25 +197,56 @@ impl Context {
 			.enabled_layer_names(&layers)
 			.enabled_extension_names(&device_extensions);
 		
-		let logical_device = unsafe { instance.create_device(physical_device.handle, &device_create_info, None).unwrap() };
+		let logical_device = unsafe { instance.create_device(physical_device.handle, &device_create_info, None) }?;
 		let graphics_queue = unsafe { logical_device.get_device_queue(graphics_queue_family, 0) };
 		let present_queue = unsafe { logical_device.get_device_queue(present_queue_family, 0) };
+		let transfer_queue = transfer_queue_family.map(|transfer_queue_family| unsafe { logical_device.get_device_queue(transfer_queue_family, 0) });
 
-		Self {
+		// Create the pipeline cache, the driver may still reject data with a valid header so fall back to an empty cache
+		let pipeline_cache_data = pipeline_cache::load_data(Path::new(PIPELINE_CACHE_PATH), &physical_device.pipeline_cache_key);
+		let pipeline_cache_create_info = vk::PipelineCacheCreateInfo::builder()
+			.initial_data(&pipeline_cache_data);
+
+		let pipeline_cache = match unsafe { logical_device.create_pipeline_cache(&pipeline_cache_create_info, None) } {
+			Ok(pipeline_cache) => pipeline_cache,
+			Err(_) => unsafe { logical_device.create_pipeline_cache(&vk::PipelineCacheCreateInfo::default(), None) }?
+		};
+
+		Ok(Self {
 			instance,
 			debug_utils,
 			physical_device,
-			surface: Surface {
-				extension: surface_extension,
-				handle: surface_handle,
-				format: surface_format
-			},
+			surface,
 			logical_device,
 			graphics_queue,
-			present_queue
-		}
+			present_queue,
+			transfer_queue,
+			pipeline_cache
+		})
 	}
 	
+	// Names only help debugging so a name that can't be set is logged and ignored
+	pub fn set_object_name<T: Handle>(&self, handle: T, name: &str) {
+		if let Some(debug_utils) = &self.debug_utils {
+			let c_name = match CString::new(name) {
+				Ok(c_name) => c_name,
+				Err(_) => {
+					println!("Cannot name object {:?} because the name contains a nul byte", name);
+					return;
+				}
+			};
+
+			let name_info = vk::DebugUtilsObjectNameInfoEXT::builder()
+				.object_type(T::TYPE)
+				.object_handle(handle.as_raw())
+				.object_name(&c_name);
+
+			if let Err(error) = unsafe { debug_utils.extension.debug_utils_set_object_name(self.logical_device.handle(), &name_info) } {
+				println!("Cannot name object {} because {}", name, error);
+			}
+		}
+	}
+
 	unsafe extern "system" fn debug_message_callback(
 		_message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
 		_message_type: vk::DebugUtilsMessageTypeFlagsEXT,
@@ -163,10 +262,49 @@ impl Context {
 impl Drop for Context {
 	fn drop(&mut self) {
 		unsafe {
+			// Failing to save the pipeline cache only makes the next launch slower
+			match self.logical_device.get_pipeline_cache_data(self.pipeline_cache) {
+				Ok(data) => {
+					if let Err(error) = pipeline_cache::save_data(Path::new(PIPELINE_CACHE_PATH), &data) {
+						println!("Cannot save the pipeline cache\n{}", error);
+					}
+				},
+				Err(result) => println!("Cannot get the pipeline cache data\n{}", result)
+			}
+
+			self.logical_device.destroy_pipeline_cache(self.pipeline_cache, None);
 			self.logical_device.destroy_device(None);
-			self.surface.extension.destroy_surface(self.surface.handle, None);
-			self.debug_utils.extension.destroy_debug_utils_messenger(self.debug_utils.messenger_handle, None);
+
+			if let Some(surface) = &self.surface {
+				surface.extension.destroy_surface(surface.handle, None);
+			}
+
+			if let Some(debug_utils) = &self.debug_utils {
+				debug_utils.extension.destroy_debug_utils_messenger(debug_utils.messenger_handle, None);
+			}
+
 			self.instance.destroy_instance(None);
 		}
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::vulkan::Buffer;
+
+	// Needs a display, a Vulkan device and the validation layer so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn set_object_name() {
+		let window = crate::Window::new("Test");
+		let context = Context::new(&window.glfw, &window.glfw_window, true).unwrap();
+		assert!(context.debug_utils.is_some());
+
+		let buffer = Buffer::new(&context, 64, vk::BufferUsageFlags::UNIFORM_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE).unwrap();
+		context.set_object_name(buffer.handle, "test_buffer");
+
+		// An interior nul can't be passed to Vulkan so the name is skipped instead of panicking
+		context.set_object_name(buffer.handle, "test\0buffer");
+	}
 }
\ No newline at end of file
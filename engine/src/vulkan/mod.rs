@@ -4,5 +4,11 @@ pub(crate) use context::Context;
 pub(crate) mod physical_device;
 pub(crate) use physical_device::PhysicalDevice;
 
+pub(crate) mod pipeline_cache;
+pub(crate) use pipeline_cache::PipelineCacheKey;
+
 pub(crate) mod buffer;
-pub(crate) use buffer::Buffer;
\ No newline at end of file
+pub(crate) use buffer::Buffer;
+
+pub(crate) mod staging_buffer;
+pub(crate) use staging_buffer::StagingBuffer;
\ No newline at end of file
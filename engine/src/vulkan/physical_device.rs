@@ -1,18 +1,25 @@
 use ash::{vk, version::InstanceV1_0, extensions::khr};
 use std::ffi::{CString, CStr};
+use crate::system::RenderSystemError;
+use super::PipelineCacheKey;
 
 pub struct PhysicalDevice {
 	pub handle: vk::PhysicalDevice,
 	pub graphics_queue_family: u32,
 	pub present_queue_family: u32,
+	pub transfer_queue_family: Option<u32>,
 	pub memory_properties: vk::PhysicalDeviceMemoryProperties,
 	pub min_uniform_buffer_offset_alignment: u64,
-	pub min_storage_buffer_offset_alignment: u64
+	pub min_storage_buffer_offset_alignment: u64,
+	pub supported_sample_counts: vk::SampleCountFlags,
+	pub timestamp_period: Option<f32>,
+	pub pipeline_cache_key: PipelineCacheKey
 }
 
 impl PhysicalDevice {
-	pub fn new(instance: &ash::Instance, surface_handle: vk::SurfaceKHR, surface_extension: &khr::Surface, device_extensions: &[CString]) -> Self {
-		let physical_devices = unsafe { instance.enumerate_physical_devices().unwrap() };
+	// Without a surface the present checks are skipped and the graphics queue family doubles as the present queue family
+	pub fn new(instance: &ash::Instance, surface: Option<(&khr::Surface, vk::SurfaceKHR)>, device_extensions: &[CString]) -> Result<Self, RenderSystemError> {
+		let physical_devices = unsafe { instance.enumerate_physical_devices() }?;
 
 		'main: for device in physical_devices {
 			let properties = unsafe { instance.get_physical_device_properties(device) };
@@ -33,16 +40,22 @@ impl PhysicalDevice {
 					graphics_queue_family = Some(i);
 				}
 
-				if unsafe { surface_extension.get_physical_device_surface_support(device, i as u32, surface_handle).unwrap() } {
-					present_queue_family = Some(i);
+				if let Some((surface_extension, surface_handle)) = surface {
+					if unsafe { surface_extension.get_physical_device_surface_support(device, i as u32, surface_handle) }? {
+						present_queue_family = Some(i);
+					}
 				}
 			}
 
+			if surface.is_none() {
+				present_queue_family = graphics_queue_family;
+			}
+
 			if graphics_queue_family.is_none() || present_queue_family.is_none() {
 				continue;
 			}
 
-			let available_device_extensions = unsafe { instance.enumerate_device_extension_properties(device).unwrap() };
+			let available_device_extensions = unsafe { instance.enumerate_device_extension_properties(device) }?;
 			for device_extension in device_extensions {
 				let extension = available_device_extensions.iter().find(|e| unsafe { CStr::from_ptr(e.extension_name.as_ptr()) } == device_extension.as_c_str());
 				if extension.is_none() {
@@ -50,27 +63,42 @@ impl PhysicalDevice {
 				}
 			}
 
-			let formats = unsafe { surface_extension.get_physical_device_surface_formats(device, surface_handle).unwrap() };
-			if formats.is_empty() {
-				continue;
+			if let Some((surface_extension, surface_handle)) = surface {
+				let formats = unsafe { surface_extension.get_physical_device_surface_formats(device, surface_handle) }?;
+				if formats.is_empty() {
+					continue;
+				}
+
+				let present_modes = unsafe { surface_extension.get_physical_device_surface_present_modes(device, surface_handle) }?;
+				if present_modes.is_empty() {
+					continue;
+				}
 			}
 
-			let present_modes = unsafe { surface_extension.get_physical_device_surface_present_modes(device, surface_handle).unwrap() };
-			if present_modes.is_empty() {
-				continue;
+			// Timestamps are only usable if the graphics queue family can write them
+			let graphics_queue_family_properties = &queue_family_properties[graphics_queue_family.unwrap()];
+			let timestamp_period = if properties.limits.timestamp_compute_and_graphics == vk::TRUE && graphics_queue_family_properties.timestamp_valid_bits != 0 {
+				Some(properties.limits.timestamp_period)
 			}
+			else {
+				None
+			};
 
-			return Self {
+			return Ok(Self {
 				handle: device,
 				graphics_queue_family: graphics_queue_family.unwrap() as u32,
 				present_queue_family: present_queue_family.unwrap() as u32,
+				transfer_queue_family: find_dedicated_transfer_queue_family(&queue_family_properties).map(|i| i as u32),
 				memory_properties: unsafe { instance.get_physical_device_memory_properties(device) },
 				min_uniform_buffer_offset_alignment: properties.limits.min_uniform_buffer_offset_alignment,
-				min_storage_buffer_offset_alignment: properties.limits.min_storage_buffer_offset_alignment
-			}
+				min_storage_buffer_offset_alignment: properties.limits.min_storage_buffer_offset_alignment,
+				supported_sample_counts: properties.limits.framebuffer_color_sample_counts & properties.limits.framebuffer_depth_sample_counts,
+				timestamp_period,
+				pipeline_cache_key: PipelineCacheKey::new(&properties)
+			});
 		}
 
-		panic!("No suitable physical device found");
+		Err(RenderSystemError::NoSuitableDevice)
 	}
 
 	pub fn find_memory_type_index(&self, r#type: u32, properties: vk::MemoryPropertyFlags) -> usize {
@@ -80,4 +108,42 @@ impl PhysicalDevice {
 			.find(|&i| r#type & (1 << i) != 0 && available_types[i].property_flags.contains(properties))
 			.expect("Could not find suitable memory type")
 	}
+}
+
+// A family with transfer but no graphics support is usually backed by the copy engines so uploads run alongside rendering
+// Transfer only families are preferred over ones which also support compute
+pub(crate) fn find_dedicated_transfer_queue_family(queue_family_properties: &[vk::QueueFamilyProperties]) -> Option<usize> {
+	let supports_transfer = |property: &vk::QueueFamilyProperties| property.queue_count > 0
+		&& property.queue_flags.contains(vk::QueueFlags::TRANSFER)
+		&& !property.queue_flags.contains(vk::QueueFlags::GRAPHICS);
+
+	queue_family_properties.iter()
+		.position(|property| supports_transfer(property) && !property.queue_flags.contains(vk::QueueFlags::COMPUTE))
+		.or_else(|| queue_family_properties.iter().position(supports_transfer))
+}
+
+#[cfg(test)]
+mod tests {
+	use ash::vk;
+	use super::find_dedicated_transfer_queue_family;
+
+	fn family(queue_flags: vk::QueueFlags, queue_count: u32) -> vk::QueueFamilyProperties {
+		vk::QueueFamilyProperties {
+			queue_flags,
+			queue_count,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn dedicated_transfer_queue_family() {
+		let graphics = family(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER, 16);
+		let compute = family(vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER, 8);
+		let transfer = family(vk::QueueFlags::TRANSFER, 2);
+
+		assert_eq!(find_dedicated_transfer_queue_family(&[graphics, compute, transfer]), Some(2));
+		assert_eq!(find_dedicated_transfer_queue_family(&[graphics, compute]), Some(1));
+		assert_eq!(find_dedicated_transfer_queue_family(&[graphics]), None);
+		assert_eq!(find_dedicated_transfer_queue_family(&[graphics, family(vk::QueueFlags::TRANSFER, 0)]), None);
+	}
 }
\ No newline at end of file
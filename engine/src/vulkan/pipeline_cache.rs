@@ -0,0 +1,111 @@
+use std::{fs, io, path::Path, convert::TryInto};
+use ash::vk;
+
+pub const PIPELINE_CACHE_PATH: &str = "target/pipeline_cache.bin";
+
+// Header version one layout
+// - u32 header size at 0
+// - u32 header version at 4
+// - u32 vendor id at 8
+// - u32 device id at 12
+// - u8 array pipeline cache UUID at 16
+const HEADER_SIZE: usize = 16 + vk::UUID_SIZE;
+
+// Identifies the driver and device a cache blob was written by
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PipelineCacheKey {
+	pub vendor_id: u32,
+	pub device_id: u32,
+	pub uuid: [u8; vk::UUID_SIZE]
+}
+
+impl PipelineCacheKey {
+	pub fn new(properties: &vk::PhysicalDeviceProperties) -> Self {
+		Self {
+			vendor_id: properties.vendor_id,
+			device_id: properties.device_id,
+			uuid: properties.pipeline_cache_uuid
+		}
+	}
+
+	pub fn matches(&self, data: &[u8]) -> bool {
+		if data.len() < HEADER_SIZE {
+			return false;
+		}
+
+		let read_u32 = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+		read_u32(0) as usize >= HEADER_SIZE
+			&& read_u32(4) == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32
+			&& read_u32(8) == self.vendor_id
+			&& read_u32(12) == self.device_id
+			&& data[16..HEADER_SIZE] == self.uuid
+	}
+}
+
+// A missing file or a blob from another driver or device starts with an empty cache
+pub fn load_data(path: &Path, key: &PipelineCacheKey) -> Vec<u8> {
+	match fs::read(path) {
+		Ok(data) if key.matches(&data) => data,
+		_ => vec![]
+	}
+}
+
+pub fn save_data(path: &Path, data: &[u8]) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	fs::write(path, data)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn key() -> PipelineCacheKey {
+		PipelineCacheKey { vendor_id: 0x10DE, device_id: 0x1B80, uuid: [7; vk::UUID_SIZE] }
+	}
+
+	fn blob(key: &PipelineCacheKey) -> Vec<u8> {
+		let mut data = vec![];
+		data.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+		data.extend_from_slice(&1u32.to_le_bytes());
+		data.extend_from_slice(&key.vendor_id.to_le_bytes());
+		data.extend_from_slice(&key.device_id.to_le_bytes());
+		data.extend_from_slice(&key.uuid);
+		data.extend_from_slice(&[1, 2, 3, 4, 5]);
+		data
+	}
+
+	#[test]
+	fn round_trip() {
+		let path = std::env::temp_dir().join("pipeline_cache_round_trip.bin");
+		let data = blob(&key());
+
+		save_data(&path, &data).unwrap();
+		assert_eq!(load_data(&path, &key()), data);
+
+		fs::remove_file(path).unwrap();
+	}
+
+	#[test]
+	fn mismatched_or_corrupt_data_is_discarded() {
+		let path = std::env::temp_dir().join("pipeline_cache_mismatch.bin");
+
+		let other_device = PipelineCacheKey { device_id: 0x1C03, ..key() };
+		save_data(&path, &blob(&other_device)).unwrap();
+		assert!(load_data(&path, &key()).is_empty());
+
+		let mut other_uuid = blob(&key());
+		other_uuid[16] = 0;
+		save_data(&path, &other_uuid).unwrap();
+		assert!(load_data(&path, &key()).is_empty());
+
+		save_data(&path, &blob(&key())[..HEADER_SIZE - 1]).unwrap();
+		assert!(load_data(&path, &key()).is_empty());
+
+		fs::remove_file(&path).unwrap();
+		assert!(load_data(&path, &key()).is_empty());
+	}
+}
\ No newline at end of file
@@ -0,0 +1,163 @@
+use ash::{vk, version::DeviceV1_0};
+use crate::system::RenderSystemError;
+use super::{Buffer, Context};
+
+// A host visible buffer shared by every upload, it only grows so uploads which fit reuse the same allocation
+// Copies submitted to the transfer queue aren't waited on straight away, the next write, submit or frame waits for them instead
+pub struct StagingBuffer {
+	buffer: Buffer,
+	fence: vk::Fence,
+	transfer_command_pool: Option<vk::CommandPool>,
+	transfer_command_buffer: Option<vk::CommandBuffer>
+}
+
+impl StagingBuffer {
+	pub fn new(context: &Context) -> Result<Self, RenderSystemError> {
+		let logical_device = &context.logical_device;
+
+		let fence_create_info = vk::FenceCreateInfo::builder();
+		let fence = unsafe { logical_device.create_fence(&fence_create_info, None) }?;
+
+		let transfer_command_pool = match context.physical_device.transfer_queue_family {
+			Some(transfer_queue_family) => {
+				let command_pool_create_info = vk::CommandPoolCreateInfo::builder()
+					.queue_family_index(transfer_queue_family)
+					.flags(vk::CommandPoolCreateFlags::TRANSIENT);
+
+				Some(unsafe { logical_device.create_command_pool(&command_pool_create_info, None) }?)
+			},
+			None => None
+		};
+
+		Ok(Self {
+			buffer: Buffer::null(vk::BufferUsageFlags::TRANSFER_SRC, vk::MemoryPropertyFlags::HOST_VISIBLE),
+			fence,
+			transfer_command_pool,
+			transfer_command_buffer: None
+		})
+	}
+
+	pub fn memory(&self) -> vk::DeviceMemory {
+		self.buffer.memory
+	}
+
+	// Grows the buffer to at least size bytes and maps it so the caller can write the data
+	pub fn write<F: FnOnce(*mut u8)>(&mut self, context: &Context, size: vk::DeviceSize, write: F) -> Result<(), RenderSystemError> {
+		let logical_device = &context.logical_device;
+		self.wait_for_transfer(logical_device)?;
+
+		if self.buffer.reserve(context, size)? {
+			context.set_object_name(self.buffer.handle, "staging_buffer");
+			println!("Staging buffer reallocated");
+		}
+
+		let ptr = unsafe { logical_device.map_memory(self.buffer.memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty()) }?;
+		write(ptr as *mut u8);
+
+		let range = vk::MappedMemoryRange::builder()
+			.memory(self.buffer.memory)
+			.offset(0)
+			.size(vk::WHOLE_SIZE);
+
+		unsafe {
+			logical_device.flush_mapped_memory_ranges(&[range.build()])?;
+			logical_device.unmap_memory(self.buffer.memory);
+		}
+
+		Ok(())
+	}
+
+	// Records the caller's copy commands from the staging buffer into a one time command buffer and waits until they've executed
+	pub fn submit<F: FnOnce(vk::CommandBuffer, vk::Buffer)>(&mut self, context: &Context, command_pool: vk::CommandPool, record: F) -> Result<(), RenderSystemError> {
+		let logical_device = &context.logical_device;
+		self.wait_for_transfer(logical_device)?;
+
+		let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
+			.level(vk::CommandBufferLevel::PRIMARY)
+			.command_pool(command_pool)
+			.command_buffer_count(1);
+
+		let command_buffer = unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }?[0];
+
+		let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
+			.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+		unsafe { logical_device.begin_command_buffer(command_buffer, &command_buffer_begin_info) }?;
+		record(command_buffer, self.buffer.handle);
+		unsafe { logical_device.end_command_buffer(command_buffer) }?;
+
+		let command_buffers = [command_buffer];
+		let submit_info = vk::SubmitInfo::builder()
+			.command_buffers(&command_buffers);
+
+		unsafe {
+			logical_device.reset_fences(&[self.fence])?;
+			logical_device.queue_submit(context.graphics_queue, &[submit_info.build()], self.fence)?;
+			logical_device.wait_for_fences(&[self.fence], true, u64::MAX)?;
+			logical_device.free_command_buffers(command_pool, &command_buffers);
+		}
+
+		Ok(())
+	}
+
+	// Buffer copies go to the dedicated transfer queue when there is one so rendering isn't stalled, otherwise they're submitted to the graphics queue
+	// The destination must be shared with the transfer queue family
+	pub fn submit_transfer<F: FnOnce(vk::CommandBuffer, vk::Buffer)>(&mut self, context: &Context, command_pool: vk::CommandPool, record: F) -> Result<(), RenderSystemError> {
+		let (transfer_command_pool, transfer_queue) = match (self.transfer_command_pool, context.transfer_queue) {
+			(Some(transfer_command_pool), Some(transfer_queue)) => (transfer_command_pool, transfer_queue),
+			_ => return self.submit(context, command_pool, record)
+		};
+
+		let logical_device = &context.logical_device;
+		self.wait_for_transfer(logical_device)?;
+
+		let command_buffer_allocate_info = vk::CommandBufferAllocateInfo::builder()
+			.level(vk::CommandBufferLevel::PRIMARY)
+			.command_pool(transfer_command_pool)
+			.command_buffer_count(1);
+
+		let command_buffer = unsafe { logical_device.allocate_command_buffers(&command_buffer_allocate_info) }?[0];
+
+		let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
+			.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+		unsafe { logical_device.begin_command_buffer(command_buffer, &command_buffer_begin_info) }?;
+		record(command_buffer, self.buffer.handle);
+		unsafe { logical_device.end_command_buffer(command_buffer) }?;
+
+		let command_buffers = [command_buffer];
+		let submit_info = vk::SubmitInfo::builder()
+			.command_buffers(&command_buffers);
+
+		unsafe {
+			logical_device.reset_fences(&[self.fence])?;
+			logical_device.queue_submit(transfer_queue, &[submit_info.build()], self.fence)?;
+		}
+
+		self.transfer_command_buffer = Some(command_buffer);
+		Ok(())
+	}
+
+	// Blocks until the last transfer queue copy has executed, the fence signal makes its writes visible to later submissions
+	pub fn wait_for_transfer(&mut self, logical_device: &ash::Device) -> Result<(), RenderSystemError> {
+		if let Some(command_buffer) = self.transfer_command_buffer.take() {
+			unsafe {
+				logical_device.wait_for_fences(&[self.fence], true, u64::MAX)?;
+				logical_device.free_command_buffers(self.transfer_command_pool.unwrap(), &[command_buffer]);
+			}
+		}
+
+		Ok(())
+	}
+
+	// The caller ensures the device is idle
+	pub fn drop(&self, logical_device: &ash::Device) {
+		unsafe {
+			if let Some(transfer_command_pool) = self.transfer_command_pool {
+				logical_device.destroy_command_pool(transfer_command_pool, None);
+			}
+
+			logical_device.destroy_fence(self.fence, None);
+		}
+	}
+}
\ No newline at end of file
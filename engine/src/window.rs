@@ -1,5 +1,6 @@
 use std::sync::mpsc::Receiver;
 
+// Wraps the glfw setup and event loop shared by the examples, the window has no client API since Vulkan renders to it
 pub struct Window {
 	pub glfw: glfw::Glfw,
 	pub glfw_window: glfw::Window,
@@ -64,4 +65,20 @@ impl Window {
 			render(resized, width, height);
 		}
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Needs a display so it's only run with --ignored
+	#[test]
+	#[ignore]
+	fn create_and_close() {
+		for _ in 0..2 {
+			let mut window = Window::new("Test");
+			window.glfw_window.set_should_close(true);
+			window.main_loop(|_, _, _| panic!("Cannot run the main loop because the window should have closed"));
+		}
+	}
 }
\ No newline at end of file
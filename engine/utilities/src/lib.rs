@@ -1,2 +0,0 @@
-mod window;
-pub use window::Window;
\ No newline at end of file
@@ -1,14 +1,28 @@
 use std::time::Duration;
-use engine::{Camera, glfw, math::{vector3, Euler, Order}};
+use engine::{Camera, glfw, math::{vector3, Euler, Order, Vector3}};
 
-const TRANSLATION_SPEED: f32 = 2.5;
-const ROTATION_SPEED: f32 = 0.003;
+const DEFAULT_TRANSLATION_SPEED: f32 = 2.5;
+const DEFAULT_ROTATION_SPEED: f32 = 0.003;
 const MAX_VERTICAL_ROTATION_ANGLE: f32 = 1.57;
 
+// Each scroll step scales the translation speed by this factor
+const SCROLL_SPEED_FACTOR: f32 = 1.2;
+const MIN_TRANSLATION_SPEED: f32 = 0.1;
+const MAX_TRANSLATION_SPEED: f32 = 100.0;
+
 pub struct CameraController {
+	pub translation_speed: f32,
+	pub rotation_speed: f32,
+	// When set the camera eases toward the input with this time constant in seconds instead of snapping to it
+	pub smoothing_time: Option<f32>,
 	prev_mouse_pos_x: f32,
 	prev_mouse_pos_y: f32,
-	euler: Euler
+	// The orientation is rebuilt from these each frame rather than read back from the camera so roll can't accumulate
+	yaw: f32,
+	pitch: f32,
+	velocity: Vector3,
+	pending_yaw: f32,
+	pending_pitch: f32
 }
 
 impl CameraController {
@@ -16,9 +30,16 @@ impl CameraController {
 		let (mouse_pos_x, mouse_pos_y) = window.get_cursor_pos();
 
 		Self {
+			translation_speed: DEFAULT_TRANSLATION_SPEED,
+			rotation_speed: DEFAULT_ROTATION_SPEED,
 			prev_mouse_pos_x: mouse_pos_x as f32,
 			prev_mouse_pos_y: mouse_pos_y as f32,
-			euler: Euler::new(0.0, 0.0, 0.0, Order::Yxz)
+			yaw: 0.0,
+			pitch: 0.0,
+			smoothing_time: None,
+			velocity: vector3::ZERO,
+			pending_yaw: 0.0,
+			pending_pitch: 0.0
 		}
 	}
 
@@ -29,6 +50,20 @@ impl CameraController {
 		self.prev_mouse_pos_y = mouse_pos_y as f32;
 	}
 
+	// Picks up the camera's current yaw and pitch, any roll is discarded
+	pub fn sync_with_camera(&mut self, camera: &Camera) {
+		let mut euler = Euler::new(0.0, 0.0, 0.0, Order::Yxz);
+		euler.set_from_quaternion(&camera.transform.orientation);
+		self.yaw = euler.y;
+		self.pitch = euler.x;
+	}
+
+	// Scrolling up speeds up the camera's movement and scrolling down slows it down
+	pub fn handle_scroll(&mut self, y_offset: f32) {
+		self.translation_speed *= SCROLL_SPEED_FACTOR.powf(y_offset);
+		self.translation_speed = self.translation_speed.max(MIN_TRANSLATION_SPEED).min(MAX_TRANSLATION_SPEED);
+	}
+
 	pub fn update(&mut self, window: &glfw::Window, camera: &mut Camera, delta_time: &Duration) {
 		let mut translation_direction = vector3::ZERO;
 
@@ -64,17 +99,135 @@ impl CameraController {
 		let mouse_pos_diff_x = mouse_pos_x - self.prev_mouse_pos_x;
 		let mouse_pos_diff_y = mouse_pos_y - self.prev_mouse_pos_y;
 
-		let transform = &mut camera.transform;
-		self.euler.set_from_quaternion(&transform.orientation);
-		self.euler.y -= mouse_pos_diff_x * ROTATION_SPEED;
-		self.euler.x += mouse_pos_diff_y * ROTATION_SPEED;
-		self.euler.x = self.euler.x.max(-MAX_VERTICAL_ROTATION_ANGLE).min(MAX_VERTICAL_ROTATION_ANGLE);
-		transform.orientation.set_from_euler(&self.euler);
-
 		self.prev_mouse_pos_x = mouse_pos_x;
 		self.prev_mouse_pos_y = mouse_pos_y;
 
-		transform.translate_on_axis(translation_direction, TRANSLATION_SPEED * delta_time.as_secs_f32());
+		self.apply_input(camera, translation_direction, mouse_pos_diff_x, mouse_pos_diff_y, delta_time.as_secs_f32());
+	}
+
+	fn apply_input(&mut self, camera: &mut Camera, translation_direction: Vector3, mouse_pos_diff_x: f32, mouse_pos_diff_y: f32, delta_time: f32) {
+		// The fraction of the remaining distance covered this frame, it's frame rate independent
+		let blend = match self.smoothing_time {
+			Some(smoothing_time) if smoothing_time > 0.0 => 1.0 - (-delta_time / smoothing_time).exp(),
+			_ => 1.0
+		};
+
+		// Mouse movement is accumulated then applied gradually
+		self.pending_yaw -= mouse_pos_diff_x * self.rotation_speed;
+		self.pending_pitch += mouse_pos_diff_y * self.rotation_speed;
+		let yaw = self.pending_yaw * blend;
+		let pitch = self.pending_pitch * blend;
+		self.pending_yaw -= yaw;
+		self.pending_pitch -= pitch;
+
+		self.yaw += yaw;
+		self.pitch += pitch;
+		self.pitch = self.pitch.max(-MAX_VERTICAL_ROTATION_ANGLE).min(MAX_VERTICAL_ROTATION_ANGLE);
+
+		let transform = &mut camera.transform;
+		transform.orientation.set_from_euler(&Euler::new(self.pitch, self.yaw, 0.0, Order::Yxz));
+
+		let target_velocity = translation_direction * self.translation_speed;
+		self.velocity += (target_velocity - self.velocity) * blend;
+
+		transform.translate_on_axis(self.velocity, delta_time);
 		camera.update();
 	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn create_camera_controller() -> CameraController {
+		CameraController {
+			translation_speed: DEFAULT_TRANSLATION_SPEED,
+			rotation_speed: DEFAULT_ROTATION_SPEED,
+			prev_mouse_pos_x: 0.0,
+			prev_mouse_pos_y: 0.0,
+			yaw: 0.0,
+			pitch: 0.0,
+			smoothing_time: None,
+			velocity: vector3::ZERO,
+			pending_yaw: 0.0,
+			pending_pitch: 0.0
+		}
+	}
+
+	#[test]
+	fn handle_scroll() {
+		let mut camera_controller = create_camera_controller();
+
+		camera_controller.handle_scroll(1.0);
+		assert!((camera_controller.translation_speed - DEFAULT_TRANSLATION_SPEED * SCROLL_SPEED_FACTOR).abs() < 1e-6);
+
+		camera_controller.handle_scroll(-2.0);
+		assert!((camera_controller.translation_speed - DEFAULT_TRANSLATION_SPEED / SCROLL_SPEED_FACTOR).abs() < 1e-6);
+
+		// The speed is clamped in both directions
+		camera_controller.handle_scroll(100.0);
+		assert_eq!(camera_controller.translation_speed, MAX_TRANSLATION_SPEED);
+
+		camera_controller.handle_scroll(-100.0);
+		assert_eq!(camera_controller.translation_speed, MIN_TRANSLATION_SPEED);
+	}
+
+	#[test]
+	fn apply_input_without_smoothing() {
+		let mut camera_controller = create_camera_controller();
+		let mut camera = Camera::new(1.0, 75.0, 0.1, 50.0);
+
+		camera_controller.apply_input(&mut camera, vector3::UNIT_Z, 0.0, 0.0, 0.5);
+		assert!((camera.transform.position.z - DEFAULT_TRANSLATION_SPEED * 0.5).abs() < 1e-6);
+	}
+
+	#[test]
+	fn apply_input_with_smoothing() {
+		let mut camera_controller = create_camera_controller();
+		camera_controller.smoothing_time = Some(0.1);
+		let mut camera = Camera::new(1.0, 75.0, 0.1, 50.0);
+		let delta_time = 1.0 / 60.0;
+
+		// A single frame of input doesn't reach the full speed or the full rotation
+		let mouse_pos_diff_x = -100.0;
+		let target_yaw = -mouse_pos_diff_x * DEFAULT_ROTATION_SPEED;
+		camera_controller.apply_input(&mut camera, vector3::UNIT_Z, mouse_pos_diff_x, 0.0, delta_time);
+		assert!(camera_controller.velocity.length() < DEFAULT_TRANSLATION_SPEED * 0.5);
+		assert!(camera_controller.yaw < target_yaw * 0.5);
+
+		// Holding the input converges on the full speed and rotation
+		for _ in 0..120 {
+			camera_controller.apply_input(&mut camera, vector3::UNIT_Z, 0.0, 0.0, delta_time);
+		}
+
+		assert!((camera_controller.velocity.length() - DEFAULT_TRANSLATION_SPEED).abs() < 1e-3);
+		assert!((camera_controller.yaw - target_yaw).abs() < 1e-3);
+	}
+
+	#[test]
+	fn no_roll() {
+		let mut camera_controller = create_camera_controller();
+		let mut camera = Camera::new(1.0, 75.0, 0.1, 50.0);
+
+		for i in 0..10000 {
+			let mouse_pos_diff_x = (i as f32 * 0.37).sin() * 3.0;
+			let mouse_pos_diff_y = (i as f32 * 0.23).cos() * 2.0;
+			camera_controller.apply_input(&mut camera, vector3::ZERO, mouse_pos_diff_x, mouse_pos_diff_y, 1.0 / 60.0);
+		}
+
+		// The camera's right axis stays level with the horizon
+		let right = camera.transform.right();
+		assert!(right.y.abs() < 1e-6, "right axis {:?}", right);
+	}
+
+	#[test]
+	fn sync_with_camera() {
+		let mut camera_controller = create_camera_controller();
+		let mut camera = Camera::new(1.0, 75.0, 0.1, 50.0);
+		camera.transform.rotate_y(0.5);
+
+		camera_controller.sync_with_camera(&camera);
+		assert!((camera_controller.yaw - 0.5).abs() < 1e-6);
+		assert!(camera_controller.pitch.abs() < 1e-6);
+	}
 }
\ No newline at end of file
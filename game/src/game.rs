@@ -1,14 +1,16 @@
-use std::time::Duration;
+use std::{error::Error, time::Duration};
 use engine::{
 	Camera,
 	EntityManager,
 	Font,
 	Geometry3D,
-	component::{ComponentList, MultiComponentList, Light, Mesh, MeshBoundsHelper, Text, TextComponentList, Transform2D, Transform2DComponentList, Transform3D, Transform3DComponentList, mesh::Material},
+	gltf_loader,
+	component::{ComponentList, MultiComponentList, Light, Mesh, MeshBoundsHelper, NameComponentList, Text, TextComponentList, Transform2D, Transform2DComponentList, Transform3D, Transform3DComponentList, mesh::Material},
 	glfw::{self, Glfw},
 	math::{Vector3, box3, vector3},
 	pool::Pool,
-	system::{MeshBoundsHelperSystem, RenderSystem}
+	Projection,
+	system::{MeshBoundsHelperSystem, RenderSystem, RenderSystemError, SampleCount, Viewport}
 };
 use crate::{CameraController, component::RigidBody, system::{FrameMetricsSystem, PhysicsSystem}};
 
@@ -32,8 +34,8 @@ pub struct Game {
 }
 
 impl Game {
-	pub fn new(glfw: &Glfw, window: &glfw::Window) -> Self {
-		let mut render_system = RenderSystem::new(glfw, window);
+	pub fn new(glfw: &Glfw, window: &glfw::Window) -> Result<Self, Box<dyn Error>> {
+		let mut render_system = RenderSystem::new(glfw, window, cfg!(debug_assertions), SampleCount::X4, 2)?;
 		let (extent_width, extent_height) = render_system.get_swapchain_extent();
 		let mut camera = Camera::new(extent_width as f32 / extent_height as f32, 75.0, 0.1, 50.0);
 		camera.transform.position.set(-5.0, 3.0, -5.0);
@@ -51,13 +53,14 @@ impl Game {
 		let mut transform3d_components = Transform3DComponentList::new();
 		let mut rigid_body_components = ComponentList::<RigidBody>::new();
 		let mut mesh_bounds_helper_components = ComponentList::<MeshBoundsHelper>::new();
+		let mut name_components = NameComponentList::new();
 
 		let mut physics_system = PhysicsSystem::new();
 		let mut mesh_bounds_helper_system = MeshBoundsHelperSystem::new();
 
 		let label_entity = entity_manager.create();
-		let font_handle = fonts.add(Font::new("game/res/roboto.ttf", 14));
-		render_system.submit_fonts(&mut fonts);
+		let font_handle = fonts.add(Font::new("game/res/roboto.ttf", 14)?);
+		render_system.submit_fonts(&mut fonts)?;
 		text_components.add(label_entity, Text::new(font_handle, String::from("...")));
 		let mut transform = Transform2D::new();
 		transform.position.set(10.0, 20.0);
@@ -68,7 +71,7 @@ impl Game {
 		let box_1_bounds_helper = entity_manager.create();
 		transform3d_components.add(box_1_bounds_helper, Transform3D::new());
 		let geometry_handle = geometries.add(Geometry3D::create_box_helper(&box3::DEFAULT_SQUARE));
-		let index = mesh_components.add(Mesh { geometry_handle, material: Material::Line });
+		let index = mesh_components.add(Mesh::new(geometry_handle, Material::Line));
 		mesh_components.assign(box_1_bounds_helper, index);
 
 		let box_1 = entity_manager.create();
@@ -79,7 +82,7 @@ impl Game {
 		transform.scale.set_from_scalar(0.5);
 		transform3d_components.add(box_1, transform);
 		let geometry_handle = geometries.add(Geometry3D::create_box());
-		let index = mesh_components.add(Mesh { geometry_handle, material: Material::Normal });
+		let index = mesh_components.add(Mesh::new(geometry_handle, Material::Normal));
 		mesh_components.assign(box_1, index);
 		rigid_body_components.add(box_1, RigidBody { velocity: vector3::ZERO, acceleration: Vector3::new(0.0, -0.00001, 0.0) });
 		mesh_bounds_helper_components.add(box_1, MeshBoundsHelper { bounds_entity: box_1_bounds_helper });
@@ -91,10 +94,16 @@ impl Game {
 		transform.scale.set_from_scalar(10.0);
 		transform3d_components.add(plane, transform);
 		let geometry_handle = geometries.add(Geometry3D::create_plane());
-		let index = mesh_components.add(Mesh { geometry_handle, material: Material::Normal });
+		let index = mesh_components.add(Mesh::new(geometry_handle, Material::Normal));
 		mesh_components.assign(plane, index);
 
-		Self {
+		let monkey_entities = gltf_loader::load_scene("game/res/monkey.gltf", &mut entity_manager, &mut geometries, &mut transform3d_components, &mut mesh_components, &mut name_components).unwrap();
+		for entity in monkey_entities {
+			transform3d_components.borrow_mut(entity).position.set(3.0, 1.0, 0.0);
+			transform3d_components.update(entity);
+		}
+
+		Ok(Self {
 			camera,
 			camera_controller: CameraController::new(window),
 			camera_controller_enabled: false,
@@ -111,7 +120,7 @@ impl Game {
 			transform3d_components,
 			rigid_body_components,
 			mesh_bounds_helper_components
-		}
+		})
 	}
 
 	pub fn handle_event(&mut self, event: &glfw::WindowEvent, window: &mut glfw::Window) {
@@ -121,23 +130,45 @@ impl Game {
 
 				if self.camera_controller_enabled {
 					self.camera_controller.poll_mouse_pos(window);
+					self.camera_controller.sync_with_camera(&self.camera);
 					window.set_cursor_mode(glfw::CursorMode::Disabled);
 				}
 				else {
 					window.set_cursor_mode(glfw::CursorMode::Normal);
 				}
 			},
+			glfw::WindowEvent::Scroll(_, y_offset) => {
+				if self.camera_controller_enabled {
+					self.camera_controller.handle_scroll(*y_offset as f32);
+				}
+			},
+			glfw::WindowEvent::Key(glfw::Key::F12, _, glfw::Action::Press, _) => {
+				if let Err(error) = self.render_system.capture_screenshot("screenshot.png") {
+					eprintln!("{}", error);
+				}
+			},
+			glfw::WindowEvent::Key(glfw::Key::F5, _, glfw::Action::Press, _) => {
+				if let Err(error) = self.render_system.reload_shaders() {
+					eprintln!("{}", error);
+				}
+			},
 			_ => ()
 		}
 	}
 
-	pub fn handle_resize(&mut self, width: i32, height: i32) {
-		let (extent_width, extent_height) = self.render_system.recreate_swapchain(width, height);
-		self.camera.projection_matrix.make_perspective(extent_width as f32 / extent_height as f32, 75.0, 0.1, 50.0);
+	pub fn handle_resize(&mut self, width: i32, height: i32) -> Result<(), RenderSystemError> {
+		self.render_system.recreate_swapchain(width, height)?;
+		self.update_projection();
+		Ok(())
+	}
+
+	fn update_projection(&mut self) {
+		let (extent_width, extent_height) = self.render_system.get_swapchain_extent();
+		self.camera.set_projection(Projection::Perspective { aspect: extent_width as f32 / extent_height as f32, fov: 75.0, near: 0.1, far: 50.0 });
 	}
 
 	pub fn update(&mut self, window: &glfw::Window, delta_time: &Duration) {
-		self.frame_metrics_system.update(&mut self.text_components, delta_time);
+		self.frame_metrics_system.update(&mut self.text_components, delta_time, &self.render_system.last_gpu_frame_time());
 
 		if self.camera_controller_enabled {
 			self.camera_controller.update(window, &mut self.camera, delta_time);
@@ -147,11 +178,23 @@ impl Game {
 		self.mesh_bounds_helper_system.update(&mut self.transform3d_components, &self.mesh_components, &mut self.geometries, &self.mesh_bounds_helper_components);
 		
 		self.text_components.generate_dirties(&self.fonts);
-		self.transform2d_components.check_for_dirties();
-		self.transform3d_components.check_for_dirties();
+		self.update_scene();
+	}
+
+	// Brings every matrix up to date once so rendering can assume they're current
+	fn update_scene(&mut self) {
+		self.camera.update();
+		self.transform2d_components.update_dirties();
+		self.transform3d_components.update_dirties();
 	}
 
-	pub fn render(&mut self) -> bool {
-		self.render_system.render(&self.camera, &self.light_components, &self.geometries, &self.mesh_components, &self.transform3d_components, &self.fonts, &self.text_components, &self.transform2d_components)
+	pub fn render(&mut self) -> Result<(), RenderSystemError> {
+		let swapchain_recreated = self.render_system.render(&[(&self.camera, Viewport::FULL)], &self.light_components, &self.geometries, &self.mesh_components, &self.transform3d_components, &self.fonts, &self.text_components, &self.transform2d_components)?;
+
+		if swapchain_recreated {
+			self.update_projection();
+		}
+
+		Ok(())
 	}
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-use std::time::{Instant, Duration};
-use engine::glfw;
+use std::time::Duration;
+use engine::{glfw, Clock};
 
 mod component;
 mod system;
@@ -19,18 +19,24 @@ fn main() {
 	let (mut window, events) = glfw.create_window(1280, 720, "Vulkan", glfw::WindowMode::Windowed).unwrap();
 	window.set_framebuffer_size_polling(true);
 	window.set_key_polling(true);
+	window.set_scroll_polling(true);
 
-	let mut game = Game::new(&glfw, &window);
+	let mut game = match Game::new(&glfw, &window) {
+		Ok(game) => game,
+		Err(error) => {
+			eprintln!("{}", error);
+			std::process::exit(1);
+		}
+	};
 
 	let duration_zero = Duration::new(0, 0);
 	let max_duration = Duration::from_secs_f64(MAX_FRAME_TIME);
-	let mut frame_start = Instant::now();
+	let mut clock = Clock::new(max_duration * MAX_UPDATES_PER_FRAME);
 
 	let mut minimized = false;
 	let mut resized;
 	let mut width = 0;
 	let mut height = 0;
-	let mut surface_changed = false;
 
 	while !window.should_close() {
 		resized = false;
@@ -64,16 +70,17 @@ fn main() {
 			continue;
 		}
 
-		if resized || surface_changed {
-			game.handle_resize(width, height);
+		if resized {
+			if let Err(error) = game.handle_resize(width, height) {
+				eprintln!("{}", error);
+				std::process::exit(1);
+			}
 		}
 
-		let frame_end = Instant::now();
-		let mut duration = frame_end.duration_since(frame_start);
-		frame_start = frame_end;
+		let mut duration = clock.tick();
 		let mut updates = 0;
 
-		while duration > duration_zero && updates <= MAX_UPDATES_PER_FRAME {
+		while duration > duration_zero && updates < MAX_UPDATES_PER_FRAME {
 			let duration_capped = duration.min(max_duration);
 			
 			game.update(&window, &duration_capped);
@@ -82,6 +89,10 @@ fn main() {
 			updates += 1;
 		}
 
-		surface_changed = game.render();
+		// A lost device or surface can't be recovered from
+		if let Err(error) = game.render() {
+			eprintln!("{}", error);
+			std::process::exit(1);
+		}
 	}
 }
\ No newline at end of file
@@ -28,7 +28,7 @@ impl FrameMetricsSystem {
 		}
 	}
 
-	pub fn update(&mut self, text_component_list: &mut TextComponentList, delta_time: &Duration) {
+	pub fn update(&mut self, text_component_list: &mut TextComponentList, delta_time: &Duration, gpu_frame_time: &Duration) {
 		self.fps_sampled_frames += 1;
 
 		self.frame_times[self.current_frame] = delta_time.as_micros() as u32;
@@ -53,8 +53,10 @@ impl FrameMetricsSystem {
 			let average = total as f32 / MAX_SAMPLED_FRAMES as f32 / 1000.0;
 			let max = max as f32 / 1000.0;
 
-			let string = format!("{:.1}fps {:.1}ms avg {:.1}ms max", fps, average, max);
-			text_component_list.borrow_mut(self.label_entity).string = string;
+			let gpu = gpu_frame_time.as_secs_f32() * 1000.0;
+
+			let string = format!("{:.1}fps {:.1}ms avg {:.1}ms max {:.2}ms gpu", fps, average, max, gpu);
+			text_component_list.set_string(self.label_entity, string);
 			
 			self.duration = Duration::new(0, 0);
 			self.fps_sampled_frames = 0;